@@ -0,0 +1,134 @@
+use std::{collections::BTreeSet, env, fs, path::{Path, PathBuf}};
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}};
+
+use crate::{game::Game, narration::narrate, ui, window_size};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    FirstKill,
+    SpeedRun,
+    AllSecrets
+}
+
+pub const ACHIEVEMENTS: [Achievement; 3] = [Achievement::FirstKill, Achievement::SpeedRun, Achievement::AllSecrets];
+
+// a map finished faster than this many seconds counts as a speed run
+pub const SPEED_RUN_TIME: f32 = 60.0;
+
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+// seconds a toast stays on screen, the last of them fading out
+const TOAST_TIME: f32 = 4.0;
+const TOAST_FADE: f32 = 1.0;
+
+impl Achievement {
+    // the name it is saved under
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstKill => "first_kill",
+            Achievement::SpeedRun => "speed_run",
+            Achievement::AllSecrets => "all_secrets"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Achievement> {
+        ACHIEVEMENTS.iter().copied().find(|achievement| achievement.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::FirstKill => "First Blood",
+            Achievement::SpeedRun => "Speed Runner",
+            Achievement::AllSecrets => "Explorer"
+        }
+    }
+}
+
+// where the game keeps what belongs to the player instead of to the game, None without a home folder
+pub fn user_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(Path::new(&dir).join("raycasting"))
+    }
+    if let Some(dir) = env::var_os("APPDATA") {
+        return Some(Path::new(&dir).join("raycasting"))
+    }
+    env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share").join("raycasting"))
+}
+
+// unlocked achievements, one name per line in the file they are saved to.
+// without a file they are only kept until the game closes
+#[derive(Default)]
+pub struct Achievements {
+    pub unlocked: BTreeSet<Achievement>,
+    path: Option<PathBuf>,
+    // the ones unlocked a moment ago and how many more seconds they are shown
+    toasts: Vec<(Achievement, f32)>
+}
+
+impl Achievements {
+    pub fn new() -> Achievements {
+        Achievements::default()
+    }
+
+    // a missing file means nothing is unlocked yet, names this version doesn't know are dropped
+    pub fn load(dir: &Path) -> Result<Achievements, String> {
+        let path = dir.join(ACHIEVEMENTS_FILE);
+        let mut achievements = Achievements { path: Some(path.clone()), ..Achievements::default() };
+        if !path.exists() {
+            return Ok(achievements)
+        }
+
+        let text = fs::read_to_string(&path).map_err(|err| format!("Could not load achievements '{}': {}", path.display(), err))?;
+        for name in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            match Achievement::from_name(name) {
+                Some(achievement) => { achievements.unlocked.insert(achievement); },
+                None => println!("INFO: Unknown achievement '{}' in {}", name, path.display())
+            }
+        }
+        Ok(achievements)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| format!("Could not create '{}': {}", dir.display(), err))?;
+        }
+
+        let text: String = self.unlocked.iter().map(|achievement| format!("{}\n", achievement.name())).collect();
+        fs::write(path, text).map_err(|err| format!("Could not save achievements '{}': {}", path.display(), err))
+    }
+
+    // shows a toast and saves right away the first time, so quitting can't lose it
+    pub fn unlock(&mut self, achievement: Achievement) {
+        if !self.unlocked.insert(achievement) { return }
+
+        println!("INFO: Achievement unlocked: {}", achievement.title());
+        narrate(&format!("Achievement unlocked: {}", achievement.title()));
+        self.toasts.push((achievement, TOAST_TIME));
+        if let Err(err) = self.save() {
+            println!("ERROR: {}", err);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for (_, time) in self.toasts.iter_mut() {
+            *time -= dt;
+        }
+        self.toasts.retain(|(_, time)| *time > 0.0);
+    }
+}
+
+// stacked in the top right corner, newest at the bottom
+pub fn render_toasts(d: &mut RaylibDrawHandle, game: &Game) {
+    let (size, margin) = (ui(20), ui(10));
+    let (width, height) = (ui(320), 2 * size + 2 * margin);
+    for (i, (achievement, time)) in game.achievements.toasts.iter().enumerate() {
+        let alpha = f32::min(time / TOAST_FADE, 1.0);
+        let (x, y) = (window_size().x as i32 - width - margin, margin + i as i32 * (height + margin));
+
+        d.draw_rectangle(x, y, width, height, Color::BLACK.alpha(0.8 * alpha));
+        d.draw_rectangle_lines(x, y, width, height, Color::GOLD.alpha(alpha));
+        d.draw_text("Achievement unlocked", x + margin, y + ui(8), size, Color::GOLD.alpha(alpha));
+        d.draw_text(achievement.title(), x + margin, y + ui(12) + size, size, Color::WHITE.alpha(alpha));
+    }
+}
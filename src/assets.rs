@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+pub const BASE_ROOT: &str = "./res";
+
+// asset folders searched from the last one added back to the base folder,
+// so a mod replaces a base file just by shipping one with the same name
+#[derive(Clone)]
+pub struct AssetRoots {
+    roots: Vec<PathBuf>
+}
+
+impl Default for AssetRoots {
+    fn default() -> AssetRoots {
+        AssetRoots::new()
+    }
+}
+
+impl AssetRoots {
+    pub fn new() -> AssetRoots {
+        AssetRoots { roots: vec![PathBuf::from(BASE_ROOT)] }
+    }
+
+    pub fn add(&mut self, root: &str) -> Result<(), String> {
+        if !Path::new(root).is_dir() {
+            return Err(format!("Mod folder '{}' does not exist", root))
+        }
+
+        self.roots.push(PathBuf::from(root));
+        Ok(())
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    // path of the file that wins for `name`, the base path when no root has it
+    pub fn resolve(&self, name: &str) -> String {
+        self.roots.iter().rev()
+            .map(|root| root.join(name))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| Path::new(BASE_ROOT).join(name))
+            .to_string_lossy().into_owned()
+    }
+
+    // names of every file with `extension` in any of the folders, each name once
+    pub fn files(&self, extension: &str) -> Vec<String> {
+        let mut names: Vec<String> = self.roots.iter()
+            .filter_map(|root| std::fs::read_dir(root).ok())
+            .flat_map(|entries| entries.flatten())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == extension))
+            .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // where the base game keeps `name`, which stays the same whatever mods are loaded
+    pub fn base_path(&self, name: &str) -> String {
+        Path::new(BASE_ROOT).join(name).to_string_lossy().into_owned()
+    }
+}
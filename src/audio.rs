@@ -0,0 +1,183 @@
+use std::ops::Sub;
+
+use raylib::{core::audio::{Music, RaylibAudio, Sound}, math::Vector2};
+
+use crate::{assets::AssetRoots, board::Board, player::Player, visibility::line_of_sight};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEffect {
+    Footstep,
+    DoorOpen,
+    WeaponFire,
+    Heartbeat,
+    Secret
+}
+
+pub const SOUND_EFFECTS: [SoundEffect; 5] = [SoundEffect::Footstep, SoundEffect::DoorOpen, SoundEffect::WeaponFire, SoundEffect::Heartbeat, SoundEffect::Secret];
+
+const MUSIC: &str = "music.wav";
+
+// sounds keep their full volume up to this distance and fade out until the hearing distance
+const REFERENCE_DISTANCE: f32 = 1.0;
+pub const HEARING_DISTANCE: f32 = 15.0;
+
+// sounds behind walls play a low passed copy at this share of their volume
+const OCCLUDED_VOLUME: f32 = 0.5;
+// how much of each new sample the low pass lets through, lower is more muffled
+const LOW_PASS: f32 = 0.15;
+
+impl SoundEffect {
+    pub fn file(self) -> &'static str {
+        match self {
+            SoundEffect::Footstep => "footstep.wav",
+            SoundEffect::DoorOpen => "door.wav",
+            SoundEffect::WeaponFire => "fire.wav",
+            SoundEffect::Heartbeat => "heartbeat.wav",
+            SoundEffect::Secret => "secret.wav"
+        }
+    }
+}
+
+// a loaded sound and where it was last played from, None for sounds coming from the player.
+// the muffled copy plays alongside it and takes over while walls are in the way
+struct Source {
+    effect: SoundEffect,
+    sound: Sound<'static>,
+    muffled: Option<Sound<'static>>,
+    pos: Option<Vector2>
+}
+
+// sounds borrow the audio device, which lives for the whole program.
+// without a device or with missing files the game simply stays silent
+pub struct AudioManager {
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub music_volume: f32,
+    audio: Option<&'static RaylibAudio>,
+    sources: Vec<Source>,
+    music: Option<Music<'static>>
+}
+
+impl AudioManager {
+    pub fn new(audio: Option<&'static RaylibAudio>, assets: &AssetRoots) -> AudioManager {
+        let mut manager = AudioManager {
+            master_volume: 1.0,
+            effects_volume: 0.8,
+            music_volume: 0.4,
+            audio,
+            sources: vec![],
+            music: None
+        };
+
+        let Some(audio) = audio else { return manager };
+        for effect in SOUND_EFFECTS {
+            let path = assets.resolve(effect.file());
+            match audio.new_sound(&path) {
+                Ok(sound) => {
+                    let muffled = muffle(audio, &path).map_err(|err| println!("ERROR: {}", err)).ok();
+                    manager.sources.push(Source { effect, sound, muffled, pos: None });
+                },
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+
+        match audio.new_music(&assets.resolve(MUSIC)) {
+            Ok(mut music) => {
+                music.looping = true;
+                music.play_stream();
+                manager.music = Some(music);
+            },
+            Err(err) => println!("ERROR: {}", err)
+        }
+
+        manager
+    }
+
+    pub fn play(&mut self, effect: SoundEffect, pos: Option<Vector2>, listener: &Player, board: &Board) {
+        if let Some(source) = self.sources.iter_mut().find(|source| source.effect == effect) {
+            source.pos = pos;
+            spatialize(source, listener, board, self.effects_volume);
+            source.sound.play();
+            if let Some(muffled) = source.muffled.as_mut() {
+                muffled.play();
+            }
+        }
+    }
+
+    // has to run every frame to keep the music stream fed and the sounds following the listener
+    pub fn update(&mut self, listener: &Player, board: &Board) {
+        for source in self.sources.iter_mut().filter(|source| source.sound.is_playing()) {
+            spatialize(source, listener, board, self.effects_volume);
+        }
+
+        if let Some(audio) = self.audio {
+            audio.set_master_volume(self.master_volume);
+        }
+
+        if let Some(music) = self.music.as_mut() {
+            music.set_volume(self.music_volume);
+            if !music.is_stream_playing() {
+                music.resume_stream();
+            }
+            music.update_stream();
+        }
+    }
+
+    pub fn pause_music(&mut self) {
+        if let Some(music) = self.music.as_mut() {
+            music.pause_stream();
+        }
+    }
+}
+
+// volume falloff and stereo pan of a source as heard by the player
+pub fn attenuate(listener: &Player, pos: Vector2) -> (f32, f32) {
+    let rel = pos.sub(listener.pos);
+    let dist = rel.length();
+    if dist <= REFERENCE_DISTANCE { return (1.0, 0.5) }
+
+    let volume = (1.0 - (dist - REFERENCE_DISTANCE) / (HEARING_DISTANCE - REFERENCE_DISTANCE)).clamp(0.0, 1.0);
+    let right = rel.normalized().dot(listener.dir.rotated(std::f32::consts::FRAC_PI_2));
+
+    // raylib pans fully left at 1 and fully right at 0
+    (volume * volume, 0.5 - 0.5 * right)
+}
+
+// a source is occluded when a wall blocks the straight line from the player to it
+fn spatialize(source: &mut Source, listener: &Player, board: &Board, effects_volume: f32) {
+    let (volume, pan) = source.pos.map_or((1.0, 0.5), |pos| attenuate(listener, pos));
+    let occluded = source.pos.is_some_and(|pos| !line_of_sight(board, listener.pos, pos));
+
+    // without a muffled copy an occluded sound just gets quieter
+    let (dry, wet) = match (occluded, source.muffled.is_some()) {
+        (false, _) => (1.0, 0.0),
+        (true, true) => (0.0, OCCLUDED_VOLUME),
+        (true, false) => (OCCLUDED_VOLUME, 0.0)
+    };
+
+    source.sound.set_volume(volume * dry * effects_volume);
+    source.sound.set_pan(pan);
+    if let Some(muffled) = source.muffled.as_mut() {
+        muffled.set_volume(volume * wet * effects_volume);
+        muffled.set_pan(pan);
+    }
+}
+
+// the same file run through a one pole low pass, taking the highs off like a wall would
+fn muffle(audio: &'static RaylibAudio, path: &str) -> Result<Sound<'static>, String> {
+    let mut wave = audio.new_wave(path)?;
+    wave.format(wave.sample_rate() as i32, 32, wave.channels() as i32);
+
+    let channels = wave.channels() as usize;
+    // 32 bit waves hold interleaved f32 samples, owned by the wave until it is dropped
+    let samples = unsafe { std::slice::from_raw_parts_mut(wave.data as *mut f32, wave.frame_count() as usize * channels) };
+    let mut last = vec![0.0; channels];
+    for frame in samples.chunks_mut(channels) {
+        for (sample, last) in frame.iter_mut().zip(last.iter_mut()) {
+            *last += LOW_PASS * (*sample - *last);
+            *sample = *last;
+        }
+    }
+
+    audio.new_sound_from_wave(&wave)
+}
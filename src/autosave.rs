@@ -0,0 +1,266 @@
+use std::fs;
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::Vector2, RaylibHandle, RaylibThread};
+
+use crate::{board::Board, map::{board_to_text, text_hash}, menu::{render_lines, select, MenuInput, MenuNav}, state::{GameState, Transition}, window_size};
+
+// where the editor keeps what wasn't saved yet, found again on the next launch if the game didn't
+// get to save or drop it
+pub const AUTOSAVE_PATH: &str = "./editor.autosave";
+// seconds between autosaves, only taken when the map changed since the last one
+const AUTOSAVE_INTERVAL: f32 = 30.0;
+// bumped whenever the file changes meaning
+const AUTOSAVE_VERSION: u32 = 1;
+
+// the lines of the map text that differ from the map saved at `map`, which has to be the same as it was
+// when the autosave was taken for them to apply.
+//
+// autosave <version>
+// map <path>
+// base <hash of the saved map>
+// lines <lines in the edited map>
+// then a line per changed line: <index> <line>
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recovery {
+    pub map: String,
+    base: u64,
+    count: usize,
+    lines: Vec<(usize, String)>
+}
+
+impl Recovery {
+    pub fn diff(map: &str, base: &str, text: &str) -> Recovery {
+        let old: Vec<&str> = base.lines().collect();
+        let lines = text.lines().enumerate()
+            .filter(|(i, line)| old.get(*i) != Some(line))
+            .map(|(i, line)| (i, line.to_string()))
+            .collect();
+        Recovery { map: map.to_string(), base: text_hash(base), count: text.lines().count(), lines }
+    }
+
+    pub fn apply(&self, base: &str) -> Result<String, String> {
+        if text_hash(base) != self.base {
+            return Err(format!("'{}' changed since the autosave was taken", self.map))
+        }
+
+        let old: Vec<&str> = base.lines().collect();
+        let mut lines = self.lines.iter().peekable();
+        let mut text = String::new();
+        for i in 0..self.count {
+            let line = match lines.peek() {
+                Some((index, line)) if *index == i => {
+                    lines.next();
+                    line.as_str()
+                },
+                _ => old.get(i).ok_or(format!("Autosave is missing line {}", i))?
+            };
+            text += line;
+            text += "\n";
+        }
+        Ok(text)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("autosave {}\nmap {}\nbase {:016x}\nlines {}\n", AUTOSAVE_VERSION, self.map, self.base, self.count);
+        for (i, line) in self.lines.iter() {
+            text += &format!("{} {}\n", i, line);
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Recovery, String> {
+        let mut lines = text.lines();
+        let mut header = |key: &str| {
+            let line = lines.next().unwrap_or_default();
+            line.strip_prefix(key).and_then(|value| value.strip_prefix(' ')).map(|value| value.to_string())
+                .ok_or(format!("Expected '{} ...', got '{}'", key, line))
+        };
+
+        let version = header("autosave")?;
+        if version != AUTOSAVE_VERSION.to_string() {
+            return Err(format!("Unsupported autosave version {}", version))
+        }
+        let map = header("map")?;
+        let base = header("base")?;
+        let base = u64::from_str_radix(&base, 16).map_err(|err| format!("Invalid base '{}': {}", base, err))?;
+        let count = header("lines")?;
+        let count = count.parse::<usize>().map_err(|err| format!("Invalid line count '{}': {}", count, err))?;
+
+        let lines = lines.map(|line| {
+            let (index, line) = line.split_once(' ').unwrap_or((line, ""));
+            let index = index.parse::<usize>().map_err(|err| format!("Invalid line index '{}': {}", index, err))?;
+            Ok((index, line.to_string()))
+        }).collect::<Result<Vec<_>, String>>()?;
+        Ok(Recovery { map, base, count, lines })
+    }
+
+    // None without an autosave
+    pub fn load(path: &str) -> Result<Option<Recovery>, String> {
+        let Ok(text) = fs::read_to_string(path) else { return Ok(None) };
+        Recovery::from_text(&text).map(Some).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // the edited map as text, from the saved one and the changes
+    pub fn restore(&self) -> Result<String, String> {
+        // a map that was never saved is all in the changes
+        let base = fs::read_to_string(&self.map).unwrap_or_default();
+        self.apply(&base)
+    }
+}
+
+// drops the autosave once `map` is saved in full, an autosave of another map is left for the next launch
+pub fn clear_autosave(map: &str) {
+    if Recovery::load(AUTOSAVE_PATH).is_ok_and(|recovery| recovery.is_some_and(|recovery| recovery.map == map)) {
+        if let Err(err) = fs::remove_file(AUTOSAVE_PATH) {
+            println!("ERROR: Could not remove '{}': {}", AUTOSAVE_PATH, err);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Autosave {
+    timer: f32,
+    // of the map text last autosaved or found unchanged, so an untouched map isn't diffed again
+    last: Option<u64>
+}
+
+impl Autosave {
+    pub fn new() -> Autosave {
+        Autosave::default()
+    }
+
+    pub fn update(&mut self, dt: f32, map: &str, board: &Board) {
+        self.timer += dt;
+        if self.timer < AUTOSAVE_INTERVAL { return }
+        self.timer = 0.0;
+
+        let text = board_to_text(board);
+        let hash = text_hash(&text);
+        if self.last == Some(hash) { return }
+        self.last = Some(hash);
+
+        let base = fs::read_to_string(map).unwrap_or_default();
+        if base == text {
+            clear_autosave(map);
+            return
+        }
+        match fs::write(AUTOSAVE_PATH, Recovery::diff(map, &base, &text).to_text()) {
+            Ok(()) => println!("INFO: Autosaved {}", map),
+            Err(err) => println!("ERROR: Could not write '{}': {}", AUTOSAVE_PATH, err)
+        }
+    }
+
+    // the map was saved in full, so it starts over from there
+    pub fn saved(&mut self, map: &str, board: &Board) {
+        self.timer = 0.0;
+        self.last = Some(text_hash(&board_to_text(board)));
+        clear_autosave(map);
+    }
+}
+
+const PROMPT_ITEMS: [&str; 2] = ["Restore", "Discard"];
+
+// asks on launch what to do with an autosave left behind, restoring opens it in the editor
+pub struct RecoveryPrompt {
+    map: String,
+    selected: usize,
+    nav: MenuNav,
+    // the command to restore went down, the prompt closes on the next frame
+    done: bool
+}
+
+impl RecoveryPrompt {
+    pub fn new(recovery: &Recovery) -> RecoveryPrompt {
+        RecoveryPrompt { map: recovery.map.clone(), selected: 0, nav: MenuNav::default(), done: false }
+    }
+}
+
+impl GameState for RecoveryPrompt {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread) -> Transition {
+        if self.done { return Transition::Pop }
+
+        let input = self.nav.read(rl);
+        self.selected = select(input, self.selected, PROMPT_ITEMS.len());
+        self.nav.focus("Unsaved changes", PROMPT_ITEMS[self.selected].to_string());
+        if input != MenuInput::Accept { return Transition::None }
+
+        self.done = true;
+        match PROMPT_ITEMS[self.selected] {
+            "Restore" => Transition::Command("editor_recover".to_string()),
+            _ => {
+                if let Err(err) = fs::remove_file(AUTOSAVE_PATH) {
+                    println!("ERROR: Could not remove '{}': {}", AUTOSAVE_PATH, err);
+                }
+                Transition::Pop
+            }
+        }
+    }
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn render(&mut self, d: &mut RaylibDrawHandle, _thread: &RaylibThread, _alpha: f32) {
+        d.draw_rectangle_v(Vector2::zero(), window_size(), Color::BLACK.alpha(0.7));
+        let lines: Vec<String> = PROMPT_ITEMS.iter().map(|item| match *item {
+            "Restore" => format!("Restore {}", self.map),
+            item => item.to_string()
+        }).collect();
+        render_lines(d, "UNSAVED CHANGES", &lines, self.selected);
+    }
+
+    fn is_overlay(&self) -> bool { true }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "3 2\nt:0 . t:0\nt:0 t:0 t:0\nexit 1 0\n";
+
+    // the edited map back from the autosave file written for it
+    fn round_trip(text: &str) -> String {
+        let recovery = Recovery::from_text(&Recovery::diff("maps/a.txt", BASE, text).to_text()).unwrap();
+        assert_eq!(recovery.map, "maps/a.txt");
+        recovery.apply(BASE).unwrap()
+    }
+
+    #[test]
+    fn a_changed_map_is_restored() {
+        let text = "3 2\nt:0 t:1 t:0\nt:0 t:0 t:0\nexit 1 0 maps/b.txt\n";
+        assert_eq!(Recovery::diff("maps/a.txt", BASE, text).lines.len(), 2);
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn a_shortened_map_is_restored() {
+        let text = "3 2\nt:0 . t:0\nt:0 t:0 t:0\n";
+        assert!(Recovery::diff("maps/a.txt", BASE, text).lines.is_empty());
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn a_lengthened_map_is_restored() {
+        let text = "3 2\nt:0 . t:0\nt:0 t:0 t:0\nexit 1 0\nsecret 2 1\nsign 0 1 hello there\n";
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn a_changed_base_is_refused() {
+        let recovery = Recovery::diff("maps/a.txt", BASE, "3 2\n");
+        assert!(recovery.apply("3 2\nt:0 t:0 t:0\n").unwrap_err().contains("changed since"));
+    }
+
+    #[test]
+    fn a_malformed_header_is_refused() {
+        let text = Recovery::diff("maps/a.txt", BASE, "3 2\n").to_text();
+        for broken in [
+            text.replacen("autosave", "autosaved", 1),
+            text.replacen(&format!("autosave {}", AUTOSAVE_VERSION), "autosave 99", 1),
+            text.replacen("base ", "base zz", 1),
+            text.replacen("lines 1", "lines one", 1),
+            "0 t:0\n".to_string(),
+            String::new()
+        ] {
+            assert!(Recovery::from_text(&broken).is_err(), "{}", broken);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, ffi::{GamepadButton, KeyboardKey}, math::{Rectangle, Vector2}, texture::Texture2D, RaylibHandle};
+
+// everything the 3d view draws with, so the raycaster only needs something that can do these.
+// raylib's draw handle is the only window backend, textures are still raylib's own and the hud,
+// menus and minimap still draw on the handle directly, so there is no web build yet
+pub trait Backend {
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color);
+    fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color);
+    fn draw_rectangle_v(&mut self, pos: Vector2, size: Vector2, color: Color);
+    fn draw_texture_pro(&mut self, texture: &Texture2D, source_rec: Rectangle, dest_rec: Rectangle, origin: Vector2, rotation: f32, tint: Color);
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, size: i32, color: Color);
+    fn draw_fps(&mut self, x: i32, y: i32);
+    fn measure_text(&self, text: &str, size: i32) -> i32;
+    // seconds the last frame took
+    fn get_frame_time(&self) -> f32;
+}
+
+impl Backend for RaylibDrawHandle<'_> {
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        RaylibDraw::draw_rectangle(self, x, y, width, height, color)
+    }
+
+    fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color) {
+        RaylibDraw::draw_rectangle_rec(self, rec, color)
+    }
+
+    fn draw_rectangle_v(&mut self, pos: Vector2, size: Vector2, color: Color) {
+        RaylibDraw::draw_rectangle_v(self, pos, size, color)
+    }
+
+    fn draw_texture_pro(&mut self, texture: &Texture2D, source_rec: Rectangle, dest_rec: Rectangle, origin: Vector2, rotation: f32, tint: Color) {
+        RaylibDraw::draw_texture_pro(self, texture, source_rec, dest_rec, origin, rotation, tint)
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, size: i32, color: Color) {
+        RaylibDraw::draw_text(self, text, x, y, size, color)
+    }
+
+    fn draw_fps(&mut self, x: i32, y: i32) {
+        RaylibDraw::draw_fps(self, x, y)
+    }
+
+    fn measure_text(&self, text: &str, size: i32) -> i32 {
+        (**self).measure_text(text, size)
+    }
+
+    fn get_frame_time(&self) -> f32 {
+        (**self).get_frame_time()
+    }
+}
+
+const GAMEPAD: i32 = 0;
+
+// the keys and buttons the game's actions are bound to, read from the window by raylib's handle
+pub trait Input {
+    fn is_key_down(&self, key: KeyboardKey) -> bool;
+    fn is_key_pressed(&self, key: KeyboardKey) -> bool;
+    // false while no gamepad is plugged in
+    fn is_button_down(&self, button: GamepadButton) -> bool;
+    fn is_button_pressed(&self, button: GamepadButton) -> bool;
+}
+
+impl Input for RaylibHandle {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        RaylibHandle::is_key_down(self, key)
+    }
+
+    fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+        RaylibHandle::is_key_pressed(self, key)
+    }
+
+    fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.is_gamepad_available(GAMEPAD) && self.is_gamepad_button_down(GAMEPAD, button)
+    }
+
+    fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.is_gamepad_available(GAMEPAD) && self.is_gamepad_button_pressed(GAMEPAD, button)
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs;
+
+use raylib::math::Vector2;
+
+use crate::{board::Board, enemy::Enemy, path::NavGraph, visibility::VisibilityCache};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    SeesTarget,
+    TargetWithin(f32),
+    HealthBelow(f32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Chase,
+    Flee,
+    ReturnHome,
+    Wait
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    // runs children in order until one of them doesn't succeed
+    Sequence(Vec<Node>),
+    // runs children in order until one of them doesn't fail
+    Selector(Vec<Node>),
+    Condition(Condition),
+    Action(Action)
+}
+
+// what a tree can look at while it's ticked for one enemy
+pub struct Context<'a> {
+    pub id: usize,
+    pub board: &'a Board,
+    pub nav: &'a NavGraph,
+    pub visibility: &'a mut VisibilityCache,
+    pub target: Vector2,
+    pub dt: f32
+}
+
+// the tree is evaluated from the root on every tick, so it reacts as soon as a condition changes
+pub struct BehaviorTree {
+    pub name: String,
+    pub root: Node
+}
+
+impl Node {
+    pub fn tick(&self, enemy: &mut Enemy, ctx: &mut Context) -> Status {
+        match self {
+            Node::Sequence(children) => {
+                for child in children {
+                    let status = child.tick(enemy, ctx);
+                    if status != Status::Success { return status }
+                }
+                Status::Success
+            },
+            Node::Selector(children) => {
+                for child in children {
+                    let status = child.tick(enemy, ctx);
+                    if status != Status::Failure { return status }
+                }
+                Status::Failure
+            },
+            Node::Condition(condition) => {
+                let passed = match condition {
+                    Condition::SeesTarget => ctx.visibility.visible(ctx.id, ctx.board, enemy.pos(), ctx.target),
+                    Condition::TargetWithin(distance) => enemy.pos().distance_to(ctx.target) <= *distance,
+                    Condition::HealthBelow(health) => enemy.health < *health
+                };
+
+                if passed { Status::Success }
+                else { Status::Failure }
+            },
+            Node::Action(action) => match action {
+                Action::Chase => {
+                    let sees_target = ctx.visibility.visible(ctx.id, ctx.board, enemy.pos(), ctx.target);
+                    enemy.chase(ctx.board, ctx.nav, sees_target, ctx.target, ctx.dt)
+                },
+                Action::Flee => enemy.flee(ctx.board, ctx.target, ctx.dt),
+                Action::ReturnHome => enemy.return_home(ctx.board, ctx.nav, ctx.dt),
+                Action::Wait => Status::Success
+            }
+        }
+    }
+}
+
+// one node per line, children indented deeper than their parent, "#" starts a comment:
+//   selector
+//     sequence
+//       health_below 30
+//       flee
+//     sequence
+//       sees_target
+//       chase
+//     return_home
+fn parse_node(lines: &[(usize, usize, Vec<&str>)], i: &mut usize) -> Result<Node, String> {
+    let (line, indent, words) = &lines[*i];
+    *i += 1;
+
+    let number = |name: &str| -> Result<f32, String> {
+        let value = words.get(1).ok_or(format!("Line {}: '{}' needs a value", line, name))?;
+        value.parse::<f32>().map_err(|err| format!("Line {}: Invalid value '{}': {}", line, value, err))
+    };
+
+    let node = match words[0] {
+        "sequence" | "selector" => {
+            let mut children = vec![];
+            while *i < lines.len() && lines[*i].1 > *indent {
+                children.push(parse_node(lines, i)?);
+            }
+            if children.is_empty() { return Err(format!("Line {}: '{}' has no children", line, words[0])) }
+
+            if words[0] == "sequence" { Node::Sequence(children) }
+            else { Node::Selector(children) }
+        },
+        "sees_target" => Node::Condition(Condition::SeesTarget),
+        "target_within" => Node::Condition(Condition::TargetWithin(number("target_within")?)),
+        "health_below" => Node::Condition(Condition::HealthBelow(number("health_below")?)),
+        "chase" => Node::Action(Action::Chase),
+        "flee" => Node::Action(Action::Flee),
+        "return_home" => Node::Action(Action::ReturnHome),
+        "wait" => Node::Action(Action::Wait),
+        other => return Err(format!("Line {}: Unknown node '{}'", line, other))
+    };
+
+    if *i < lines.len() && lines[*i].1 > *indent {
+        return Err(format!("Line {}: '{}' can't have children", lines[*i].0, words[0]))
+    }
+
+    Ok(node)
+}
+
+pub fn parse_tree(name: &str, text: &str) -> Result<BehaviorTree, String> {
+    let lines: Vec<_> = text.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default()))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i, line.len() - line.trim_start().len(), line.split_whitespace().collect::<Vec<_>>()))
+        .collect();
+
+    if lines.is_empty() { return Err("Empty behavior tree".to_string()) }
+
+    let mut i = 0;
+    let root = parse_node(&lines, &mut i)?;
+    if i < lines.len() {
+        return Err(format!("Line {}: Only one root node is allowed", lines[i].0))
+    }
+
+    Ok(BehaviorTree { name: name.to_string(), root })
+}
+
+pub fn load_tree(path: &str) -> Result<BehaviorTree, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Could not load behavior '{}': {}", path, err))?;
+    parse_tree(path, &text).map_err(|err| format!("{}: {}", path, err))
+}
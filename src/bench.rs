@@ -0,0 +1,155 @@
+use std::{f32::consts::TAU, ops::Add};
+
+use raylib::math::Vector2;
+
+use crate::{board::{Board, Cell}, math::Lcg, player::Player, sprite::Sprite, texture::TextureId};
+
+// pathological boards used to measure worst case frame times
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BenchMap {
+    Sightlines,
+    Glass,
+    Entities
+}
+
+impl BenchMap {
+    pub fn from_name(name: &str) -> Result<BenchMap, String> {
+        match name {
+            "sightlines" => Ok(BenchMap::Sightlines),
+            "glass" => Ok(BenchMap::Glass),
+            "entities" => Ok(BenchMap::Entities),
+            _ => Err(format!("Unknown bench map '{}', expected sightlines, glass or entities", name))
+        }
+    }
+}
+
+// how long `--benchmark` flies the camera, and the first seconds left out of the
+// frame times while everything warms up
+pub const BENCHMARK_TIME: f32 = 30.0;
+const WARMUP_TIME: f32 = 1.0;
+// seconds for one lap of the flythrough around the board
+const LAP_TIME: f32 = 10.0;
+
+// frame times of the flythrough, the camera only depends on the time so every run sees the same path
+#[derive(Default)]
+pub struct Benchmark {
+    pub time: f32,
+    frame_times: Vec<f32>
+}
+
+impl Benchmark {
+    pub fn new() -> Benchmark {
+        Benchmark::default()
+    }
+
+    // false once the benchmark is over
+    pub fn update(&mut self, frame_time: f32) -> bool {
+        self.time += frame_time;
+        if self.time > WARMUP_TIME {
+            self.frame_times.push(frame_time);
+        }
+        self.time < BENCHMARK_TIME
+    }
+
+    pub fn report(&self) -> String {
+        report(&self.frame_times)
+    }
+}
+
+// min, average and 99th percentile of frame times in seconds, in milliseconds
+pub fn report(frame_times: &[f32]) -> String {
+    if frame_times.is_empty() {
+        return "no frames measured".to_string()
+    }
+
+    let mut times = frame_times.to_vec();
+    times.sort_by(|a, b| a.total_cmp(b));
+    let average = times.iter().sum::<f32>() / times.len() as f32;
+    let p99 = times[((times.len() - 1) as f32 * 0.99).round() as usize];
+    format!("{} frames, min {:.2} ms, avg {:.2} ms, 99th percentile {:.2} ms", times.len(), times[0] * 1000.0, average * 1000.0, p99 * 1000.0)
+}
+
+// laps around the middle of the board swinging in and out, looking ahead and sweeping side to side.
+// it flies through whatever is in the way, collisions would make the path depend on the frame rate
+pub fn flythrough(board: &Board, time: f32) -> (Vector2, Vector2) {
+    let center = Vector2::new(board.cols as f32, board.rows as f32).scale_by(0.5);
+    let angle = time / LAP_TIME * TAU;
+    let radius = f32::max(f32::min(center.x, center.y) - 1.5, 0.5) * (0.7 + 0.3 * f32::sin(angle * 3.0));
+
+    let pos = center.add(Vector2::new(f32::cos(angle), f32::sin(angle)).scale_by(radius));
+    let dir = Vector2::new(-f32::sin(angle), f32::cos(angle)).rotated(0.6 * f32::sin(time));
+    (pos, dir)
+}
+
+pub struct BenchScene {
+    pub board: Board,
+    pub player: Player,
+    pub sprites: Vec<Sprite>
+}
+
+pub fn generate(map: BenchMap, wall: TextureId, glass: TextureId, sprite: TextureId) -> BenchScene {
+    match map {
+        BenchMap::Sightlines => sightlines(wall),
+        BenchMap::Glass => glass_layers(wall, glass),
+        BenchMap::Entities => entities(sprite)
+    }
+}
+
+fn border(board: &mut Board, wall: TextureId) {
+    for x in 0..board.cols {
+        board.set(x, 0, Cell::TEXTURE(wall));
+        board.set(x, board.rows - 1, Cell::TEXTURE(wall));
+    }
+
+    for y in 0..board.rows {
+        board.set(0, y, Cell::TEXTURE(wall));
+        board.set(board.cols - 1, y, Cell::TEXTURE(wall));
+    }
+}
+
+// an open hall with sparse pillars, so every ray travels up to the far plane
+fn sightlines(wall: TextureId) -> BenchScene {
+    let mut board = Board::new(64, 64);
+    border(&mut board, wall);
+
+    for y in (4..board.rows - 4).step_by(8) {
+        for x in (4..board.cols - 4).step_by(8) {
+            board.set(x, y, Cell::TEXTURE(wall));
+        }
+    }
+
+    let mut player = Player::new(1.5, 1.5);
+    player.dir = Vector2::new(1.0, 1.0).normalized();
+    BenchScene { board, player, sprites: vec![] }
+}
+
+// rows of glass in front of the player, so every ray stacks many translucent hits
+fn glass_layers(wall: TextureId, glass: TextureId) -> BenchScene {
+    let mut board = Board::new(32, 32);
+    border(&mut board, wall);
+
+    for x in (3..board.cols - 1).step_by(2) {
+        for y in 1..board.rows - 1 {
+            board.set(x, y, Cell::TranslucentTexture(glass));
+        }
+    }
+
+    let player = Player::new(1.5, board.rows as f32 / 2.0);
+    BenchScene { board, player, sprites: vec![] }
+}
+
+// thousands of sprites scattered around an empty board
+fn entities(sprite: TextureId) -> BenchScene {
+    let board = Board::new(32, 32);
+    // the same seed every run so every run measures the same scene
+    let mut rng = Lcg::new(0x5eed);
+
+    let sprites = (0..5000).map(|_| {
+        let x = 1.0 + rng.next_float() * (board.cols - 2) as f32;
+        let y = 1.0 + rng.next_float() * (board.rows - 2) as f32;
+        Sprite::new(x, y, sprite)
+    }).collect();
+
+    let player = Player::new(board.cols as f32 / 2.0, board.rows as f32 / 2.0);
+    BenchScene { board, player, sprites }
+}
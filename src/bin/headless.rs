@@ -0,0 +1,61 @@
+use std::{process::exit, time::Instant};
+
+use raycast::{bench::{self, flythrough, BenchMap}, game::Game, raycast::{cast_columns, Traversal, View}, texture::TextureRegistry, NUM_OF_RAYS, SIM_DT};
+
+const DEFAULT_FRAMES: usize = 1000;
+
+// the value after `flag`, if it was given
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next()
+        }
+    }
+
+    None
+}
+
+// casts the rays of "--frames <n>" frames of the benchmark flythrough over "--bench-map <name>"
+// without opening a window, so the raycaster can be measured on a machine without a display.
+// "--dda" walks the grid with DDA instead. textures are only named, nothing is drawn
+fn main() {
+    let frames = match arg_value("--frames").map(|frames| frames.parse::<usize>()) {
+        Some(Ok(frames)) => frames,
+        Some(Err(err)) => {
+            println!("ERROR: Invalid frame count: {}", err);
+            exit(1);
+        },
+        None => DEFAULT_FRAMES
+    };
+    let map = match BenchMap::from_name(&arg_value("--bench-map").unwrap_or("sightlines".to_string())) {
+        Ok(map) => map,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    let traversal = if std::env::args().any(|arg| arg == "--dda") { Traversal::Dda }
+    else { Traversal::Straight };
+
+    let mut textures = TextureRegistry::new();
+    let (wall, glass, sprite) = (textures.register("wall", "wall"), textures.register("glass", "glass"), textures.register("sprite", "sprite"));
+    let scene = bench::generate(map, wall, glass, sprite);
+    // only for the view settings the game starts with
+    let game = Game::new(scene.board, textures, scene.player);
+
+    let mut frame_times = vec![];
+    let mut hits = 0;
+    for frame in 0..frames {
+        let (pos, dir) = flythrough(&game.board, frame as f32 * SIM_DT);
+        let view = View { pos, dir, ..View::of(&game) };
+
+        let start = Instant::now();
+        let columns = cast_columns(traversal, view, &game.board, NUM_OF_RAYS);
+        frame_times.push(start.elapsed().as_secs_f32());
+        hits += columns.iter().map(|cells| cells.len() - 1).sum::<usize>();
+    }
+
+    println!("INFO: Cast {} rays a frame over {:?} with {:?}: {}", NUM_OF_RAYS, map, traversal, bench::report(&frame_times));
+    println!("INFO: {:.1} hits a ray on average", hits as f32 / (frames * NUM_OF_RAYS).max(1) as f32);
+}
@@ -0,0 +1,407 @@
+use raylib::ffi::{GamepadButton, KeyboardKey};
+use serde::Deserialize;
+
+use crate::backend::Input;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Forward,
+    Backward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Use,
+    Fire,
+    Jump,
+    Crouch,
+    Sprint,
+    Quicksave,
+    Quickload
+}
+
+pub const ACTIONS: [Action; 13] = [
+    Action::Forward, Action::Backward, Action::StrafeLeft, Action::StrafeRight, Action::TurnLeft, Action::TurnRight,
+    Action::Use, Action::Fire, Action::Jump, Action::Crouch, Action::Sprint, Action::Quicksave, Action::Quickload
+];
+
+impl Action {
+    // the same names the config uses
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Backward => "backward",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::Use => "use",
+            Action::Fire => "fire",
+            Action::Jump => "jump",
+            Action::Crouch => "crouch",
+            Action::Sprint => "sprint",
+            Action::Quicksave => "quicksave",
+            Action::Quickload => "quickload"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|action| action.name() == name).copied()
+    }
+}
+
+// a key on the keyboard, a button on the first gamepad, or either key of a modifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Key(KeyboardKey),
+    Button(GamepadButton),
+    Modifier(KeyboardKey, KeyboardKey)
+}
+
+// a control, held together with another one for chords like "SHIFT+E"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub modifier: Option<Control>,
+    pub control: Control
+}
+
+// names keys are written with in the config, the raylib name without "KEY_"
+const KEY_NAMES: &[(&str, KeyboardKey)] = &[
+    ("APOSTROPHE", KeyboardKey::KEY_APOSTROPHE), ("COMMA", KeyboardKey::KEY_COMMA), ("MINUS", KeyboardKey::KEY_MINUS),
+    ("PERIOD", KeyboardKey::KEY_PERIOD), ("SLASH", KeyboardKey::KEY_SLASH), ("ZERO", KeyboardKey::KEY_ZERO),
+    ("ONE", KeyboardKey::KEY_ONE), ("TWO", KeyboardKey::KEY_TWO), ("THREE", KeyboardKey::KEY_THREE),
+    ("FOUR", KeyboardKey::KEY_FOUR), ("FIVE", KeyboardKey::KEY_FIVE), ("SIX", KeyboardKey::KEY_SIX),
+    ("SEVEN", KeyboardKey::KEY_SEVEN), ("EIGHT", KeyboardKey::KEY_EIGHT), ("NINE", KeyboardKey::KEY_NINE),
+    ("SEMICOLON", KeyboardKey::KEY_SEMICOLON), ("EQUAL", KeyboardKey::KEY_EQUAL), ("A", KeyboardKey::KEY_A),
+    ("B", KeyboardKey::KEY_B), ("C", KeyboardKey::KEY_C), ("D", KeyboardKey::KEY_D), ("E", KeyboardKey::KEY_E),
+    ("F", KeyboardKey::KEY_F), ("G", KeyboardKey::KEY_G), ("H", KeyboardKey::KEY_H), ("I", KeyboardKey::KEY_I),
+    ("J", KeyboardKey::KEY_J), ("K", KeyboardKey::KEY_K), ("L", KeyboardKey::KEY_L), ("M", KeyboardKey::KEY_M),
+    ("N", KeyboardKey::KEY_N), ("O", KeyboardKey::KEY_O), ("P", KeyboardKey::KEY_P), ("Q", KeyboardKey::KEY_Q),
+    ("R", KeyboardKey::KEY_R), ("S", KeyboardKey::KEY_S), ("T", KeyboardKey::KEY_T), ("U", KeyboardKey::KEY_U),
+    ("V", KeyboardKey::KEY_V), ("W", KeyboardKey::KEY_W), ("X", KeyboardKey::KEY_X), ("Y", KeyboardKey::KEY_Y),
+    ("Z", KeyboardKey::KEY_Z), ("LEFT_BRACKET", KeyboardKey::KEY_LEFT_BRACKET),
+    ("BACKSLASH", KeyboardKey::KEY_BACKSLASH), ("RIGHT_BRACKET", KeyboardKey::KEY_RIGHT_BRACKET),
+    ("GRAVE", KeyboardKey::KEY_GRAVE), ("SPACE", KeyboardKey::KEY_SPACE), ("ESCAPE", KeyboardKey::KEY_ESCAPE),
+    ("ENTER", KeyboardKey::KEY_ENTER), ("TAB", KeyboardKey::KEY_TAB), ("BACKSPACE", KeyboardKey::KEY_BACKSPACE),
+    ("INSERT", KeyboardKey::KEY_INSERT), ("DELETE", KeyboardKey::KEY_DELETE), ("RIGHT", KeyboardKey::KEY_RIGHT),
+    ("LEFT", KeyboardKey::KEY_LEFT), ("DOWN", KeyboardKey::KEY_DOWN), ("UP", KeyboardKey::KEY_UP),
+    ("PAGE_UP", KeyboardKey::KEY_PAGE_UP), ("PAGE_DOWN", KeyboardKey::KEY_PAGE_DOWN), ("HOME", KeyboardKey::KEY_HOME),
+    ("END", KeyboardKey::KEY_END), ("F1", KeyboardKey::KEY_F1), ("F2", KeyboardKey::KEY_F2),
+    ("F3", KeyboardKey::KEY_F3), ("F4", KeyboardKey::KEY_F4), ("F5", KeyboardKey::KEY_F5), ("F6", KeyboardKey::KEY_F6),
+    ("F7", KeyboardKey::KEY_F7), ("F8", KeyboardKey::KEY_F8), ("F9", KeyboardKey::KEY_F9),
+    ("F10", KeyboardKey::KEY_F10), ("F11", KeyboardKey::KEY_F11), ("F12", KeyboardKey::KEY_F12),
+    ("LEFT_SHIFT", KeyboardKey::KEY_LEFT_SHIFT), ("LEFT_CONTROL", KeyboardKey::KEY_LEFT_CONTROL),
+    ("LEFT_ALT", KeyboardKey::KEY_LEFT_ALT), ("RIGHT_SHIFT", KeyboardKey::KEY_RIGHT_SHIFT),
+    ("RIGHT_CONTROL", KeyboardKey::KEY_RIGHT_CONTROL), ("RIGHT_ALT", KeyboardKey::KEY_RIGHT_ALT),
+    ("KP_0", KeyboardKey::KEY_KP_0), ("KP_1", KeyboardKey::KEY_KP_1), ("KP_2", KeyboardKey::KEY_KP_2),
+    ("KP_3", KeyboardKey::KEY_KP_3), ("KP_4", KeyboardKey::KEY_KP_4), ("KP_5", KeyboardKey::KEY_KP_5),
+    ("KP_6", KeyboardKey::KEY_KP_6), ("KP_7", KeyboardKey::KEY_KP_7), ("KP_8", KeyboardKey::KEY_KP_8),
+    ("KP_9", KeyboardKey::KEY_KP_9), ("KP_DECIMAL", KeyboardKey::KEY_KP_DECIMAL),
+    ("KP_DIVIDE", KeyboardKey::KEY_KP_DIVIDE), ("KP_MULTIPLY", KeyboardKey::KEY_KP_MULTIPLY),
+    ("KP_SUBTRACT", KeyboardKey::KEY_KP_SUBTRACT), ("KP_ADD", KeyboardKey::KEY_KP_ADD),
+    ("KP_ENTER", KeyboardKey::KEY_KP_ENTER)
+];
+
+// gamepad buttons by the position on an xbox pad, with a "PAD_" prefix so they can't be taken for keys
+const BUTTON_NAMES: &[(&str, GamepadButton)] = &[
+    ("PAD_A", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN), ("PAD_B", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+    ("PAD_X", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT), ("PAD_Y", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP),
+    ("PAD_UP", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP), ("PAD_DOWN", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    ("PAD_LEFT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT), ("PAD_RIGHT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    ("PAD_LB", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1), ("PAD_RB", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+    ("PAD_LT", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2), ("PAD_RT", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2),
+    ("PAD_BACK", GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT), ("PAD_START", GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT),
+    ("PAD_LS", GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB), ("PAD_RS", GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB)
+];
+
+// modifiers that don't care which side of the keyboard they are pressed on
+const MODIFIER_NAMES: &[(&str, KeyboardKey, KeyboardKey)] = &[
+    ("SHIFT", KeyboardKey::KEY_LEFT_SHIFT, KeyboardKey::KEY_RIGHT_SHIFT),
+    ("CONTROL", KeyboardKey::KEY_LEFT_CONTROL, KeyboardKey::KEY_RIGHT_CONTROL),
+    ("ALT", KeyboardKey::KEY_LEFT_ALT, KeyboardKey::KEY_RIGHT_ALT)
+];
+
+impl Control {
+    pub fn from_name(name: &str) -> Option<Control> {
+        KEY_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, key)| Control::Key(*key))
+            .or_else(|| BUTTON_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, button)| Control::Button(*button)))
+            .or_else(|| MODIFIER_NAMES.iter().find(|(n, _, _)| n.eq_ignore_ascii_case(name)).map(|(_, left, right)| Control::Modifier(*left, *right)))
+    }
+
+    pub fn name(self) -> &'static str {
+        let name = match self {
+            Control::Key(key) => KEY_NAMES.iter().find(|(_, k)| *k == key).map(|(n, _)| *n),
+            Control::Button(button) => BUTTON_NAMES.iter().find(|(_, b)| *b == button).map(|(n, _)| *n),
+            Control::Modifier(left, _) => MODIFIER_NAMES.iter().find(|(_, l, _)| *l == left).map(|(n, _, _)| *n)
+        };
+        name.unwrap_or("?")
+    }
+
+    fn is_down(self, input: &dyn Input) -> bool {
+        match self {
+            Control::Key(key) => input.is_key_down(key),
+            Control::Button(button) => input.is_button_down(button),
+            Control::Modifier(left, right) => input.is_key_down(left) || input.is_key_down(right)
+        }
+    }
+
+    fn is_pressed(self, input: &dyn Input) -> bool {
+        match self {
+            Control::Key(key) => input.is_key_pressed(key),
+            Control::Button(button) => input.is_button_pressed(button),
+            Control::Modifier(left, right) => input.is_key_pressed(left) || input.is_key_pressed(right)
+        }
+    }
+}
+
+impl Binding {
+    pub fn new(control: Control) -> Binding {
+        Binding { modifier: None, control }
+    }
+
+    // a single control, or "<modifier>+<control>"
+    pub fn from_name(name: &str) -> Option<Binding> {
+        match name.split_once('+') {
+            Some((modifier, control)) => Some(Binding { modifier: Some(Control::from_name(modifier)?), control: Control::from_name(control)? }),
+            None => Control::from_name(name).map(Binding::new)
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self.modifier {
+            Some(modifier) => format!("{}+{}", modifier.name(), self.control.name()),
+            None => self.control.name().to_string()
+        }
+    }
+
+    fn modifier_down(self, input: &dyn Input) -> bool {
+        self.modifier.is_none_or(|modifier| modifier.is_down(input))
+    }
+}
+
+// the "[bindings]" table of the config, every action takes a list of key and button names
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BindingsConfig {
+    pub forward: Vec<String>,
+    pub backward: Vec<String>,
+    pub strafe_left: Vec<String>,
+    pub strafe_right: Vec<String>,
+    pub turn_left: Vec<String>,
+    pub turn_right: Vec<String>,
+    pub r#use: Vec<String>,
+    pub fire: Vec<String>,
+    pub jump: Vec<String>,
+    pub crouch: Vec<String>,
+    pub sprint: Vec<String>,
+    pub quicksave: Vec<String>,
+    pub quickload: Vec<String>
+}
+
+impl Default for BindingsConfig {
+    fn default() -> BindingsConfig {
+        let keys = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        BindingsConfig {
+            forward: keys(&["W", "PAD_UP"]),
+            backward: keys(&["S", "PAD_DOWN"]),
+            strafe_left: keys(&["A", "PAD_LEFT"]),
+            strafe_right: keys(&["D", "PAD_RIGHT"]),
+            turn_left: keys(&["LEFT", "PAD_LB"]),
+            turn_right: keys(&["RIGHT", "PAD_RB"]),
+            r#use: keys(&["E", "PAD_X"]),
+            fire: keys(&["F", "PAD_RT"]),
+            jump: keys(&["SPACE", "PAD_A"]),
+            crouch: keys(&["LEFT_CONTROL", "RIGHT_CONTROL", "PAD_B"]),
+            sprint: keys(&["LEFT_SHIFT", "PAD_LS"]),
+            quicksave: keys(&["F5"]),
+            quickload: keys(&["F9"])
+        }
+    }
+}
+
+impl BindingsConfig {
+    fn actions(&self) -> [(Action, &[String]); 13] {
+        [
+            (Action::Forward, &self.forward),
+            (Action::Backward, &self.backward),
+            (Action::StrafeLeft, &self.strafe_left),
+            (Action::StrafeRight, &self.strafe_right),
+            (Action::TurnLeft, &self.turn_left),
+            (Action::TurnRight, &self.turn_right),
+            (Action::Use, &self.r#use),
+            (Action::Fire, &self.fire),
+            (Action::Jump, &self.jump),
+            (Action::Crouch, &self.crouch),
+            (Action::Sprint, &self.sprint),
+            (Action::Quicksave, &self.quicksave),
+            (Action::Quickload, &self.quickload)
+        ]
+    }
+
+    // one message per name that isn't a key or a button
+    pub fn validate(&self) -> Vec<String> {
+        self.actions().iter()
+            .flat_map(|(action, names)| names.iter().filter(|name| Binding::from_name(name).is_none()).map(move |name| format!("Unknown key '{}' bound to {}", name, action.name())))
+            .collect()
+    }
+}
+
+// the keys and buttons that trigger each action, any of them will do
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    bindings: Vec<(Action, Vec<Binding>)>
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::from_config(&BindingsConfig::default())
+    }
+}
+
+impl Bindings {
+    // unknown names are left out, `BindingsConfig::validate` reports them
+    pub fn from_config(config: &BindingsConfig) -> Bindings {
+        Bindings {
+            bindings: config.actions().iter().map(|(action, names)| (*action, names.iter().filter_map(|name| Binding::from_name(name)).collect())).collect()
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.iter().find(|(a, _)| *a == action).map_or(&[], |(_, bindings)| bindings)
+    }
+
+    fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        let i = match self.bindings.iter().position(|(a, _)| *a == action) {
+            Some(i) => i,
+            None => {
+                self.bindings.push((action, vec![]));
+                self.bindings.len() - 1
+            }
+        };
+        &mut self.bindings[i].1
+    }
+
+    // binding the same key twice changes nothing
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.get_mut(action);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    // returns whether the action had that binding
+    pub fn unbind(&mut self, action: Action, binding: Binding) -> bool {
+        let bindings = self.get_mut(action);
+        let len = bindings.len();
+        bindings.retain(|b| *b != binding);
+        bindings.len() != len
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.get_mut(action).clear();
+    }
+
+    // with "SHIFT+E" bound, shift and E together mean only the chord and not E on its own
+    fn is_shadowed(&self, binding: Binding, input: &dyn Input) -> bool {
+        binding.modifier.is_none() && self.bindings.iter()
+            .flat_map(|(_, bindings)| bindings)
+            .any(|chord| chord.control == binding.control && chord.modifier.is_some_and(|modifier| modifier.is_down(input)))
+    }
+}
+
+// what the game asks instead of looking at keys directly
+pub struct InputState<'a> {
+    input: &'a dyn Input,
+    bindings: &'a Bindings
+}
+
+impl<'a> InputState<'a> {
+    pub fn new(input: &'a dyn Input, bindings: &'a Bindings) -> InputState<'a> {
+        InputState { input, bindings }
+    }
+
+    // a chord's modifier has to be held first, its control is what makes it pressed
+    pub fn is_down(&self, action: Action) -> bool {
+        self.bindings.get(action).iter()
+            .any(|binding| binding.modifier_down(self.input) && binding.control.is_down(self.input) && !self.bindings.is_shadowed(*binding, self.input))
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.bindings.get(action).iter()
+            .any(|binding| binding.modifier_down(self.input) && binding.control.is_pressed(self.input) && !self.bindings.is_shadowed(*binding, self.input))
+    }
+}
+
+// longest gap between two presses that still makes a double tap, in seconds
+pub const DOUBLE_TAP_TIME: f64 = 0.3;
+
+// an action pressed twice in quick succession, like tapping forward twice to sprint
+#[derive(Default)]
+pub struct DoubleTap {
+    last_press: Option<f64>,
+    active: bool
+}
+
+impl DoubleTap {
+    pub fn new() -> DoubleTap {
+        DoubleTap::default()
+    }
+
+    // stays on from the second press until the action is let go
+    pub fn update(&mut self, pressed: bool, down: bool, time: f64) -> bool {
+        if pressed {
+            self.active = self.last_press.is_some_and(|last| time - last <= DOUBLE_TAP_TIME);
+            self.last_press = if self.active { None } else { Some(time) };
+        }
+        if !down {
+            self.active = false;
+        }
+
+        self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // keys held down this frame, all of them pressed this frame too
+    struct Held(Vec<KeyboardKey>);
+
+    impl Input for Held {
+        fn is_key_down(&self, key: KeyboardKey) -> bool {
+            self.0.contains(&key)
+        }
+
+        fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+            self.0.contains(&key)
+        }
+
+        fn is_button_down(&self, _button: GamepadButton) -> bool {
+            false
+        }
+
+        fn is_button_pressed(&self, _button: GamepadButton) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn a_chord_held_down_shadows_its_key_on_its_own() {
+        let mut bindings = Bindings::from_config(&BindingsConfig::default());
+        bindings.clear(Action::Use);
+        bindings.clear(Action::Quicksave);
+        bindings.bind(Action::Use, Binding::from_name("E").unwrap());
+        bindings.bind(Action::Quicksave, Binding::from_name("SHIFT+E").unwrap());
+
+        let e = Held(vec![KeyboardKey::KEY_E]);
+        let state = InputState::new(&e, &bindings);
+        assert!(state.is_pressed(Action::Use));
+        assert!(!state.is_pressed(Action::Quicksave));
+
+        let shift_e = Held(vec![KeyboardKey::KEY_LEFT_SHIFT, KeyboardKey::KEY_E]);
+        let state = InputState::new(&shift_e, &bindings);
+        assert!(!state.is_down(Action::Use));
+        assert!(state.is_pressed(Action::Quicksave));
+    }
+}
@@ -0,0 +1,524 @@
+use std::{collections::{BTreeMap, BTreeSet, HashSet}, sync::atomic::{AtomicU64, Ordering}};
+
+use raylib::{color::Color, math::Vector2};
+
+use crate::{cellkind::{cell_kind, CellKindId, RayResponse}, decal::Decal, door::Door, grade::ColorGrade, item::Key, spawn::Spawn, teleporter::Teleporter, trigger::{Requirement, Trigger}, texture::{AnimationId, FlatId, TextureId, TextureRegistry}};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    EMPTY,
+    COLOR(Color),
+    TEXTURE(TextureId),
+    TranslucentTexture(TextureId),
+    Door(TextureId),
+    AnimatedTexture(AnimationId),
+    Segment(TextureId, Segment),
+    // reflects the rays that hit it back into the board
+    Mirror,
+    // a kind of cell from the registry, drawn with the texture
+    Custom(CellKindId, TextureId)
+}
+
+// the glass of a mirror, tinting its reflection and filling it once the rays are out of bounces
+pub const MIRROR_COLOR: Color = Color::new(170, 200, 230, 255);
+
+// a wall that doesn't fill its cell: along one edge or across the cell at 45 degrees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment {
+    North,
+    East,
+    South,
+    West,
+    Slash,
+    Backslash
+}
+
+pub const SEGMENTS: [Segment; 6] = [Segment::North, Segment::East, Segment::South, Segment::West, Segment::Slash, Segment::Backslash];
+
+// edge walls sit just inside their cell, so a hit always maps back to the cell it belongs to
+const SEGMENT_INSET: f32 = 1e-3;
+
+impl Segment {
+    pub fn name(self) -> &'static str {
+        match self {
+            Segment::North => "n",
+            Segment::East => "e",
+            Segment::South => "s",
+            Segment::West => "w",
+            Segment::Slash => "/",
+            Segment::Backslash => "\\"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Segment> {
+        SEGMENTS.iter().copied().find(|segment| segment.name() == name)
+    }
+
+    // the same wall after turning its cell a quarter clockwise
+    pub fn turned(self) -> Segment {
+        match self {
+            Segment::North => Segment::East,
+            Segment::East => Segment::South,
+            Segment::South => Segment::West,
+            Segment::West => Segment::North,
+            Segment::Slash => Segment::Backslash,
+            Segment::Backslash => Segment::Slash
+        }
+    }
+
+    // both ends of the wall inside the cell at `x`, `y`
+    pub fn endpoints(self, x: usize, y: usize) -> (Vector2, Vector2) {
+        let (x, y) = (x as f32, y as f32);
+        match self {
+            Segment::North => (Vector2::new(x, y + SEGMENT_INSET), Vector2::new(x + 1.0, y + SEGMENT_INSET)),
+            Segment::East => (Vector2::new(x + 1.0 - SEGMENT_INSET, y), Vector2::new(x + 1.0 - SEGMENT_INSET, y + 1.0)),
+            Segment::South => (Vector2::new(x, y + 1.0 - SEGMENT_INSET), Vector2::new(x + 1.0, y + 1.0 - SEGMENT_INSET)),
+            Segment::West => (Vector2::new(x + SEGMENT_INSET, y), Vector2::new(x + SEGMENT_INSET, y + 1.0)),
+            Segment::Slash => (Vector2::new(x, y + 1.0), Vector2::new(x + 1.0, y)),
+            Segment::Backslash => (Vector2::new(x, y), Vector2::new(x + 1.0, y + 1.0))
+        }
+    }
+
+    // texture coordinate of a point on the wall
+    pub fn u(self, point: Vector2) -> f32 {
+        match self {
+            Segment::East | Segment::West => point.y - f32::floor(point.y),
+            _ => point.x - f32::floor(point.x)
+        }
+    }
+}
+
+// walls with the `base` texture pick one of it and `others` by their cell, so long runs of one wall don't repeat
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureVariants {
+    pub base: TextureId,
+    pub others: Vec<TextureId>,
+    // the same seed always picks the same texture for a cell
+    pub seed: u32
+}
+
+impl TextureVariants {
+    pub fn pick(&self, x: usize, y: usize) -> TextureId {
+        // integer hash of the cell, well enough mixed that neighbours don't follow a pattern
+        let mut hash = (x as u32).wrapping_mul(0x8da6b343) ^ (y as u32).wrapping_mul(0xd8163841) ^ self.seed.wrapping_mul(0xcb1ab31f);
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x7feb352d);
+        hash ^= hash >> 15;
+
+        match hash as usize % (self.others.len() + 1) {
+            0 => self.base,
+            i => self.others[i - 1]
+        }
+    }
+}
+
+// revisions are unique across every board, so swapping in another board is also seen as a change
+fn next_revision() -> u64 {
+    static REVISION: AtomicU64 = AtomicU64::new(0);
+    REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Clone)]
+pub struct Board {
+    pub rows: usize,
+    pub cols: usize,
+    pub spawns: Vec<Spawn>,
+    pub grade: Option<ColorGrade>,
+    pub variants: Vec<TextureVariants>,
+    // textures stretched over this many cells along a wall instead of repeating in every cell
+    pub spans: Vec<(TextureId, usize)>,
+    // posters and signs the map puts on its walls
+    pub decals: Vec<Decal>,
+    // the image drawn all around the horizon above the cells without a ceiling
+    pub sky: Option<String>,
+    // live entities the map wants at most, under the global budget (see budget.rs)
+    pub budget: Option<usize>,
+    cells: Vec<Cell>,
+    floors: Vec<Option<FlatId>>,
+    ceilings: Vec<Option<FlatId>>,
+    // solid floor and ceiling colors set by the map, shown instead of the flats
+    floor_colors: Vec<Option<Color>>,
+    ceiling_colors: Vec<Option<Color>>,
+    heights: Vec<f32>,
+    // ordered, so doors update and report in the same order on every run
+    doors: BTreeMap<(usize, usize), Door>,
+    teleporters: BTreeMap<(usize, usize), Teleporter>,
+    // stepping on one of these finishes the map, going on to the named map instead of the next one if it has one
+    exits: BTreeMap<(usize, usize), Option<String>>,
+    // cells that count as found secrets once the player steps on them
+    secrets: BTreeSet<(usize, usize)>,
+    // doors that stay shut until the named map is finished
+    gates: BTreeMap<(usize, usize), String>,
+    triggers: BTreeMap<(usize, usize), Trigger>,
+    // doors that only open for the player holding the key
+    locks: BTreeMap<(usize, usize), Key>,
+    requirements: BTreeMap<(usize, usize), Vec<Requirement>>,
+    // textures moving across their walls, in texture sizes per second
+    scrolls: BTreeMap<(usize, usize), Vector2>,
+    revision: u64,
+    // bumped only when a cell itself changes, not for its looks or doors moving
+    layout: u64
+}
+
+impl Board {
+    pub fn new(rows: usize, cols: usize) -> Board {
+        Board {
+            rows, cols,
+            spawns: vec![],
+            grade: None,
+            variants: vec![],
+            spans: vec![],
+            decals: vec![],
+            sky: None,
+            budget: None,
+            cells: vec![Cell::EMPTY; rows * cols],
+            floors: vec![None; rows * cols],
+            ceilings: vec![None; rows * cols],
+            floor_colors: vec![None; rows * cols],
+            ceiling_colors: vec![None; rows * cols],
+            heights: vec![1.0; rows * cols],
+            doors: BTreeMap::new(),
+            teleporters: BTreeMap::new(),
+            exits: BTreeMap::new(),
+            secrets: BTreeSet::new(),
+            gates: BTreeMap::new(),
+            triggers: BTreeMap::new(),
+            locks: BTreeMap::new(),
+            requirements: BTreeMap::new(),
+            scrolls: BTreeMap::new(),
+            revision: next_revision(),
+            layout: next_revision()
+        }
+    }
+
+    pub fn at(&self, x: usize, y: usize) -> &Cell {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        &self.cells[y * self.cols + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.cells[y * self.cols + x] = cell;
+        self.revision = next_revision();
+        self.layout = self.revision;
+
+        if let Cell::Door(_) = cell {
+            self.doors.entry((x, y)).or_default();
+        } else {
+            self.doors.remove(&(x, y));
+        }
+    }
+
+    pub fn door(&self, x: usize, y: usize) -> Option<&Door> {
+        self.doors.get(&(x, y))
+    }
+
+    pub fn door_mut(&mut self, x: usize, y: usize) -> Option<&mut Door> {
+        self.revision = next_revision();
+        self.doors.get_mut(&(x, y))
+    }
+
+    // advances every door animation, returning the doors that finished opening
+    pub fn update_doors(&mut self, dt: f32) -> Vec<(usize, usize)> {
+        let mut opened = vec![];
+        for (pos, door) in self.doors.iter_mut() {
+            if door.is_moving() {
+                self.revision = next_revision();
+            }
+
+            if door.update(dt) {
+                opened.push(*pos);
+            }
+        }
+
+        opened
+    }
+
+    pub fn teleporter(&self, x: usize, y: usize) -> Option<&Teleporter> {
+        self.teleporters.get(&(x, y))
+    }
+
+    pub fn teleporters(&self) -> impl Iterator<Item = (&(usize, usize), &Teleporter)> {
+        self.teleporters.iter()
+    }
+
+    pub fn set_teleporter(&mut self, x: usize, y: usize, teleporter: Option<Teleporter>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        match teleporter {
+            Some(teleporter) => self.teleporters.insert((x, y), teleporter),
+            None => self.teleporters.remove(&(x, y))
+        };
+        self.revision = next_revision();
+    }
+
+    // teleporters both ways, coming back undoes the turn
+    pub fn link_teleporters(&mut self, a: (usize, usize), b: (usize, usize), turn: f32) {
+        self.set_teleporter(a.0, a.1, Some(Teleporter::new(b, turn)));
+        self.set_teleporter(b.0, b.1, Some(Teleporter::new(a, -turn)));
+    }
+
+    pub fn trigger(&self, x: usize, y: usize) -> Option<&Trigger> {
+        self.triggers.get(&(x, y))
+    }
+
+    pub fn trigger_mut(&mut self, x: usize, y: usize) -> Option<&mut Trigger> {
+        self.triggers.get_mut(&(x, y))
+    }
+
+    pub fn triggers(&self) -> impl Iterator<Item = (&(usize, usize), &Trigger)> {
+        self.triggers.iter()
+    }
+
+    pub fn set_trigger(&mut self, x: usize, y: usize, trigger: Option<Trigger>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        match trigger {
+            Some(trigger) => self.triggers.insert((x, y), trigger),
+            None => self.triggers.remove(&(x, y))
+        };
+        self.revision = next_revision();
+    }
+
+    pub fn lock(&self, x: usize, y: usize) -> Option<Key> {
+        self.locks.get(&(x, y)).copied()
+    }
+
+    pub fn locks(&self) -> impl Iterator<Item = (&(usize, usize), &Key)> {
+        self.locks.iter()
+    }
+
+    pub fn set_lock(&mut self, x: usize, y: usize, key: Option<Key>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        match key {
+            Some(key) => self.locks.insert((x, y), key),
+            None => self.locks.remove(&(x, y))
+        };
+        self.revision = next_revision();
+    }
+
+    // every one of them has to hold, none is always met
+    pub fn requirements_at(&self, x: usize, y: usize) -> &[Requirement] {
+        self.requirements.get(&(x, y)).map_or(&[], |requirements| requirements.as_slice())
+    }
+
+    pub fn requirements(&self) -> impl Iterator<Item = (&(usize, usize), &Vec<Requirement>)> {
+        self.requirements.iter()
+    }
+
+    pub fn add_requirement(&mut self, x: usize, y: usize, requirement: Requirement) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.requirements.entry((x, y)).or_default().push(requirement);
+        self.revision = next_revision();
+    }
+
+    pub fn scroll_at(&self, x: usize, y: usize) -> Vector2 {
+        self.scrolls.get(&(x, y)).copied().unwrap_or(Vector2::zero())
+    }
+
+    pub fn scrolls(&self) -> impl Iterator<Item = (&(usize, usize), &Vector2)> {
+        self.scrolls.iter()
+    }
+
+    pub fn set_scroll(&mut self, x: usize, y: usize, speed: Vector2) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        if speed == Vector2::zero() { self.scrolls.remove(&(x, y)); }
+        else { self.scrolls.insert((x, y), speed); }
+        self.revision = next_revision();
+    }
+
+    pub fn is_exit(&self, x: usize, y: usize) -> bool {
+        self.exits.contains_key(&(x, y))
+    }
+
+    pub fn exit_target(&self, x: usize, y: usize) -> Option<&str> {
+        self.exits.get(&(x, y)).and_then(|target| target.as_deref())
+    }
+
+    pub fn exits(&self) -> impl Iterator<Item = &(usize, usize)> {
+        self.exits.keys()
+    }
+
+    pub fn set_exit(&mut self, x: usize, y: usize, exit: bool) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        if exit { self.exits.insert((x, y), None); }
+        else { self.exits.remove(&(x, y)); }
+        self.revision = next_revision();
+    }
+
+    // an exit that leads to a map of its own, like the entrances of a hub
+    pub fn link_exit(&mut self, x: usize, y: usize, target: &str) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.exits.insert((x, y), Some(target.to_string()));
+        self.revision = next_revision();
+    }
+
+    pub fn is_secret(&self, x: usize, y: usize) -> bool {
+        self.secrets.contains(&(x, y))
+    }
+
+    pub fn secrets(&self) -> impl Iterator<Item = &(usize, usize)> {
+        self.secrets.iter()
+    }
+
+    pub fn set_secret(&mut self, x: usize, y: usize, secret: bool) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        if secret { self.secrets.insert((x, y)); }
+        else { self.secrets.remove(&(x, y)); }
+        self.revision = next_revision();
+    }
+
+    pub fn gate(&self, x: usize, y: usize) -> Option<&str> {
+        self.gates.get(&(x, y)).map(|level| level.as_str())
+    }
+
+    pub fn gates(&self) -> impl Iterator<Item = (&(usize, usize), &String)> {
+        self.gates.iter()
+    }
+
+    pub fn set_gate(&mut self, x: usize, y: usize, level: Option<String>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        match level {
+            Some(level) => self.gates.insert((x, y), level),
+            None => self.gates.remove(&(x, y))
+        };
+        self.revision = next_revision();
+    }
+
+    // bumped on every change that can affect what the rays see
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // bumped when walls and doors are put down or taken away, what paths through the board depend on
+    pub fn layout_revision(&self) -> u64 {
+        self.layout
+    }
+
+    // the texture a wall at x,y is drawn with, one of its variants if it has any
+    pub fn texture_at(&self, id: TextureId, x: usize, y: usize) -> TextureId {
+        match self.variants.iter().find(|variants| variants.base == id) {
+            Some(variants) => variants.pick(x, y),
+            None => id
+        }
+    }
+
+    pub fn span_of(&self, id: TextureId) -> usize {
+        self.spans.iter().find(|(texture, _)| *texture == id).map_or(1, |(_, span)| *span)
+    }
+
+    // every texture the walls can be drawn with, variants and animation frames too
+    pub fn textures(&self, registry: &TextureRegistry) -> HashSet<TextureId> {
+        let mut textures = HashSet::new();
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                match self.at(x, y) {
+                    Cell::TEXTURE(id) | Cell::TranslucentTexture(id) | Cell::Door(id) | Cell::Segment(id, _) | Cell::Custom(_, id) => { textures.insert(*id); },
+                    Cell::AnimatedTexture(id) => textures.extend(registry.animation(*id).frames.iter().map(|(texture, _)| *texture)),
+                    Cell::EMPTY | Cell::COLOR(_) | Cell::Mirror => {}
+                }
+            }
+        }
+        let others: Vec<TextureId> = self.variants.iter()
+            .filter(|variants| textures.contains(&variants.base))
+            .flat_map(|variants| variants.others.iter().copied())
+            .collect();
+        textures.extend(others);
+        textures.extend(self.decals.iter().map(|decal| decal.texture));
+        textures
+    }
+
+    pub fn is_passable(&self, x: usize, y: usize) -> bool {
+        match self.at(x, y) {
+            Cell::EMPTY => true,
+            Cell::Door(_) => self.door(x, y).is_some_and(|door| door.is_passable()),
+            Cell::Custom(kind, _) => cell_kind(*kind).is_some_and(|kind| kind.passable),
+            _ => false
+        }
+    }
+
+    // translucent cells and fully open doors can be seen through
+    pub fn blocks_sight(&self, x: usize, y: usize) -> bool {
+        match self.at(x, y) {
+            Cell::EMPTY | Cell::TranslucentTexture(_) => false,
+            Cell::Door(_) => !self.is_passable(x, y),
+            Cell::Custom(kind, _) => cell_kind(*kind).is_none_or(|kind| (kind.on_ray_hit)(self, (x, y)) == RayResponse::Stop),
+            _ => true
+        }
+    }
+
+    pub fn floor_at(&self, x: usize, y: usize) -> Option<FlatId> {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.floors[y * self.cols + x]
+    }
+
+    pub fn set_floor(&mut self, x: usize, y: usize, floor: Option<FlatId>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.floors[y * self.cols + x] = floor;
+        self.revision = next_revision();
+    }
+
+    pub fn ceiling_at(&self, x: usize, y: usize) -> Option<FlatId> {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.ceilings[y * self.cols + x]
+    }
+
+    pub fn set_ceiling(&mut self, x: usize, y: usize, ceiling: Option<FlatId>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.ceilings[y * self.cols + x] = ceiling;
+        self.revision = next_revision();
+    }
+
+    pub fn floor_color_at(&self, x: usize, y: usize) -> Option<Color> {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.floor_colors[y * self.cols + x]
+    }
+
+    pub fn set_floor_color(&mut self, x: usize, y: usize, color: Option<Color>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.floor_colors[y * self.cols + x] = color;
+        self.revision = next_revision();
+    }
+
+    pub fn ceiling_color_at(&self, x: usize, y: usize) -> Option<Color> {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.ceiling_colors[y * self.cols + x]
+    }
+
+    pub fn set_ceiling_color(&mut self, x: usize, y: usize, color: Option<Color>) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.ceiling_colors[y * self.cols + x] = color;
+        self.revision = next_revision();
+    }
+
+    // wall height relative to the standard one, short walls can be seen over
+    pub fn height_at(&self, x: usize, y: usize) -> f32 {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.heights[y * self.cols + x]
+    }
+
+    pub fn set_height(&mut self, x: usize, y: usize, height: f32) {
+        assert!(x < self.cols, "X out of bounds");
+        assert!(y < self.rows, "Y out of bounds");
+        self.heights[y * self.cols + x] = height;
+        self.revision = next_revision();
+    }
+}
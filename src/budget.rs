@@ -0,0 +1,59 @@
+use crate::{game::Game, particle::MAX_PARTICLES};
+
+// live entities at once before effects are cut back, 0 is no limit
+pub const DEFAULT_ENTITY_BUDGET: usize = 1500;
+
+// the cvar's cap on live entities, a map's own "budget" line can only bring it down
+pub struct EntityBudget {
+    pub max: usize,
+    // effects are being dropped, so it is only reported when that starts
+    throttled: bool
+}
+
+impl Default for EntityBudget {
+    fn default() -> EntityBudget {
+        EntityBudget::new()
+    }
+}
+
+impl EntityBudget {
+    pub fn new() -> EntityBudget {
+        EntityBudget { max: DEFAULT_ENTITY_BUDGET, throttled: false }
+    }
+
+    // None without a limit
+    pub fn limit(&self, map: Option<usize>) -> Option<usize> {
+        match (self.max, map) {
+            (0, map) => map,
+            (max, Some(map)) => Some(max.min(map)),
+            (max, None) => Some(max)
+        }
+    }
+}
+
+// enemies, pickups and sprites are what the map is played with and are never dropped, so effects make
+// the room: the oldest particles go first, then the oldest bullet holes. new particles get what is left
+// until the next step, an impact over the budget throws out old ones instead of adding to them
+pub fn enforce_budget(game: &mut Game) {
+    let Some(max) = game.budget.limit(game.board.budget) else {
+        game.particles.limit = MAX_PARTICLES;
+        return
+    };
+
+    let fixed = game.enemies.len() + game.pickups.len() + game.sprites.len() + game.remote_players.len();
+    let room = max.saturating_sub(fixed);
+    let holes = game.bullet_holes.len().min(room);
+    let particles = room - holes;
+
+    let dropped_holes = game.bullet_holes.len() - holes;
+    game.bullet_holes.drain(..dropped_holes);
+    let dropped_particles = game.particles.particles.len().saturating_sub(particles);
+    game.particles.particles.drain(..dropped_particles);
+    game.particles.limit = particles.min(MAX_PARTICLES);
+
+    let throttled = dropped_holes > 0 || dropped_particles > 0;
+    if throttled && !game.budget.throttled {
+        println!("INFO: Over the budget of {} entities, dropping effects", max);
+    }
+    game.budget.throttled = throttled;
+}
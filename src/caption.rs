@@ -0,0 +1,136 @@
+use std::ops::Sub;
+
+use raylib::{color::Color, math::{Rectangle, Vector2}};
+
+use crate::{audio::{SoundEffect, HEARING_DISTANCE}, backend::Backend, game::Game, panel::draw_panel, player::Player, ui, window_size};
+
+// seconds a caption stays up, hearing the same sound again before then starts it over
+const CAPTION_TIME: f32 = 2.5;
+// seconds it takes to fade out at the end
+const CAPTION_FADE: f32 = 0.5;
+// more at once and the oldest make room
+const MAX_CAPTIONS: usize = 4;
+
+// where a sound came from, as the player is facing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Ahead,
+    Left,
+    Right,
+    Behind
+}
+
+impl Direction {
+    pub fn of(listener: &Player, pos: Vector2) -> Direction {
+        let rel = pos.sub(listener.pos);
+        let ahead = rel.dot(listener.dir);
+        let right = rel.dot(listener.dir.rotated(std::f32::consts::FRAC_PI_2));
+        if ahead.abs() >= right.abs() {
+            if ahead >= 0.0 { Direction::Ahead }
+            else { Direction::Behind }
+        }
+        else if right > 0.0 { Direction::Right }
+        else { Direction::Left }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Ahead => "ahead",
+            Direction::Left => "to the left",
+            Direction::Right => "to the right",
+            Direction::Behind => "behind"
+        }
+    }
+
+    // the default font has no arrows
+    fn arrow(self) -> &'static str {
+        match self {
+            Direction::Ahead => "^",
+            Direction::Left => "<",
+            Direction::Right => ">",
+            Direction::Behind => "v"
+        }
+    }
+}
+
+impl SoundEffect {
+    // None for the sounds the player makes, which they already know about
+    pub fn caption(self) -> Option<&'static str> {
+        match self {
+            SoundEffect::DoorOpen => Some("Door opens"),
+            SoundEffect::Heartbeat => Some("Heartbeat"),
+            SoundEffect::Secret => Some("Secret found"),
+            SoundEffect::Footstep | SoundEffect::WeaponFire => None
+        }
+    }
+}
+
+pub struct Caption {
+    pub effect: SoundEffect,
+    // None for sounds coming from the player
+    pub direction: Option<Direction>,
+    pub time: f32
+}
+
+impl Caption {
+    pub fn text(&self) -> String {
+        let text = self.effect.caption().unwrap_or_default();
+        match self.direction {
+            Some(direction) => format!("{} {}", text, direction.name()),
+            None => text.to_string()
+        }
+    }
+}
+
+// the sounds played lately written out, for playing without sound
+#[derive(Default)]
+pub struct Captions {
+    pub enabled: bool,
+    pub shown: Vec<Caption>
+}
+
+impl Captions {
+    pub fn new() -> Captions {
+        Captions::default()
+    }
+
+    // sounds too far away to be heard get no caption either
+    pub fn hear(&mut self, effect: SoundEffect, pos: Option<Vector2>, listener: &Player) {
+        if !self.enabled || effect.caption().is_none() { return }
+        if pos.is_some_and(|pos| pos.distance_to(listener.pos) > HEARING_DISTANCE) { return }
+
+        let direction = pos.map(|pos| Direction::of(listener, pos));
+        self.shown.retain(|caption| caption.effect != effect || caption.direction != direction);
+        if self.shown.len() >= MAX_CAPTIONS {
+            self.shown.remove(0);
+        }
+        self.shown.push(Caption { effect, direction, time: CAPTION_TIME });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for caption in self.shown.iter_mut() {
+            caption.time -= dt;
+        }
+        self.shown.retain(|caption| caption.time > 0.0);
+    }
+}
+
+// newest at the bottom, above where signs show up, with an arrow on the side the sound came from
+pub fn render_captions(d: &mut impl Backend, game: &Game) {
+    let (size, margin) = (ui(20), ui(6));
+    let bottom = window_size().y as i32 * 3 / 4 - ui(24) - 4 * margin;
+
+    for (i, caption) in game.captions.shown.iter().rev().enumerate() {
+        let alpha = (caption.time / CAPTION_FADE).clamp(0.0, 1.0);
+        let text = match caption.direction {
+            Some(Direction::Left) => format!("{} {}", Direction::Left.arrow(), caption.text()),
+            Some(direction) => format!("{} {}", caption.text(), direction.arrow()),
+            None => caption.text()
+        };
+
+        let width = d.measure_text(&text, size);
+        let (x, y) = ((window_size().x as i32 - width) / 2, bottom - i as i32 * (size + 3 * margin));
+        draw_panel(d, game.panel.as_deref(), Rectangle::new((x - margin) as f32, (y - margin) as f32, (width + 2 * margin) as f32, (size + 2 * margin) as f32), Color::BLACK.alpha(0.7 * alpha));
+        d.draw_text(&text, x, y, size, Color::WHITE.alpha(alpha));
+    }
+}
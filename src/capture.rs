@@ -0,0 +1,151 @@
+use std::{collections::VecDeque, fs, path::Path, sync::mpsc::{channel, sync_channel, Receiver, SyncSender, TrySendError}, thread::{self, JoinHandle}, time::{SystemTime, UNIX_EPOCH}};
+
+use raylib::{drawing::RaylibDrawHandle, texture::Image, RaylibThread};
+
+pub const SCREENSHOTS_DIR: &str = "./screenshots";
+
+// clip frames are kept at this rate and scaled down by this much, a full rate full size buffer would take gigabytes
+const CLIP_FPS: f64 = 15.0;
+const CLIP_SCALE: i32 = 2;
+// screenshots and clips waiting to be encoded, more than this and new ones are dropped instead of
+// making the frame wait
+const QUEUE_SIZE: usize = 4;
+
+// what the encoder thread writes out, a png or a folder of them
+enum Job {
+    Screenshot(Image, String),
+    Clip(Vec<Image>, String)
+}
+
+// an image is only pixels in memory, encoding it touches nothing of the window or the gpu,
+// so it can be written out on another thread
+unsafe impl Send for Job {}
+
+impl Job {
+    fn run(self) -> Result<String, String> {
+        match self {
+            Job::Screenshot(image, path) => {
+                fs::create_dir_all(SCREENSHOTS_DIR).map_err(|err| format!("Could not create '{}': {}", SCREENSHOTS_DIR, err))?;
+                export(&image, &path)?;
+                Ok(path)
+            },
+            Job::Clip(frames, dir) => {
+                fs::create_dir_all(&dir).map_err(|err| format!("Could not create '{}': {}", dir, err))?;
+                for (i, frame) in frames.iter().enumerate() {
+                    export(frame, &format!("{}/frame-{:04}.png", dir, i))?;
+                }
+                Ok(dir)
+            }
+        }
+    }
+}
+
+// screenshots and the rolling buffer of recent frames that a clip is saved from.
+// the frames are grabbed on the render thread and encoded on a thread of their own
+pub struct Capture {
+    // seconds of frames kept for a clip, 0 keeps none
+    pub clip_seconds: f32,
+    frames: VecDeque<Image>,
+    last_frame: f64,
+    // None once dropped, so the encoder knows no more jobs are coming
+    jobs: Option<SyncSender<Job>>,
+    done: Receiver<Result<String, String>>,
+    encoder: Option<JoinHandle<()>>
+}
+
+// milliseconds since the unix epoch, they sort in the order they were taken
+pub fn timestamp() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis())
+}
+
+// the screen as drawn so far this frame, so it has to run after everything is rendered
+fn grab(d: &mut RaylibDrawHandle, thread: &RaylibThread) -> Image {
+    // draws still waiting in raylib's batch aren't on the screen yet
+    unsafe { raylib::ffi::rlDrawRenderBatchActive() };
+    d.load_image_from_screen(thread)
+}
+
+fn export(image: &Image, path: &str) -> Result<(), String> {
+    image.export_image(path);
+    if Path::new(path).exists() { Ok(()) }
+    else { Err(format!("Could not write '{}'", path)) }
+}
+
+impl Capture {
+    pub fn new(clip_seconds: f32) -> Capture {
+        let (jobs, queue) = sync_channel::<Job>(QUEUE_SIZE);
+        let (finished, done) = channel();
+        let encoder = thread::spawn(move || {
+            for job in queue {
+                // nobody is left to tell once the game closed
+                let _ = finished.send(job.run());
+            }
+        });
+
+        Capture { clip_seconds, frames: VecDeque::new(), last_frame: 0.0, jobs: Some(jobs), done, encoder: Some(encoder) }
+    }
+
+    fn queue(&self, job: Job) -> Result<(), String> {
+        let Some(jobs) = &self.jobs else { return Err("The encoder is gone".to_string()) };
+        match jobs.try_send(job) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err("Still saving earlier captures, try again in a moment".to_string()),
+            Err(TrySendError::Disconnected(_)) => Err("The encoder is gone".to_string())
+        }
+    }
+
+    // the path the screenshot is going to be saved to once it is encoded
+    pub fn screenshot(&self, d: &mut RaylibDrawHandle, thread: &RaylibThread) -> Result<String, String> {
+        let path = format!("{}/screenshot-{}.png", SCREENSHOTS_DIR, timestamp());
+        self.queue(Job::Screenshot(grab(d, thread), path.clone()))?;
+        Ok(path)
+    }
+
+    // the paths of the screenshots and clips written out since the last call, or why they couldn't be
+    pub fn finished(&self) -> Vec<Result<String, String>> {
+        self.done.try_iter().collect()
+    }
+
+    // keeps a frame now and then, dropping the ones older than the clip length
+    pub fn record(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, time: f64) {
+        if self.clip_seconds <= 0.0 || time - self.last_frame < 1.0 / CLIP_FPS { return }
+        self.last_frame = time;
+
+        let mut frame = grab(d, thread);
+        frame.resize(frame.width / CLIP_SCALE, frame.height / CLIP_SCALE);
+        self.frames.push_back(frame);
+        while self.frames.len() as f64 > self.clip_seconds as f64 * CLIP_FPS {
+            self.frames.pop_front();
+        }
+    }
+
+    // one numbered png per frame in a folder of its own, returning the folder it is going to be saved to.
+    // the frames are kept when the encoder is too busy for them
+    pub fn save_clip(&mut self) -> Result<String, String> {
+        if self.frames.is_empty() {
+            return Err("No frames recorded, set clip_seconds in the engine config to keep some".to_string())
+        }
+
+        let dir = format!("{}/clip-{}", SCREENSHOTS_DIR, timestamp());
+        let Some(jobs) = &self.jobs else { return Err("The encoder is gone".to_string()) };
+        let frames: Vec<Image> = self.frames.drain(..).collect();
+        match jobs.try_send(Job::Clip(frames, dir.clone())) {
+            Ok(()) => Ok(dir),
+            Err(TrySendError::Full(Job::Clip(frames, _))) => {
+                self.frames = frames.into();
+                Err("Still saving earlier captures, try again in a moment".to_string())
+            },
+            Err(_) => Err("The encoder is gone".to_string())
+        }
+    }
+}
+
+// what is still queued is written out before the game closes
+impl Drop for Capture {
+    fn drop(&mut self) {
+        self.jobs = None;
+        if let Some(encoder) = self.encoder.take() {
+            let _ = encoder.join();
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::{LazyLock, RwLock};
+
+use crate::{board::Board, game::Game};
+
+// what a ray does on entering a cell of a registered kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RayResponse {
+    // goes on as if the cell was empty
+    Pass,
+    // hits it and goes on, the way it does through glass
+    PassThrough,
+    // stops at it like at a wall
+    Stop
+}
+
+// how the renderer draws a cell of a registered kind once a ray hit it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderHint {
+    // its texture fills the wall
+    Solid,
+    // its texture is drawn over what is behind it
+    Translucent,
+    // nothing is drawn, only the floor and ceiling show
+    Hidden
+}
+
+// what a cell does to the game when the player steps into it or uses it
+pub type CellAction = fn(&mut Game, (usize, usize));
+
+// a kind of cell added by registering it rather than by a variant of its own, everything the rays,
+// the renderer and the player do with it is asked of these. a cell of the kind is `Cell::Custom`,
+// saved in maps as "k:<name>:<texture>"
+#[derive(Clone, Copy)]
+pub struct CellKind {
+    pub name: &'static str,
+    // whether the player and enemies can walk into it
+    pub passable: bool,
+    pub on_ray_hit: fn(&Board, (usize, usize)) -> RayResponse,
+    pub render_hint: fn(&Board, (usize, usize)) -> RenderHint,
+    pub on_player_enter: Option<CellAction>,
+    // when the player uses it, None lets the use reach past it if it is passable
+    pub on_interact: Option<CellAction>
+}
+
+impl CellKind {
+    // a wall of its own kind, drawn with its texture, that nothing happens to
+    pub fn new(name: &'static str) -> CellKind {
+        CellKind {
+            name,
+            passable: false,
+            on_ray_hit: |_, _| RayResponse::Stop,
+            render_hint: |_, _| RenderHint::Solid,
+            on_player_enter: None,
+            on_interact: None
+        }
+    }
+}
+
+// index of a registered kind, only meaningful to the registry it was registered in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CellKindId(u16);
+
+impl CellKindId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// the ones the game comes with first, so maps can use them before anything else is registered
+static CELL_KINDS: LazyLock<RwLock<Vec<CellKind>>> = LazyLock::new(|| RwLock::new(builtin_cell_kinds()));
+
+// registering a name again replaces the kind, cells already of it keep their id
+pub fn register_cell_kind(kind: CellKind) -> CellKindId {
+    let mut kinds = CELL_KINDS.write().unwrap_or_else(|err| err.into_inner());
+    if let Some(index) = kinds.iter().position(|other| other.name == kind.name) {
+        kinds[index] = kind;
+        return CellKindId(index as u16)
+    }
+
+    kinds.push(kind);
+    CellKindId(kinds.len() as u16 - 1)
+}
+
+pub fn cell_kind(id: CellKindId) -> Option<CellKind> {
+    CELL_KINDS.read().unwrap_or_else(|err| err.into_inner()).get(id.index()).copied()
+}
+
+pub fn cell_kind_by_name(name: &str) -> Option<CellKindId> {
+    CELL_KINDS.read().unwrap_or_else(|err| err.into_inner()).iter().position(|kind| kind.name == name).map(|index| CellKindId(index as u16))
+}
+
+// hurts the player by this much every time they step into one
+const HAZARD_DAMAGE: f32 = 10.0;
+
+fn builtin_cell_kinds() -> Vec<CellKind> {
+    vec![
+        // a field that can be seen and walked through, hurting whoever does
+        CellKind {
+            passable: true,
+            on_ray_hit: |_, _| RayResponse::PassThrough,
+            render_hint: |_, _| RenderHint::Translucent,
+            on_player_enter: Some(|game, _| game.player.damage(HAZARD_DAMAGE)),
+            ..CellKind::new("hazard")
+        }
+    ]
+}
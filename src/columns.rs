@@ -0,0 +1,82 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+// distances, texture coordinates and shades closer than this count as the same
+pub const DIFF_TOLERANCE: f32 = 1e-3;
+
+// one cell a ray hit on its way through a column, in the order they were cast
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColumnHit {
+    pub cell: String,
+    pub x: usize,
+    pub y: usize,
+    pub side: String,
+    // along the view direction, what the wall height is worked out from
+    pub distance: f32,
+    // across the texture from 0 to 1, after spans and doors sliding open
+    pub u: f32,
+    // light, exposure and the side shade together, before the fog
+    pub shade: f32
+}
+
+// what the renderer worked out for every column of a frame, so two builds of it can be compared
+// column by column without looking at pixels
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColumnCapture {
+    pub map: String,
+    pub pos: (f32, f32),
+    pub dir: (f32, f32),
+    pub columns: Vec<Vec<ColumnHit>>
+}
+
+pub fn save_capture(path: &str, capture: &ColumnCapture) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(capture).map_err(|err| format!("Could not serialize capture: {}", err))?;
+    fs::write(path, json).map_err(|err| format!("Could not write '{}': {}", path, err))
+}
+
+pub fn load_capture(path: &str) -> Result<ColumnCapture, String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("Could not read '{}': {}", path, err))?;
+    serde_json::from_str(&json).map_err(|err| format!("Could not parse '{}': {}", path, err))
+}
+
+fn differs(a: f32, b: f32, tolerance: f32) -> bool {
+    !((a - b).abs() <= tolerance || (a.is_infinite() && a == b))
+}
+
+// one line for every way a hit of `b` differs from the same hit of `a`
+fn diff_hit(a: &ColumnHit, b: &ColumnHit, tolerance: f32) -> Vec<String> {
+    let mut diffs = vec![];
+    if a.cell != b.cell { diffs.push(format!("cell {} -> {}", a.cell, b.cell)) }
+    if (a.x, a.y) != (b.x, b.y) { diffs.push(format!("at {},{} -> {},{}", a.x, a.y, b.x, b.y)) }
+    if a.side != b.side { diffs.push(format!("side {} -> {}", a.side, b.side)) }
+    if differs(a.distance, b.distance, tolerance) { diffs.push(format!("distance {} -> {}", a.distance, b.distance)) }
+    if differs(a.u, b.u, tolerance) { diffs.push(format!("u {} -> {}", a.u, b.u)) }
+    if differs(a.shade, b.shade, tolerance) { diffs.push(format!("shade {} -> {}", a.shade, b.shade)) }
+    diffs
+}
+
+// one line for every column that doesn't match, empty when the captures agree
+pub fn diff_captures(a: &ColumnCapture, b: &ColumnCapture, tolerance: f32) -> Vec<String> {
+    let mut lines = vec![];
+    if a.pos != b.pos || a.dir != b.dir {
+        lines.push(format!("view {:?} {:?} -> {:?} {:?}, the captures weren't taken from the same place", a.pos, a.dir, b.pos, b.dir));
+    }
+    if a.columns.len() != b.columns.len() {
+        lines.push(format!("{} columns -> {}", a.columns.len(), b.columns.len()));
+    }
+
+    for (x, (a, b)) in a.columns.iter().zip(b.columns.iter()).enumerate() {
+        if a.len() != b.len() {
+            lines.push(format!("column {}: {} hits -> {}", x, a.len(), b.len()));
+        }
+        for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            let diffs = diff_hit(a, b, tolerance);
+            if !diffs.is_empty() {
+                lines.push(format!("column {} hit {}: {}", x, i, diffs.join(", ")));
+            }
+        }
+    }
+
+    lines
+}
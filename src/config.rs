@@ -0,0 +1,116 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{bindings::BindingsConfig, raycast::{DEFAULT_TRANSLUCENT_LAYERS, MAX_TRANSLUCENT_LAYERS}, render::MIN_RAYS, FAR_CLIPING_PLANE, FOV, NUM_OF_RAYS, WINDOW_SIZE};
+
+// clip frames are kept in memory, so the buffer can't grow without bounds
+const MAX_CLIP_SECONDS: f32 = 30.0;
+
+// engine settings read once at startup, anything left out keeps its default
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub fov: f32,
+    pub rays: usize,
+    pub far_plane: f32,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub player_speed: f32,
+    pub turn_speed: f32,
+    pub smooth_movement: bool,
+    pub head_bob: bool,
+    // keyboard turns in 45 degree steps, for grid maps
+    pub snap_turn: bool,
+    // the sounds that matter written out on screen, with where they came from
+    pub captions: bool,
+    // seconds of recent frames kept for F10 to save as a clip, 0 keeps none
+    pub clip_seconds: f32,
+    // textures are only loaded while a map needs them instead of all of them at startup
+    pub stream_textures: bool,
+    // see-through cells a ray collects before stopping, fewer keeps glass corridors cheap
+    pub translucent_layers: usize,
+    // speech program the menus and hud are read out with, see narration.rs, empty for none
+    pub narrator: String,
+    // the theme in ./themes the textures start out drawn from, the first one when empty
+    pub theme: String,
+    pub bindings: BindingsConfig
+}
+
+impl Default for EngineConfig {
+    fn default() -> EngineConfig {
+        EngineConfig {
+            fov: FOV,
+            rays: NUM_OF_RAYS,
+            far_plane: FAR_CLIPING_PLANE,
+            window_width: WINDOW_SIZE.x as u32,
+            window_height: WINDOW_SIZE.y as u32,
+            player_speed: 3.0,
+            turn_speed: std::f32::consts::PI,
+            smooth_movement: true,
+            head_bob: true,
+            snap_turn: false,
+            captions: false,
+            clip_seconds: 0.0,
+            stream_textures: true,
+            translucent_layers: DEFAULT_TRANSLUCENT_LAYERS,
+            narrator: String::new(),
+            theme: String::new(),
+            bindings: BindingsConfig::default()
+        }
+    }
+}
+
+impl EngineConfig {
+    // one message per setting that is out of range
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if !(10.0..=170.0).contains(&self.fov) {
+            problems.push(format!("fov must be between 10 and 170 degrees, got {}", self.fov));
+        }
+        if self.rays < MIN_RAYS {
+            problems.push(format!("rays must be at least {}, got {}", MIN_RAYS, self.rays));
+        }
+        if self.rays > self.window_width as usize {
+            problems.push(format!("rays can't be more than the window width ({}), got {}", self.window_width, self.rays));
+        }
+        if self.far_plane <= 0.0 {
+            problems.push(format!("far_plane must be positive, got {}", self.far_plane));
+        }
+        if self.window_width < 320 || self.window_height < 240 {
+            problems.push(format!("window must be at least 320x240, got {}x{}", self.window_width, self.window_height));
+        }
+        if self.player_speed <= 0.0 {
+            problems.push(format!("player_speed must be positive, got {}", self.player_speed));
+        }
+        if self.turn_speed <= 0.0 {
+            problems.push(format!("turn_speed must be positive, got {}", self.turn_speed));
+        }
+        if !(0.0..=MAX_CLIP_SECONDS).contains(&self.clip_seconds) {
+            problems.push(format!("clip_seconds must be between 0 and {}, got {}", MAX_CLIP_SECONDS, self.clip_seconds));
+        }
+        if !(1..=MAX_TRANSLUCENT_LAYERS).contains(&self.translucent_layers) {
+            problems.push(format!("translucent_layers must be between 1 and {}, got {}", MAX_TRANSLUCENT_LAYERS, self.translucent_layers));
+        }
+        problems.extend(self.bindings.validate());
+
+        problems
+    }
+}
+
+// a missing file is the default config, a broken or invalid one is an error
+pub fn load_engine_config(path: &str) -> Result<EngineConfig, String> {
+    if !Path::new(path).exists() {
+        return Ok(EngineConfig::default())
+    }
+
+    let text = fs::read_to_string(path).map_err(|err| format!("Could not load config '{}': {}", path, err))?;
+    let config: EngineConfig = toml::from_str(&text).map_err(|err| format!("Could not parse config '{}': {}", path, err))?;
+
+    let problems = config.validate();
+    if !problems.is_empty() {
+        return Err(format!("Invalid config '{}': {}", path, problems.join(", ")))
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,68 @@
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::Rectangle, RaylibHandle};
+
+use crate::{panel::{draw_panel, Panel}, ui, window_size};
+
+// lines of output kept on screen
+const CONSOLE_LINES: usize = 12;
+const CONSOLE_FONT: i32 = 20;
+
+// drop-down command line, opened with the key under escape
+#[derive(Default)]
+pub struct Console {
+    pub active: bool,
+    pub line: String,
+    pub log: Vec<String>
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console::default()
+    }
+
+    fn push(&mut self, text: String) {
+        self.log.push(text);
+        if self.log.len() > CONSOLE_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    pub fn print(&mut self, text: String) {
+        println!("INFO: {}", text);
+        self.push(text);
+    }
+
+    // returns a command once enter is pressed
+    pub fn handle_input(&mut self, rl: &mut RaylibHandle) -> Option<String> {
+        while let Some(c) = rl.get_char_pressed() {
+            // the key that opened the console types a character as well
+            if c != '`' && c != '~' {
+                self.line.push(c);
+            }
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_BACKSPACE) {
+            self.line.pop();
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_ENTER) && !self.line.trim().is_empty() {
+            let command = std::mem::take(&mut self.line);
+            self.push(format!("> {}", command));
+            return Some(command)
+        }
+
+        None
+    }
+}
+
+pub fn render_console(d: &mut RaylibDrawHandle, console: &Console, panel: Option<&Panel>) {
+    if !console.active { return }
+
+    let (font, margin) = (ui(CONSOLE_FONT), ui(5));
+    let height = (CONSOLE_LINES as i32 + 1) * font + 2 * margin;
+    draw_panel(d, panel, Rectangle::new(0.0, 0.0, window_size().x, height as f32), Color::BLACK.alpha(0.8));
+
+    for (i, line) in console.log.iter().enumerate() {
+        d.draw_text(line, margin, margin + i as i32 * font, font, Color::LIGHTGRAY);
+    }
+    d.draw_text(&format!("> {}_", console.line), margin, height - font - margin, font, Color::WHITE);
+}
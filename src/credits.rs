@@ -0,0 +1,89 @@
+use std::fs;
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, RaylibHandle, RaylibThread};
+
+use crate::{menu::{MenuInput, MenuNav}, narration::narrate, state::{GameState, Transition}, ui, ui_scale, window_size};
+
+// one line of text per line of the file, "# <heading>" for a heading and an empty line for a gap
+pub const CREDITS_PATH: &str = "./res/credits.txt";
+// in pixels per second at a ui scale of 1
+const SCROLL_SPEED: f32 = 40.0;
+const HEADING_FONT: i32 = 30;
+const TEXT_FONT: i32 = 20;
+const LINE_HEIGHT: i32 = 34;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CreditLine {
+    Heading(String),
+    Text(String),
+    Gap
+}
+
+pub fn parse_credits(text: &str) -> Vec<CreditLine> {
+    text.lines().map(|line| line.trim()).map(|line| {
+        if line.is_empty() { CreditLine::Gap }
+        else if let Some(heading) = line.strip_prefix('#') { CreditLine::Heading(heading.trim().to_string()) }
+        else { CreditLine::Text(line.to_string()) }
+    }).collect()
+}
+
+// rolls up from below the screen until the last line is gone, any key or button skips it
+pub struct Credits {
+    lines: Vec<CreditLine>,
+    // how far the lines have moved up, the one before is kept to draw between updates
+    offset: f32,
+    last_offset: f32,
+    nav: MenuNav
+}
+
+impl Credits {
+    pub fn new(lines: Vec<CreditLine>) -> Credits {
+        narrate("Credits");
+        Credits { lines, offset: 0.0, last_offset: 0.0, nav: MenuNav::default() }
+    }
+
+    pub fn load(path: &str) -> Result<Credits, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Could not read '{}': {}", path, err))?;
+        Ok(Credits::new(parse_credits(&text)))
+    }
+
+    fn height(&self) -> f32 {
+        (self.lines.len() as i32 * ui(LINE_HEIGHT)) as f32
+    }
+
+    fn is_done(&self) -> bool {
+        self.offset > window_size().y + self.height()
+    }
+}
+
+impl GameState for Credits {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread) -> Transition {
+        let skipped = self.nav.read(rl) != MenuInput::None || rl.get_key_pressed().is_some();
+        if skipped || self.is_done() { Transition::Pop }
+        else { Transition::None }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.last_offset = self.offset;
+        self.offset += SCROLL_SPEED * ui_scale() * dt;
+    }
+
+    fn render(&mut self, d: &mut RaylibDrawHandle, _thread: &RaylibThread, alpha: f32) {
+        d.clear_background(Color::BLACK);
+
+        let offset = self.last_offset + (self.offset - self.last_offset) * alpha;
+        let top = window_size().y - offset;
+        for (i, line) in self.lines.iter().enumerate() {
+            let y = top as i32 + i as i32 * ui(LINE_HEIGHT);
+            if y < -ui(LINE_HEIGHT) || y > window_size().y as i32 { continue }
+
+            let (text, size, color) = match line {
+                CreditLine::Heading(text) => (text, ui(HEADING_FONT), Color::YELLOW),
+                CreditLine::Text(text) => (text, ui(TEXT_FONT), Color::LIGHTGRAY),
+                CreditLine::Gap => continue
+            };
+            let width = d.measure_text(text, size);
+            d.draw_text(text, (window_size().x as i32 - width) / 2, y, size, color);
+        }
+    }
+}
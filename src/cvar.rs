@@ -0,0 +1,138 @@
+use std::fs;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32)
+}
+
+impl CvarValue {
+    // text is read as the same type as `self`
+    fn parse(self, text: &str) -> Result<CvarValue, String> {
+        match self {
+            CvarValue::Bool(_) => match text {
+                "1" | "true" | "on" => Ok(CvarValue::Bool(true)),
+                "0" | "false" | "off" => Ok(CvarValue::Bool(false)),
+                _ => Err(format!("Expected a boolean, got '{}'", text))
+            },
+            CvarValue::Int(_) => text.parse::<i64>().map(CvarValue::Int).map_err(|err| format!("Expected an integer, got '{}': {}", text, err)),
+            CvarValue::Float(_) => text.parse::<f32>().map(CvarValue::Float).map_err(|err| format!("Expected a number, got '{}': {}", text, err))
+        }
+    }
+
+    pub fn as_bool(self) -> bool {
+        match self {
+            CvarValue::Bool(value) => value,
+            CvarValue::Int(value) => value != 0,
+            CvarValue::Float(value) => value != 0.0
+        }
+    }
+
+    pub fn as_int(self) -> i64 {
+        match self {
+            CvarValue::Bool(value) => value as i64,
+            CvarValue::Int(value) => value,
+            CvarValue::Float(value) => value as i64
+        }
+    }
+
+    pub fn as_float(self) -> f32 {
+        match self {
+            CvarValue::Bool(value) => value as i32 as f32,
+            CvarValue::Int(value) => value as f32,
+            CvarValue::Float(value) => value
+        }
+    }
+}
+
+impl std::fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CvarValue::Bool(value) => write!(f, "{}", *value as i32),
+            CvarValue::Int(value) => write!(f, "{}", value),
+            CvarValue::Float(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+// called with the new value whenever a variable is set, on whatever owns the registry
+pub type Callback<T> = fn(&mut T, CvarValue);
+
+pub struct Cvar<T> {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub value: CvarValue,
+    pub default: CvarValue,
+    on_change: Callback<T>
+}
+
+// a value that was stored but hasn't reached its subsystem yet
+pub struct Change<T> {
+    callback: Callback<T>,
+    value: CvarValue
+}
+
+impl<T> Change<T> {
+    pub fn apply(self, target: &mut T) {
+        (self.callback)(target, self.value)
+    }
+}
+
+// named runtime variables, set from the console, the config file or the command line
+pub struct CvarRegistry<T> {
+    cvars: Vec<Cvar<T>>
+}
+
+impl<T> Default for CvarRegistry<T> {
+    fn default() -> CvarRegistry<T> {
+        CvarRegistry::new()
+    }
+}
+
+impl<T> CvarRegistry<T> {
+    pub fn new() -> CvarRegistry<T> {
+        CvarRegistry { cvars: vec![] }
+    }
+
+    pub fn register(&mut self, name: &'static str, help: &'static str, default: CvarValue, on_change: Callback<T>) {
+        assert!(self.find(name).is_none(), "Cvar registered twice");
+        self.cvars.push(Cvar { name, help, value: default, default, on_change });
+    }
+
+    fn find(&self, name: &str) -> Option<&Cvar<T>> {
+        self.cvars.iter().find(|cvar| cvar.name == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<CvarValue> {
+        self.find(name).map(|cvar| cvar.value)
+    }
+
+    pub fn cvars(&self) -> &[Cvar<T>] {
+        &self.cvars
+    }
+
+    // the registry is usually owned by the callback's target, so applying the change is left to the caller
+    pub fn set(&mut self, name: &str, text: &str) -> Result<Change<T>, String> {
+        let cvar = self.cvars.iter_mut().find(|cvar| cvar.name == name).ok_or(format!("Unknown cvar '{}'", name))?;
+        cvar.value = cvar.value.parse(text).map_err(|err| format!("{}: {}", name, err))?;
+        Ok(Change { callback: cvar.on_change, value: cvar.value })
+    }
+}
+
+// "name value" pairs, one per line, "#" starts a comment
+pub fn parse_config(text: &str) -> Result<Vec<(String, String)>, String> {
+    text.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| match line.split_once(char::is_whitespace) {
+            Some((name, value)) => Ok((name.to_string(), value.trim().to_string())),
+            None => Err(format!("Line {}: '{}' has no value", i, line))
+        })
+        .collect()
+}
+
+pub fn load_config(path: &str) -> Result<Vec<(String, String)>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Could not load config '{}': {}", path, err))?;
+    parse_config(&text).map_err(|err| format!("{}: {}", path, err))
+}
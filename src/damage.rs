@@ -0,0 +1,75 @@
+use std::ops::{Div, Sub};
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::Vector2};
+
+use crate::{game::Game, window_size};
+
+// seconds an arc stays on screen after a hit
+const INDICATOR_TIME: f32 = 1.0;
+// width of the arc in degrees, and how far it sits from the middle of the screen
+const INDICATOR_SPREAD: f32 = 40.0;
+const INDICATOR_RADIUS: f32 = 0.4;
+const INDICATOR_THICKNESS: f32 = 12.0;
+
+// the heartbeat speeds up from one beat every this many seconds as health runs out
+const SLOWEST_HEARTBEAT: f32 = 1.2;
+const FASTEST_HEARTBEAT: f32 = 0.5;
+// share of the screen the vignette reaches in from each edge
+const VIGNETTE_SIZE: f32 = 0.2;
+
+pub fn heartbeat_interval(low_health: f32) -> f32 {
+    SLOWEST_HEARTBEAT + (FASTEST_HEARTBEAT - SLOWEST_HEARTBEAT) * low_health
+}
+
+// where a hit came from, kept until it fades out
+pub struct DamageIndicator {
+    pub source: Vector2,
+    pub time: f32
+}
+
+impl DamageIndicator {
+    pub fn new(source: Vector2) -> DamageIndicator {
+        DamageIndicator { source, time: INDICATOR_TIME }
+    }
+}
+
+pub fn update_damage_indicators(indicators: &mut Vec<DamageIndicator>, dt: f32) {
+    for indicator in indicators.iter_mut() {
+        indicator.time -= dt;
+    }
+    indicators.retain(|indicator| indicator.time > 0.0);
+}
+
+// an arc around the crosshair pointing at every recent hit, up being in front of the player
+pub fn render_damage_indicators(d: &mut RaylibDrawHandle, game: &Game) {
+    let center = window_size().div(2.0);
+    let radius = f32::min(window_size().x, window_size().y) * INDICATOR_RADIUS;
+
+    for indicator in game.damage_indicators.iter() {
+        let to_source = indicator.source.sub(game.player.pos);
+        let dir = game.player.dir;
+        // turning right is clockwise on the board as well as on the screen
+        let angle = f32::atan2(dir.x * to_source.y - dir.y * to_source.x, dir.dot(to_source)).to_degrees() - 90.0;
+
+        let alpha = (indicator.time / INDICATOR_TIME).clamp(0.0, 1.0);
+        d.draw_ring(center, radius, radius + INDICATOR_THICKNESS, angle - INDICATOR_SPREAD / 2.0, angle + INDICATOR_SPREAD / 2.0, 16, Color::RED.alpha(alpha * 0.8));
+    }
+}
+
+// red edges that pulse with the heartbeat while health is low
+pub fn render_low_health(d: &mut RaylibDrawHandle, game: &Game) {
+    let low_health = game.player.low_health();
+    if low_health <= 0.0 || game.editor.active { return }
+
+    let phase = game.time / heartbeat_interval(low_health) * std::f32::consts::TAU;
+    let pulse = 0.6 + 0.4 * f32::sin(phase);
+    let edge = Color::RED.alpha(low_health * pulse * 0.7);
+    let clear = Color::RED.alpha(0.0);
+
+    let (w, h) = (window_size().x as i32, window_size().y as i32);
+    let size = (f32::min(window_size().x, window_size().y) * VIGNETTE_SIZE) as i32;
+    d.draw_rectangle_gradient_v(0, 0, w, size, edge, clear);
+    d.draw_rectangle_gradient_v(0, h - size, w, size, clear, edge);
+    d.draw_rectangle_gradient_h(0, 0, size, h, edge, clear);
+    d.draw_rectangle_gradient_h(w - size, 0, size, h, clear, edge);
+}
@@ -0,0 +1,40 @@
+use std::f32::consts::TAU;
+
+// scales everything the view draws, walls, floors, sprites and particles alike, on top of the
+// light map. with a cycle it swings between full daylight at noon and `night` at midnight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Daylight {
+    // from 0 (pitch black) to 1 (as lit as the light map says)
+    pub level: f32,
+    // seconds for a whole day, 0 keeps it at `level`
+    pub cycle: f32,
+    // share of `level` left in the middle of the night
+    pub night: f32,
+    // seconds into the day, it starts at noon
+    pub time: f32
+}
+
+impl Default for Daylight {
+    fn default() -> Daylight {
+        Daylight::new()
+    }
+}
+
+impl Daylight {
+    pub fn new() -> Daylight {
+        Daylight { level: 1.0, cycle: 0.0, night: 0.2, time: 0.0 }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.cycle > 0.0 {
+            self.time = (self.time + dt).rem_euclid(self.cycle);
+        }
+    }
+
+    pub fn brightness(&self) -> f32 {
+        if self.cycle <= 0.0 { return self.level }
+
+        let day = (f32::cos(TAU * self.time / self.cycle) + 1.0) / 2.0;
+        self.level * (self.night + (1.0 - self.night) * day)
+    }
+}
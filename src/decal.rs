@@ -0,0 +1,70 @@
+use raylib::math::Vector2;
+
+use crate::{raycast::Side, texture::TextureId};
+
+// more than this and the oldest bullet holes make room for new ones
+pub const MAX_BULLET_HOLES: usize = 64;
+// in wall heights, what a map's decal is without a size
+pub const DEFAULT_DECAL_SIZE: f32 = 0.5;
+pub const BULLET_HOLE_SIZE: f32 = 0.06;
+
+// which of the four faces of a cell a decal sits on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    North,
+    South,
+    East,
+    West
+}
+
+impl Face {
+    pub fn name(self) -> &'static str {
+        match self {
+            Face::North => "north",
+            Face::South => "south",
+            Face::East => "east",
+            Face::West => "west"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Face> {
+        [Face::North, Face::South, Face::East, Face::West].into_iter().find(|face| face.name() == name)
+    }
+
+    // the face of `cell` a point on its edge is on, by the grid line it was hit across
+    pub fn of(cell: (usize, usize), point: Vector2, side: Side) -> Face {
+        match side {
+            Side::X => if point.x < cell.0 as f32 + 0.5 { Face::West } else { Face::East },
+            Side::Y => if point.y < cell.1 as f32 + 0.5 { Face::North } else { Face::South }
+        }
+    }
+}
+
+// a small texture stuck on one face of a wall, drawn over the wall in the columns it covers.
+// `u` is where its middle is along the face, the same way the wall texture runs, and `height`
+// how far its middle is above the floor. it is `size` wide and as tall as its texture says
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decal {
+    pub texture: TextureId,
+    pub cell: (usize, usize),
+    pub face: Face,
+    pub u: f32,
+    pub height: f32,
+    pub size: f32
+}
+
+impl Decal {
+    // how far across the decal a column at `wall_x` along its face is, None when it misses it
+    pub fn column(&self, wall_x: f32) -> Option<f32> {
+        let t = (wall_x - self.u) / self.size + 0.5;
+        (0.0..1.0).contains(&t).then_some(t)
+    }
+}
+
+// the newest last, so a hole shot over an older one is drawn on top
+pub fn add_bullet_hole(holes: &mut Vec<Decal>, hole: Decal) {
+    if holes.len() >= MAX_BULLET_HOLES {
+        holes.remove(0);
+    }
+    holes.push(hole);
+}
@@ -0,0 +1,127 @@
+use std::fs;
+
+use raylib::math::Vector2;
+
+use crate::game::Input;
+
+// bumped whenever a tick line changes meaning, old demos would replay into something else
+const DEMO_VERSION: u32 = 1;
+
+// the held and pressed buttons of a tick, in the order of their bit in the tick line
+fn buttons(input: &mut Input) -> [&mut bool; 11] {
+    [
+        &mut input.forward, &mut input.backward, &mut input.strafe_left, &mut input.strafe_right,
+        &mut input.turn_left, &mut input.turn_right, &mut input.use_door, &mut input.fire,
+        &mut input.jump, &mut input.crouch, &mut input.sprint
+    ]
+}
+
+// the input of every simulation step since the map was started fresh, replaying them
+// from the same start walks the same path at any frame rate.
+//
+// demo <version>
+// map <path>
+// player <dir x> <dir y> <health> <ammo>
+// then a line per tick: <buttons as hex bits> <look> <pitch> [<teleport x> <teleport y>]
+#[derive(Clone, Debug, Default)]
+pub struct Demo {
+    pub map: String,
+    pub dir: Vector2,
+    pub health: f32,
+    pub ammo: u32,
+    pub ticks: Vec<Input>
+}
+
+fn tick_to_text(input: &Input) -> String {
+    let mut input = input.clone();
+    let bits = buttons(&mut input).iter().enumerate().fold(0u32, |bits, (i, button)| bits | ((**button as u32) << i));
+    let mut line = format!("{:x} {} {}", bits, input.look, input.pitch);
+    if let Some(pos) = input.teleport {
+        line += &format!(" {} {}", pos.x, pos.y);
+    }
+    line
+}
+
+fn tick_from_text(line: &str) -> Result<Input, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if !matches!(words.len(), 3 | 5) {
+        return Err(format!("Expected 3 or 5 values in tick '{}'", line))
+    }
+
+    let number = |word: &str| word.parse::<f32>().map_err(|err| format!("Invalid number '{}' in tick '{}': {}", word, line, err));
+    let bits = u32::from_str_radix(words[0], 16).map_err(|err| format!("Invalid buttons '{}' in tick '{}': {}", words[0], line, err))?;
+    let mut input = Input { look: number(words[1])?, pitch: number(words[2])?, ..Input::default() };
+    for (i, button) in buttons(&mut input).into_iter().enumerate() {
+        *button = bits & (1 << i) != 0;
+    }
+    if words.len() == 5 {
+        input.teleport = Some(Vector2::new(number(words[3])?, number(words[4])?));
+    }
+    Ok(input)
+}
+
+impl Demo {
+    pub fn new(map: String, dir: Vector2, health: f32, ammo: u32) -> Demo {
+        Demo { map, dir, health, ammo, ticks: vec![] }
+    }
+
+    // saving and loading would replay into files the demo doesn't have, so they are left out
+    pub fn record(&mut self, input: &Input) {
+        self.ticks.push(Input { quicksave: false, quickload: false, ..input.clone() });
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("demo {}\nmap {}\nplayer {} {} {} {}\n", DEMO_VERSION, self.map, self.dir.x, self.dir.y, self.health, self.ammo);
+        for tick in self.ticks.iter() {
+            text += &tick_to_text(tick);
+            text += "\n";
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Demo, String> {
+        let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let mut header = |keyword: &str| -> Result<Vec<String>, String> {
+            let line = lines.next().ok_or(format!("Missing '{}' line", keyword))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(keyword) {
+                return Err(format!("Expected '{}' line, got '{}'", keyword, line))
+            }
+            Ok(words.map(|word| word.to_string()).collect())
+        };
+
+        let version = header("demo")?;
+        if version.first().map(|version| version.as_str()) != Some(&DEMO_VERSION.to_string()) {
+            return Err(format!("Unsupported demo version '{}', expected {}", version.join(" "), DEMO_VERSION))
+        }
+        let map = header("map")?.join(" ");
+        let player = header("player")?;
+        if player.len() != 4 {
+            return Err(format!("Expected 4 values in 'player' line, got {}", player.len()))
+        }
+        let number = |word: &str| word.parse::<f32>().map_err(|err| format!("Invalid number '{}' in 'player' line: {}", word, err));
+        let ammo = player[3].parse::<u32>().map_err(|err| format!("Invalid ammo '{}' in 'player' line: {}", player[3], err))?;
+        let mut demo = Demo::new(map, Vector2::new(number(&player[0])?, number(&player[1])?), number(&player[2])?, ammo);
+
+        for line in lines {
+            demo.ticks.push(tick_from_text(line)?);
+        }
+        Ok(demo)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|err| format!("Could not save demo '{}': {}", path, err))
+    }
+
+    pub fn load(path: &str) -> Result<Demo, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Could not load demo '{}': {}", path, err))?;
+        Demo::from_text(&text).map_err(|err| format!("Could not load demo '{}': {}", path, err))
+    }
+}
+
+// what the gameplay is doing with a demo, playback counts the ticks already replayed
+pub enum DemoMode {
+    Off,
+    Recording(String, Demo),
+    Playing(Demo, usize)
+}
@@ -0,0 +1,69 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing
+}
+
+// `open` is how far the door has slid along its face, from 0 (closed) to 1 (open)
+#[derive(Clone, Copy, Debug)]
+pub struct Door {
+    pub open: f32,
+    pub state: DoorState,
+    pub speed: f32
+}
+
+impl Default for Door {
+    fn default() -> Door {
+        Door {
+            open: 0.0,
+            state: DoorState::Closed,
+            speed: 1.0
+        }
+    }
+}
+
+impl Door {
+    pub fn toggle(&mut self) {
+        self.state = match self.state {
+            DoorState::Closed | DoorState::Closing => DoorState::Opening,
+            DoorState::Open | DoorState::Opening => DoorState::Closing
+        }
+    }
+
+    // returns true on the frame the door becomes fully open
+    pub fn update(&mut self, dt: f32) -> bool {
+        match self.state {
+            DoorState::Opening => {
+                self.open = f32::min(self.open + self.speed * dt, 1.0);
+                if self.open >= 1.0 {
+                    self.state = DoorState::Open;
+                    return true
+                }
+            },
+            DoorState::Closing => {
+                self.open = f32::max(self.open - self.speed * dt, 0.0);
+                if self.open <= 0.0 {
+                    self.state = DoorState::Closed;
+                }
+            },
+            _ => {}
+        }
+
+        false
+    }
+
+    pub fn is_moving(&self) -> bool {
+        matches!(self.state, DoorState::Opening | DoorState::Closing)
+    }
+
+    pub fn is_passable(&self) -> bool {
+        self.state == DoorState::Open
+    }
+
+    // `u` is the position of the hit along the door face
+    pub fn blocks(&self, u: f32) -> bool {
+        u >= self.open
+    }
+}
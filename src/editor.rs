@@ -0,0 +1,669 @@
+use std::path::Path;
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle, RaylibScissorModeExt}, math::{Rectangle, Vector2}, RaylibHandle};
+
+use crate::{autosave::Autosave, board::{Board, Cell, MIRROR_COLOR}, game::Game, map::save_board, prefab::{copy_region, stamp}, spawn::{Spawn, SpawnKind, SPAWN_KINDS}, thumbnail::save_thumbnail, tiled::is_tiled_map, transform::Transform2DApplayer, undo::UndoHistory, window_size};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditorMode {
+    Cells,
+    Entities
+}
+
+// how the left button paints and the right one erases cells. lines and rectangles are dragged out
+// from where the button went down and painted when it is let go
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Pencil,
+    Line,
+    Rectangle,
+    Fill
+}
+
+pub const TOOLS: [Tool; 4] = [Tool::Pencil, Tool::Line, Tool::Rectangle, Tool::Fill];
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pencil => "PENCIL",
+            Tool::Line => "LINE",
+            Tool::Rectangle => "RECTANGLE",
+            Tool::Fill => "FILL"
+        }
+    }
+
+    // the cells painted dragging from `from` to `to`
+    pub fn cells(self, board: &Board, from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+        match self {
+            Tool::Pencil => vec![to],
+            Tool::Line => line_cells(from, to),
+            Tool::Rectangle => rectangle_cells(from, to),
+            Tool::Fill => flood_cells(board, to.0, to.1)
+        }
+    }
+}
+
+// changes collected from input and applied to the board on update
+enum Edit {
+    Paint(usize, usize, Cell),
+    Height(usize, usize, f32),
+    Place(SpawnKind, Vector2),
+    Move(usize, Vector2),
+    Delete(usize),
+    Adjust(usize, usize, f32),
+    // x, y, width and height of the region copied to the clipboard
+    Copy(usize, usize, usize, usize),
+    Stamp(usize, usize),
+    Fill(usize, usize, Cell),
+    Undo,
+    Redo
+}
+
+// what the editor looked like before a playtest started, restored when it ends
+pub struct Playtest {
+    pub board: Board,
+    pub pos: Vector2,
+    pub dir: Vector2
+}
+
+// how close the cursor has to be to grab a spawn
+const PICK_RADIUS: f32 = 0.4;
+
+const HEIGHT_STEP: f32 = 0.25;
+const MIN_HEIGHT: f32 = 0.25;
+const MAX_HEIGHT: f32 = 3.0;
+
+pub struct Editor {
+    pub active: bool,
+    pub mode: EditorMode,
+    pub palette: Vec<Cell>,
+    pub selected: usize,
+    pub kind: usize,
+    pub tool: usize,
+    pub spawn: Option<usize>,
+    pub property: usize,
+    pub path: String,
+    pub playtest: Option<Playtest>,
+    pub problems: Vec<String>,
+    pub validated: Option<u64>,
+    // what ctrl+c copied or a prefab loaded, stamped with its top left corner on the hovered cell by ctrl+v
+    pub clipboard: Option<Board>,
+    // corners of the region being selected with shift and the left button
+    selection: Option<((usize, usize), (usize, usize))>,
+    selecting: bool,
+    // where the line or rectangle being dragged out started and what it paints
+    shape: Option<((usize, usize), Cell)>,
+    dragging: bool,
+    // a mouse button is held, everything painted until it is let go is undone together
+    stroke: bool,
+    pub history: UndoHistory,
+    edits: Vec<Edit>,
+    save: bool,
+    autosave: Autosave
+}
+
+impl Default for Editor {
+    fn default() -> Editor {
+        Editor::new(vec![])
+    }
+}
+
+impl Editor {
+    pub fn new(palette: Vec<Cell>) -> Editor {
+        Editor {
+            active: false,
+            mode: EditorMode::Cells,
+            palette,
+            selected: 0,
+            kind: 0,
+            tool: 0,
+            spawn: None,
+            property: 0,
+            path: "./map.txt".to_string(),
+            playtest: None,
+            problems: vec![],
+            validated: None,
+            clipboard: None,
+            selection: None,
+            selecting: false,
+            shape: None,
+            dragging: false,
+            stroke: false,
+            history: UndoHistory::new(),
+            edits: vec![],
+            save: false,
+            autosave: Autosave::new()
+        }
+    }
+
+    pub fn selected_cell(&self) -> Option<Cell> {
+        self.palette.get(self.selected).copied()
+    }
+
+    // x, y, width and height of the selected cells
+    pub fn selection(&self) -> Option<(usize, usize, usize, usize)> {
+        let ((ax, ay), (bx, by)) = self.selection?;
+        Some((usize::min(ax, bx), usize::min(ay, by), ax.abs_diff(bx) + 1, ay.abs_diff(by) + 1))
+    }
+
+    pub fn selected_kind(&self) -> SpawnKind {
+        SPAWN_KINDS[self.kind]
+    }
+
+    pub fn selected_tool(&self) -> Tool {
+        TOOLS[self.tool]
+    }
+
+    // the cells the tool would paint with the cursor on x, y and what it would paint them with
+    pub fn preview(&self, board: &Board, x: usize, y: usize) -> Option<(Vec<(usize, usize)>, Cell)> {
+        match (self.selected_tool(), self.shape) {
+            (tool @ (Tool::Line | Tool::Rectangle), Some((start, cell))) => Some((tool.cells(board, start, (x, y)), cell)),
+            (Tool::Fill, _) => Some((flood_cells(board, x, y), self.selected_cell()?)),
+            _ => None
+        }
+    }
+
+    pub fn cycle(&mut self, amount: i32) {
+        match self.mode {
+            EditorMode::Cells => {
+                if self.palette.is_empty() { return }
+                let len = self.palette.len() as i32;
+                self.selected = (self.selected as i32 + amount).rem_euclid(len) as usize;
+            },
+            EditorMode::Entities => {
+                let len = SPAWN_KINDS.len() as i32;
+                self.kind = (self.kind as i32 + amount).rem_euclid(len) as usize;
+            }
+        }
+    }
+
+    // `hovered` is the board position under the mouse on the minimap
+    pub fn handle_input(&mut self, rl: &RaylibHandle, board: &Board, hovered: Option<Vector2>) {
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_Q) {
+            self.mode = match self.mode {
+                EditorMode::Cells => EditorMode::Entities,
+                EditorMode::Entities => EditorMode::Cells
+            };
+            self.shape = None;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel > 0.0 { self.cycle(1) }
+        else if wheel < 0.0 { self.cycle(-1) }
+
+        let ctrl = rl.is_key_down(raylib::ffi::KeyboardKey::KEY_LEFT_CONTROL) || rl.is_key_down(raylib::ffi::KeyboardKey::KEY_RIGHT_CONTROL);
+        match self.mode {
+            EditorMode::Cells => self.handle_cells(rl, board, hovered, ctrl),
+            EditorMode::Entities => self.handle_entities(rl, board, hovered)
+        }
+
+        self.save |= ctrl && rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_S);
+
+        // ctrl+z undoes, ctrl+y or ctrl+shift+z redoes
+        self.stroke = rl.is_mouse_button_down(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) || rl.is_mouse_button_down(raylib::ffi::MouseButton::MOUSE_BUTTON_RIGHT);
+        let shift = rl.is_key_down(raylib::ffi::KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(raylib::ffi::KeyboardKey::KEY_RIGHT_SHIFT);
+        if ctrl && rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_Z) {
+            self.edits.push(if shift { Edit::Redo } else { Edit::Undo });
+        }
+        if ctrl && rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_Y) {
+            self.edits.push(Edit::Redo);
+        }
+    }
+
+    fn handle_cells(&mut self, rl: &RaylibHandle, board: &Board, hovered: Option<Vector2>, ctrl: bool) {
+        if !rl.is_mouse_button_down(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
+            self.selecting = false;
+        }
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_B) {
+            self.tool = (self.tool + 1) % TOOLS.len();
+            self.shape = None;
+        }
+        // letting go of a line or rectangle off the map drops it
+        if hovered.is_none() && self.held_cell(rl).is_none() {
+            self.shape = None;
+        }
+        if ctrl && rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_C) {
+            if let Some((x, y, w, h)) = self.selection() {
+                self.edits.push(Edit::Copy(x, y, w, h));
+            }
+        }
+
+        let Some(pos) = hovered else { return };
+        let (x, y) = (pos.x as usize, pos.y as usize);
+
+        if ctrl && rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_V) {
+            self.edits.push(Edit::Stamp(x, y));
+        }
+
+        // shift and the left button drag out a selection instead of painting, the right button drops it
+        let shift = rl.is_key_down(raylib::ffi::KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(raylib::ffi::KeyboardKey::KEY_RIGHT_SHIFT);
+        if self.shape.is_some() {
+            self.handle_tool(rl, board, x, y);
+        } else if shift && rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
+            self.selection = Some(((x, y), (x, y)));
+            self.selecting = true;
+        } else if self.selecting {
+            if let Some((start, _)) = self.selection {
+                self.selection = Some((start, (x, y)));
+            }
+        } else if shift {
+            if rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_RIGHT) {
+                self.selection = None;
+            }
+        } else {
+            self.handle_tool(rl, board, x, y);
+        }
+
+        // page up/down raise and lower the hovered wall
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_PAGE_UP) {
+            self.edits.push(Edit::Height(x, y, HEIGHT_STEP));
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_PAGE_DOWN) {
+            self.edits.push(Edit::Height(x, y, -HEIGHT_STEP));
+        }
+    }
+
+    // what the left button paints or the right one erases with, None with neither held
+    fn held_cell(&self, rl: &RaylibHandle) -> Option<Cell> {
+        if rl.is_mouse_button_down(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) { self.selected_cell() }
+        else if rl.is_mouse_button_down(raylib::ffi::MouseButton::MOUSE_BUTTON_RIGHT) { Some(Cell::EMPTY) }
+        else { None }
+    }
+
+    fn handle_tool(&mut self, rl: &RaylibHandle, board: &Board, x: usize, y: usize) {
+        let pressed = rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) || rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_RIGHT);
+        let held = self.held_cell(rl);
+        match (self.selected_tool(), self.shape) {
+            (Tool::Pencil, _) => if let Some(cell) = held {
+                self.edits.push(Edit::Paint(x, y, cell));
+            },
+            (Tool::Fill, _) => if let Some(cell) = held.filter(|_| pressed) {
+                self.edits.push(Edit::Fill(x, y, cell));
+            },
+            (Tool::Line | Tool::Rectangle, None) => if pressed {
+                self.shape = held.map(|cell| ((x, y), cell));
+            },
+            (tool @ (Tool::Line | Tool::Rectangle), Some((start, cell))) => if held.is_none() {
+                for (px, py) in tool.cells(board, start, (x, y)) {
+                    self.edits.push(Edit::Paint(px, py, cell));
+                }
+                self.shape = None;
+            }
+        }
+    }
+
+    fn handle_entities(&mut self, rl: &RaylibHandle, board: &Board, hovered: Option<Vector2>) {
+        if rl.is_mouse_button_released(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
+            self.dragging = false;
+        }
+
+        if let Some(pos) = hovered {
+            let picked = pick_spawn(board, pos);
+            if rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
+                match picked {
+                    Some(index) => self.select(Some(index)),
+                    None => {
+                        self.edits.push(Edit::Place(self.selected_kind(), pos));
+                        self.select(Some(board.spawns.len()));
+                    }
+                }
+                self.dragging = true;
+            } else if self.dragging {
+                if let Some(index) = self.spawn {
+                    self.edits.push(Edit::Move(index, pos));
+                }
+            }
+
+            if rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_RIGHT) {
+                if let Some(index) = picked {
+                    self.delete(index);
+                }
+            }
+        }
+
+        let Some(index) = self.spawn else { return };
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_DELETE) {
+            self.delete(index);
+            return
+        }
+
+        // side panel: up/down picks a property and [ / ] changes it, hold shift for bigger steps
+        let count = board.spawns.get(index).map_or(0, |spawn| spawn.properties.len());
+        if count == 0 { return }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_UP) {
+            self.property = (self.property + count - 1) % count;
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_DOWN) {
+            self.property = (self.property + 1) % count;
+        }
+
+        let step = if rl.is_key_down(raylib::ffi::KeyboardKey::KEY_LEFT_SHIFT) { 10.0 }
+        else { 1.0 };
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_LEFT_BRACKET) {
+            self.edits.push(Edit::Adjust(index, self.property, -step));
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_RIGHT_BRACKET) {
+            self.edits.push(Edit::Adjust(index, self.property, step));
+        }
+    }
+
+    fn select(&mut self, spawn: Option<usize>) {
+        if self.spawn != spawn {
+            self.property = 0;
+        }
+        self.spawn = spawn;
+    }
+
+    fn delete(&mut self, index: usize) {
+        self.edits.push(Edit::Delete(index));
+        self.spawn = match self.spawn {
+            Some(selected) if selected == index => None,
+            Some(selected) if selected > index => Some(selected - 1),
+            selected => selected
+        };
+        self.dragging = false;
+    }
+
+    pub fn update(&mut self, board: &mut Board, dt: f32) {
+        for edit in std::mem::take(&mut self.edits) {
+            match edit {
+                Edit::Paint(x, y, _) | Edit::Height(x, y, _) => self.history.touch(board, x, y),
+                Edit::Place(..) | Edit::Move(..) | Edit::Delete(_) | Edit::Adjust(..) => self.history.touch_spawns(board),
+                Edit::Stamp(x, y) => if let Some(clipboard) = self.clipboard.as_ref() {
+                    for (px, py) in (0..clipboard.rows).flat_map(|py| (0..clipboard.cols).map(move |px| (px, py))) {
+                        self.history.touch(board, x + px, y + py);
+                    }
+                    self.history.touch_spawns(board);
+                },
+                // touched as it spreads
+                Edit::Fill(..) | Edit::Copy(..) | Edit::Undo | Edit::Redo => {}
+            }
+
+            match edit {
+                Edit::Paint(x, y, cell) => {
+                    if *board.at(x, y) != cell {
+                        board.set(x, y, cell);
+                    }
+                },
+                Edit::Height(x, y, delta) => {
+                    let height = (board.height_at(x, y) + delta).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                    board.set_height(x, y, height);
+                },
+                Edit::Place(kind, pos) => board.spawns.push(Spawn::new(kind, pos)),
+                Edit::Move(index, pos) => {
+                    if let Some(spawn) = board.spawns.get_mut(index) {
+                        spawn.pos = pos;
+                    }
+                },
+                Edit::Delete(index) => {
+                    if index < board.spawns.len() {
+                        board.spawns.remove(index);
+                    }
+                },
+                Edit::Adjust(index, property, delta) => {
+                    if let Some((_, value)) = board.spawns.get_mut(index).and_then(|spawn| spawn.properties.get_mut(property)) {
+                        *value += delta;
+                    }
+                },
+                Edit::Copy(x, y, w, h) => self.clipboard = Some(copy_region(board, x, y, w, h)),
+                Edit::Stamp(x, y) => if let Some(clipboard) = self.clipboard.as_ref() {
+                    stamp(board, clipboard, x, y);
+                },
+                Edit::Fill(x, y, cell) => if x < board.cols && y < board.rows && *board.at(x, y) != cell {
+                    for (px, py) in flood_cells(board, x, y) {
+                        self.history.touch(board, px, py);
+                        board.set(px, py, cell);
+                    }
+                },
+                // the spawn indices may point at other spawns or none at all afterwards
+                Edit::Undo => if self.history.undo(board) {
+                    self.select(None);
+                    self.dragging = false;
+                },
+                Edit::Redo => if self.history.redo(board) {
+                    self.select(None);
+                    self.dragging = false;
+                }
+            }
+        }
+        if !self.stroke {
+            self.history.finish(board);
+        }
+
+        if self.save {
+            self.save = false;
+            // the Tiled file stays the Tiled editor's, its edits are kept as a text map beside it
+            if is_tiled_map(&self.path) {
+                self.path = Path::new(&self.path).with_extension("txt").to_string_lossy().into_owned();
+            }
+            match save_board(&self.path, board) {
+                Ok(()) => {
+                    println!("INFO: Map saved to {}", self.path);
+                    self.autosave.saved(&self.path, board);
+                    if let Err(err) = save_thumbnail(&self.path, board) {
+                        println!("ERROR: {}", err);
+                    }
+                },
+                Err(err) => println!("ERROR: {}", err)
+            }
+        } else {
+            self.autosave.update(dt, &self.path, board);
+        }
+    }
+}
+
+// every cell a line from `from` to `to` crosses, without any two only touching by a corner being skipped
+pub fn line_cells(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (tx, ty) = (to.0 as i64, to.1 as i64);
+    let (dx, dy) = ((tx - x).abs(), -(ty - y).abs());
+    let (sx, sy) = ((tx - x).signum(), (ty - y).signum());
+    let mut err = dx + dy;
+    let mut cells = vec![(x as usize, y as usize)];
+    while (x, y) != (tx, ty) {
+        let double = 2 * err;
+        if double >= dy {
+            err += dy;
+            x += sx;
+        }
+        if double <= dx {
+            err += dx;
+            y += sy;
+        }
+        cells.push((x as usize, y as usize));
+    }
+    cells
+}
+
+// the cells of the rectangle with `from` and `to` as opposite corners, filled
+pub fn rectangle_cells(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (xs, ys) = (usize::min(from.0, to.0)..=usize::max(from.0, to.0), usize::min(from.1, to.1)..=usize::max(from.1, to.1));
+    ys.flat_map(|y| xs.clone().map(move |x| (x, y))).collect()
+}
+
+// the cells equal to the one on x, y reachable from it through their sides
+pub fn flood_cells(board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+    if x >= board.cols || y >= board.rows { return vec![] }
+    let target = *board.at(x, y);
+    let mut seen = vec![false; board.cols * board.rows];
+    seen[y * board.cols + x] = true;
+    let mut stack = vec![(x, y)];
+    let mut cells = vec![];
+    while let Some((x, y)) = stack.pop() {
+        cells.push((x, y));
+        for (nx, ny) in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)] {
+            if nx >= board.cols || ny >= board.rows || seen[ny * board.cols + nx] || *board.at(nx, ny) != target { continue }
+            seen[ny * board.cols + nx] = true;
+            stack.push((nx, ny));
+        }
+    }
+    cells
+}
+
+fn pick_spawn(board: &Board, pos: Vector2) -> Option<usize> {
+    board.spawns.iter().enumerate()
+        .map(|(i, spawn)| (i, spawn.pos.distance_to(pos)))
+        .filter(|(_, dist)| *dist <= PICK_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+pub fn spawn_color(kind: SpawnKind) -> Color {
+    match kind {
+        SpawnKind::Player => Color::PURPLE,
+        SpawnKind::Enemy => Color::RED,
+        SpawnKind::Pickup => Color::LIME,
+        SpawnKind::Light => Color::YELLOW,
+        SpawnKind::Trigger => Color::SKYBLUE
+    }
+}
+
+pub fn render_editor(d: &mut RaylibDrawHandle, game: &Game) {
+    let editor = &game.editor;
+    if !editor.active { return }
+
+    // spawns and the hovered cell stay inside the minimap when it is zoomed or panned
+    {
+        let mt = &game.minimap;
+        let viewport = game.minimap_viewport();
+        let mut d = d.begin_scissor_mode(viewport.x as i32, viewport.y as i32, viewport.width as i32, viewport.height as i32);
+
+        let zoom = f32::max(mt.zoom.x, mt.zoom.y);
+        for (i, spawn) in game.board.spawns.iter().enumerate() {
+            let pos = spawn.pos.apply(mt);
+            d.draw_circle_v(pos, 0.25 * zoom, spawn_color(spawn.kind));
+            if editor.spawn == Some(i) {
+                d.draw_circle_lines(pos.x as i32, pos.y as i32, 0.35 * zoom, Color::WHITE);
+            }
+        }
+
+        if editor.mode == EditorMode::Cells {
+            if let Some((x, y, w, h)) = editor.selection() {
+                let rec = Rectangle::new(x as f32, y as f32, w as f32, h as f32).apply(mt);
+                d.draw_rectangle_lines_ex(rec, 2.0, Color::YELLOW);
+            }
+            if let Some((x, y)) = game.minimap_cell(d.get_mouse_position()) {
+                // what letting go of the line or rectangle, or clicking with the fill, would paint
+                if let Some((cells, cell)) = editor.preview(&game.board, x, y) {
+                    let tint = match cell {
+                        Cell::EMPTY => Color::RED,
+                        Cell::COLOR(color) => color,
+                        _ => Color::WHITE
+                    };
+                    for (px, py) in cells {
+                        let rec = Rectangle::new(px as f32, py as f32, 1.0, 1.0).apply(mt);
+                        d.draw_rectangle_rec(rec, tint.alpha(0.5));
+                    }
+                }
+                // where ctrl+v would stamp the clipboard
+                if let Some(clipboard) = editor.clipboard.as_ref() {
+                    let rec = Rectangle::new(x as f32, y as f32, clipboard.cols as f32, clipboard.rows as f32).apply(mt);
+                    d.draw_rectangle_lines_ex(rec, 1.0, Color::SKYBLUE);
+                }
+                let rec = Rectangle::new(x as f32, y as f32, 1.0, 1.0).apply(mt);
+                d.draw_rectangle_lines_ex(rec, 2.0, Color::WHITE);
+            }
+        }
+    }
+
+    // selected palette entry or spawn kind
+    let swatch = Rectangle::new(10.0, 40.0, 48.0, 48.0);
+    match editor.mode {
+        EditorMode::Cells => {
+            d.draw_text(&format!("EDITOR - CELLS - {}", editor.selected_tool().name()), 10, 10, 20, Color::WHITE);
+            match editor.selected_cell() {
+                Some(Cell::COLOR(color)) => d.draw_rectangle_rec(swatch, color),
+                Some(Cell::Mirror) => d.draw_rectangle_rec(swatch, MIRROR_COLOR),
+                Some(Cell::TEXTURE(id) | Cell::TranslucentTexture(id) | Cell::Door(id) | Cell::Segment(id, _) | Cell::Custom(_, id)) => {
+                    let (texture, source_rec) = game.textures.region(id);
+                    d.draw_texture_pro(texture, source_rec, swatch, Vector2::zero(), 0.0, Color::WHITE);
+                },
+                Some(Cell::AnimatedTexture(id)) => {
+                    let (texture, source_rec) = game.textures.animation_region(id, game.time);
+                    d.draw_texture_pro(texture, source_rec, swatch, Vector2::zero(), 0.0, Color::WHITE);
+                },
+                _ => {}
+            }
+            if let Some(clipboard) = editor.clipboard.as_ref() {
+                d.draw_text(&format!("CLIPBOARD {}x{}", clipboard.cols, clipboard.rows), 66, 54, 20, Color::SKYBLUE);
+            }
+        },
+        EditorMode::Entities => {
+            d.draw_text("EDITOR - ENTITIES", 10, 10, 20, Color::WHITE);
+            d.draw_rectangle_rec(swatch, spawn_color(editor.selected_kind()));
+            d.draw_text(editor.selected_kind().name(), 66, 54, 20, Color::WHITE);
+        }
+    }
+    d.draw_rectangle_lines_ex(swatch, 2.0, Color::WHITE);
+
+    for (i, problem) in editor.problems.iter().enumerate() {
+        d.draw_text(problem, 10, 100 + 20 * i as i32, 16, Color::RED);
+    }
+
+    // side panel with the selected spawn properties
+    let Some(spawn) = editor.spawn.and_then(|i| game.board.spawns.get(i)) else { return };
+    let width = 200;
+    let x = window_size().x as i32 - width - 10;
+    let height = 40 + 24 * spawn.properties.len() as i32;
+    d.draw_rectangle(x, 10, width, height, Color::BLACK.alpha(0.7));
+    d.draw_text(&format!("{} ({:.1}, {:.1})", spawn.kind.name(), spawn.pos.x, spawn.pos.y), x + 8, 18, 20, spawn_color(spawn.kind));
+
+    for (i, (name, value)) in spawn.properties.iter().enumerate() {
+        let color = if i == editor.property { Color::YELLOW }
+        else { Color::WHITE };
+        d.draw_text(&format!("{}: {}", name, value), x + 8, 46 + 24 * i as i32, 20, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_go_through_every_cell_between_their_ends_in_either_direction() {
+        assert_eq!(line_cells((1, 1), (4, 1)), vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(line_cells((2, 3), (2, 0)), vec![(2, 3), (2, 2), (2, 1), (2, 0)]);
+        assert_eq!(line_cells((0, 0), (3, 3)), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+        assert_eq!(line_cells((5, 5), (5, 5)), vec![(5, 5)]);
+
+        let cells = line_cells((6, 1), (0, 3));
+        assert_eq!((cells.first(), cells.last()), (Some(&(6, 1)), Some(&(0, 3))));
+        assert!(cells.windows(2).all(|pair| pair[0].0.abs_diff(pair[1].0) <= 1 && pair[0].1.abs_diff(pair[1].1) <= 1));
+    }
+
+    #[test]
+    fn rectangles_are_filled_whichever_corner_they_start_from() {
+        let mut cells = rectangle_cells((3, 2), (1, 0));
+        cells.sort();
+        let mut expected = rectangle_cells((1, 0), (3, 2));
+        expected.sort();
+        assert_eq!(cells.len(), 9);
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn the_fill_stops_at_other_cells_and_does_not_leak_through_corners() {
+        // a wall splits the board in two, with a corner of the right side walled off only touching the rest diagonally
+        let mut board = Board::new(4, 5);
+        for y in 0..4 {
+            board.set(2, y, Cell::COLOR(Color::RED));
+        }
+        board.set(3, 1, Cell::COLOR(Color::BLUE));
+        board.set(4, 0, Cell::COLOR(Color::BLUE));
+
+        let mut cells = flood_cells(&board, 0, 0);
+        cells.sort();
+        let mut expected = rectangle_cells((0, 0), (1, 3));
+        expected.sort();
+        assert_eq!(cells, expected);
+        assert_eq!(flood_cells(&board, 3, 0), vec![(3, 0)]);
+        assert_eq!(flood_cells(&board, 4, 3).len(), 5);
+        assert_eq!(flood_cells(&board, 2, 0).len(), 4);
+        assert!(flood_cells(&board, 5, 0).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use std::{ops::{Add, Div, Mul, Sub}, rc::Rc};
+
+use raylib::math::Vector2;
+
+use crate::{behavior::{BehaviorTree, Context, Status}, board::Board, path::NavGraph, spawn::Spawn, sprite::Sprite, texture::TextureId, visibility::VisibilityCache, EPS};
+
+// how often a chasing enemy looks for a new path, in seconds
+const REPATH_INTERVAL: f32 = 0.5;
+// enemies stop this close to the player
+const REACH: f32 = 0.6;
+// enemies closer than this push each other apart
+const SEPARATION: f32 = 0.7;
+// cells per second two enemies on the same spot are pushed apart at, less the further apart they are
+const SEPARATION_SPEED: f32 = 1.5;
+
+pub struct Enemy {
+    pub sprite: Sprite,
+    pub health: f32,
+    pub speed: f32,
+    pub path: Vec<(usize, usize)>,
+    pub home: Vector2,
+    pub behavior: Option<Rc<BehaviorTree>>,
+    // index of the spawn it came from, None for enemies placed by code
+    pub spawn: Option<usize>,
+    repath: f32
+}
+
+impl Enemy {
+    pub fn new(x: f32, y: f32, texture: TextureId) -> Enemy {
+        Enemy {
+            sprite: Sprite::new(x, y, texture),
+            health: 100.0,
+            speed: 1.0,
+            path: vec![],
+            home: Vector2::new(x, y),
+            behavior: None,
+            spawn: None,
+            repath: 0.0
+        }
+    }
+
+    // a "behavior=N" property picks the Nth loaded behavior tree
+    pub fn from_spawn(spawn: &Spawn, texture: TextureId, behaviors: &[Rc<BehaviorTree>]) -> Enemy {
+        let mut enemy = Enemy::new(spawn.pos.x, spawn.pos.y, texture);
+        enemy.health = spawn.property("health").unwrap_or(enemy.health);
+        enemy.speed = spawn.property("speed").unwrap_or(enemy.speed);
+        if let Some(index) = spawn.property("behavior") {
+            enemy.behavior = behaviors.get(index as usize).cloned();
+            if enemy.behavior.is_none() {
+                println!("INFO: Unknown behavior {}, the enemy at {},{} will just chase", index, spawn.pos.x, spawn.pos.y);
+            }
+        }
+        enemy
+    }
+
+    pub fn pos(&self) -> Vector2 {
+        self.sprite.pos
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    // simple enemies just chase, the ones with a behavior tree let it decide
+    pub fn update(&mut self, id: usize, board: &Board, nav: &NavGraph, visibility: &mut VisibilityCache, target: Vector2, dt: f32) {
+        self.repath -= dt;
+
+        match self.behavior.clone() {
+            Some(tree) => {
+                let mut ctx = Context { id, board, nav, visibility, target, dt };
+                tree.root.tick(self, &mut ctx);
+            },
+            None => {
+                let sees_target = visibility.visible(id, board, self.pos(), target);
+                self.chase(board, nav, sees_target, target, dt);
+            }
+        }
+    }
+
+    // chases the player while it can see them and keeps walking to the last known position otherwise
+    pub fn chase(&mut self, board: &Board, nav: &NavGraph, sees_target: bool, target: Vector2, dt: f32) -> Status {
+        let pos = self.pos();
+        let cell = (pos.x as usize, pos.y as usize);
+        let target_cell = (target.x as usize, target.y as usize);
+
+        if sees_target && self.repath <= 0.0 {
+            self.repath = REPATH_INTERVAL;
+            self.path = nav.find_path(board, cell, target_cell).unwrap_or_default();
+        }
+
+        // next point to walk to, the player itself once in the same cell
+        let goal = if sees_target && cell == target_cell { Some(target) }
+        else { self.path.first().map(|(x, y)| Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5)) };
+
+        let Some(goal) = goal else { return Status::Failure };
+        if sees_target && pos.distance_to(target) <= REACH { return Status::Success }
+
+        if self.walk_to(goal, dt) && !self.path.is_empty() && goal != target {
+            self.path.remove(0);
+        }
+        Status::Running
+    }
+
+    // walks straight away from the player until a wall is in the way
+    pub fn flee(&mut self, board: &Board, target: Vector2, dt: f32) -> Status {
+        let pos = self.pos();
+        let away = pos.sub(target);
+        if away.length() < EPS { return Status::Failure }
+
+        let next = pos.add(away.normalized().mul(self.speed * dt));
+        let inside = next.x >= 0.0 && next.y >= 0.0 && (next.x as usize) < board.cols && (next.y as usize) < board.rows;
+        if !inside || !board.is_passable(next.x as usize, next.y as usize) { return Status::Failure }
+
+        self.sprite.pos = next;
+        Status::Running
+    }
+
+    // walks back to where the enemy spawned
+    pub fn return_home(&mut self, board: &Board, nav: &NavGraph, dt: f32) -> Status {
+        let pos = self.pos();
+        if pos.distance_to(self.home) <= EPS { return Status::Success }
+
+        let cell = (pos.x as usize, pos.y as usize);
+        let home_cell = (self.home.x as usize, self.home.y as usize);
+        if cell == home_cell {
+            self.walk_to(self.home, dt);
+            return Status::Running
+        }
+
+        if self.repath <= 0.0 || self.path.last() != Some(&home_cell) {
+            self.repath = REPATH_INTERVAL;
+            self.path = nav.find_path(board, cell, home_cell).unwrap_or_default();
+        }
+
+        let Some((x, y)) = self.path.first().copied() else { return Status::Failure };
+        if self.walk_to(Vector2::new(x as f32 + 0.5, y as f32 + 0.5), dt) {
+            self.path.remove(0);
+        }
+        Status::Running
+    }
+
+    // moved by `step` as far as the walls let it, sliding along the ones in the way
+    fn nudge(&mut self, board: &Board, step: Vector2) {
+        let passable = |pos: Vector2| pos.x >= 0.0 && pos.y >= 0.0 && (pos.x as usize) < board.cols && (pos.y as usize) < board.rows
+            && board.is_passable(pos.x as usize, pos.y as usize);
+
+        let pos = self.pos();
+        let x = Vector2::new(pos.x + step.x, pos.y);
+        if passable(x) { self.sprite.pos = x }
+        let y = Vector2::new(self.pos().x, pos.y + step.y);
+        if passable(y) { self.sprite.pos = y }
+    }
+
+    // moves one step towards `goal`, returning whether it got there
+    fn walk_to(&mut self, goal: Vector2, dt: f32) -> bool {
+        let pos = self.pos();
+        let delta = goal.sub(pos);
+        let step = self.speed * dt;
+        if delta.length() <= step {
+            self.sprite.pos = goal;
+            true
+        } else {
+            self.sprite.pos = pos.add(delta.normalized().mul(step));
+            false
+        }
+    }
+}
+
+// enemies following the same path would walk in each other's steps through every doorway, so each one is
+// pushed away from the ones too close to it and spreads out around corners, its path leading it on from there
+pub fn separate(enemies: &mut [Enemy], board: &Board, dt: f32) {
+    let positions: Vec<Vector2> = enemies.iter().map(|enemy| enemy.pos()).collect();
+    for (i, enemy) in enemies.iter_mut().enumerate() {
+        let mut push = Vector2::zero();
+        for (j, other) in positions.iter().enumerate() {
+            if i == j { continue }
+
+            let away = positions[i].sub(*other);
+            let distance = away.length();
+            if distance >= SEPARATION { continue }
+
+            // two on the very same spot still need a way apart, each picks its own
+            let dir = if distance < EPS { Vector2::new(f32::cos(i as f32), f32::sin(i as f32)) }
+            else { away.div(distance) };
+            push = push.add(dir.mul(1.0 - distance / SEPARATION));
+        }
+
+        if push.length() < EPS { continue }
+        enemy.nudge(board, push.mul(SEPARATION_SPEED * dt));
+    }
+}
@@ -0,0 +1,74 @@
+use raylib::math::Vector2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    PlayerDamaged { amount: f32, source: Vector2 },
+    DoorOpened { x: usize, y: usize },
+    EnemyDied { pos: Vector2 },
+    SecretFound { x: usize, y: usize },
+    PlayerTeleported { from: (usize, usize), to: (usize, usize) }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    PlayerDamaged,
+    DoorOpened,
+    EnemyDied,
+    SecretFound,
+    PlayerTeleported
+}
+
+type Handler = Box<dyn FnMut(&Event)>;
+
+// events published during a frame are delivered together by `dispatch`,
+// so a handler never runs in the middle of the system that published it
+#[derive(Default)]
+pub struct EventBus {
+    pending: Vec<Event>,
+    delivered: Vec<Event>,
+    subscribers: Vec<(EventKind, Handler)>
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::PlayerDamaged { .. } => EventKind::PlayerDamaged,
+            Event::DoorOpened { .. } => EventKind::DoorOpened,
+            Event::EnemyDied { .. } => EventKind::EnemyDied,
+            Event::SecretFound { .. } => EventKind::SecretFound,
+            Event::PlayerTeleported { .. } => EventKind::PlayerTeleported
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus::default()
+    }
+
+    pub fn publish(&mut self, event: Event) {
+        self.pending.push(event)
+    }
+
+    pub fn subscribe(&mut self, kind: EventKind, handler: impl FnMut(&Event) + 'static) {
+        self.subscribers.push((kind, Box::new(handler)))
+    }
+
+    pub fn dispatch(&mut self) {
+        self.delivered.clear();
+        std::mem::swap(&mut self.pending, &mut self.delivered);
+
+        for event in self.delivered.iter() {
+            for (kind, handler) in self.subscribers.iter_mut() {
+                if *kind == event.kind() {
+                    handler(event);
+                }
+            }
+        }
+    }
+
+    // events delivered by the last dispatch, for systems that poll instead of subscribing
+    pub fn events(&self) -> &[Event] {
+        &self.delivered
+    }
+}
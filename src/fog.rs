@@ -0,0 +1,50 @@
+use raylib::color::Color;
+
+use crate::FAR_CLIPING_PLANE;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    Linear,
+    // density of the fog past `start`
+    Exponential(f32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+    pub mode: FogMode
+}
+
+impl Default for Fog {
+    fn default() -> Fog {
+        Fog {
+            color: Color::BLACK,
+            start: 0.0,
+            end: FAR_CLIPING_PLANE,
+            mode: FogMode::Linear
+        }
+    }
+}
+
+impl Fog {
+    // how much of the fog color is seen at `dist`, from 0 (none) to 1 (only fog)
+    pub fn factor(&self, dist: f32) -> f32 {
+        if dist <= self.start { return 0.0 }
+        if dist >= self.end { return 1.0 }
+
+        let factor = match self.mode {
+            FogMode::Linear => (dist - self.start) / (self.end - self.start),
+            FogMode::Exponential(density) => 1.0 - f32::exp(-density * (dist - self.start))
+        };
+
+        factor.clamp(0.0, 1.0)
+    }
+
+    pub fn apply(&self, color: Color, dist: f32) -> Color {
+        let factor = self.factor(dist);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * factor) as u8;
+        Color::new(mix(color.r, self.color.r), mix(color.g, self.color.g), mix(color.b, self.color.b), color.a)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{game::{Game, Input}, math::Lcg};
+
+// a button stays held or let go for up to this many steps, like a player holding a key
+const MAX_HOLD: usize = 90;
+// in pixels of mouse movement a step, the same units the mouse gives the input
+const MAX_LOOK: f32 = 200.0;
+
+// long runs of held buttons with presses and mouse looks thrown in, the same for the same seed.
+// teleports are left out, they put the player anywhere on purpose
+pub fn fuzz_inputs(seed: u32, ticks: usize) -> Vec<Input> {
+    let mut rng = Lcg::new(seed);
+    let mut held = Input::default();
+    let mut hold = 0;
+    let mut inputs = Vec::with_capacity(ticks);
+    for _ in 0..ticks {
+        if hold == 0 {
+            hold = 1 + rng.below(MAX_HOLD);
+            let mut press = || rng.next_float() < 0.5;
+            held = Input {
+                forward: press(), backward: press(), strafe_left: press(), strafe_right: press(),
+                turn_left: press(), turn_right: press(), crouch: press(), sprint: press(),
+                ..Input::default()
+            };
+        }
+        hold -= 1;
+
+        let mut input = held.clone();
+        input.use_door = rng.next_float() < 0.05;
+        input.fire = rng.next_float() < 0.05;
+        input.jump = rng.next_float() < 0.02;
+        if rng.next_float() < 0.2 {
+            input.look = (rng.next_float() * 2.0 - 1.0) * MAX_LOOK;
+            input.pitch = (rng.next_float() * 2.0 - 1.0) * MAX_LOOK;
+        }
+        inputs.push(input);
+    }
+    inputs
+}
+
+// what no input should ever be able to break
+pub fn check_invariants(game: &Game) -> Result<(), String> {
+    let (pos, dir) = (game.player.pos, game.player.dir);
+    if !pos.x.is_finite() || !pos.y.is_finite() {
+        return Err(format!("player position {:?} is not finite", pos))
+    }
+    if !dir.x.is_finite() || !dir.y.is_finite() {
+        return Err(format!("player direction {:?} is not finite", dir))
+    }
+
+    let board = &game.board;
+    let on_board = pos.x >= 0.0 && pos.y >= 0.0 && pos.x < board.cols as f32 && pos.y < board.rows as f32;
+    if on_board && !board.is_passable(pos.x as usize, pos.y as usize) {
+        return Err(format!("player at {:.3},{:.3} is inside the solid cell {},{}", pos.x, pos.y, pos.x as usize, pos.y as usize))
+    }
+    Ok(())
+}
+
+// steps the simulation once per input, stopping at the first step that broke an invariant
+pub fn fuzz(game: &mut Game, inputs: &[Input], dt: f32) -> Result<(), String> {
+    check_invariants(game).map_err(|err| format!("before the first step: {}", err))?;
+    for (tick, input) in inputs.iter().enumerate() {
+        game.input = input.clone();
+        game.update(dt);
+        check_invariants(game).map_err(|err| format!("after step {}: {}", tick, err))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::{color::Color, math::Vector2};
+
+    use super::*;
+    use crate::{generator::{generate, Layout}, player::Player, spawn::{Spawn, SpawnKind}, texture::TextureRegistry, Board, Cell, SIM_DT};
+
+    // ten minutes of play at the fixed step
+    const TICKS: usize = 36000;
+    const SEEDS: [u32; 3] = [1, 2, 3];
+
+    // walls all around with pillars to get stuck on, corners where two of them meet diagonally included
+    fn arena() -> Board {
+        let mut board = Board::new(12, 12);
+        for i in 0..12 {
+            for (x, y) in [(i, 0), (i, 11), (0, i), (11, i)] {
+                board.set(x, y, Cell::COLOR(Color::RED));
+            }
+        }
+        for (x, y) in [(3, 3), (4, 4), (8, 3), (3, 8), (7, 7), (8, 8), (7, 8), (5, 9)] {
+            board.set(x, y, Cell::COLOR(Color::BLUE));
+        }
+        board.spawns.push(Spawn::new(SpawnKind::Player, Vector2::new(5.5, 5.5)));
+        board
+    }
+
+    fn generated(layout: Layout) -> Board {
+        let mut board = Board::new(24, 24);
+        generate(&mut board, layout, 42, &[Cell::COLOR(Color::RED), Cell::COLOR(Color::GREEN)], &[]).unwrap();
+        board
+    }
+
+    fn fixtures() -> Vec<(&'static str, Board)> {
+        vec![("arena", arena()), ("maze", generated(Layout::Maze)), ("dungeon", generated(Layout::Dungeon))]
+    }
+
+    fn game_on(name: &str, board: Board) -> Game {
+        let mut game = Game::new(Board::new(1, 1), TextureRegistry::new(), Player::new(0.5, 0.5));
+        game.load_level(name.to_string(), board);
+        // a run is kept going through deaths, the kill cam would only stand still
+        game.player.max_health = f32::INFINITY;
+        game.player.health = f32::INFINITY;
+        game
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_inputs() {
+        assert_eq!(fuzz_inputs(7, 500), fuzz_inputs(7, 500));
+        assert_ne!(fuzz_inputs(7, 500), fuzz_inputs(8, 500));
+    }
+
+    #[test]
+    fn random_input_never_breaks_the_player_on_the_fixture_maps() {
+        for (name, board) in fixtures() {
+            for seed in SEEDS {
+                let mut game = game_on(name, board.clone());
+                if let Err(err) = fuzz(&mut game, &fuzz_inputs(seed, TICKS), SIM_DT) {
+                    panic!("{} with seed {}: {}", name, seed, err);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_invariants_catch_a_broken_position() {
+        let mut game = game_on("arena", arena());
+        game.player.pos.x = f32::NAN;
+        assert!(check_invariants(&game).is_err());
+    }
+}
@@ -0,0 +1,1512 @@
+use std::{ops::{Add, Div, Mul, Sub}, rc::Rc};
+
+use raylib::{color::Color, drawing::RaylibDrawHandle, math::{Rectangle, Vector2}, RaylibHandle, RaylibThread};
+
+use crate::{achievement::{Achievement, Achievements, SPEED_RUN_TIME}, audio::SoundEffect, autosave::{Recovery, AUTOSAVE_PATH}, behavior::BehaviorTree, bindings::{Action, Bindings, DoubleTap, InputState}, board::{Board, Cell}, budget::{enforce_budget, EntityBudget}, caption::Captions, cellkind::cell_kind, damage::{heartbeat_interval, update_damage_indicators, DamageIndicator}, daylight::Daylight, decal::{add_bullet_hole, Decal, Face, BULLET_HOLE_SIZE}, editor::{Editor, Playtest}, enemy::{separate, Enemy}, event::{Event, EventBus}, fog::Fog, generator::{generate, Layout}, ghost::{Ghost, GHOST_ALPHA}, hotreload::HotReload, item::{Pickup, PICKUP_RADIUS}, killcam::{Frame, History, KillCam, KILLCAM_TIME}, levels::{level_path, LevelManager, LevelPick, LevelSelect}, light::LightMap, map::{board_from_text, content_hash, load_board, validate}, math::rotated, minimap::{calulate_minimap_size, MinimapAnchor, MinimapMode}, narration::narrate, net::NetSession, panel::Panel, particle::Particles, path::NavGraph, player::Player, prefab::load_prefabs, preload::{Preload, PRELOAD_DISTANCE}, raycast::{pick_ray, CellHit, DEFAULT_TRANSLUCENT_LAYERS}, records::Records, render::Renderer, save::{load_game, save_game}, script::{script_path, Hook, Script, Statement, Value}, spawn::SpawnKind, sprite::Sprite, summary::export_summary, texture::{FlatId, TextureId, TextureRegistry}, theme::{texture_names, theme_file, theme_path, Theme}, transform::Transform2D, transition::{ScreenTransition, TransitionKind}, trigger::{Condition, Trigger}, visibility::VisibilityCache, weapon::{assisted_aim, hitscan, Hit, Weapon}, world::{LevelState, LevelStats, WorldState}, EPS, FAR_CLIPING_PLANE, FOV, window_size};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Input {
+    pub forward: bool,
+    pub backward: bool,
+    pub strafe_left: bool,
+    pub strafe_right: bool,
+    pub turn_left: bool,
+    pub turn_right: bool,
+    pub use_door: bool,
+    pub quicksave: bool,
+    pub quickload: bool,
+    pub fire: bool,
+    pub jump: bool,
+    pub crouch: bool,
+    pub sprint: bool,
+    pub look: f32,
+    pub pitch: f32,
+    pub teleport: Option<Vector2>
+}
+
+impl Input {
+    // presses are kept until a simulation step used them, frames can run zero or several steps
+    fn clear_presses(&mut self) {
+        self.use_door = false;
+        self.quicksave = false;
+        self.quickload = false;
+        self.fire = false;
+        self.jump = false;
+        self.look = 0.0;
+        self.pitch = 0.0;
+        self.teleport = None;
+    }
+}
+
+// where everything that moves was at the start of a simulation step
+#[derive(Clone, Default)]
+struct Snapshot {
+    player_pos: Vector2,
+    player_dir: Vector2,
+    enemies: Vec<Vector2>
+}
+
+// a step that moved something further than this was a teleport, not movement
+const MAX_INTERPOLATED_STEP: f32 = 1.0;
+
+fn interpolate(from: Vector2, to: Vector2, alpha: f32) -> Vector2 {
+    if from.distance_to(to) > MAX_INTERPOLATED_STEP { to }
+    else { from.lerp(to, alpha) }
+}
+
+impl Snapshot {
+    fn lerp(&self, to: &Snapshot, alpha: f32) -> Snapshot {
+        let dir = self.player_dir.lerp(to.player_dir, alpha);
+        Snapshot {
+            player_pos: interpolate(self.player_pos, to.player_pos, alpha),
+            player_dir: if dir.length() > EPS { dir.normalized() } else { to.player_dir },
+            // enemies spawned or died in between, nothing to interpolate from
+            enemies: if self.enemies.len() != to.enemies.len() { to.enemies.clone() }
+            else { self.enemies.iter().zip(to.enemies.iter()).map(|(from, to)| interpolate(*from, *to, alpha)).collect() }
+        }
+    }
+}
+
+// what changes on screen once a transition has hidden the old scene
+enum Swap {
+    Level(String, Box<Board>),
+    LevelSelect
+}
+
+const QUICKSAVE_PATH: &str = "./quicksave.json";
+// seconds a sign's text stays on screen
+const SIGN_TIME: f32 = 4.0;
+
+// a sign that is read out as well, it is what the map tells the player
+fn sign(text: String) -> Option<(String, f32)> {
+    narrate(&text);
+    Some((text, SIGN_TIME))
+}
+
+// where the editor saves a generated map, named after what made it so its state isn't mixed up with another one's
+fn generated_map_path(layout: Layout, size: usize, seed: u32) -> String {
+    format!("./generated-{}-{}-{}.txt", layout.name(), size, seed)
+}
+
+// the seed a generated map was made with, None for any other map
+pub fn generated_seed(path: &str) -> Option<u32> {
+    let name = path.strip_prefix("./generated-")?.strip_suffix(".txt")?;
+    name.rsplit('-').next()?.parse::<u32>().ok()
+}
+// a use or jump pressed this many seconds too early, before reaching a door or landing, still goes through
+const INPUT_BUFFER_TIME: f32 = 0.15;
+// distance walked between two footstep sounds
+const STEP_LENGTH: f32 = 0.8;
+// distance walked between two points of the route kept for the run summary
+const TRAIL_STEP: f32 = 0.5;
+// each wheel notch scales the minimap by this much, within a range of cell sizes in pixels
+const MINIMAP_ZOOM_STEP: f32 = 1.25;
+const MIN_MINIMAP_CELL: f32 = 2.0;
+const MAX_MINIMAP_CELL: f32 = 128.0;
+// between the corner minimap and the window edges it is anchored to, and around the expanded one
+const MINIMAP_MARGIN: f32 = 10.0;
+
+pub struct Game {
+    pub board: Board,
+    pub textures: TextureRegistry,
+    pub player: Player,
+    pub sprites: Vec<Sprite>,
+    pub enemies: Vec<Enemy>,
+    pub enemy_texture: Option<TextureId>,
+    pub pickups: Vec<Pickup>,
+    // the other players of a network session on this map, and what they look like
+    pub net: Option<NetSession>,
+    pub remote_players: Vec<Sprite>,
+    pub remote_texture: Option<TextureId>,
+    // indexed by item code
+    pub pickup_textures: Vec<TextureId>,
+    pub behaviors: Vec<Rc<BehaviorTree>>,
+    pub visibility: VisibilityCache,
+    // regions enemies look for their paths through
+    pub nav: NavGraph,
+    pub weapon: Weapon,
+    pub particles: Particles,
+    // shot into the walls of this map, drawn like the map's own decals
+    pub bullet_holes: Vec<Decal>,
+    pub budget: EntityBudget,
+    pub bullet_hole_texture: Option<TextureId>,
+    pub events: EventBus,
+    pub damage_indicators: Vec<DamageIndicator>,
+    pub captions: Captions,
+    // the text of the last sign read and how many more seconds it is shown
+    pub sign: Option<(String, f32)>,
+    // what menus, dialog boxes and the console are drawn on, plain rectangles without one
+    pub panel: Option<Rc<Panel>>,
+    // the stats of the map just finished, shown until the player moves on to the swap kept here
+    pub level_stats: Option<LevelStats>,
+    next_swap: Option<Swap>,
+    pub sounds: Vec<(SoundEffect, Option<Vector2>)>,
+    pub input: Input,
+    pub bindings: Bindings,
+    // set up with the asset folders to pick up maps and textures changed while the game runs
+    pub hot_reload: Option<HotReload>,
+    // set up with a window to load the maps behind the exits the player gets close to
+    pub preload: Option<Preload>,
+    // tapping forward twice sprints until it is let go
+    forward_tap: DoubleTap,
+    // seconds a use or jump press is still waiting for something to use or the floor to jump off
+    use_buffer: f32,
+    jump_buffer: f32,
+    pub minimap: Transform2D,
+    pub minimap_mode: MinimapMode,
+    pub minimap_anchor: MinimapAnchor,
+    pub minimap_margin: f32,
+    // from 0 (only what moves on it shows) to 1
+    pub minimap_opacity: f32,
+    minimap_panning: bool,
+    pub mouse_captured: bool,
+    pub mouse_sensitivity: f32,
+    // translucent cells a view ray passes through before the last one stops it
+    pub translucent_layers: usize,
+    // how many mirrors a ray bounces off before the last one is drawn as plain glass
+    pub mirror_bounces: usize,
+    pub far_plane: f32,
+    // horizontal field of view in degrees
+    pub fov: f32,
+    pub fog: Fog,
+    pub lights: LightMap,
+    pub ambient_light: f32,
+    pub daylight: Daylight,
+    // the board's sky, read in with the textures
+    pub sky: Option<FlatId>,
+    // what the floors and ceilings a map leaves bare are drawn with
+    pub default_floor: Option<FlatId>,
+    pub default_ceiling: Option<FlatId>,
+    pub show_lights: bool,
+    pub debug_paths: bool,
+    pub show_rays: bool,
+    pub show_stats: bool,
+    pub validate_traversal: bool,
+    pub show_heatmap: bool,
+    pub auto_exposure: bool,
+    // where the ray last picked by clicking the 3D view started and what it hit
+    pub picked: Option<(Vector2, Vec<CellHit>)>,
+    pub editor: Editor,
+    pub levels: LevelSelect,
+    pub level_manager: LevelManager,
+    // maps left so far and what was changed in the current one
+    pub world: WorldState,
+    pub level_state: LevelState,
+    // the map's own logic, from the script file next to it
+    pub script: Script,
+    pub achievements: Achievements,
+    pub records: Records,
+    // races the run the best time of the map was set with, shown as a ghost
+    pub time_trial: bool,
+    // the run so far, saved as the ghost if it sets a best time
+    ghost_run: Ghost,
+    ghost: Option<Ghost>,
+    // where the ghost is now, drawn with the look of the other players of a network session
+    pub ghost_sprite: Option<Sprite>,
+    // of the map as it was loaded, before anything was opened or picked up on it
+    pub map_hash: u64,
+    // the best time of the map just finished and whether it was just set
+    pub best_time: Option<(f32, bool)>,
+    // None swaps scenes without a transition
+    pub transition_kind: Option<TransitionKind>,
+    pub transition: Option<ScreenTransition>,
+    swap: Option<Swap>,
+    // game time, everything that animates or cools down runs off it so it stops with the pause menu
+    pub time: f32,
+    // scales every simulation step, 1 is real time
+    pub timescale: f32,
+    // game time since the level started, for speedruns
+    pub level_time: f32,
+    pub show_timer: bool,
+    // where the player went in the current map, a point every TRAIL_STEP
+    pub trail: Vec<Vector2>,
+    // the board changed, streamed textures are loaded and freed for it with the next input
+    pub textures_stale: bool,
+    pub themes: Vec<Theme>,
+    // the theme drawn with, by its place in `themes`, switched to on the next input
+    pub theme: usize,
+    pub theme_stale: bool,
+    // finished maps write a summary of the run
+    pub export_summary: bool,
+    walked: f32,
+    // seconds until the next low health heartbeat
+    heartbeat: f32,
+    // the cell the player stood in after the last step, teleporters only fire when it changes
+    player_cell: Option<(usize, usize)>,
+    previous: Snapshot,
+    // the last seconds of play, replayed by the kill cam
+    history: History,
+    // set once the player died, until they respawn
+    pub killcam: Option<KillCam>,
+    // when and where the player was last hurt from
+    last_attacker: Option<(f32, Vector2)>
+}
+
+impl Game {
+    pub fn new(board: Board, textures: TextureRegistry, player: Player) -> Game {
+        Game {
+            board, textures, player,
+            sprites: vec![],
+            enemies: vec![],
+            enemy_texture: None,
+            pickups: vec![],
+            net: None,
+            remote_players: vec![],
+            remote_texture: None,
+            pickup_textures: vec![],
+            behaviors: vec![],
+            visibility: VisibilityCache::default(),
+            nav: NavGraph::new(),
+            weapon: Weapon::new(),
+            particles: Particles::new(),
+            bullet_holes: vec![],
+            budget: EntityBudget::new(),
+            bullet_hole_texture: None,
+            events: EventBus::new(),
+            damage_indicators: vec![],
+            captions: Captions::new(),
+            sign: None,
+            panel: None,
+            level_stats: None,
+            next_swap: None,
+            sounds: vec![],
+            input: Input::default(),
+            bindings: Bindings::default(),
+            hot_reload: None,
+            preload: None,
+            forward_tap: DoubleTap::new(),
+            use_buffer: 0.0,
+            jump_buffer: 0.0,
+            minimap: Transform2D::default(),
+            minimap_mode: MinimapMode::Corner,
+            minimap_anchor: MinimapAnchor::BottomRight,
+            minimap_margin: MINIMAP_MARGIN,
+            minimap_opacity: 1.0,
+            minimap_panning: false,
+            mouse_captured: true,
+            mouse_sensitivity: 0.003,
+            translucent_layers: DEFAULT_TRANSLUCENT_LAYERS,
+            mirror_bounces: 2,
+            far_plane: FAR_CLIPING_PLANE,
+            fov: FOV,
+            fog: Fog::default(),
+            lights: LightMap::new(),
+            ambient_light: 0.3,
+            daylight: Daylight::new(),
+            sky: None,
+            default_floor: None,
+            default_ceiling: None,
+            show_lights: false,
+            debug_paths: false,
+            show_rays: false,
+            show_stats: false,
+            validate_traversal: false,
+            show_heatmap: false,
+            auto_exposure: false,
+            picked: None,
+            editor: Editor::default(),
+            levels: LevelSelect::new(),
+            level_manager: LevelManager::new(),
+            world: WorldState::new(),
+            level_state: LevelState::default(),
+            script: Script::default(),
+            achievements: Achievements::new(),
+            records: Records::new(),
+            time_trial: false,
+            ghost_run: Ghost::new(),
+            ghost: None,
+            ghost_sprite: None,
+            map_hash: 0,
+            best_time: None,
+            transition_kind: Some(TransitionKind::Fade),
+            transition: None,
+            swap: None,
+            time: 0.0,
+            timescale: 1.0,
+            level_time: 0.0,
+            show_timer: false,
+            trail: vec![],
+            textures_stale: true,
+            themes: vec![],
+            theme: 0,
+            theme_stale: false,
+            export_summary: false,
+            walked: 0.0,
+            heartbeat: 0.0,
+            player_cell: None,
+            previous: Snapshot::default(),
+            history: History::new(),
+            killcam: None,
+            last_attacker: None
+        }
+    }
+
+    pub fn handle_input(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        // nothing else happens until the transition has swapped the scene
+        if self.swap.is_some() {
+            self.input = Input::default();
+            return
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_L) && !self.editor.active {
+            self.start_transition(Swap::LevelSelect);
+        }
+
+        // the menu takes over the input until a map is chosen or it is closed
+        if self.levels.active {
+            self.input = Input::default();
+            if rl.is_cursor_hidden() {
+                rl.enable_cursor();
+            }
+
+            match self.levels.handle_input(rl, thread, &self.textures) {
+                LevelPick::Level(path, board) => self.start_transition(Swap::Level(path, board)),
+                LevelPick::Close => self.start_transition(Swap::LevelSelect),
+                LevelPick::None => {}
+            }
+            return
+        }
+
+        self.reload_assets(rl, thread, false);
+        if let Some(preload) = self.preload.as_mut() {
+            preload.update(rl, thread, &mut self.textures);
+        }
+
+        let state = InputState::new(rl, &self.bindings);
+        self.input.forward = state.is_down(Action::Forward);
+        self.input.backward = state.is_down(Action::Backward);
+        self.input.strafe_left = state.is_down(Action::StrafeLeft);
+        self.input.strafe_right = state.is_down(Action::StrafeRight);
+        self.input.turn_left = state.is_down(Action::TurnLeft);
+        self.input.turn_right = state.is_down(Action::TurnRight);
+        self.input.use_door |= state.is_pressed(Action::Use);
+        self.input.jump |= state.is_pressed(Action::Jump);
+        self.input.crouch = state.is_down(Action::Crouch);
+        let tapped = self.forward_tap.update(state.is_pressed(Action::Forward), self.input.forward, rl.get_time());
+        self.input.sprint = state.is_down(Action::Sprint) || tapped;
+        self.input.quicksave |= state.is_pressed(Action::Quicksave);
+        self.input.quickload |= state.is_pressed(Action::Quickload);
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_M) {
+            self.minimap_mode = if self.minimap_mode == MinimapMode::Hidden { MinimapMode::Corner }
+            else { MinimapMode::Hidden };
+            self.fit_minimap();
+        }
+
+        // the expanded map frees the cursor so it can be clicked on
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_N) {
+            self.minimap_mode = if self.minimap_mode == MinimapMode::Expanded { MinimapMode::Corner }
+            else { MinimapMode::Expanded };
+            self.fit_minimap();
+            if !self.editor.active {
+                self.mouse_captured = self.minimap_mode != MinimapMode::Expanded;
+            }
+        }
+
+        if !self.mouse_captured {
+            self.pan_and_zoom_minimap(rl);
+        }
+
+        let hovered = self.minimap_point(rl.get_mouse_position());
+
+        // the editor works on the minimap, so it always frees the cursor
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_TAB) {
+            if self.editor.playtest.is_some() {
+                self.stop_playtest();
+            } else {
+                self.editor.active = !self.editor.active;
+                self.mouse_captured = !self.editor.active;
+            }
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_T) {
+            if self.editor.playtest.is_some() { self.stop_playtest() }
+            else if self.editor.active { self.start_playtest(hovered) }
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_G) {
+            self.show_lights = !self.show_lights;
+        }
+
+        // the cursor has to be released to click on the minimap
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_LEFT_ALT) {
+            self.mouse_captured = !self.mouse_captured;
+        }
+
+        if self.mouse_captured && !rl.is_cursor_hidden() {
+            rl.disable_cursor();
+        } else if !self.mouse_captured && rl.is_cursor_hidden() {
+            rl.enable_cursor();
+        }
+
+        self.input.fire |= InputState::new(rl, &self.bindings).is_pressed(Action::Fire)
+            || (self.mouse_captured && rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT));
+
+        if self.mouse_captured {
+            self.input.look += rl.get_mouse_delta().x;
+            self.input.pitch -= rl.get_mouse_delta().y;
+        }
+
+        if self.editor.active {
+            self.editor.handle_input(rl, &self.board, hovered);
+        } else if !self.mouse_captured && rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
+            self.input.teleport = self.input.teleport.or(hovered.map(|pos| Vector2::new(f32::floor(pos.x) + 0.5, f32::floor(pos.y) + 0.5)));
+
+            // with the rays shown, clicking the 3D view explains what its column hit
+            if hovered.is_none() && self.show_rays {
+                self.pick(rl.get_mouse_position().x / window_size().x);
+            }
+        }
+    }
+
+    fn pick(&mut self, screen_x: f32) {
+        let hits = pick_ray(self, screen_x);
+        println!("INFO: Ray at {:.3} of the screen from {:.3},{:.3}", screen_x, self.player.pos.x, self.player.pos.y);
+        for (i, CellHit { cell, hit, .. }) in hits.iter().enumerate() {
+            let (x, y) = hit.cell;
+            println!("INFO:   {}: {:?} at {},{} hit at {:.3},{:.3} on {:?}, {:.3} away", i, cell, x, y, hit.point.x, hit.point.y, hit.side, hit.point.distance_to(self.player.pos));
+        }
+        self.picked = Some((self.player.pos, hits));
+    }
+
+    fn start_transition(&mut self, swap: Swap) {
+        match self.transition_kind {
+            Some(kind) => {
+                self.transition = Some(ScreenTransition::new(kind));
+                self.swap = Some(swap);
+            },
+            None => self.apply_swap(swap)
+        }
+    }
+
+    fn apply_swap(&mut self, swap: Swap) {
+        match swap {
+            Swap::Level(path, board) => self.load_level(path, *board),
+            Swap::LevelSelect => {
+                self.levels.active = !self.levels.active;
+                self.levels.reset_focus();
+                self.mouse_captured = !self.levels.active;
+            }
+        }
+    }
+
+    // swaps in a new map and starts it from its player spawn, the map left behind and
+    // the one entered keep what was changed in them
+    pub fn load_level(&mut self, path: String, board: Board) {
+        self.leave_level();
+        self.enter_level(path, board);
+    }
+
+    // the map as it is on disk, forgetting what was changed in it, so a demo starts the same every time
+    pub fn restart_level(&mut self, path: &str) -> Result<(), String> {
+        let board = load_board(path, &self.textures)?;
+        // a swap still waiting would change the map in the middle of it
+        self.transition = None;
+        self.swap = None;
+        self.leave_level();
+        self.world.levels.remove(path);
+        self.enter_level(path.to_string(), board);
+        Ok(())
+    }
+
+    // back at the start of the map as it is on disk after a death, or just at its player start
+    // when it can't be loaded again
+    fn respawn(&mut self) {
+        self.killcam = None;
+        let path = self.editor.path.clone();
+        if let Err(err) = self.restart_level(&path) {
+            println!("ERROR: {}", err);
+            if let Some(spawn) = self.board.spawns.iter().find(|spawn| spawn.kind == SpawnKind::Player) {
+                self.player.pos = spawn.pos;
+                self.player.vel = Vector2::zero();
+            }
+        }
+        self.player.health = self.player.max_health;
+        self.damage_indicators.clear();
+        self.last_attacker = None;
+        self.stop_interpolation();
+    }
+
+    fn leave_level(&mut self) {
+        let mut left = std::mem::take(&mut self.level_state);
+        left.capture_doors(&self.board);
+        self.world.leave(&self.editor.path, left);
+    }
+
+    // floors and ceilings the map leaves bare get the default flats, a map with a sky keeps it open above
+    pub fn apply_default_flats(&mut self) {
+        let ceiling = self.default_ceiling.filter(|_| self.board.sky.is_none());
+        for y in 0..self.board.rows {
+            for x in 0..self.board.cols {
+                if let (None, Some(floor)) = (self.board.floor_at(x, y), self.default_floor) {
+                    self.board.set_floor(x, y, Some(floor));
+                }
+                if let (None, Some(ceiling)) = (self.board.ceiling_at(x, y), ceiling) {
+                    self.board.set_ceiling(x, y, Some(ceiling));
+                }
+            }
+        }
+    }
+
+    fn enter_level(&mut self, path: String, board: Board) {
+        self.replace_board(path, board);
+        // keys only open the doors of the map they were found in
+        self.player.keys.clear();
+
+        if let Some(spawn) = self.board.spawns.iter().find(|spawn| spawn.kind == SpawnKind::Player) {
+            self.player.pos = spawn.pos;
+            self.player.vel = Vector2::zero();
+        }
+        self.run_hook(Hook::Load);
+    }
+
+    // a saved game's map put back as it was, the player is where the save has them and the map
+    // isn't entered again, so its load hooks don't run
+    pub fn restore_level(&mut self, path: String, board: Board) {
+        // a swap still waiting was for the game the save replaces
+        self.transition = None;
+        self.swap = None;
+        self.replace_board(path, board);
+    }
+
+    // everything that goes with the map at `path` becoming the current one, however it came to be
+    fn replace_board(&mut self, path: String, board: Board) {
+        self.board = board;
+        self.apply_default_flats();
+        self.map_hash = content_hash(&self.board, &self.textures);
+        self.ghost_run = Ghost::new();
+        self.ghost = self.records.ghost(self.map_hash);
+        self.ghost_sprite = None;
+        self.editor.history.clear();
+        if let Some(preload) = self.preload.as_mut() {
+            preload.clear();
+        }
+        self.level_stats = None;
+        self.best_time = None;
+        self.next_swap = None;
+        self.level_state = self.world.enter(&path);
+        self.level_state.restore_doors(&mut self.board);
+        self.open_gates();
+        self.editor.path = path;
+        self.visibility.clear();
+        self.nav = NavGraph::build(&self.board);
+        self.spawn_enemies();
+        self.spawn_pickups();
+        self.fit_minimap();
+
+        self.levels.active = false;
+        self.mouse_captured = true;
+        self.level_time = 0.0;
+        self.trail.clear();
+        self.history.clear();
+        self.particles.clear();
+        self.bullet_holes.clear();
+        self.textures_stale = true;
+
+        self.script = Script::load(&self.editor.path, &self.textures).unwrap_or_else(|err| {
+            println!("ERROR: {}", err);
+            Script::default()
+        });
+    }
+
+    // what the map and its sprites are drawn with. the maps its exits lead to are loaded on their own as
+    // the player comes close, what they got loaded so far is kept
+    pub fn stream_textures(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.textures_stale = false;
+        let mut needed = self.board.textures(&self.textures);
+        needed.extend(self.sprites.iter().map(|sprite| sprite.texture));
+
+        let preloaded = self.preload.iter().flat_map(|preload| preload.boards()).flat_map(|board| board.textures(&self.textures));
+        let preloaded: Vec<TextureId> = preloaded.filter(|id| self.textures.is_loaded(*id)).collect();
+        needed.extend(preloaded);
+
+        // a sky that doesn't load leaves the view black above the walls, like before there were skies
+        self.sky = match self.board.sky.clone().map(|path| self.textures.load_flat(&path)) {
+            Some(Ok(id)) => Some(id),
+            Some(Err(err)) => {
+                println!("ERROR: {}", err);
+                None
+            },
+            None => None
+        };
+
+        if self.textures.stream(rl, thread, &needed) {
+            if let Err(err) = self.textures.pack_atlas(rl, thread) {
+                println!("ERROR: {}", err);
+            }
+        }
+    }
+
+    // runs the script blocks of the hook, true if there were any
+    fn run_hook(&mut self, hook: Hook) -> bool {
+        let blocks = self.script.fire(hook);
+        self.run_blocks(&blocks)
+    }
+
+    fn run_blocks(&mut self, blocks: &[usize]) -> bool {
+        for i in blocks.iter().copied() {
+            let statements = self.script.blocks[i].statements.clone();
+            if statements.iter().all(|statement| self.run_statement(statement)) {
+                self.script.blocks[i].ran = true;
+            }
+        }
+        !blocks.is_empty()
+    }
+
+    // false when a require stops the block there
+    fn run_statement(&mut self, statement: &Statement) -> bool {
+        let inside = |x: usize, y: usize| x < self.board.cols && y < self.board.rows;
+        match statement {
+            Statement::Require(value, compare, number) => return compare.holds(self.value(*value), *number),
+            Statement::Say(text) => self.sign = sign(text.clone()),
+            Statement::Toggle(x, y) => self.toggle_target(*x, *y),
+            Statement::Set(x, y, cell) => if inside(*x, *y) {
+                self.board.set(*x, *y, *cell);
+                self.textures_stale = true;
+            },
+            Statement::Teleport(x, y) => if inside(*x, *y) {
+                self.player.pos = Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5);
+                self.player_cell = Some((*x, *y));
+            },
+            Statement::Damage(amount) => self.player.damage(*amount),
+            Statement::Heal(amount) => self.player.heal(*amount),
+            Statement::Daylight(level, cycle) => {
+                self.daylight.level = level.clamp(0.0, 1.0);
+                if let Some(cycle) = cycle {
+                    self.daylight.cycle = cycle.max(0.0);
+                }
+            },
+            // like exits, left alone while editing or playtesting, and a map already being left isn't finished again
+            Statement::Finish(map) => if !self.editor.active && self.editor.playtest.is_none() && self.swap.is_none() && self.level_stats.is_none() {
+                self.finish_level(map.as_deref().map(level_path));
+            }
+        }
+        true
+    }
+
+    fn value(&self, value: Value) -> f32 {
+        match value {
+            Value::Enemies => self.enemies.len() as f32,
+            Value::Kills => self.level_state.killed.len() as f32,
+            Value::Secrets => self.level_state.secrets.len() as f32,
+            Value::Health => self.player.health,
+            Value::Ammo => self.player.ammo as f32,
+            Value::Time => self.level_time
+        }
+    }
+
+    // what to tell the player about the first requirement of the cell that doesn't hold, None when they all do
+    fn unmet(&self, x: usize, y: usize) -> Option<String> {
+        self.board.requirements_at(x, y).iter().find(|requirement| match &requirement.condition {
+            Condition::HasKey(key) => !self.player.has_key(*key),
+            Condition::Finished(map) => !self.world.is_completed(&level_path(map)),
+            Condition::Compare(value, compare, number) => !compare.holds(self.value(*value), *number)
+        }).map(|requirement| requirement.text())
+    }
+
+    // a random board the size of `size` by `size`, walled with the editor palette
+    pub fn generate_level(&mut self, layout: Layout, size: usize, seed: u32) -> Result<(), String> {
+        let palette: Vec<Cell> = self.editor.palette.iter()
+            .filter(|cell| matches!(cell, Cell::COLOR(_) | Cell::TEXTURE(_) | Cell::AnimatedTexture(_)))
+            .copied()
+            .collect();
+
+        let mut board = Board::new(size, size);
+        generate(&mut board, layout, seed, &palette, &load_prefabs(&self.textures))?;
+
+        self.start_transition(Swap::Level(generated_map_path(layout, size, seed), Box::new(board)));
+        Ok(())
+    }
+
+    // the map the autosave was taken of, opened in the editor with the changes that weren't saved
+    pub fn recover_autosave(&mut self) -> Result<String, String> {
+        let recovery = Recovery::load(AUTOSAVE_PATH)?.ok_or("There is no autosave to restore")?;
+        let board = board_from_text(&recovery.restore()?, &self.textures)?;
+        self.load_level(recovery.map.clone(), board);
+        self.editor.active = true;
+        self.mouse_captured = false;
+        Ok(recovery.map)
+    }
+
+    // a texture slot for every name the themes draw, pointed at the files of `theme`
+    pub fn register_themes(&mut self, themes: Vec<Theme>, theme: usize) {
+        for name in texture_names(&themes) {
+            if let Some(file) = theme_file(&themes, theme, &name) {
+                self.textures.register(&theme_path(&name), file);
+            }
+        }
+        self.themes = themes;
+        self.theme = theme;
+    }
+
+    // the same ids drawn from the current theme's files, a name it doesn't have keeps what it had
+    pub fn apply_theme(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.theme_stale = false;
+        let Some(theme) = self.themes.get(self.theme) else { return };
+        for (name, file) in theme.files.iter() {
+            if let Err(err) = self.textures.retarget(rl, thread, &theme_path(name), file) {
+                println!("ERROR: {}", err);
+            }
+        }
+        println!("INFO: Switched to theme {}", theme.name);
+    }
+
+    // swaps in what changed on disk, `force` reloads the map right away even if it didn't change
+    pub fn reload_assets(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, force: bool) {
+        let Some(hot_reload) = self.hot_reload.as_mut() else { return };
+        let changes = hot_reload.poll(rl.get_time(), force, &self.editor.path);
+
+        for (path, file) in changes.textures {
+            match self.textures.reload_as(rl, thread, &path, &file) {
+                Ok(_) => println!("INFO: Reloaded texture {}", file),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+
+        // a map reloaded below brings its script along
+        if changes.script && (!changes.map || self.editor.active) {
+            self.reload_script();
+        }
+
+        // the editor's board wins over the file until the editor is closed
+        if !changes.map || self.editor.active { return }
+        match load_board(&self.editor.path, &self.textures) {
+            Ok(board) => {
+                println!("INFO: Reloaded map {}", self.editor.path);
+                self.reload_level(board);
+            },
+            Err(err) => println!("ERROR: {}", err)
+        }
+    }
+
+    // the script swapped for what is on disk now without entering the map again, the old one stays
+    // when the new one doesn't parse
+    fn reload_script(&mut self) {
+        match Script::load(&self.editor.path, &self.textures) {
+            Ok(mut script) => {
+                script.keep_progress(&self.script);
+                self.script = script;
+                println!("INFO: Reloaded script {}", script_path(&self.editor.path));
+            },
+            Err(err) => println!("ERROR: {}", err)
+        }
+    }
+
+    // like `load_level`, but the player stays where it is unless a wall was put there
+    fn reload_level(&mut self, board: Board) {
+        let (pos, vel) = (self.player.pos, self.player.vel);
+        let path = self.editor.path.clone();
+        let level_time = self.level_time;
+        let keys = self.player.keys.clone();
+        let inside = pos.x >= 0.0 && pos.y >= 0.0 && (pos.x as usize) < board.cols && (pos.y as usize) < board.rows;
+        let keep = inside && board.is_passable(pos.x as usize, pos.y as usize);
+
+        self.load_level(path, board);
+        self.level_time = level_time;
+        self.player.keys = keys;
+        if keep {
+            self.player.pos = pos;
+            self.player.vel = vel;
+        }
+    }
+
+    // gates swing open once the map they wait for is finished
+    fn open_gates(&mut self) {
+        let open: Vec<(usize, usize)> = self.board.gates()
+            .filter(|(_, level)| self.world.is_completed(&level_path(level)))
+            .map(|(pos, _)| *pos)
+            .collect();
+
+        for (x, y) in open {
+            if let Some(door) = self.board.door_mut(x, y).filter(|door| !door.is_passable()) {
+                door.toggle();
+            }
+        }
+    }
+
+    pub fn spawn_enemies(&mut self) {
+        let Some(texture) = self.enemy_texture else { return };
+        self.enemies = self.board.spawns.iter().enumerate()
+            .filter(|(i, spawn)| spawn.kind == SpawnKind::Enemy && !self.level_state.killed.contains(i))
+            .map(|(i, spawn)| {
+                let mut enemy = Enemy::from_spawn(spawn, texture, &self.behaviors);
+                enemy.spawn = Some(i);
+                enemy
+            })
+            .collect();
+    }
+
+    pub fn spawn_pickups(&mut self) {
+        self.pickups = self.board.spawns.iter().enumerate()
+            .filter(|(i, spawn)| spawn.kind == SpawnKind::Pickup && !self.level_state.collected.contains(i))
+            .filter_map(|(i, spawn)| Pickup::from_spawn(spawn, i, &self.pickup_textures))
+            .collect();
+    }
+
+    fn update_network(&mut self, dt: f32) {
+        let Some(net) = self.net.as_mut() else { return };
+        net.update(dt, &self.editor.path, self.player.pos, self.player.dir);
+
+        let Some(texture) = self.remote_texture else { return };
+        self.remote_players = net.players().iter()
+            .filter(|player| player.map == self.editor.path)
+            .map(|player| Sprite::new(player.pos.x, player.pos.y, texture))
+            .collect();
+    }
+
+    // walking over a pickup uses it up, unless the player has no use for it yet
+    fn collect_pickups(&mut self) {
+        let (player, collected) = (&mut self.player, &mut self.level_state.collected);
+        self.pickups.retain(|pickup| {
+            let taken = pickup.pos().distance_to(player.pos) <= PICKUP_RADIUS && player.pick_up(pickup.item);
+            if taken {
+                collected.push(pickup.spawn);
+            }
+            !taken
+        });
+    }
+
+    // where the minimap is drawn and can be clicked, the editor always shows it
+    pub fn minimap_viewport(&self) -> Rectangle {
+        match self.minimap_mode {
+            MinimapMode::Expanded => Rectangle::new(0.0, 0.0, window_size().x, window_size().y),
+            MinimapMode::Corner | MinimapMode::Hidden => {
+                let size = calulate_minimap_size(Vector2::new(self.board.cols as f32, self.board.rows as f32));
+                let pos = self.minimap_anchor.place(size, self.minimap_margin);
+                Rectangle::new(pos.x, pos.y, size.x, size.y)
+            }
+        }
+    }
+
+    pub fn minimap_shown(&self) -> bool {
+        self.minimap_mode != MinimapMode::Hidden || self.editor.active
+    }
+
+    // scales the minimap to fit its viewport, undoing any zoom and pan
+    pub fn fit_minimap(&mut self) {
+        let board_size = Vector2::new(self.board.cols as f32, self.board.rows as f32);
+        let viewport = self.minimap_viewport();
+        let margin = Vector2::one().mul(MINIMAP_MARGIN);
+        let available = Vector2::new(viewport.width, viewport.height);
+
+        let fit = if self.minimap_mode == MinimapMode::Expanded { available.sub(margin.mul(2.0)).div(board_size) }
+        else { available.div(board_size) };
+        let zoom = Vector2::one().mul(f32::min(fit.x, fit.y));
+        let size = board_size.mul(zoom);
+
+        self.minimap.zoom = zoom;
+        self.minimap.offset = Vector2::new(viewport.x, viewport.y).add(available.sub(size).div(2.0));
+    }
+
+    // middle mouse drags the minimap around, the wheel zooms around the cursor
+    fn pan_and_zoom_minimap(&mut self, rl: &RaylibHandle) {
+        if !self.minimap_shown() { return }
+
+        let mouse = rl.get_mouse_position();
+        let inside = self.minimap_viewport().check_collision_point_rec(mouse);
+
+        if rl.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_MIDDLE) {
+            self.minimap_panning = inside;
+        }
+        if rl.is_mouse_button_up(raylib::ffi::MouseButton::MOUSE_BUTTON_MIDDLE) {
+            self.minimap_panning = false;
+        }
+        if self.minimap_panning {
+            self.minimap.offset = self.minimap.offset.add(rl.get_mouse_delta());
+        }
+
+        // the editor already uses the wheel to pick from its palette
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel == 0.0 || !inside || self.editor.active { return }
+
+        let anchor = mouse.sub(self.minimap.offset).div(self.minimap.zoom);
+        let cell = f32::clamp(self.minimap.zoom.x * MINIMAP_ZOOM_STEP.powf(wheel), MIN_MINIMAP_CELL, MAX_MINIMAP_CELL);
+        self.minimap.zoom = self.minimap.zoom.mul(cell / self.minimap.zoom.x);
+        self.minimap.offset = mouse.sub(anchor.mul(self.minimap.zoom));
+    }
+
+    // plays the edited board from the cursor, or the player spawn when the cursor is off the minimap
+    fn start_playtest(&mut self, hovered: Option<Vector2>) {
+        // refuse to play a broken map, the problems are listed in the editor as well
+        let problems = validate(&self.board, &self.textures);
+        if !problems.is_empty() {
+            for problem in problems.iter() {
+                println!("ERROR: {}", problem);
+            }
+            return
+        }
+
+        self.editor.playtest = Some(Playtest { board: self.board.clone(), pos: self.player.pos, dir: self.player.dir });
+        // the edited map may not match what was changed in it before, so a playtest starts it untouched
+        self.level_state = LevelState::default();
+        self.spawn_pickups();
+
+        let spawn = self.board.spawns.iter().find(|spawn| spawn.kind == SpawnKind::Player).map(|spawn| spawn.pos);
+        if let Some(pos) = hovered.or(spawn) {
+            self.player.pos = pos;
+        }
+
+        self.editor.active = false;
+        self.mouse_captured = true;
+    }
+
+    // back to the editor with the board as it was when the playtest started
+    fn stop_playtest(&mut self) {
+        if let Some(playtest) = self.editor.playtest.take() {
+            self.board = playtest.board;
+            self.textures_stale = true;
+            self.player.pos = playtest.pos;
+            self.player.dir = playtest.dir;
+        }
+        self.level_state = LevelState::default();
+        self.spawn_pickups();
+
+        self.editor.active = true;
+        self.mouse_captured = false;
+    }
+
+    // board position under a screen position on the minimap
+    pub fn minimap_point(&self, screen: Vector2) -> Option<Vector2> {
+        if !self.minimap_shown() || !self.minimap_viewport().check_collision_point_rec(screen) { return None }
+
+        let mouse = screen.sub(self.minimap.offset).div(self.minimap.zoom);
+        if mouse.x >= 0.0 && mouse.y >= 0.0 && mouse.x < self.board.cols as f32 && mouse.y < self.board.rows as f32 {
+            Some(mouse)
+        } else {
+            None
+        }
+    }
+
+    pub fn minimap_cell(&self, screen: Vector2) -> Option<(usize, usize)> {
+        self.minimap_point(screen).map(|pos| (pos.x as usize, pos.y as usize))
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.transition.as_ref().is_some_and(|transition| transition.ready()) {
+            if let Some(swap) = self.swap.take() {
+                self.apply_swap(swap);
+            }
+        }
+
+        // nothing moves behind the stats, use or fire goes on
+        if self.level_stats.is_some() {
+            if self.input.use_door || self.input.fire {
+                self.level_stats = None;
+                if let Some(swap) = self.next_swap.take() {
+                    self.start_transition(swap);
+                }
+            }
+            self.previous = self.snapshot();
+            self.input.clear_presses();
+            self.achievements.update(dt);
+            return
+        }
+
+        // nothing moves while the death is replayed, use or fire respawns once it is over
+        if let Some(killcam) = self.killcam.as_mut() {
+            killcam.update(dt);
+            if killcam.is_done() && (self.input.use_door || self.input.fire) {
+                self.respawn();
+            }
+            self.previous = self.snapshot();
+            self.input.clear_presses();
+            self.achievements.update(dt);
+            return
+        }
+
+        self.previous = self.snapshot();
+        self.history.push(Frame { player_pos: self.previous.player_pos, player_dir: self.previous.player_dir, enemies: self.previous.enemies.clone() });
+        self.time += dt;
+        if !self.editor.active {
+            self.level_time += dt;
+            self.ghost_run.record(self.level_time, self.player.pos, self.player.dir);
+        }
+        self.ghost_sprite = match (&self.ghost, self.remote_texture) {
+            (Some(ghost), Some(texture)) if self.time_trial && !self.editor.active => ghost.at(self.level_time)
+                .map(|pos| Sprite { alpha: GHOST_ALPHA, ..Sprite::new(pos.x, pos.y, texture) }),
+            _ => None
+        };
+
+        let old_pos = self.player.pos;
+        let forward = self.input.forward as i32 - self.input.backward as i32;
+        let right = self.input.strafe_right as i32 - self.input.strafe_left as i32;
+        self.player.walk(forward as f32, right as f32, self.input.sprint, dt);
+        self.resolve_collision(old_pos);
+        // walls take away whatever speed went into them
+        if dt > 0.0 {
+            self.player.vel = self.player.pos.sub(old_pos).div(dt);
+        }
+
+        let walked = self.player.pos.distance_to(old_pos);
+        self.player.update_bob(walked);
+        self.walked += walked;
+        if self.walked >= STEP_LENGTH {
+            self.walked = 0.0;
+            self.sounds.push((SoundEffect::Footstep, None));
+        }
+        if !self.editor.active && !self.trail.last().is_some_and(|last| last.distance_to(self.player.pos) < TRAIL_STEP) {
+            self.trail.push(self.player.pos);
+        }
+
+        if self.player.snap_turn {
+            let turn = self.input.turn_right as i32 - self.input.turn_left as i32;
+            self.player.turn_snapped(turn as f32, dt);
+        } else {
+            if self.input.turn_left {
+                self.player.turn_left(dt);
+            }
+
+            if self.input.turn_right {
+                self.player.turn_right(dt);
+            }
+        }
+
+        self.player.dir = rotated(self.player.dir, self.input.look * self.mouse_sensitivity);
+        self.player.look_up(self.input.pitch * self.mouse_sensitivity);
+        if self.input.jump {
+            self.jump_buffer = INPUT_BUFFER_TIME;
+        }
+        self.jump_buffer = if self.player.update_height(dt, self.jump_buffer > 0.0, self.input.crouch) { 0.0 }
+        else { (self.jump_buffer - dt).max(0.0) };
+
+        if let Some(pos) = self.input.teleport {
+            self.player.pos = pos;
+        }
+
+        if !self.editor.active {
+            self.collect_pickups();
+            self.enter_cell();
+            self.preload_exits();
+        }
+
+        if self.editor.active {
+            self.editor.update(&mut self.board, dt);
+
+            if self.editor.validated != Some(self.board.revision()) {
+                self.editor.validated = Some(self.board.revision());
+                self.editor.problems = validate(&self.board, &self.textures);
+                // a stamped prefab can bring walls the map didn't use before
+                self.textures_stale = true;
+            }
+        }
+
+        if self.input.use_door {
+            self.use_buffer = INPUT_BUFFER_TIME;
+        }
+        if self.use_buffer > 0.0 {
+            self.use_buffer = if self.use_cell() { 0.0 }
+            else { (self.use_buffer - dt).max(0.0) };
+        }
+
+        if self.input.quicksave {
+            match save_game(QUICKSAVE_PATH, self) {
+                Ok(()) => println!("INFO: Game saved to {}", QUICKSAVE_PATH),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+
+        if self.input.quickload {
+            match load_game(QUICKSAVE_PATH, self) {
+                Ok(()) => println!("INFO: Game loaded from {}", QUICKSAVE_PATH),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+
+        for (x, y) in self.board.update_doors(dt) {
+            self.events.publish(Event::DoorOpened { x, y });
+        }
+
+        self.weapon.update(dt);
+        if self.input.fire && !self.editor.active && self.player.ammo > 0 && self.weapon.fire() {
+            self.player.ammo -= 1;
+            self.fire();
+        }
+
+        if !self.editor.active {
+            self.update_enemies(dt);
+            let due = self.script.tick(dt);
+            self.run_blocks(&due);
+        }
+
+        self.lights.update(&self.board, self.ambient_light);
+        self.daylight.update(dt);
+        self.particles.update(&self.board, dt);
+        enforce_budget(self);
+
+        self.update_network(dt);
+
+        self.input.clear_presses();
+        self.events.dispatch();
+
+        update_damage_indicators(&mut self.damage_indicators, dt);
+        if let Some((_, time)) = self.sign.as_mut() {
+            *time -= dt;
+        }
+        if self.sign.as_ref().is_some_and(|(_, time)| *time <= 0.0) {
+            self.sign = None;
+        }
+        // achievements only count in maps being played, not in playtests
+        let playing = self.editor.playtest.is_none();
+        for event in self.events.events() {
+            match event {
+                Event::PlayerDamaged { amount, source } => {
+                    self.player.damage(*amount);
+                    self.damage_indicators.push(DamageIndicator::new(*source));
+                    self.last_attacker = Some((self.time, *source));
+                },
+                Event::EnemyDied { .. } if playing => self.achievements.unlock(Achievement::FirstKill),
+                Event::SecretFound { .. } => {
+                    let (found, total) = (self.level_state.secrets.len(), self.board.secrets().count());
+                    self.sign = sign(format!("A secret! {} of {} found", found, total));
+                    self.sounds.push((SoundEffect::Secret, None));
+                    if playing && found == total {
+                        self.achievements.unlock(Achievement::AllSecrets);
+                    }
+                },
+                _ => {}
+            }
+        }
+        self.achievements.update(dt);
+        self.captions.update(dt);
+
+        // dying while editing just goes on
+        if self.player.health <= 0.0 && !self.editor.active {
+            let attacker = self.last_attacker.filter(|(time, _)| self.time - time < KILLCAM_TIME).map(|(_, pos)| pos);
+            self.killcam = Some(KillCam::new(&self.history, attacker));
+            narrate("You died");
+        }
+
+        let low_health = self.player.low_health();
+        self.heartbeat -= dt;
+        if low_health <= 0.0 {
+            self.heartbeat = 0.0;
+        } else if self.heartbeat <= 0.0 {
+            self.heartbeat = heartbeat_interval(low_health);
+            self.sounds.push((SoundEffect::Heartbeat, None));
+        }
+    }
+
+    fn fire(&mut self) {
+        self.sounds.push((SoundEffect::WeaponFire, None));
+        let dir = assisted_aim(&self.board, &self.enemies, self.player.pos, self.player.dir, self.weapon.range, self.weapon.aim_assist);
+        let hit = hitscan(&self.board, &self.enemies, self.player.pos, dir, self.weapon.range);
+        let back = dir.mul(-1.0);
+        match hit {
+            Hit::Enemy(i, point) => {
+                self.enemies[i].health -= self.weapon.damage;
+                self.particles.impact(point, back, self.player.eye_height(), Color::RED);
+            },
+            Hit::Wall(wall) => {
+                self.particles.impact(wall.point, back, self.player.eye_height(), Color::BEIGE);
+                // doors slide and glass, segments and mirrors don't fill their face, so only solid walls keep a hole
+                let solid = matches!(self.board.at(wall.cell.0, wall.cell.1), Cell::TEXTURE(_) | Cell::COLOR(_) | Cell::AnimatedTexture(_));
+                if let (true, Some(texture)) = (solid, self.bullet_hole_texture) {
+                    add_bullet_hole(&mut self.bullet_holes, Decal {
+                        texture,
+                        cell: wall.cell,
+                        face: Face::of(wall.cell, wall.point, wall.side),
+                        u: wall.wall_x,
+                        height: self.player.eye_height(),
+                        size: BULLET_HOLE_SIZE
+                    });
+                }
+            },
+            Hit::Nothing => {}
+        }
+
+        self.weapon.last_hit = hit;
+    }
+
+    fn update_enemies(&mut self, dt: f32) {
+        // the editor may have moved walls since the last frame
+        self.nav.update(&self.board);
+        for (id, enemy) in self.enemies.iter_mut().enumerate() {
+            enemy.update(id, &self.board, &self.nav, &mut self.visibility, self.player.pos, dt);
+        }
+        separate(&mut self.enemies, &self.board, dt);
+
+        if self.enemies.iter().any(|enemy| enemy.is_dead()) {
+            for enemy in self.enemies.iter().filter(|enemy| enemy.is_dead()) {
+                self.events.publish(Event::EnemyDied { pos: enemy.pos() });
+                self.level_state.killed.extend(enemy.spawn);
+            }
+
+            // enemies are identified by index, so the cached sightlines no longer line up
+            self.enemies.retain(|enemy| !enemy.is_dead());
+            self.visibility.clear();
+        }
+    }
+
+    fn is_passable(&self, pos: Vector2) -> bool {
+        if pos.x < 0.0 || pos.y < 0.0 || pos.x >= self.board.cols as f32 || pos.y >= self.board.rows as f32 {
+            return true
+        }
+
+        self.board.is_passable(pos.x as usize, pos.y as usize)
+    }
+
+    // undo movement on each axis that ended inside a blocking cell
+    fn resolve_collision(&mut self, old_pos: Vector2) {
+        let new_pos = self.player.pos;
+        self.player.pos = old_pos;
+
+        if self.is_passable(Vector2::new(new_pos.x, old_pos.y)) {
+            self.player.pos.x = new_pos.x;
+        }
+
+        if self.is_passable(self.player.pos.add(Vector2::new(0.0, new_pos.y - old_pos.y))) {
+            self.player.pos.y = new_pos.y;
+        }
+    }
+
+    // stepping onto an exit finishes the map and onto a teleporter sends the player to the middle of its
+    // target, arriving there doesn't count as stepping onto it so linked teleporters don't bounce back
+    fn enter_cell(&mut self) {
+        let pos = self.player.pos;
+        let inside = pos.x >= 0.0 && pos.y >= 0.0 && pos.x < self.board.cols as f32 && pos.y < self.board.rows as f32;
+        let cell = if inside { Some((pos.x as usize, pos.y as usize)) }
+        else { None };
+
+        let entered = cell != self.player_cell;
+        self.player_cell = cell;
+        let Some((x, y)) = cell else { return };
+        if !entered { return }
+
+        // an unmet requirement holds back what the cell does by itself, secrets and scripts still go
+        let met = self.unmet(x, y).is_none();
+        if let Cell::Custom(kind, _) = *self.board.at(x, y) {
+            if let Some(enter) = cell_kind(kind).and_then(|kind| kind.on_player_enter).filter(|_| met) {
+                enter(self, (x, y));
+            }
+        }
+
+        // exits are left alone while the map is being edited or playtested
+        if self.board.is_exit(x, y) && met && !self.editor.active && self.editor.playtest.is_none() {
+            self.finish_level(self.board.exit_target(x, y).map(level_path));
+            return
+        }
+
+        if self.board.is_secret(x, y) && !self.level_state.secrets.contains(&(x, y)) {
+            self.level_state.secrets.push((x, y));
+            self.events.publish(Event::SecretFound { x, y });
+        }
+
+        // a script that moved the player already decided where they go
+        self.run_hook(Hook::Enter(x, y));
+        if self.player_cell != Some((x, y)) || !met { return }
+
+        let Some(teleporter) = self.board.teleporter(x, y).copied() else { return };
+        let (tx, ty) = teleporter.target;
+        if tx >= self.board.cols || ty >= self.board.rows { return }
+
+        self.player.pos = Vector2::new(tx as f32 + 0.5, ty as f32 + 0.5);
+        self.player.dir = rotated(self.player.dir, teleporter.turn.to_radians());
+        self.player.vel = rotated(self.player.vel, teleporter.turn.to_radians());
+        self.player_cell = Some((tx, ty));
+        self.events.publish(Event::PlayerTeleported { from: (x, y), to: (tx, ty) });
+    }
+
+    // starts loading the maps behind the exits close by, an exit without a map of its own leads to the next one of the list
+    fn preload_exits(&mut self) {
+        let Some(preload) = self.preload.as_mut() else { return };
+        if self.editor.playtest.is_some() { return }
+        let near = self.board.exits().filter(|(x, y)| Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5).distance_to(self.player.pos) <= PRELOAD_DISTANCE);
+        for (x, y) in near {
+            let target = self.board.exit_target(*x, *y).map(level_path).or(self.level_manager.next(&self.editor.path).map(|path| path.to_string()));
+            if let Some(path) = target {
+                preload.request(&path);
+            }
+        }
+    }
+
+    // on to the exit's own map or the next one of the list, or back to the menu after the last one,
+    // once the player has seen the stats
+    fn finish_level(&mut self, target: Option<String>) {
+        self.level_state.completed = true;
+        // leaving the hub quickly is no feat, and there is nothing in it to tally
+        let hub = self.level_manager.is_hub(&self.editor.path);
+        if self.level_time < SPEED_RUN_TIME && !hub {
+            self.achievements.unlock(Achievement::SpeedRun);
+        }
+
+        let swap = match target.or(self.level_manager.next(&self.editor.path).map(|path| path.to_string())) {
+            Some(path) => match self.preload.as_mut().and_then(|preload| preload.take(&path)).unwrap_or_else(|| load_board(&path, &self.textures)) {
+                Ok(board) => Swap::Level(path, Box::new(board)),
+                Err(err) => {
+                    println!("ERROR: {}", err);
+                    return
+                }
+            },
+            None => {
+                println!("INFO: Finished {}", self.editor.path);
+                Swap::LevelSelect
+            }
+        };
+
+        if hub {
+            self.start_transition(swap);
+            return
+        }
+        let stats = LevelStats::new(&self.board, &self.level_state, self.level_time);
+        if self.export_summary {
+            match export_summary(self, &stats) {
+                Ok(path) => println!("INFO: Run summary saved to {}", path),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+        let new_best = self.records.submit(&self.editor.path, self.map_hash, stats.time);
+        if new_best {
+            if let Err(err) = self.records.save_ghost(self.map_hash, &self.ghost_run) {
+                println!("ERROR: {}", err);
+            }
+            self.ghost = Some(self.ghost_run.clone());
+        }
+        self.best_time = self.records.best(&self.editor.path, self.map_hash).map(|best| (best, new_best));
+        narrate(&format!("Level complete. Kills {} of {}, items {} of {}, secrets {} of {}", stats.kills, stats.enemies, stats.items, stats.pickups, stats.secrets, stats.total_secrets));
+        self.level_stats = Some(stats);
+        self.next_swap = Some(swap);
+    }
+
+    // activates the first trigger or door within reach in front of the player, walls stop the reach
+    // whether there was something to use, a sign saying why it can't be used counts
+    fn use_cell(&mut self) -> bool {
+        for step in 1..=3 {
+            let pos = self.player.pos.add(self.player.dir.mul(step as f32 * 0.5));
+            if pos.x < 0.0 || pos.y < 0.0 || pos.x >= self.board.cols as f32 || pos.y >= self.board.rows as f32 {
+                return false
+            }
+
+            let (x, y) = (pos.x as usize, pos.y as usize);
+            if self.run_hook(Hook::Use(x, y)) { return true }
+
+            let interactable = matches!(self.board.at(x, y), Cell::Custom(kind, _) if cell_kind(*kind).is_some_and(|kind| kind.on_interact.is_some()));
+            if self.board.trigger(x, y).is_some() || matches!(self.board.at(x, y), Cell::Door(_)) || interactable {
+                if let Some(text) = self.unmet(x, y) {
+                    self.sign = sign(text);
+                    return true
+                }
+            }
+
+            match self.board.trigger_mut(x, y) {
+                Some(Trigger::Switch { targets, on }) => {
+                    *on = !*on;
+                    let targets = targets.clone();
+                    for (tx, ty) in targets {
+                        self.toggle_target(tx, ty);
+                    }
+                    return true
+                },
+                Some(Trigger::Sign(text)) => {
+                    self.sign = sign(text.clone());
+                    return true
+                },
+                None => {}
+            }
+
+            if let Cell::Custom(kind, _) = *self.board.at(x, y) {
+                if let Some(interact) = cell_kind(kind).and_then(|kind| kind.on_interact) {
+                    interact(self, (x, y));
+                    return true
+                }
+            }
+
+            if let Cell::Door(_) = self.board.at(x, y) {
+                let gate = self.board.gate(x, y).filter(|level| !self.world.is_completed(&level_path(level)));
+                match (self.board.lock(x, y), gate) {
+                    (Some(key), _) if !self.player.has_key(key) => self.sign = sign(format!("You need the {} key", key.name())),
+                    (_, Some(level)) => self.sign = sign(format!("Finish {} first", level)),
+                    _ => self.toggle_door(x, y)
+                }
+                return true
+            }
+            if !self.board.is_passable(x, y) { return false }
+        }
+        false
+    }
+
+    fn toggle_door(&mut self, x: usize, y: usize) {
+        if let Some(door) = self.board.door_mut(x, y) {
+            door.toggle();
+            self.sounds.push((SoundEffect::DoorOpen, Some(Vector2::new(x as f32 + 0.5, y as f32 + 0.5))));
+        }
+    }
+
+    // the door in the cell and every light standing in it
+    fn toggle_target(&mut self, x: usize, y: usize) {
+        if x >= self.board.cols || y >= self.board.rows { return }
+
+        self.toggle_door(x, y);
+        for spawn in self.board.spawns.iter_mut() {
+            if spawn.kind == SpawnKind::Light && spawn.pos.x as usize == x && spawn.pos.y as usize == y {
+                let off = spawn.property("off").unwrap_or(0.0) != 0.0;
+                spawn.set_property("off", !off as i32 as f32);
+            }
+        }
+    }
+
+    pub fn half_fov(&self) -> f32 {
+        (self.fov / 2.0).to_radians()
+    }
+
+    // walls and sprites were sized for a 90 degree view, narrower views magnify them
+    pub fn focal_length(&self) -> f32 {
+        1.0 / f32::tan(self.half_fov())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            player_pos: self.player.pos,
+            player_dir: self.player.dir,
+            enemies: self.enemies.iter().map(|enemy| enemy.pos()).collect()
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.player.pos = snapshot.player_pos;
+        self.player.dir = snapshot.player_dir;
+        for (enemy, pos) in self.enemies.iter_mut().zip(snapshot.enemies.iter()) {
+            enemy.sprite.pos = *pos;
+        }
+    }
+
+    // the next frames show the current positions until the simulation steps again
+    pub fn stop_interpolation(&mut self) {
+        self.previous = self.snapshot();
+    }
+
+    // draws everything at its position between the last two simulation steps,
+    // so a display faster than the simulation still sees smooth movement
+    pub fn render(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, renderer: &mut Renderer, alpha: f32) {
+        let current = self.snapshot();
+        match self.killcam.as_ref().and_then(|killcam| killcam.frame().cloned().zip(killcam.camera(&self.board))) {
+            // watched from the outside, so the player is drawn where they were
+            Some((frame, (eye, dir))) => {
+                self.restore(&Snapshot { player_pos: eye, player_dir: dir, enemies: frame.enemies });
+                if let Some(texture) = self.remote_texture {
+                    self.sprites.push(Sprite::new(frame.player_pos.x, frame.player_pos.y, texture));
+                }
+                renderer.render(d, thread, self);
+                if self.remote_texture.is_some() {
+                    self.sprites.pop();
+                }
+            },
+            None => {
+                self.restore(&self.previous.lerp(&current, alpha));
+                renderer.render(d, thread, self);
+            }
+        }
+        self.restore(&current);
+
+        // drawn last so it covers the hud and the menus too
+        if let Some(transition) = &mut self.transition {
+            if transition.needs_snapshot() {
+                transition.capture(d, thread);
+            }
+            transition.advance(d.get_frame_time());
+            transition.render(d);
+        }
+        if self.transition.as_ref().is_some_and(|transition| transition.is_done() && self.swap.is_none()) {
+            self.transition = None;
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use raylib::math::Vector2;
+
+use crate::{board::{Board, Cell}, math::Lcg, prefab::{rotated, stamp, WeightedPrefab}, spawn::{Spawn, SpawnKind}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    // one-cell corridors with exactly one way between any two places
+    Maze,
+    // rectangular rooms joined by corridors
+    Dungeon
+}
+
+impl Layout {
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Maze => "maze",
+            Layout::Dungeon => "dungeon"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Layout, String> {
+        match name {
+            "maze" => Ok(Layout::Maze),
+            "dungeon" => Ok(Layout::Dungeon),
+            _ => Err(format!("Unknown layout '{}', expected maze or dungeon", name))
+        }
+    }
+}
+
+// rooms tried before the dungeon gives up on fitting more
+const ROOM_ATTEMPTS: usize = 60;
+const MIN_ROOM: usize = 3;
+const MAX_ROOM: usize = 8;
+// one room in this many gets a prefab, when there is one that fits
+const PREFAB_CHANCE: usize = 2;
+
+// replaces the whole board, walls are picked from the palette and the old spawns are dropped for one player spawn
+pub fn generate(board: &mut Board, layout: Layout, seed: u32, palette: &[Cell], prefabs: &[WeightedPrefab]) -> Result<(), String> {
+    if palette.is_empty() {
+        return Err("The generator needs at least one wall in its palette".to_string())
+    }
+    if board.cols < 5 || board.rows < 5 {
+        return Err(format!("The generator needs a board of at least 5x5, got {}x{}", board.cols, board.rows))
+    }
+
+    let mut rng = Lcg::new(seed);
+    for y in 0..board.rows {
+        for x in 0..board.cols {
+            board.set(x, y, palette[rng.below(palette.len())]);
+            board.set_height(x, y, 1.0);
+            board.set_teleporter(x, y, None);
+        }
+    }
+
+    // before the layout, the prefabs bring their own spawns
+    board.spawns.clear();
+    let start = match layout {
+        Layout::Maze => maze(board, &mut rng),
+        Layout::Dungeon => dungeon(board, &mut rng, prefabs)
+    };
+
+    board.spawns.push(Spawn::new(SpawnKind::Player, Vector2::new(start.0 as f32 + 0.5, start.1 as f32 + 0.5)));
+    Ok(())
+}
+
+// a depth first walk over the odd cells, knocking down the wall between each step
+fn maze(board: &mut Board, rng: &mut Lcg) -> (usize, usize) {
+    let (cols, rows) = ((board.cols - 1) / 2, (board.rows - 1) / 2);
+    let mut visited = vec![false; cols * rows];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    board.set(1, 1, Cell::EMPTY);
+
+    while let Some(&(x, y)) = stack.last() {
+        let neighbours: Vec<(usize, usize)> = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+            .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
+            .filter(|(nx, ny)| *nx >= 0 && *ny >= 0 && (*nx as usize) < cols && (*ny as usize) < rows)
+            .map(|(nx, ny)| (nx as usize, ny as usize))
+            .filter(|(nx, ny)| !visited[ny * cols + nx])
+            .collect();
+
+        if neighbours.is_empty() {
+            stack.pop();
+            continue
+        }
+
+        let (nx, ny) = neighbours[rng.below(neighbours.len())];
+        visited[ny * cols + nx] = true;
+        board.set(x + nx + 1, y + ny + 1, Cell::EMPTY);
+        board.set(2 * nx + 1, 2 * ny + 1, Cell::EMPTY);
+        stack.push((nx, ny));
+    }
+
+    (1, 1)
+}
+
+// one of `prefabs` by their weights, which add up to `total`
+fn pick_weighted<'a>(rng: &mut Lcg, prefabs: &[&'a WeightedPrefab], total: usize) -> &'a WeightedPrefab {
+    let mut pick = rng.below(total);
+    for prefab in prefabs.iter() {
+        if pick < prefab.weight { return prefab }
+        pick -= prefab.weight;
+    }
+    prefabs[prefabs.len() - 1]
+}
+
+// rooms that don't touch, each joined to the one placed before it by an L shaped corridor.
+// every room but the first may get one of the prefabs that fit in it, picked by weight and turned
+// whichever way still fits. the corridors are dug through it after
+fn dungeon(board: &mut Board, rng: &mut Lcg, prefabs: &[WeightedPrefab]) -> (usize, usize) {
+    // x, y, width and height inside the walls around the board
+    let mut rooms: Vec<(usize, usize, usize, usize)> = vec![];
+    for _ in 0..ROOM_ATTEMPTS {
+        let w = usize::min(MIN_ROOM + rng.below(MAX_ROOM - MIN_ROOM + 1), board.cols - 2);
+        let h = usize::min(MIN_ROOM + rng.below(MAX_ROOM - MIN_ROOM + 1), board.rows - 2);
+        let x = 1 + rng.below(board.cols - 1 - w);
+        let y = 1 + rng.below(board.rows - 1 - h);
+
+        // a wall is kept between rooms so they stay apart
+        let overlaps = rooms.iter().any(|&(rx, ry, rw, rh)| x <= rx + rw && rx <= x + w && y <= ry + rh && ry <= y + h);
+        if overlaps { continue }
+
+        for cy in y..y + h {
+            for cx in x..x + w {
+                board.set(cx, cy, Cell::EMPTY);
+            }
+        }
+        rooms.push((x, y, w, h));
+    }
+
+    for &(x, y, w, h) in rooms.iter().skip(1) {
+        // odd turns swap the width and the height
+        let fits = |prefab: &Board, turns: usize| if turns.is_multiple_of(2) { prefab.cols <= w && prefab.rows <= h }
+        else { prefab.rows <= w && prefab.cols <= h };
+        let fitting: Vec<&WeightedPrefab> = prefabs.iter().filter(|choice| choice.weight > 0 && (0..2).any(|turns| fits(&choice.prefab, turns))).collect();
+        let total: usize = fitting.iter().map(|choice| choice.weight).sum();
+        if fitting.is_empty() || rng.below(PREFAB_CHANCE) != 0 { continue }
+
+        let choice = pick_weighted(rng, &fitting, total);
+        let turns: Vec<usize> = (0..4).filter(|turns| fits(&choice.prefab, *turns)).collect();
+        let prefab = rotated(&choice.prefab, turns[rng.below(turns.len())]);
+        stamp(board, &prefab, x + (w - prefab.cols) / 2, y + (h - prefab.rows) / 2);
+    }
+
+    let centers: Vec<(usize, usize)> = rooms.iter().map(|(x, y, w, h)| (x + w / 2, y + h / 2)).collect();
+    for pair in centers.windows(2) {
+        let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+        for x in usize::min(ax, bx)..=usize::max(ax, bx) {
+            board.set(x, ay, Cell::EMPTY);
+        }
+        for y in usize::min(ay, by)..=usize::max(ay, by) {
+            board.set(bx, y, Cell::EMPTY);
+        }
+    }
+
+    centers[0]
+}
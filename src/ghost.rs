@@ -0,0 +1,53 @@
+use raylib::math::Vector2;
+
+// seconds between two samples of a run, the ghost is moved smoothly between them
+pub const GHOST_INTERVAL: f32 = 0.1;
+// how much of the ghost shows, enough to race against without hiding what is behind it
+pub const GHOST_ALPHA: f32 = 0.4;
+
+// where the player was and where they looked every `GHOST_INTERVAL` seconds of a run,
+// saved with the best time of a map so the next attempts can race it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ghost {
+    pub samples: Vec<(Vector2, Vector2)>
+}
+
+impl Ghost {
+    pub fn new() -> Ghost {
+        Ghost::default()
+    }
+
+    // keeps a sample once `time` into the run reaches the next one due
+    pub fn record(&mut self, time: f32, pos: Vector2, dir: Vector2) {
+        if time >= self.samples.len() as f32 * GHOST_INTERVAL {
+            self.samples.push((pos, dir));
+        }
+    }
+
+    // where the run was `time` seconds in, None once it is over
+    pub fn at(&self, time: f32) -> Option<Vector2> {
+        let step = time.max(0.0) / GHOST_INTERVAL;
+        let i = step as usize;
+        let (from, _) = self.samples.get(i)?;
+        match self.samples.get(i + 1) {
+            Some((to, _)) => Some(from.lerp(*to, step - i as f32)),
+            None => Some(*from)
+        }
+    }
+
+    // one "<x> <y> <dir x> <dir y>" line per sample
+    pub fn to_text(&self) -> String {
+        self.samples.iter().map(|(pos, dir)| format!("{} {} {} {}\n", pos.x, pos.y, dir.x, dir.y)).collect()
+    }
+
+    pub fn from_text(text: &str) -> Result<Ghost, String> {
+        let mut ghost = Ghost::new();
+        for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let numbers = line.split_whitespace().map(|word| word.parse::<f32>()).collect::<Result<Vec<f32>, _>>()
+                .map_err(|err| format!("Invalid ghost sample '{}': {}", line, err))?;
+            let [x, y, dx, dy] = numbers[..] else { return Err(format!("Expected a position and a direction in '{}'", line)) };
+            ghost.samples.push((Vector2::new(x, y), Vector2::new(dx, dy)));
+        }
+        Ok(ghost)
+    }
+}
@@ -0,0 +1,51 @@
+use raylib::{color::Color, consts::BlendMode, drawing::{RaylibBlendModeExt, RaylibDraw, RaylibDrawHandle}, math::Vector2};
+
+use crate::window_size;
+
+// a tint the whole 3D view is multiplied by, so a map can feel warm, cold or washed out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorGrade {
+    pub tint: Color,
+    // 0 leaves the view as it is, 1 applies the full tint
+    pub strength: f32
+}
+
+impl ColorGrade {
+    pub fn new(tint: Color, strength: f32) -> ColorGrade {
+        ColorGrade { tint, strength: strength.clamp(0.0, 1.0) }
+    }
+
+    // "RRGGBB [strength]", as written after "grade" in a map
+    pub fn parse(text: &str) -> Result<ColorGrade, String> {
+        let mut tokens = text.split_whitespace();
+        let rgb = tokens.next().ok_or(format!("Invalid grade '{}'", text))?;
+        let rgb = u32::from_str_radix(rgb, 16).map_err(|err| format!("Invalid grade color '{}': {}", rgb, err))?;
+        let [_, r, g, b] = rgb.to_be_bytes();
+
+        let strength = match tokens.next() {
+            Some(strength) => strength.parse::<f32>().map_err(|err| format!("Invalid grade strength '{}': {}", strength, err))?,
+            None => 1.0
+        };
+
+        Ok(ColorGrade::new(Color::new(r, g, b, 255), strength))
+    }
+
+    pub fn to_text(self) -> String {
+        format!("{:02x}{:02x}{:02x} {}", self.tint.r, self.tint.g, self.tint.b, self.strength)
+    }
+
+    // the tint faded towards white, which multiplies to no change
+    fn multiplier(self) -> Color {
+        let mix = |c: u8| (255.0 + (c as f32 - 255.0) * self.strength) as u8;
+        Color::new(mix(self.tint.r), mix(self.tint.g), mix(self.tint.b), 255)
+    }
+}
+
+// runs after the world is drawn and before the hud, so only the view is graded
+pub fn render_color_grade(d: &mut RaylibDrawHandle, grade: Option<ColorGrade>) {
+    let Some(grade) = grade else { return };
+    if grade.strength <= 0.0 { return }
+
+    let mut d = d.begin_blend_mode(BlendMode::BLEND_MULTIPLIED);
+    d.draw_rectangle_v(Vector2::zero(), window_size(), grade.multiplier());
+}
@@ -0,0 +1,60 @@
+use std::{collections::HashMap, fs, time::SystemTime};
+
+use crate::{assets::AssetRoots, script::script_path};
+
+// seconds between looks at the disk, every frame would be wasted work
+const POLL_INTERVAL: f64 = 0.5;
+
+// what changed on disk since the last poll
+#[derive(Default)]
+pub struct Changes {
+    pub map: bool,
+    // the script next to the map, an added or removed one too
+    pub script: bool,
+    // base path the texture is registered under and the file to read it from
+    pub textures: Vec<(String, String)>
+}
+
+// watches the current map, its script and the textures in the asset folders by their modification times
+pub struct HotReload {
+    assets: AssetRoots,
+    modified: HashMap<String, Option<SystemTime>>,
+    last_poll: f64
+}
+
+impl HotReload {
+    // the files as they are now count as loaded, only later changes are reported
+    pub fn new(assets: AssetRoots) -> HotReload {
+        let mut reload = HotReload { assets, modified: HashMap::new(), last_poll: 0.0 };
+        for (_, file) in reload.texture_files() {
+            reload.changed(&file);
+        }
+        reload
+    }
+
+    fn texture_files(&self) -> Vec<(String, String)> {
+        self.assets.files("png").iter().map(|name| (self.assets.base_path(name), self.assets.resolve(name))).collect()
+    }
+
+    // a file seen for the first time is a change too
+    fn changed(&mut self, file: &str) -> bool {
+        let modified = fs::metadata(file).and_then(|meta| meta.modified()).ok();
+        self.modified.insert(file.to_string(), modified) != Some(modified)
+    }
+
+    // `force` looks right away instead of waiting for the next interval and reloads the map even if
+    // it didn't change, a map that wasn't watched before only starts being watched
+    pub fn poll(&mut self, time: f64, force: bool, map_path: &str) -> Changes {
+        if !force && time - self.last_poll < POLL_INTERVAL {
+            return Changes::default()
+        }
+        self.last_poll = time;
+
+        let watched = self.modified.contains_key(map_path);
+        let map = (self.changed(map_path) && watched) || force;
+        let script = script_path(map_path);
+        let script = self.changed(&script) && watched;
+        let textures = self.texture_files().into_iter().filter(|(_, file)| self.changed(file)).collect();
+        Changes { map, script, textures }
+    }
+}
@@ -0,0 +1,122 @@
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::Vector2};
+
+use crate::{game::Game, spawn::Spawn, sprite::Sprite, texture::TextureId, ui, window_size};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Red,
+    Blue,
+    Yellow
+}
+
+pub const KEYS: [Key; 3] = [Key::Red, Key::Blue, Key::Yellow];
+
+impl Key {
+    pub fn name(self) -> &'static str {
+        match self {
+            Key::Red => "red",
+            Key::Blue => "blue",
+            Key::Yellow => "yellow"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Key> {
+        KEYS.iter().copied().find(|key| key.name() == name)
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Key::Red => Color::RED,
+            Key::Blue => Color::BLUE,
+            Key::Yellow => Color::YELLOW
+        }
+    }
+}
+
+// what a pickup gives the player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Item {
+    Health(f32),
+    Ammo(u32),
+    Key(Key)
+}
+
+impl Item {
+    // pickup spawns say what they are with an "item" property, 0 health, 1 ammo and 2 on the keys,
+    // "amount" is how much health or ammo they give
+    pub fn from_spawn(spawn: &Spawn) -> Option<Item> {
+        let amount = spawn.property("amount").unwrap_or(0.0);
+        match spawn.property("item").unwrap_or(0.0) as usize {
+            0 => Some(Item::Health(amount)),
+            1 => Some(Item::Ammo(amount as u32)),
+            code => KEYS.get(code - 2).map(|key| Item::Key(*key))
+        }
+    }
+
+    pub fn code(self) -> usize {
+        match self {
+            Item::Health(_) => 0,
+            Item::Ammo(_) => 1,
+            Item::Key(key) => 2 + KEYS.iter().position(|k| *k == key).unwrap_or(0)
+        }
+    }
+}
+
+pub struct Pickup {
+    pub item: Item,
+    pub sprite: Sprite,
+    // index of the spawn it came from
+    pub spawn: usize
+}
+
+// pickups sit on the floor, smaller than the walls
+const PICKUP_SCALE: f32 = 0.4;
+// the player picks up what is this close to it
+pub const PICKUP_RADIUS: f32 = 0.5;
+
+impl Pickup {
+    pub fn from_spawn(spawn: &Spawn, index: usize, textures: &[TextureId]) -> Option<Pickup> {
+        let item = Item::from_spawn(spawn)?;
+        let texture = *textures.get(item.code())?;
+        let mut sprite = Sprite::new(spawn.pos.x, spawn.pos.y, texture);
+        sprite.scale = PICKUP_SCALE;
+        Some(Pickup { item, sprite, spawn: index })
+    }
+
+    pub fn pos(&self) -> Vector2 {
+        self.sprite.pos
+    }
+}
+
+const STATUS_BAR_HEIGHT: i32 = 40;
+
+pub fn status_bar_height() -> i32 {
+    ui(STATUS_BAR_HEIGHT)
+}
+
+// health, ammo and the keys collected, along the bottom of the screen
+pub fn render_status_bar(d: &mut RaylibDrawHandle, game: &Game) {
+    if game.editor.active { return }
+
+    let player = &game.player;
+    let (width, height) = (window_size().x as i32, status_bar_height());
+    let top = window_size().y as i32 - height;
+    let (size, margin) = (ui(24), ui(10));
+    let y = top + (height - size) / 2;
+    d.draw_rectangle(0, top, width, height, Color::BLACK.alpha(0.7));
+
+    let health = if player.low_health() > 0.0 { Color::RED }
+    else { Color::WHITE };
+    d.draw_text(&format!("HEALTH {}", player.health.ceil() as i32), margin, y, size, health);
+
+    let ammo = if player.ammo == 0 { Color::RED }
+    else { Color::WHITE };
+    let text = format!("AMMO {}", player.ammo);
+    d.draw_text(&text, (width - d.measure_text(&text, size)) / 2, y, size, ammo);
+
+    for (i, key) in player.keys.iter().enumerate() {
+        let x = width - (i as i32 + 1) * (size + margin);
+        d.draw_rectangle(x, y, size, size, key.color());
+        d.draw_rectangle_lines(x, y, size, size, Color::WHITE);
+    }
+}
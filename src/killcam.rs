@@ -0,0 +1,110 @@
+use std::{collections::VecDeque, ops::{Add, Mul, Sub}};
+
+use raylib::math::Vector2;
+
+use crate::{board::Board, visibility::line_of_sight, EPS, SIM_DT};
+
+// seconds of play kept to be replayed after a death
+pub const KILLCAM_TIME: f32 = 3.0;
+// how far behind the player the camera watches from when nobody was attacking them
+const CHASE_DISTANCE: f32 = 2.0;
+// how much closer the camera comes each try when a wall is between it and the player
+const CHASE_STEP: f32 = 0.25;
+
+// where the player and the enemies were at one simulation step
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub player_pos: Vector2,
+    pub player_dir: Vector2,
+    pub enemies: Vec<Vector2>
+}
+
+// the last `KILLCAM_TIME` seconds of play, one frame a step, the oldest dropped as new ones come
+pub struct History {
+    frames: VecDeque<Frame>
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History { frames: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        if self.frames.len() as f32 * SIM_DT >= KILLCAM_TIME {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+// the last seconds before a death played back from where the attack came from,
+// or from behind the player without an attacker, before the respawn prompt
+pub struct KillCam {
+    frames: Vec<Frame>,
+    // where the killing blow came from
+    attacker: Option<Vector2>,
+    time: f32
+}
+
+impl KillCam {
+    pub fn new(history: &History, attacker: Option<Vector2>) -> KillCam {
+        KillCam { frames: history.frames.iter().cloned().collect(), attacker, time: 0.0 }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time = f32::min(self.time + dt, self.frames.len() as f32 * SIM_DT);
+    }
+
+    // the replay is over and the prompt is up
+    pub fn is_done(&self) -> bool {
+        self.time >= self.frames.len() as f32 * SIM_DT
+    }
+
+    // what is being played, the last frame stays once it is over
+    pub fn frame(&self) -> Option<&Frame> {
+        let index = usize::min((self.time / SIM_DT) as usize, self.frames.len().saturating_sub(1));
+        self.frames.get(index)
+    }
+
+    // where the camera stands and looks in the frame being played, always at the player.
+    // it follows the enemy standing closest to where the attack came from, so a moving attacker is kept up with
+    pub fn camera(&self, board: &Board) -> Option<(Vector2, Vector2)> {
+        let frame = self.frame()?;
+        let target = frame.player_pos;
+
+        let attacker = self.attacker.and_then(|attacker| frame.enemies.iter().copied()
+            .min_by(|a, b| a.distance_to(attacker).total_cmp(&b.distance_to(attacker))))
+            .filter(|pos| pos.distance_to(target) > EPS);
+        let eye = match attacker {
+            Some(pos) => pos,
+            None => {
+                let back = frame.player_dir.mul(-1.0);
+                let mut distance = CHASE_DISTANCE;
+                while distance > CHASE_STEP && !in_view(board, target, target.add(back.mul(distance))) {
+                    distance -= CHASE_STEP;
+                }
+                target.add(back.mul(distance))
+            }
+        };
+
+        let dir = target.sub(eye);
+        if dir.length() < EPS { return Some((eye, frame.player_dir)) }
+        Some((eye, dir.normalized()))
+    }
+}
+
+// `eye` is on the board, out of the walls, and sees `target`
+fn in_view(board: &Board, target: Vector2, eye: Vector2) -> bool {
+    let inside = eye.x >= 0.0 && eye.y >= 0.0 && (eye.x as usize) < board.cols && (eye.y as usize) < board.rows;
+    inside && !board.blocks_sight(eye.x as usize, eye.y as usize) && line_of_sight(board, target, eye)
+}
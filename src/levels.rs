@@ -0,0 +1,279 @@
+use std::{fs, path::Path, time::SystemTime};
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::{Rectangle, Vector2}, texture::{Image, RaylibTexture2D, Texture2D}, RaylibHandle, RaylibThread};
+
+use crate::{board::Board, game::Game, map::{load_board, validate}, menu::{select, MenuInput, MenuNav}, texture::TextureRegistry, thumbnail::ensure_thumbnail, ui, window_size};
+
+pub const MAPS_DIR: &str = "./maps";
+pub const CUSTOM_MAPS_DIR: &str = "./maps/custom";
+
+// map names in the order they are played, one per line, "#" starts a comment.
+// a "hub <name>" line names a map every other one goes back to when finished
+pub const LEVEL_LIST_PATH: &str = "./maps/levels.txt";
+
+// how often the map folders are checked for new or changed files, in seconds
+const RESCAN_INTERVAL: f32 = 1.0;
+
+pub struct Level {
+    pub path: String,
+    pub custom: bool,
+    pub problems: Vec<String>,
+    thumbnail: Option<Texture2D>,
+    modified: Option<SystemTime>
+}
+
+pub struct LevelSelect {
+    pub active: bool,
+    pub levels: Vec<Level>,
+    pub selected: usize,
+    pub error: Option<String>,
+    rescan: f32,
+    nav: MenuNav
+}
+
+// what the level select did with the input of a frame
+pub enum LevelPick {
+    None,
+    Close,
+    Level(String, Box<Board>)
+}
+
+impl Default for LevelSelect {
+    fn default() -> LevelSelect {
+        LevelSelect::new()
+    }
+}
+
+impl Level {
+    pub fn name(&self) -> String {
+        Path::new(&self.path).file_stem().map_or(self.path.clone(), |stem| stem.to_string_lossy().into_owned())
+    }
+
+    // revalidates the map and reloads its thumbnail, broken maps are listed with their problems
+    fn refresh(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, textures: &TextureRegistry) {
+        self.problems = match load_board(&self.path, textures) {
+            Ok(board) => validate(&board, textures),
+            Err(err) => vec![err]
+        };
+
+        self.thumbnail = None;
+        if !self.problems.is_empty() { return }
+
+        let thumbnail = ensure_thumbnail(&self.path, textures)
+            .and_then(|path| Image::load_image(&path))
+            .and_then(|image| rl.load_texture_from_image(thread, &image));
+        match thumbnail {
+            Ok(texture) => self.thumbnail = Some(texture),
+            Err(err) => println!("ERROR: {}", err)
+        }
+    }
+}
+
+// maps are named relative to the maps folder, in level lists, exits and gates alike
+pub fn level_path(name: &str) -> String {
+    Path::new(MAPS_DIR).join(name).to_string_lossy().into_owned()
+}
+
+// the maps played one after another, walking onto an exit starts the next one
+#[derive(Default)]
+pub struct LevelManager {
+    pub levels: Vec<String>,
+    pub hub: Option<String>
+}
+
+impl LevelManager {
+    pub fn new() -> LevelManager {
+        LevelManager::default()
+    }
+
+    // names in the list are looked up in the maps folder, without a list every map in it is played by name
+    pub fn load(path: &str) -> Result<LevelManager, String> {
+        let mut hub = None;
+        let names: Vec<String> = if Path::new(path).exists() {
+            let text = fs::read_to_string(path).map_err(|err| format!("Could not load level list '{}': {}", path, err))?;
+            let lines: Vec<&str> = text.lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .collect();
+
+            let mut names = vec![];
+            for line in lines {
+                match line.strip_prefix("hub ") {
+                    Some(name) => hub = Some(level_path(name.trim())),
+                    None => names.push(line.to_string())
+                }
+            }
+            names
+        } else {
+            let entries = fs::read_dir(MAPS_DIR).map_err(|err| format!("Could not list maps in '{}': {}", MAPS_DIR, err))?;
+            let mut names: Vec<String> = entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt" || ext == "tmj"))
+                .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+                .collect();
+            names.sort();
+            names
+        };
+
+        Ok(LevelManager {
+            levels: names.iter().map(|name| level_path(name)).collect(),
+            hub
+        })
+    }
+
+    pub fn is_hub(&self, path: &str) -> bool {
+        self.hub.as_deref() == Some(path)
+    }
+
+    // with a hub every map leads back to it, otherwise none after the last map or for a map that isn't in the list
+    pub fn next(&self, current: &str) -> Option<&str> {
+        if let Some(hub) = self.hub.as_deref().filter(|hub| *hub != current) {
+            return Some(hub)
+        }
+
+        let i = self.levels.iter().position(|level| level == current)?;
+        self.levels.get(i + 1).map(|level| level.as_str())
+    }
+}
+
+impl LevelSelect {
+    pub fn new() -> LevelSelect {
+        LevelSelect {
+            active: false,
+            levels: vec![],
+            selected: 0,
+            error: None,
+            rescan: 0.0,
+            nav: MenuNav::default()
+        }
+    }
+
+    // the next time it opens is announced like a new menu
+    pub fn reset_focus(&mut self) {
+        self.nav = MenuNav::default();
+    }
+
+    // the chosen map once it loaded without problems, or Close to leave the menu
+    pub fn handle_input(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, textures: &TextureRegistry) -> LevelPick {
+        self.rescan -= rl.get_frame_time();
+        if self.rescan <= 0.0 {
+            self.rescan = RESCAN_INTERVAL;
+            self.scan(rl, thread, textures);
+        }
+
+        let input = self.nav.read(rl);
+        if input == MenuInput::Back { return LevelPick::Close }
+        if self.levels.is_empty() { return LevelPick::None }
+
+        self.selected = select(input, self.selected, self.levels.len());
+        let level = &self.levels[self.selected];
+        let problems = if level.problems.is_empty() { String::new() }
+        else { format!(", {} problems", level.problems.len()) };
+        self.nav.focus("Level select", format!("{}{}", level.name(), problems));
+        if input != MenuInput::Accept { return LevelPick::None }
+
+        // the file may have changed since the last scan, so it is validated again
+        let level = &mut self.levels[self.selected];
+        let board = match load_board(&level.path, textures) {
+            Ok(board) => board,
+            Err(err) => {
+                level.problems = vec![err.clone()];
+                self.error = Some(err);
+                return LevelPick::None
+            }
+        };
+
+        level.problems = validate(&board, textures);
+        if let Some(problem) = level.problems.first() {
+            self.error = Some(format!("{}: {}", level.name(), problem));
+            return LevelPick::None
+        }
+
+        self.error = None;
+        LevelPick::Level(level.path.clone(), Box::new(board))
+    }
+
+    // picks up maps dropped into the folders while the game runs and forgets deleted ones
+    fn scan(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, textures: &TextureRegistry) {
+        let mut found: Vec<(String, bool)> = vec![];
+        for (dir, custom) in [(MAPS_DIR, false), (CUSTOM_MAPS_DIR, true)] {
+            let Ok(entries) = fs::read_dir(dir) else { continue };
+            let mut paths: Vec<String> = entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt" || ext == "tmj"))
+                // the level list sits next to the maps but isn't one
+                .filter(|path| path.as_path() != Path::new(LEVEL_LIST_PATH))
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            paths.sort();
+            found.extend(paths.into_iter().map(|path| (path, custom)));
+        }
+
+        self.levels.retain(|level| found.iter().any(|(path, _)| *path == level.path));
+        for (path, custom) in found {
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            match self.levels.iter_mut().find(|level| level.path == path) {
+                Some(level) if level.modified == modified => {},
+                Some(level) => {
+                    level.modified = modified;
+                    level.refresh(rl, thread, textures);
+                },
+                None => {
+                    let mut level = Level { path, custom, problems: vec![], thumbnail: None, modified };
+                    level.refresh(rl, thread, textures);
+                    self.levels.push(level);
+                }
+            }
+        }
+
+        self.levels.sort_by(|a, b| a.custom.cmp(&b.custom).then(a.path.cmp(&b.path)));
+        self.selected = usize::min(self.selected, self.levels.len().saturating_sub(1));
+    }
+}
+
+pub fn render_level_select(d: &mut RaylibDrawHandle, game: &Game) {
+    let levels = &game.levels;
+    if !levels.active { return }
+
+    d.draw_rectangle_v(Vector2::zero(), window_size(), Color::BLACK.alpha(0.8));
+    let (margin, top, font) = (ui(40), ui(90), ui(20));
+    d.draw_text("LEVEL SELECT", margin, margin, ui(30), Color::WHITE);
+
+    if levels.levels.is_empty() {
+        d.draw_text(&format!("No maps in {} or {}", MAPS_DIR, CUSTOM_MAPS_DIR), margin, top, font, Color::GRAY);
+        return
+    }
+
+    for (i, level) in levels.levels.iter().enumerate() {
+        let y = top + ui(28) * i as i32;
+        let color = if !level.problems.is_empty() { Color::RED }
+        else if i == levels.selected { Color::YELLOW }
+        else { Color::WHITE };
+
+        // the focused map gets a bar behind it as well, for pads and keyboards without a pointer
+        if i == levels.selected {
+            d.draw_rectangle(margin - ui(6), y - ui(4), window_size().x as i32 / 2 - 2 * margin + ui(12), ui(28), Color::YELLOW.alpha(0.2));
+        }
+        let marker = if i == levels.selected { "> " } else { "  " };
+        let tag = if level.custom { " [custom]" } else { "" };
+        d.draw_text(&format!("{}{}{}", marker, level.name(), tag), margin, y, font, color);
+    }
+
+    // details of the selected map on the right
+    let level = &levels.levels[levels.selected];
+    let x = window_size().x / 2.0;
+    if let Some(texture) = &level.thumbnail {
+        let width = window_size().x / 2.0 - margin as f32;
+        let height = width * texture.height() as f32 / texture.width() as f32;
+        let source_rec = Rectangle::new(0.0, 0.0, texture.width() as f32, texture.height() as f32);
+        d.draw_texture_pro(texture, source_rec, Rectangle::new(x, top as f32, width, height), Vector2::zero(), 0.0, Color::WHITE);
+    }
+
+    for (i, problem) in level.problems.iter().enumerate() {
+        d.draw_text(problem, x as i32, top + font * i as i32, ui(16), Color::RED);
+    }
+
+    if let Some(error) = &levels.error {
+        d.draw_text(error, margin, window_size().y as i32 - margin, font, Color::RED);
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use raylib::math::Vector2;
+
+pub mod achievement;
+pub mod assets;
+pub mod audio;
+pub mod autosave;
+pub mod backend;
+pub mod behavior;
+pub mod bench;
+pub mod bindings;
+pub mod console;
+pub mod credits;
+pub mod columns;
+pub mod cvar;
+pub mod board;
+pub mod budget;
+pub mod caption;
+pub mod capture;
+pub mod cellkind;
+pub mod config;
+pub mod damage;
+pub mod daylight;
+pub mod decal;
+pub mod demo;
+pub mod door;
+pub mod editor;
+pub mod enemy;
+pub mod event;
+pub mod fog;
+pub mod fuzz;
+pub mod game;
+pub mod generator;
+pub mod ghost;
+pub mod grade;
+pub mod hotreload;
+pub mod item;
+pub mod killcam;
+pub mod levels;
+pub mod light;
+pub mod map;
+pub mod menu;
+pub mod math;
+pub mod minimap;
+pub mod narration;
+pub mod net;
+pub mod panel;
+pub mod particle;
+pub mod path;
+pub mod player;
+pub mod prefab;
+pub mod preload;
+pub mod raycast;
+pub mod records;
+pub mod save;
+pub mod script;
+pub mod render;
+pub mod spawn;
+pub mod software;
+pub mod sprite;
+pub mod summary;
+pub mod state;
+pub mod teleporter;
+pub mod texture;
+pub mod theme;
+pub mod thumbnail;
+pub mod tiled;
+pub mod transform;
+pub mod transition;
+pub mod trigger;
+pub mod undo;
+pub mod visibility;
+pub mod watchdog;
+pub mod weapon;
+pub mod world;
+
+pub use board::{Board, Cell};
+pub use player::Player;
+pub use raycast::{cast_ray, RayHit};
+pub use render::Renderer;
+
+pub const WINDOW_SIZE: Vector2 = Vector2::new(860.0, 860.0);
+
+// the bits of the window's width and height, like the ui scale below
+static WINDOW_WIDTH: AtomicU32 = AtomicU32::new(WINDOW_SIZE.x.to_bits());
+static WINDOW_HEIGHT: AtomicU32 = AtomicU32::new(WINDOW_SIZE.y.to_bits());
+
+// WINDOW_SIZE until the engine config sets it, then whatever the window was last resized to
+pub fn window_size() -> Vector2 {
+    Vector2::new(f32::from_bits(WINDOW_WIDTH.load(Ordering::Relaxed)), f32::from_bits(WINDOW_HEIGHT.load(Ordering::Relaxed)))
+}
+
+pub fn set_window_size(size: Vector2) {
+    WINDOW_WIDTH.store(size.x.to_bits(), Ordering::Relaxed);
+    WINDOW_HEIGHT.store(size.y.to_bits(), Ordering::Relaxed);
+}
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.0;
+
+// how many times its size the hud, the minimap, the menus and their fonts are drawn at,
+// kept as the bits of an f32 so it can change at runtime. 1.0 until it is set
+static UI_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+pub fn ui_scale() -> f32 {
+    f32::from_bits(UI_SCALE.load(Ordering::Relaxed))
+}
+
+pub fn set_ui_scale(scale: f32) {
+    UI_SCALE.store(scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE).to_bits(), Ordering::Relaxed);
+}
+
+// a size in pixels at the ui scale
+pub fn ui(size: i32) -> i32 {
+    f32::round(size as f32 * ui_scale()) as i32
+}
+pub const MINIMAP_ASPECT_RATIO: f32 = 0.2; // 20%
+
+pub const EPS: f32 = 1e-6;
+pub const FOV: f32 = 90.0;
+pub const NUM_OF_RAYS: usize = 430;
+pub const FAR_CLIPING_PLANE: f32 = 10.0;
+
+// the simulation always advances in steps of this size, whatever the frame rate
+pub const SIM_DT: f32 = 1.0 / 60.0;
+// longest frame that is caught up on, so a stall doesn't run hundreds of steps
+pub const MAX_FRAME_TIME: f32 = 0.25;
@@ -0,0 +1,164 @@
+use std::ops::Add;
+
+use raylib::math::Vector2;
+
+use crate::{board::Board, spawn::SpawnKind, visibility::sight_doors};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub pos: Vector2,
+    pub radius: f32,
+    pub intensity: f32
+}
+
+// a cell a light reaches, as long as the doors between them are open
+struct Reach {
+    cell: usize,
+    // how much of the light's intensity is left by then
+    falloff: f32,
+    doors: Vec<(usize, usize)>
+}
+
+// the cells a light sees, cast once for where it is and not again until the walls change
+struct LightSight {
+    pos: Vector2,
+    radius: f32,
+    reach: Vec<Reach>
+}
+
+impl LightSight {
+    fn cast(board: &Board, pos: Vector2, radius: f32) -> LightSight {
+        let mut reach = vec![];
+        let (x0, y0) = (f32::max(pos.x - radius, 0.0) as usize, f32::max(pos.y - radius, 0.0) as usize);
+        let (x1, y1) = (usize::min((pos.x + radius) as usize + 1, board.cols), usize::min((pos.y + radius) as usize + 1, board.rows));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let center = Vector2::new(x as f32, y as f32).add(Vector2::new(0.5, 0.5));
+                let distance = pos.distance_to(center);
+                if distance >= radius { continue }
+                let Some(doors) = sight_doors(board, pos, center) else { continue };
+
+                reach.push(Reach { cell: y * board.cols + x, falloff: (1.0 - distance / radius).powi(2), doors });
+            }
+        }
+        LightSight { pos, radius, reach }
+    }
+}
+
+// brightness of every cell from the light spawns on the board, rebuilt only when the board or the lights change.
+// a board without lights is fully lit, so maps made before lighting look the same
+pub struct LightMap {
+    cols: usize,
+    rows: usize,
+    values: Vec<f32>,
+    lights: Vec<Light>,
+    // what every light seen so far on this layout reaches, so a rebuild for a door moving or a switch
+    // only adds up the tables. lights switched off keep theirs for when they come back on
+    sight: Vec<LightSight>,
+    layout: Option<u64>,
+    revision: Option<u64>,
+    ambient: f32,
+    generation: u64
+}
+
+impl Default for LightMap {
+    fn default() -> LightMap {
+        LightMap::new()
+    }
+}
+
+impl LightMap {
+    pub fn new() -> LightMap {
+        LightMap {
+            cols: 0,
+            rows: 0,
+            values: vec![],
+            lights: vec![],
+            sight: vec![],
+            layout: None,
+            revision: None,
+            ambient: 1.0,
+            generation: 0
+        }
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    // bumped on every rebuild, for caches of anything drawn with the light map
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn update(&mut self, board: &Board, ambient: f32) {
+        // switches turn lights off with the "off" property
+        let lights: Vec<Light> = board.spawns.iter().filter(|spawn| spawn.kind == SpawnKind::Light && spawn.property("off").unwrap_or(0.0) == 0.0).map(|spawn| Light {
+            pos: spawn.pos,
+            radius: spawn.property("radius").unwrap_or(0.0),
+            intensity: spawn.property("intensity").unwrap_or(0.0)
+        }).collect();
+
+        if self.revision == Some(board.revision()) && self.lights == lights && self.ambient == ambient { return }
+
+        self.cols = board.cols;
+        self.rows = board.rows;
+        self.revision = Some(board.revision());
+        self.lights = lights;
+        self.ambient = ambient;
+        self.generation += 1;
+
+        self.values = vec![1.0; board.rows * board.cols];
+        if self.lights.is_empty() { return }
+
+        if self.layout != Some(board.layout_revision()) {
+            self.layout = Some(board.layout_revision());
+            self.sight.clear();
+        }
+        // lights that were moved or are gone from the map
+        self.sight.retain(|sight| board.spawns.iter().any(|spawn| spawn.kind == SpawnKind::Light && spawn.pos == sight.pos));
+
+        for y in 0..board.rows {
+            for x in 0..board.cols {
+                if !board.blocks_sight(x, y) {
+                    self.values[y * board.cols + x] = ambient;
+                }
+            }
+        }
+
+        for light in self.lights.iter().filter(|light| light.radius > 0.0) {
+            let index = match self.sight.iter().position(|sight| sight.pos == light.pos && sight.radius == light.radius) {
+                Some(index) => index,
+                None => {
+                    self.sight.push(LightSight::cast(board, light.pos, light.radius));
+                    self.sight.len() - 1
+                }
+            };
+
+            for reach in self.sight[index].reach.iter() {
+                let (x, y) = (reach.cell % board.cols, reach.cell / board.cols);
+                if board.blocks_sight(x, y) || reach.doors.iter().any(|&(x, y)| board.blocks_sight(x, y)) { continue }
+                self.values[reach.cell] += light.intensity * reach.falloff;
+            }
+        }
+
+        for value in self.values.iter_mut() {
+            *value = f32::min(*value, 1.0);
+        }
+    }
+
+    pub fn value(&self, x: usize, y: usize) -> f32 {
+        if x >= self.cols || y >= self.rows { return self.ambient_or_lit() }
+        self.values[y * self.cols + x]
+    }
+
+    pub fn at(&self, pos: Vector2) -> f32 {
+        if pos.x < 0.0 || pos.y < 0.0 { return self.ambient_or_lit() }
+        self.value(pos.x as usize, pos.y as usize)
+    }
+
+    fn ambient_or_lit(&self) -> f32 {
+        if self.lights.is_empty() { 1.0 }
+        else { self.ambient }
+    }
+}
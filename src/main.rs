@@ -1,459 +1,576 @@
-use std::{ops::{Add, AddAssign, Div, Mul, MulAssign, Sub, SubAssign}, process::exit, usize};
+use std::{ops::Mul, path::Path, process::exit, rc::Rc};
 
-use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::{Rectangle, Vector2}, texture::Texture2D, RaylibHandle};
-use raylib::RaylibThread;
+use raylib::{color::Color, core::{audio::RaylibAudio, window::{get_current_monitor, get_monitor_height, get_monitor_width}}, drawing::RaylibDraw, math::{Rectangle, Vector2}, texture::Image, RaylibHandle, RaylibThread};
+use raycast::{achievement::{user_data_dir, Achievements}, assets::AssetRoots, audio::AudioManager, autosave::{Recovery, RecoveryPrompt, AUTOSAVE_PATH}, behavior::{load_tree, BehaviorTree}, bench::{self, flythrough, BenchMap, Benchmark, BENCHMARK_TIME}, bindings::Bindings, capture::Capture, cellkind::cell_kind_by_name, columns::{diff_captures, load_capture, DIFF_TOLERANCE}, config::{load_engine_config, EngineConfig}, cvar::load_config, board::{Board, Cell, Segment}, decal::{Decal, Face}, game::Game, hotreload::HotReload, preload::Preload, narration::{set_narrator, Narrator}, net::NetSession, panel::Panel, item::KEYS, levels::{LevelManager, LEVEL_LIST_PATH}, map::load_board, player::Player, records::Records, spawn::{Spawn, SpawnKind}, sprite::Sprite, state::{GameState, Gameplay, StateStack}, texture::{FlatTexture, TextureId, TextureRegistry}, theme::load_themes, Renderer, MAX_FRAME_TIME, SIM_DT, set_window_size, window_size};
 
-const WINDOW_SIZE: Vector2 = Vector2::new(860.0, 860.0);
-const MINIMAP_ASPECT_RATIO: f32 = 0.2; // 20%
-
-const EPS: f32 = 1e-6;
-const FOV: f32 = 90.0;
-const NUM_OF_RAYS: usize = 430;
-const FAR_CLIPING_PLANE: f32 = 10.0;
-
-enum Cell {
-    EMPTY,
-    COLOR(Color),
-    TEXTURE(Texture2D),
-    TranslucentTexture(Texture2D)
-}
-
-struct Board<'a> {
-    rows: usize,
-    cols: usize,
-    cells: Vec<&'a Cell>
+fn load_texture(rl: &mut RaylibHandle, thread: &RaylibThread, textures: &mut TextureRegistry, assets: &AssetRoots, name: &str) -> TextureId {
+    match textures.load_or_placeholder(rl, thread, &assets.base_path(name), &assets.resolve(name)) {
+        Ok(id) => id,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        },
+    }
 }
 
-struct Player {
-    pos: Vector2,
-    dir: Vector2,
-    spd: Vector2,
-    turn_spd: f32
-}
+const CONFIG_PATH: &str = "./config.cfg";
+const ENGINE_CONFIG_PATH: &str = "./config.toml";
+// the map --benchmark flies over when no other is given
+const BENCHMARK_MAP: &str = "./maps/arena.txt";
 
-struct Game<'a> {
-    board: Board<'a>,
-    player: Player
+fn load_behavior(assets: &AssetRoots, name: &str) -> Rc<BehaviorTree> {
+    match load_tree(&assets.resolve(name)) {
+        Ok(tree) => Rc::new(tree),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    }
 }
 
-struct Transform2D {
-    offset: Vector2,
-    zoom: Vector2
-}
+fn bench_map_arg() -> Option<BenchMap> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--bench-map" {
+            let name = args.next().unwrap_or_default();
+            match BenchMap::from_name(&name) {
+                Ok(map) => return Some(map),
+                Err(err) => {
+                    println!("ERROR: {}", err);
+                    exit(1);
+                }
+            }
+        }
+    }
 
-struct Straight {
-    a: f32,
-    b: f32,
-    dir: Vector2
+    None
 }
 
-impl<'a> Board<'a> {
-    fn new(rows: usize, cols: usize) -> Board<'a> {
-        Board {
-            rows, cols,
-            cells: vec![&Cell::EMPTY; rows * cols]
+// every "--mod <dir>" adds an asset folder on top of the ones before it
+fn asset_roots_arg() -> AssetRoots {
+    let mut assets = AssetRoots::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--mod" {
+            let root = args.next().unwrap_or_default();
+            if let Err(err) = assets.add(&root) {
+                println!("ERROR: {}", err);
+                exit(1);
+            }
         }
     }
 
-    fn at(&self, x: usize, y: usize) -> &Cell {
-        assert!(x < self.cols, "X out of bounds");
-        assert!(y < self.rows, "Y out of bounds");
-        self.cells[y * self.cols + x]
-    }
+    assets
+}
 
-    fn set(&mut self, x: usize, y: usize, cell: &'a Cell) {
-        assert!(x < self.cols, "X out of bounds");
-        assert!(y < self.rows, "Y out of bounds");
-        self.cells[y * self.cols + x] = cell
+// every "--set <name>=<value>" overrides a cvar after the config file was applied
+fn cvar_args() -> Vec<(String, String)> {
+    let mut cvars = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--set" {
+            let arg = args.next().unwrap_or_default();
+            match arg.split_once('=') {
+                Some((name, value)) => cvars.push((name.to_string(), value.to_string())),
+                None => {
+                    println!("ERROR: Invalid cvar '{}', expected <name>=<value>", arg);
+                    exit(1);
+                }
+            }
+        }
     }
+
+    cvars
 }
 
-impl Player {
-    fn new(x: f32, y: f32) -> Player {
-        Player {
-            pos: Vector2::new(x, y),
-            dir: Vector2::new(1.0, 0.0),
-            spd: Vector2::one(),
-            turn_spd: std::f32::consts::FRAC_PI_2
+// "--size <w>x<h>", "--rays <n>" and "--fov <degrees>" override the engine config
+fn engine_args(engine: &mut EngineConfig) -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !matches!(arg.as_str(), "--size" | "--rays" | "--fov") { continue }
+
+        let value = args.next().unwrap_or_default();
+        match arg.as_str() {
+            "--size" => {
+                let (w, h) = value.split_once('x').ok_or(format!("Invalid size '{}', expected <width>x<height>", value))?;
+                engine.window_width = w.parse::<u32>().map_err(|err| format!("Invalid width '{}': {}", w, err))?;
+                engine.window_height = h.parse::<u32>().map_err(|err| format!("Invalid height '{}': {}", h, err))?;
+            },
+            "--rays" => engine.rays = value.parse::<usize>().map_err(|err| format!("Invalid rays '{}': {}", value, err))?,
+            _ => engine.fov = value.parse::<f32>().map_err(|err| format!("Invalid fov '{}': {}", value, err))?
         }
     }
 
-    fn move_forward(&mut self, delta: f32) {
-        self.pos.add_assign(self.spd.mul(delta).mul(self.dir))
+    let problems = engine.validate();
+    if !problems.is_empty() {
+        return Err(format!("Invalid arguments: {}", problems.join(", ")))
     }
+    Ok(())
+}
 
-    fn move_backward(&mut self, delta: f32) {
-        self.pos.sub_assign(self.spd.mul(delta).mul(self.dir))
-    }
+// "--benchmark" flies a fixed camera path over a standard map and prints the frame times
+fn benchmark_arg() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--benchmark")
+}
 
-    fn turn_left(&mut self, delta: f32) {
-        self.dir.rotate(-self.turn_spd * delta)
+// "--host <port>" lets other players join, "--connect <address>:<port>" joins one that does
+fn net_arg() -> Result<Option<NetSession>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => {
+                let value = args.next().unwrap_or_default();
+                let port = value.parse::<u16>().map_err(|err| format!("Invalid port '{}': {}", value, err))?;
+                return NetSession::host(port).map(Some)
+            },
+            "--connect" => return NetSession::connect(&args.next().unwrap_or_default()).map(Some),
+            _ => {}
+        }
     }
 
-    fn turn_right(&mut self, delta: f32) {
-        self.dir.rotate(self.turn_spd * delta)
-    }
+    Ok(None)
 }
 
-impl Transform2D {
-    fn default() -> Transform2D {
-        Transform2D {
-            offset: Vector2::zero(),
-            zoom: Vector2::one()
+// "--map <path>" starts on that map instead of the built-in one
+fn map_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--map" {
+            return Some(args.next().unwrap_or_default())
         }
     }
-}
 
-trait Transform2DApplayer {
-    fn apply(&self, t: &Transform2D) -> Self;
-    fn apply_zoom(&self, t: &Transform2D) -> Self;
+    None
 }
 
-impl Transform2DApplayer for Vector2 {
-    fn apply(&self, t: &Transform2D) -> Vector2 {
-        self.mul(t.zoom).add(t.offset)
+// "--play <demo>" replays a demo from the start, the same camera path for every run
+fn demo_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--play" {
+            return Some(args.next().unwrap_or_default())
+        }
     }
 
-    fn apply_zoom(&self, t: &Transform2D) -> Vector2 {
-        self.mul(t.zoom)
-    }
+    None
 }
 
-impl Transform2DApplayer for Rectangle {
-    fn apply(&self, t: &Transform2D) -> Rectangle {
-        Rectangle::new(
-            (self.x * t.zoom.x) + t.offset.x,
-            (self.y * t.zoom.y) + t.offset.y,
-            self.width * t.zoom.x,
-            self.height * t.zoom.y
-        )
+// "--diff-columns <a> <b>" compares two capture_columns dumps column by column and exits,
+// failing if any column differs
+fn diff_columns_arg() -> Option<(String, String)> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--diff-columns" {
+            return Some((args.next().unwrap_or_default(), args.next().unwrap_or_default()))
+        }
     }
 
-    fn apply_zoom(&self, t: &Transform2D) -> Self {
-        Rectangle::new(
-            self.x * t.zoom.x,
-            self.y * t.zoom.y,
-            self.width * t.zoom.x,
-            self.height * t.zoom.y
-        )
-    }
+    None
 }
 
-impl Straight {
-    fn new(p1: Vector2, p2: Vector2) -> Straight {
-        let dir = p2.sub(p1);
+fn diff_columns(a: &str, b: &str) -> Result<Vec<String>, String> {
+    Ok(diff_captures(&load_capture(a)?, &load_capture(b)?, DIFF_TOLERANCE))
+}
 
-        let a = if dir.x != 0.0 { dir.y / dir.x }
-        else { 0.0 };
+// flies the scripted camera until the benchmark is over, nothing but the camera moves
+fn run_benchmark(rl: &mut RaylibHandle, thread: &RaylibThread, gameplay: &mut Gameplay) {
+    println!("INFO: Benchmark on {} at {}x{} with {} rays for {} seconds", gameplay.game.editor.path, window_size().x, window_size().y, gameplay.renderer.rays(), BENCHMARK_TIME);
 
-        let b = p1.y - (p1.x * a);
+    gameplay.game.stream_textures(rl, thread);
+    let mut benchmark = Benchmark::new();
+    while !rl.window_should_close() && benchmark.update(rl.get_frame_time()) {
+        let (pos, dir) = flythrough(&gameplay.game.board, benchmark.time);
+        gameplay.game.player.pos = pos;
+        gameplay.game.player.dir = dir;
+        gameplay.game.stop_interpolation();
 
-        Straight { a, b, dir }
+        let mut d = rl.begin_drawing(thread);
+        d.clear_background(Color::BLACK);
+        gameplay.render(&mut d, thread, 1.0);
     }
 
-    fn f(&self, x: f32) -> f32 {
-        (x * self.a) + self.b
-    }
+    println!("INFO: Benchmark: {}", benchmark.report());
+}
 
-    fn f1(&self, y: f32) -> f32 {
-        (y - self.b) / self.a
+// F11 goes fullscreen over the whole monitor, going back gives the window the size it had before
+fn toggle_fullscreen(rl: &mut RaylibHandle, windowed: &mut Vector2) {
+    if rl.is_window_fullscreen() {
+        rl.toggle_fullscreen();
+        rl.set_window_size(windowed.x as i32, windowed.y as i32);
+    } else {
+        *windowed = window_size();
+        let monitor = get_current_monitor();
+        rl.set_window_size(get_monitor_width(monitor), get_monitor_height(monitor));
+        rl.toggle_fullscreen();
     }
 }
 
-fn next_ray_step(current: Vector2, straight: &Straight) -> Vector2 {
-    let x = if straight.dir.x > 0.0 { f32::ceil(current.x) }
-    else { f32::floor(current.x) };
-    let y = straight.f(x);
-
-    if straight.a != 0.0 {
-        let y2 = if straight.dir.y > 0.0 { f32::ceil(current.y) }
-        else { f32::floor(current.y) };
-        let x2 = straight.f1(y2);
-
-        if Vector2::new(x2, y2).sub(current).length_sqr() < Vector2::new(x, y).sub(current).length_sqr() {
-            return Vector2::new(x2, y2)
+fn main() {
+    if let Some((a, b)) = diff_columns_arg() {
+        match diff_columns(&a, &b) {
+            Ok(lines) if lines.is_empty() => println!("INFO: '{}' and '{}' match", a, b),
+            Ok(lines) => {
+                for line in &lines {
+                    println!("{}", line);
+                }
+                println!("INFO: {} differences between '{}' and '{}'", lines.len(), a, b);
+                exit(1);
+            },
+            Err(err) => {
+                println!("ERROR: {}", err);
+                exit(1);
+            }
         }
+        return
     }
 
-    Vector2::new(x, y)
-}
-
-fn cast_ray(start: Vector2, dir: Vector2, board: &Board) -> Vec<Vector2> {
-    let straight = Straight::new(start, start.add(dir));
-    let eps = Vector2::new(f32::signum(straight.dir.x) * EPS, f32::signum(straight.dir.y) * EPS);
+    let bench_map = bench_map_arg();
+    let assets = asset_roots_arg();
+    let cvars = cvar_args();
+    let benchmark = benchmark_arg();
+    // the benchmark runs on the same map every time unless it is given one
+    let map = map_arg().or((benchmark && bench_map.is_none()).then(|| BENCHMARK_MAP.to_string()));
 
-    let mut point = next_ray_step(start, &straight);
-    let mut points: Vec<Vector2> = vec![];
-
-    let mut dist = point.distance_to(start).powi(2);
-    let mut last_dist = dist - 1.0;
+    let mut engine = match load_engine_config(ENGINE_CONFIG_PATH) {
+        Ok(engine) => engine,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    if let Err(err) = engine_args(&mut engine) {
+        println!("ERROR: {}", err);
+        exit(1);
+    }
+    set_window_size(Vector2::new(engine.window_width as f32, engine.window_height as f32));
+    if let Some(narrator) = Narrator::new(&engine.narrator) {
+        if let Err(err) = set_narrator(narrator) {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    }
 
-    while dist < FAR_CLIPING_PLANE*FAR_CLIPING_PLANE  && dist != last_dist {
-        let x = if dir.x > 0.0 { f32::floor(point.x) }
-        else { f32::ceil(point.x) - 1.0};
+    let (mut rl, thread) = raylib::init()
+        .size(window_size().x as i32, window_size().y as i32)
+        .title("raycasting")
+        .resizable()
+        .build();
+    // the smallest window the engine config accepts
+    rl.set_window_min_size(320, 240);
+    // Esc opens the pause menu, quitting goes through it
+    rl.set_exit_key(None);
 
-        let y = if dir.y > 0.0 { f32::floor(point.y + eps.y) }
-        else { f32::ceil(point.y) - 1.0 };
+    let board = Board::new(10, 10);
+    let player = Player::new(0.0, 0.0);
+    let mut game = Game::new(board, TextureRegistry::new(), player);
+
+    // everything in the asset folders, so maps can use textures the game doesn't ask for by name.
+    // streamed ones are only registered here and loaded once a map needs them
+    let loaded = if engine.stream_textures { game.textures.register_all(&mut rl, &thread, &assets) }
+        else { game.textures.load_all(&mut rl, &thread, &assets) };
+    if let Err(err) = loaded {
+        println!("ERROR: {}", err);
+        exit(1);
+    }
 
-        let x = f32::max(f32::min(x, board.cols as f32 - 1.0), 0.0) as usize;
-        let y = f32::max(f32::min(y, board.rows as f32 - 1.0), 0.0) as usize;
-        match board.at(x, y) {
-            Cell::EMPTY => {},
-            Cell::TranslucentTexture(_) => {
-                points.push(point)
+    // after the asset folders, so the themes' textures don't move the ids maps already use
+    let themes = load_themes();
+    let theme = if engine.theme.is_empty() { 0 }
+    else {
+        themes.iter().position(|theme| theme.name == engine.theme).unwrap_or_else(|| {
+            println!("ERROR: Unknown theme '{}'", engine.theme);
+            0
+        })
+    };
+    game.register_themes(themes, theme);
+
+    let galo_cego = load_texture(&mut rl, &thread, &mut game.textures, &assets, "galo-cego.png");
+    let atumalaca = load_texture(&mut rl, &thread, &mut game.textures, &assets, "atumalaca.png");
+    let steve_face = load_texture(&mut rl, &thread, &mut game.textures, &assets, "steve-face.png");
+    let steve_body = load_texture(&mut rl, &thread, &mut game.textures, &assets, "steve-body.png");
+    let glass = load_texture(&mut rl, &thread, &mut game.textures, &assets, "glass.png");
+
+    game.board.set(5, 5, Cell::COLOR(Color::BLUE));
+    game.board.set(5, 6, Cell::COLOR(Color::YELLOW));
+    game.board.set(5, 4, Cell::COLOR(Color::RED));
+    game.board.set(4, 3, Cell::COLOR(Color::GREEN));
+    game.board.set(7, 5, Cell::COLOR(Color::ORANGE));
+    game.board.set_height(7, 5, 0.5);
+    game.board.set_height(5, 5, 1.5);
+
+    game.board.set(1, 7, Cell::TEXTURE(galo_cego));
+    game.board.set(3, 7, Cell::TEXTURE(atumalaca));
+    game.board.set(4, 7, Cell::TEXTURE(steve_face));
+    game.board.set(2, 7, Cell::TranslucentTexture(steve_body));
+    game.board.set(4, 4, Cell::TranslucentTexture(glass));
+    game.board.set(1, 2, Cell::Segment(galo_cego, Segment::Slash));
+    game.board.set(2, 5, Cell::Segment(glass, Segment::North));
+    game.board.link_teleporters((0, 4), (8, 0), 90.0);
+
+    let door = match rl.load_texture_from_image(&thread, &Image::gen_image_checked(64, 64, 16, 64, Color::BROWN, Color::DARKBROWN)) {
+        Ok(texture) => game.textures.add(texture),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    game.board.set(5, 3, Cell::Door(door));
+
+    // flickering screen, four frames side by side in one sprite sheet
+    let mut sheet = Image::gen_image_color(256, 64, Color::BLACK);
+    for (i, color) in [Color::DARKGREEN, Color::GREEN, Color::LIME, Color::GREEN].iter().enumerate() {
+        let frame = Image::gen_image_checked(64, 64, 4 + 4 * i as i32, 64, *color, Color::BLACK);
+        sheet.draw(&frame, Rectangle::new(0.0, 0.0, 64.0, 64.0), Rectangle::new(64.0 * i as f32, 0.0, 64.0, 64.0), Color::WHITE);
+    }
+    let screen = match rl.load_texture_from_image(&thread, &sheet) {
+        Ok(texture) => game.textures.add(texture),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    let screen = game.textures.add_animation(game.textures.sheet_animation(screen, 4, 8.0));
+    game.board.set(9, 5, Cell::AnimatedTexture(screen));
+
+    // one pickup texture per item code, a cross for health, a box of ammo and the keys in their colors
+    let items = [Color::RED, Color::GOLD].into_iter().chain(KEYS.iter().map(|key| key.color()));
+    for (i, color) in items.enumerate() {
+        let mut image = Image::gen_image_color(64, 64, Color::BLANK);
+        match i {
+            0 => {
+                image.draw_rectangle(24, 8, 16, 48, color);
+                image.draw_rectangle(8, 24, 48, 16, color);
             },
+            1 => image.draw_rectangle(12, 24, 40, 32, color),
             _ => {
-                points.push(point);
-                break
-            },
+                image.draw_circle(20, 32, 12, color);
+                image.draw_rectangle(28, 28, 30, 8, color);
+            }
         }
 
-        point = next_ray_step(point.add(eps), &straight);
-
-        last_dist = dist;
-        dist = point.distance_to(start).powi(2);
-    }
-
-    points
-}
-
-fn get_hitted_cells<'a>(game: &'a Game) -> [Vec<(&'a Cell, Vector2)>; NUM_OF_RAYS] {
-    let mut all_cells = [(); NUM_OF_RAYS].map(|_| vec![(&Cell::EMPTY, Vector2::zero())]);
-
-    let half_fov = (FOV/2.0) * std::f32::consts::PI / 180.0;
-    let start = game.player.dir.rotated(half_fov);
-    let end = game.player.dir.rotated(-half_fov);
-    let lerp_amount = end.sub(start).div(NUM_OF_RAYS as f32);
-
-    let mut dir = start;
-    for cells in all_cells.iter_mut() {
-        let points = cast_ray(game.player.pos, dir, &game.board);
-        for point in points.iter() {
-            let mut cell = (&Cell::EMPTY, Vector2::zero());
-            cell.1 = *point;
-
-            if point.x >= 0.0 && point.x < game.board.cols as f32 && point.y >= 0.0 && point.y < game.board.rows  as f32{
-
-                let x = if dir.x > 0.0 { f32::floor(point.x) }
-                else { f32::ceil(point.x) - 1.0 } as usize;
-                let y = if dir.y > 0.0 { f32::floor(point.y) }
-                else { f32::ceil(point.y) - 1.0} as usize;
-                cell.0 = game.board.at(x, y);
+        match rl.load_texture_from_image(&thread, &image) {
+            Ok(texture) => game.pickup_textures.push(game.textures.add(texture)),
+            Err(err) => {
+                println!("ERROR: {}", err);
+                exit(1);
             }
-
-            cells.push(cell);
         }
-
-        dir.add_assign(lerp_amount);
     }
 
-    all_cells
-}
-
-fn darken_color(color: &Color, dist: f32) -> Color {
-    let hsv = color.color_to_hsv();
-    Color::color_from_hsv(hsv.x, hsv.y, hsv.z * (1.0 - dist))
-}
-
-fn update_controls(d: &RaylibDrawHandle, game: &mut Game) {
-    let delta = d.get_frame_time();
-    if d.is_key_down(raylib::ffi::KeyboardKey::KEY_W) {
-        game.player.move_forward(delta);
+    // a dark hole with a lighter rim torn around it, left on the walls that get shot
+    let mut hole = Image::gen_image_color(16, 16, Color::BLANK);
+    hole.draw_circle(8, 8, 7, Color::new(90, 80, 70, 160));
+    hole.draw_circle(8, 8, 4, Color::new(15, 15, 15, 255));
+    match rl.load_texture_from_image(&thread, &hole) {
+        Ok(texture) => game.bullet_hole_texture = Some(game.textures.add(texture)),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
     }
-
-    if d.is_key_down(raylib::ffi::KeyboardKey::KEY_S) {
-        game.player.move_backward(delta);
+    game.board.decals.push(Decal { texture: steve_face, cell: (5, 6), face: Face::West, u: 0.5, height: 0.6, size: 0.4 });
+
+    game.editor.palette = vec![
+        Cell::COLOR(Color::BLUE), Cell::COLOR(Color::YELLOW), Cell::COLOR(Color::RED), Cell::COLOR(Color::GREEN),
+        Cell::TEXTURE(galo_cego), Cell::TEXTURE(atumalaca), Cell::TEXTURE(steve_face),
+        Cell::TranslucentTexture(steve_body), Cell::TranslucentTexture(glass), Cell::Door(door), Cell::AnimatedTexture(screen),
+        Cell::Segment(galo_cego, Segment::Slash), Cell::Segment(galo_cego, Segment::Backslash), Cell::Segment(glass, Segment::North), Cell::Segment(glass, Segment::West), Cell::Mirror
+    ];
+    if let Some(hazard) = cell_kind_by_name("hazard") {
+        game.editor.palette.push(Cell::Custom(hazard, glass));
     }
 
-    if d.is_key_down(raylib::ffi::KeyboardKey::KEY_A) {
-        game.player.turn_left(delta);
+    if let Err(err) = game.textures.pack_atlas(&mut rl, &thread) {
+        println!("ERROR: {}", err);
+        exit(1);
     }
 
-    if d.is_key_down(raylib::ffi::KeyboardKey::KEY_D) {
-        game.player.turn_right(delta);
+    game.sprites.push(Sprite::new(7.5, 2.5, galo_cego));
+    let mut pillar = Sprite::new(2.5, 4.5, atumalaca);
+    pillar.scale = 0.5;
+    game.sprites.push(pillar);
+    game.board.spawns.push(Spawn::new(SpawnKind::Player, Vector2::new(0.5, 0.5)));
+    game.board.spawns.push(Spawn::new(SpawnKind::Enemy, Vector2::new(8.5, 8.5)));
+    let mut guard = Spawn::new(SpawnKind::Enemy, Vector2::new(0.5, 9.5));
+    guard.set_property("behavior", 0.0);
+    game.board.spawns.push(guard);
+    game.board.spawns.push(Spawn::new(SpawnKind::Light, Vector2::new(2.5, 2.5)));
+    game.board.spawns.push(Spawn::new(SpawnKind::Light, Vector2::new(7.5, 6.5)));
+
+    if let Some(map) = bench_map {
+        let scene = bench::generate(map, steve_face, glass, galo_cego);
+        game.board = scene.board;
+        game.player = scene.player;
+        game.sprites = scene.sprites;
     }
-}
-
-fn minimap_mouse_event(d: &mut RaylibDrawHandle, mt: &Transform2D, game: &mut Game) {
-    let mouse = d.get_mouse_position().sub(mt.offset).div(mt.zoom);
-
-    let x = mouse.x as usize;
-    let y = mouse.y as usize;
 
-    if mouse.x >= 0.0 && mouse.y >= 0.0 && mouse.x < game.board.cols as f32 && mouse.y < game.board.rows as f32 {
-        if d.is_mouse_button_pressed(raylib::ffi::MouseButton::MOUSE_BUTTON_LEFT) {
-            game.player.pos = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+    game.enemy_texture = Some(steve_face);
+    game.remote_texture = Some(atumalaca);
+    game.panel = match Panel::load(&mut rl, &thread) {
+        Ok(panel) => Some(Rc::new(panel)),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    game.net = match net_arg() {
+        Ok(net) => net,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            exit(1);
+        }
+    };
+    game.behaviors.push(load_behavior(&assets, "guard.bt"));
+    game.spawn_enemies();
+    game.spawn_pickups();
+
+    game.player.spd = Vector2::one().mul(engine.player_speed);
+    game.player.turn_spd = engine.turn_speed;
+    game.player.smoothing = engine.smooth_movement;
+    game.player.head_bob = engine.head_bob;
+    game.player.snap_turn = engine.snap_turn;
+    game.captions.enabled = engine.captions;
+    game.translucent_layers = engine.translucent_layers;
+    game.fov = engine.fov;
+    game.far_plane = engine.far_plane;
+    game.fog.end = engine.far_plane;
+    game.bindings = Bindings::from_config(&engine.bindings);
+
+    game.hot_reload = Some(HotReload::new(assets.clone()));
+    game.preload = Some(Preload::new());
+    // without a home folder the achievements and best times are only kept while the game runs
+    if let Some(dir) = user_data_dir() {
+        match Achievements::load(&dir) {
+            Ok(achievements) => game.achievements = achievements,
+            Err(err) => println!("ERROR: {}", err)
+        }
+        match Records::load(&dir) {
+            Ok(records) => game.records = records,
+            Err(err) => println!("ERROR: {}", err)
         }
     }
-}
-
-fn render_game(d: &mut RaylibDrawHandle, game: &Game) {
-    let mut gt = Transform2D::default();
-    gt.zoom.mul_assign(WINDOW_SIZE.x / NUM_OF_RAYS as f32);
-    let window_size = WINDOW_SIZE.div(gt.zoom);
-
-    let max_dist = Vector2::new(game.board.cols as f32, game.board.rows as f32).length();
-    let hitted_cells = get_hitted_cells(game);
-
-    for (x, cells) in hitted_cells.iter().rev().enumerate() {
-        for cell in cells.iter().rev() {
-            let dist = cell.1.sub(game.player.pos).dot(game.player.dir);
-
-            let h = (window_size.y / dist) / (2.0 * window_size.y / window_size.x);
-            let pos = Vector2::new(x as f32, (window_size.y - h) / 2.0);
-
-            match cell.0 {
-                Cell::EMPTY => {},
-                Cell::COLOR(color) => {
-                    let color = darken_color(color, max_dist);
-                    d.draw_rectangle_v(pos.apply(&gt), Vector2::new(1.0, h).apply_zoom(&gt), color);
-                },
-                Cell::TranslucentTexture(texture) | Cell::TEXTURE(texture) => {
-                    let nx = cell.1.x - f32::floor(cell.1.x);
-                    let ny = cell.1.y - f32::floor(cell.1.y);
-
-                    let mut tx = texture.width as f32;
-                    let mut ty = texture.height as f32;
-
-                    if ny ==  0.0 {
-                        tx *= nx;
-                        ty *= ny;
-                    } else {
-                        tx *= ny;
-                        ty *= nx;
-                    }
-
-                    let th = texture.height as f32;
-                    let tw = 1.0;
-
-                    let color = darken_color(&Color::WHITE, dist/max_dist);
-
-                    let source_rec = Rectangle::new(tx, ty, tw, th);
-                    let dest_rec = Rectangle::new(pos.x, pos.y, 1.0, h).apply(&gt);
-
-                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
-                }
+    game.level_manager = match LevelManager::load(LEVEL_LIST_PATH) {
+        Ok(manager) => manager,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            LevelManager::new()
+        }
+    };
+    game.fit_minimap();
+
+    game.default_floor = Some(game.textures.add_flat(FlatTexture::from_image(&Image::gen_image_checked(64, 64, 2, 2, Color::DARKGRAY, Color::GRAY))));
+    game.default_ceiling = Some(game.textures.add_flat(FlatTexture::from_image(&Image::gen_image_checked(64, 64, 4, 4, Color::DARKBLUE, Color::BLUE))));
+    // the built in board isn't entered like the maps are
+    game.apply_default_flats();
+
+    if let Some(path) = map {
+        match load_board(&path, &game.textures) {
+            Ok(board) => game.load_level(path, board),
+            Err(err) => {
+                println!("ERROR: {}", err);
+                exit(1);
             }
         }
     }
-}
 
-fn render_player(d: &mut RaylibDrawHandle, mt: &Transform2D, player: &Player) {
-    let zoom =  f32::max(mt.zoom.x, mt.zoom.y);
-    let pos = player.pos.apply(&mt);
+    let mut renderer = Renderer::new(&mut rl, &thread);
+    renderer.set_rays(engine.rays);
 
-    let half_fov = (FOV/2.0) * std::f32::consts::PI / 180.0;
-    let p1 = player.dir.rotated(half_fov);
-    let p2 = player.dir.rotated(-half_fov);
-
-    d.draw_triangle(pos, pos.add(p1.apply_zoom(&mt)), pos.add(p2.apply_zoom(&mt)), Color::PURPLE);
-    d.draw_circle_v(pos, 0.2 * zoom, Color::RED);
-}
+    // the device is kept open until the program exits
+    let audio = match RaylibAudio::init_audio_device() {
+        Ok(audio) => Some(&*Box::leak(Box::new(audio))),
+        Err(err) => {
+            println!("ERROR: {}", err);
+            None
+        }
+    };
+    let audio = AudioManager::new(audio, &assets);
 
-fn render_minimap(d: &mut RaylibDrawHandle, mt: &Transform2D,  game: &Game) {
-    let board_size = Vector2::new(game.board.cols as f32, game.board.rows as f32);
-    d.draw_rectangle_v(Vector2::zero().apply(&mt), board_size.apply_zoom(&mt), Color::BLACK);
+    let mut gameplay = Gameplay::new(game, renderer, audio);
 
-    // render grid
-    for y in 0..=game.board.rows{
-        d.draw_line_v(Vector2::new(0.0, y as f32).apply(&mt), Vector2::new(board_size.x, y as f32).apply(&mt), Color::GRAY);
-    }
-
-    for x in 0..=game.board.cols{
-        d.draw_line_v(Vector2::new(x as f32, 0.0).apply(&mt), Vector2::new(x as f32, board_size.y).apply(&mt), Color::GRAY);
-    }
+    // a missing config file just keeps the defaults
+    let config = if Path::new(CONFIG_PATH).exists() {
+        load_config(CONFIG_PATH).unwrap_or_else(|err| {
+            println!("ERROR: {}", err);
+            vec![]
+        })
+    } else { vec![] };
 
-    // render cells
-    for y in 0..game.board.rows{
-        for x in 0..game.board.cols{
-            let cell = game.board.at(x, y);
-
-            let pos = Vector2::new(x as f32, y as f32).apply(&mt);
-            let size = Vector2::one().apply_zoom(&mt);
-
-            match cell {
-                Cell::EMPTY => {},
-                Cell::COLOR(color) => d.draw_rectangle_v(pos, size, color),
-                Cell::TranslucentTexture(texture) | Cell::TEXTURE(texture) => {
-                    let source_rec =Rectangle::new(0.0, 0.0, texture.width as f32, texture.height as f32);
-                    let dest_rec = Rectangle::new(x as f32, y as f32, 1.0, 1.0).apply(&mt);
-                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, Color::WHITE);
-                },
-            }
+    for (name, value) in config.iter().chain(cvars.iter()) {
+        if let Err(err) = gameplay.set_cvar(name, value) {
+            println!("ERROR: {}", err);
         }
     }
 
-    render_player(d, &mt, &game.player);
-}
-
-fn calulate_minimap_size(board_size: Vector2) -> Vector2 {
-    if board_size.x > board_size.y {
-        let x = WINDOW_SIZE.x * MINIMAP_ASPECT_RATIO;
-        let y = (x / board_size.x) * board_size.y;
-        Vector2::new(x, y)
-    } else {
-        let y = WINDOW_SIZE.y * MINIMAP_ASPECT_RATIO;
-        let x = (y / board_size.y) * board_size.x;
-        Vector2::new(x, y)
+    let demo = demo_arg();
+    if let Some(demo) = &demo {
+        gameplay.execute(&format!("play {}", demo));
     }
-}
 
-fn load_texture(rl: &mut RaylibHandle, thread: &RaylibThread, filename: &str) -> Texture2D {
-    match rl.load_texture(&thread, filename) {
-        Ok(texture) => texture,
-        Err(err) => {
-            println!("ERROR: {}", err);
-            exit(1);
-        },
+    if benchmark {
+        run_benchmark(&mut rl, &thread, &mut gameplay);
+        return
     }
-}
-
-fn main() {
-    let (mut rl, thread) = raylib::init()
-        .size(WINDOW_SIZE.x as i32, WINDOW_SIZE.y as i32)
-        .title("raycasting")
-        .build();
 
-    let board = Board::new(10, 10);
-    let player = Player::new(0.0, 0.0);
-    let mut game = Game { board, player };
-    game.player.spd.mul_assign(3.0);
-    game.player.turn_spd *= 2.0;
-
-    let board_size = Vector2::new(game.board.cols as f32, game.board.rows as f32);
-    let minimap_size = calulate_minimap_size(board_size);
-    let margin = Vector2::one().mul(10.0);
-
-    let mut mt = Transform2D::default();
-    mt.zoom = minimap_size.div(board_size);
-    mt.offset = WINDOW_SIZE.sub(minimap_size).sub(margin);
-
-    let galo_cego = Cell::TEXTURE(load_texture(&mut rl, &thread, "./res/galo-cego.png"));
-    let atumalaca = Cell::TEXTURE(load_texture(&mut rl, &thread, "./res/atumalaca.png"));
-    let steve_face = Cell::TEXTURE(load_texture(&mut rl, &thread, "./res/steve-face.png"));
-    let steve_body = Cell::TranslucentTexture(load_texture(&mut rl, &thread, "./res/steve-body.png"));
-    let glass = Cell::TranslucentTexture(load_texture(&mut rl, &thread, "./res/glass.png"));
+    let mut states = StateStack::new();
+    states.push(Box::new(gameplay));
+    // edits the editor didn't get to save last time, a demo replays without waiting on the answer
+    match Recovery::load(AUTOSAVE_PATH) {
+        Ok(Some(recovery)) if demo.is_none() => states.push(Box::new(RecoveryPrompt::new(&recovery))),
+        Ok(_) => {},
+        Err(err) => println!("ERROR: {}", err)
+    }
+    let mut capture = Capture::new(engine.clip_seconds);
+
+    // fixed steps keep the simulation the same on every machine and frame rate
+    let mut accumulator = 0.0;
+    let mut windowed = window_size();
+    while !rl.window_should_close() && !states.is_empty() {
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F11) {
+            toggle_fullscreen(&mut rl, &mut windowed);
+        }
+        // a minimized window can report no size at all, there is nothing to fit then
+        let screen = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        if screen != window_size() && screen.x > 0.0 && screen.y > 0.0 {
+            set_window_size(screen);
+            states.resize(&mut rl, &thread);
+        }
 
-    game.board.set(5, 5, &Cell::COLOR(Color::BLUE));
-    game.board.set(5, 6, &Cell::COLOR(Color::YELLOW));
-    game.board.set(5, 4, &Cell::COLOR(Color::RED));
-    game.board.set(4, 3, &Cell::COLOR(Color::GREEN));
+        states.handle_input(&mut rl, &thread);
 
-    game.board.set(1, 7, &galo_cego);
-    game.board.set(3, 7, &atumalaca);
-    game.board.set(4, 7, &steve_face);
-    game.board.set(2, 7, &steve_body);
-    game.board.set(4, 4, &glass);
+        accumulator += f32::min(rl.get_frame_time(), MAX_FRAME_TIME);
+        while accumulator >= SIM_DT {
+            states.update(SIM_DT);
+            accumulator -= SIM_DT;
+        }
 
+        // F12 saves the frame, F10 the last few seconds of them
+        let screenshot = rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F12);
+        let clip = rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F10);
 
-    while !rl.window_should_close() {
         let mut d = rl.begin_drawing(&thread);
         d.clear_background(Color::BLACK);
-
-        update_controls(&d, &mut game);
-        minimap_mouse_event(&mut d, &mt, &mut game);
-
-        render_game(&mut d, &game);
-        render_minimap(&mut d, &mt, &game);
+        states.render(&mut d, &thread, accumulator / SIM_DT);
+
+        let time = d.get_time();
+        capture.record(&mut d, &thread, time);
+        if screenshot {
+            match capture.screenshot(&mut d, &thread) {
+                Ok(path) => println!("INFO: Saving screenshot to {}", path),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+        if clip {
+            match capture.save_clip() {
+                Ok(dir) => println!("INFO: Saving clip to {}", dir),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
+        for saved in capture.finished() {
+            match saved {
+                Ok(path) => println!("INFO: Saved {}", path),
+                Err(err) => println!("ERROR: {}", err)
+            }
+        }
     }
 }
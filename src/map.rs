@@ -0,0 +1,808 @@
+use std::{collections::{HashMap, VecDeque}, fs};
+
+use raylib::{color::Color, math::Vector2};
+
+use crate::{board::{Board, Cell, Segment, TextureVariants}, cellkind::{cell_kind, cell_kind_by_name}, decal::{Decal, Face, DEFAULT_DECAL_SIZE}, grade::ColorGrade, item::{Item, Key}, prefab::{prefab_path, stamp}, spawn::{Spawn, SpawnKind}, teleporter::Teleporter, script::{Compare, Value}, trigger::{Condition, Requirement, Trigger}, texture::{AnimationId, FlatId, TextureId, TextureRegistry}, theme::theme_path, tiled::{is_tiled_map, load_tiled}};
+
+// plain text map: a "cols rows" header followed by one line of cells per row
+//   .           empty
+//   c:RRGGBBAA  solid color
+//   t:N         texture N, or the theme's texture for N when it's a name (see theme.rs)
+//   g:N         translucent texture N
+//   d:N         door with texture N
+//   a:N         animated texture N
+//   s:N:E       wall with texture N along edge E (n, e, s, w) or diagonal E (/, \)
+//   m           mirror
+//   k:K:N       cell of the registered kind K with texture N
+// followed by "@H" for walls that are H times the standard height
+// and then one "spawn <kind> <x> <y> [name=value...]" line per entity spawn
+// and one "teleport <x> <y> <target x> <target y> [turn]" line per teleporter
+// and one "exit <x> <y> [map]" line per cell that finishes the map, going on to the given map instead of the next one
+// and one "secret <x> <y>" line per cell that is found as a secret when stepped on
+// and one "gate <x> <y> <map>" line per door that stays shut until the given map is finished
+// and one "switch <x> <y> <target x> <target y>..." line per switch, which flips the doors and lights on its targets
+// and one "sign <x> <y> <text>" line per sign
+// and one "lock <x> <y> <key>" line per door that needs the red, blue or yellow key
+// and one "require <x> <y> <condition> [message]" line per condition the cell's trigger, door, interactable,
+// exit or teleporter waits on: "key <key>", "finished <map>" or "<value> <op> <number>" with a value and
+// comparison from the script "require" statement (see script.rs). the message is shown while it doesn't hold
+// and a "grade RRGGBB [strength]" line for maps with a color grade
+// and one "variants <texture> <other texture...> [seed=N]" line per texture whose walls pick between variants
+// and one "span <texture> <cells>" line per texture stretched over several cells of a wall
+// and one "scroll <x> <y> <u speed> <v speed>" line per cell whose texture scrolls, in texture sizes per second
+// and one "floor <x> <y> RRGGBB [w h]" or "ceiling <x> <y> RRGGBB [w h]" line per cell, or rectangle of cells,
+// with a solid floor or ceiling color
+// and one "decal <x> <y> <north|south|east|west> <texture> <u> <height> [size]" line per poster or sign
+// on a wall face, centered `u` along the face and `height` above the floor,
+// and a "sky <path>" line for maps with a panorama drawn where there is no ceiling
+// and a "budget <entities>" line for maps that keep fewer entities alive at once than the game would
+// and one "prefab <x> <y> <name>" line per prefab stamped with its top left corner there, after the rest.
+// saving writes the stamped cells out, not the line
+pub fn board_to_text(board: &Board) -> String {
+    write_board(board, &|id| id.index().to_string(), &|id| id.index().to_string())
+}
+
+// the map as text with the textures and animations written by `texture` and `animation`
+fn write_board(board: &Board, texture: &dyn Fn(TextureId) -> String, animation: &dyn Fn(AnimationId) -> String) -> String {
+    let mut text = format!("{} {}\n", board.cols, board.rows);
+    for y in 0..board.rows {
+        let row: Vec<String> = (0..board.cols).map(|x| (x, match board.at(x, y) {
+            Cell::EMPTY => ".".to_string(),
+            Cell::COLOR(color) => format!("c:{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a),
+            Cell::TEXTURE(id) => format!("t:{}", texture(*id)),
+            Cell::TranslucentTexture(id) => format!("g:{}", texture(*id)),
+            Cell::Door(id) => format!("d:{}", texture(*id)),
+            Cell::AnimatedTexture(id) => format!("a:{}", animation(*id)),
+            Cell::Segment(id, segment) => format!("s:{}:{}", texture(*id), segment.name()),
+            Cell::Mirror => "m".to_string(),
+            Cell::Custom(kind, id) => format!("k:{}:{}", cell_kind(*kind).map_or("?", |kind| kind.name), texture(*id))
+        })).map(|(x, token)| {
+            let height = board.height_at(x, y);
+            if height == 1.0 { token }
+            else { format!("{}@{}", token, height) }
+        }).collect();
+
+        text.push_str(&row.join(" "));
+        text.push('\n');
+    }
+
+    for spawn in board.spawns.iter() {
+        text.push_str(&format!("spawn {} {} {}", spawn.kind.name(), spawn.pos.x, spawn.pos.y));
+        for (name, value) in spawn.properties.iter() {
+            text.push_str(&format!(" {}={}", name, value));
+        }
+        text.push('\n');
+    }
+
+    for ((x, y), teleporter) in board.teleporters() {
+        text.push_str(&format!("teleport {} {} {} {}", x, y, teleporter.target.0, teleporter.target.1));
+        if teleporter.turn != 0.0 {
+            text.push_str(&format!(" {}", teleporter.turn));
+        }
+        text.push('\n');
+    }
+
+    for (x, y) in board.exits() {
+        match board.exit_target(*x, *y) {
+            Some(target) => text.push_str(&format!("exit {} {} {}\n", x, y, target)),
+            None => text.push_str(&format!("exit {} {}\n", x, y))
+        }
+    }
+
+    for (x, y) in board.secrets() {
+        text.push_str(&format!("secret {} {}\n", x, y));
+    }
+
+    for ((x, y), level) in board.gates() {
+        text.push_str(&format!("gate {} {} {}\n", x, y, level));
+    }
+
+    for ((x, y), trigger) in board.triggers() {
+        match trigger {
+            Trigger::Switch { targets, .. } => {
+                text.push_str(&format!("switch {} {}", x, y));
+                for (tx, ty) in targets.iter() {
+                    text.push_str(&format!(" {} {}", tx, ty));
+                }
+                text.push('\n');
+            },
+            Trigger::Sign(sign) => text.push_str(&format!("sign {} {} {}\n", x, y, sign))
+        }
+    }
+
+    for ((x, y), key) in board.locks() {
+        text.push_str(&format!("lock {} {} {}\n", x, y, key.name()));
+    }
+
+    for ((x, y), requirements) in board.requirements() {
+        for requirement in requirements.iter() {
+            let condition = match &requirement.condition {
+                Condition::HasKey(key) => format!("key {}", key.name()),
+                Condition::Finished(map) => format!("finished {}", map),
+                Condition::Compare(value, compare, number) => format!("{} {} {}", value.name(), compare.name(), number)
+            };
+            text.push_str(&format!("require {} {} {}", x, y, condition));
+            if let Some(message) = &requirement.message {
+                text.push_str(&format!(" {}", message));
+            }
+            text.push('\n');
+        }
+    }
+
+    for variants in board.variants.iter() {
+        text.push_str(&format!("variants {}", texture(variants.base)));
+        for other in variants.others.iter() {
+            text.push_str(&format!(" {}", texture(*other)));
+        }
+        if variants.seed != 0 {
+            text.push_str(&format!(" seed={}", variants.seed));
+        }
+        text.push('\n');
+    }
+
+    for ((x, y), speed) in board.scrolls() {
+        text.push_str(&format!("scroll {} {} {} {}\n", x, y, speed.x, speed.y));
+    }
+
+    // a line per run of cells along a row with the same color
+    for (name, ceiling) in [("floor", false), ("ceiling", true)] {
+        let color_at = |x: usize, y: usize| if ceiling { board.ceiling_color_at(x, y) }
+        else { board.floor_color_at(x, y) };
+
+        for y in 0..board.rows {
+            let mut x = 0;
+            while x < board.cols {
+                let Some(color) = color_at(x, y) else {
+                    x += 1;
+                    continue
+                };
+                let run = (x..board.cols).take_while(|x| color_at(*x, y) == Some(color)).count();
+                text.push_str(&format!("{} {} {} {:02x}{:02x}{:02x}", name, x, y, color.r, color.g, color.b));
+                if run > 1 {
+                    text.push_str(&format!(" {} 1", run));
+                }
+                text.push('\n');
+                x += run;
+            }
+        }
+    }
+
+    for (id, span) in board.spans.iter() {
+        text.push_str(&format!("span {} {}\n", texture(*id), span));
+    }
+
+    for decal in board.decals.iter() {
+        text.push_str(&format!("decal {} {} {} {} {} {} {}\n", decal.cell.0, decal.cell.1, decal.face.name(), texture(decal.texture), decal.u, decal.height, decal.size));
+    }
+
+    if let Some(grade) = board.grade {
+        text.push_str(&format!("grade {}\n", grade.to_text()));
+    }
+
+    if let Some(sky) = board.sky.as_ref() {
+        text.push_str(&format!("sky {}\n", sky));
+    }
+
+    if let Some(budget) = board.budget {
+        text.push_str(&format!("budget {}\n", budget));
+    }
+
+    text
+}
+
+pub fn board_from_text(text: &str, textures: &TextureRegistry) -> Result<Board, String> {
+    parse_board(text, textures, 0)
+}
+
+// prefabs can stamp other prefabs, up to this many levels down
+const MAX_PREFAB_DEPTH: usize = 8;
+
+// `depth` counts the prefabs this board is stamped into, so a prefab stamping itself gives up
+fn parse_board(text: &str, textures: &TextureRegistry, depth: usize) -> Result<Board, String> {
+    let mut lines = text.lines();
+    let header = lines.next().ok_or("Map is empty")?;
+    let size: Vec<usize> = header.split_whitespace().map(|n| n.parse::<usize>().map_err(|err| format!("Invalid map size '{}': {}", header, err))).collect::<Result<_, _>>()?;
+    let [cols, rows] = size[..] else {
+        return Err(format!("Invalid map size '{}'", header))
+    };
+
+    let mut board = Board::new(rows, cols);
+    for y in 0..rows {
+        let line = lines.next().ok_or(format!("Map is missing row {}", y))?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() != cols {
+            return Err(format!("Row {} has {} cells, expected {}", y, tokens.len(), cols))
+        }
+
+        for (x, token) in tokens.iter().enumerate() {
+            let (cell, height) = parse_cell_token(token, textures).map_err(|err| format!("Cell {},{}: {}", x, y, err))?;
+            board.set(x, y, cell);
+            board.set_height(x, y, height);
+        }
+    }
+
+    let mut prefabs = vec![];
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        if let Some(grade) = line.strip_prefix("grade") {
+            board.grade = Some(ColorGrade::parse(grade)?);
+        } else if let Some(sky) = line.strip_prefix("sky ") {
+            board.sky = Some(sky.trim().to_string());
+        } else if let Some(budget) = line.strip_prefix("budget ") {
+            board.budget = Some(budget.trim().parse::<usize>().map_err(|err| format!("Invalid budget in '{}': {}", line, err))?);
+        } else if line.starts_with("exit") {
+            let mut tokens = line.split_whitespace().skip(1);
+            let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid exit position in '{}'", line));
+            let (x, y) = (coord()?, coord()?);
+            if x >= cols || y >= rows {
+                return Err(format!("Exit {},{} is out of bounds", x, y))
+            }
+            match tokens.next() {
+                Some(target) => board.link_exit(x, y, target),
+                None => board.set_exit(x, y, true)
+            }
+        } else if line.starts_with("secret") {
+            let mut tokens = line.split_whitespace().skip(1);
+            let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid secret position in '{}'", line));
+            let (x, y) = (coord()?, coord()?);
+            if x >= cols || y >= rows {
+                return Err(format!("Secret {},{} is out of bounds", x, y))
+            }
+            board.set_secret(x, y, true);
+        } else if line.starts_with("gate") {
+            let mut tokens = line.split_whitespace().skip(1);
+            let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid gate position in '{}'", line));
+            let (x, y) = (coord()?, coord()?);
+            if x >= cols || y >= rows {
+                return Err(format!("Gate {},{} is out of bounds", x, y))
+            }
+            let level = tokens.next().ok_or(format!("Gate without a map in '{}'", line))?;
+            board.set_gate(x, y, Some(level.to_string()));
+        } else if line.starts_with("switch") || line.starts_with("sign") {
+            let (pos, trigger) = parse_trigger(line)?;
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(format!("Trigger {},{} is out of bounds", pos.0, pos.1))
+            }
+            board.set_trigger(pos.0, pos.1, Some(trigger));
+        } else if line.starts_with("lock") {
+            let (pos, key) = parse_lock(line)?;
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(format!("Lock {},{} is out of bounds", pos.0, pos.1))
+            }
+            board.set_lock(pos.0, pos.1, Some(key));
+        } else if line.starts_with("require") {
+            let (pos, requirement) = parse_requirement(line)?;
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(format!("Requirement {},{} is out of bounds", pos.0, pos.1))
+            }
+            board.add_requirement(pos.0, pos.1, requirement);
+        } else if line.starts_with("scroll") {
+            let (pos, speed) = parse_scroll(line)?;
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(format!("Scroll {},{} is out of bounds", pos.0, pos.1))
+            }
+            board.set_scroll(pos.0, pos.1, speed);
+        } else if line.starts_with("floor") || line.starts_with("ceiling") {
+            let area = parse_color_area(line)?;
+            let ((x, y), (w, h)) = (area.pos, area.size);
+            if x + w > cols || y + h > rows {
+                return Err(format!("Color area {},{} {}x{} is out of bounds", x, y, w, h))
+            }
+            for y in y..y + h {
+                for x in x..x + w {
+                    if area.ceiling { board.set_ceiling_color(x, y, Some(area.color)) }
+                    else { board.set_floor_color(x, y, Some(area.color)) }
+                }
+            }
+        } else if line.starts_with("prefab") {
+            prefabs.push(parse_prefab(line, textures, depth)?);
+        } else if line.starts_with("decal") {
+            let decal = parse_decal(line, textures)?;
+            if decal.cell.0 >= cols || decal.cell.1 >= rows {
+                return Err(format!("Decal {},{} is out of bounds", decal.cell.0, decal.cell.1))
+            }
+            board.decals.push(decal);
+        } else if line.starts_with("span") {
+            board.spans.push(parse_span(line, textures)?);
+        } else if line.starts_with("variants") {
+            board.variants.push(parse_variants(line, textures)?);
+        } else if line.starts_with("teleport") {
+            let (pos, teleporter) = parse_teleporter(line)?;
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(format!("Teleporter {},{} is out of bounds", pos.0, pos.1))
+            }
+            board.set_teleporter(pos.0, pos.1, Some(teleporter));
+        } else {
+            board.spawns.push(parse_spawn(line)?);
+        }
+    }
+
+    for ((x, y), prefab) in prefabs {
+        if x >= cols || y >= rows {
+            return Err(format!("Prefab {},{} is out of bounds", x, y))
+        }
+        stamp(&mut board, &prefab, x, y);
+    }
+
+    Ok(board)
+}
+
+fn parse_teleporter(line: &str) -> Result<((usize, usize), Teleporter), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid teleporter position in '{}'", line));
+    let pos = (coord()?, coord()?);
+    let target = (coord()?, coord()?);
+
+    let turn = match tokens.next() {
+        Some(turn) => turn.parse::<f32>().map_err(|err| format!("Invalid teleporter turn in '{}': {}", line, err))?,
+        None => 0.0
+    };
+
+    Ok((pos, Teleporter::new(target, turn)))
+}
+
+fn parse_trigger(line: &str) -> Result<((usize, usize), Trigger), String> {
+    let mut tokens = line.split_whitespace();
+    let kind = tokens.next().unwrap_or_default();
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid {} position in '{}'", kind, line));
+    let pos = (coord()?, coord()?);
+
+    if kind == "sign" {
+        let text: Vec<&str> = tokens.collect();
+        if text.is_empty() {
+            return Err(format!("Sign without text in '{}'", line))
+        }
+        return Ok((pos, Trigger::Sign(text.join(" "))))
+    }
+
+    let coords = tokens.map(|n| n.parse::<usize>().map_err(|err| format!("Invalid switch target '{}' in '{}': {}", n, line, err))).collect::<Result<Vec<usize>, String>>()?;
+    if coords.is_empty() || coords.len() % 2 != 0 {
+        return Err(format!("Switch targets must be x y pairs in '{}'", line))
+    }
+    Ok((pos, Trigger::switch(coords.chunks(2).map(|pair| (pair[0], pair[1])).collect())))
+}
+
+fn parse_lock(line: &str) -> Result<((usize, usize), Key), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid lock position in '{}'", line));
+    let pos = (coord()?, coord()?);
+    let key = tokens.next().and_then(Key::from_name).ok_or(format!("Invalid lock key in '{}', expected red, blue or yellow", line))?;
+    Ok((pos, key))
+}
+
+fn parse_requirement(line: &str) -> Result<((usize, usize), Requirement), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid requirement position in '{}'", line));
+    let pos = (coord()?, coord()?);
+
+    let condition = match tokens.next() {
+        Some("key") => Condition::HasKey(tokens.next().and_then(Key::from_name).ok_or(format!("Invalid key in '{}', expected red, blue or yellow", line))?),
+        Some("finished") => Condition::Finished(tokens.next().ok_or(format!("Requirement without a map in '{}'", line))?.to_string()),
+        Some(name) => {
+            let value = Value::from_name(name).ok_or(format!("Unknown condition '{}' in '{}'", name, line))?;
+            let compare = tokens.next().and_then(Compare::from_name).ok_or(format!("Invalid comparison in '{}'", line))?;
+            let number = tokens.next().and_then(|n| n.parse::<f32>().ok()).ok_or(format!("Invalid number in '{}'", line))?;
+            Condition::Compare(value, compare, number)
+        },
+        None => return Err(format!("Requirement without a condition in '{}'", line))
+    };
+    let message: Vec<&str> = tokens.collect();
+    let message = if message.is_empty() { None }
+        else { Some(message.join(" ")) };
+    Ok((pos, Requirement { condition, message }))
+}
+
+fn parse_scroll(line: &str) -> Result<((usize, usize), Vector2), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid scroll position in '{}'", line));
+    let pos = (coord()?, coord()?);
+    let mut speed = || tokens.next().and_then(|n| n.parse::<f32>().ok()).ok_or(format!("Invalid scroll speed in '{}'", line));
+    Ok((pos, Vector2::new(speed()?, speed()?)))
+}
+
+// a rectangle of cells with a solid floor or ceiling color
+struct ColorArea {
+    ceiling: bool,
+    pos: (usize, usize),
+    size: (usize, usize),
+    color: Color
+}
+
+fn parse_color_area(line: &str) -> Result<ColorArea, String> {
+    let mut tokens = line.split_whitespace();
+    let kind = tokens.next().unwrap_or_default();
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid {} position in '{}'", kind, line));
+    let pos = (coord()?, coord()?);
+
+    let rgb = tokens.next().ok_or(format!("Missing {} color in '{}'", kind, line))?;
+    let [_, r, g, b] = u32::from_str_radix(rgb, 16).map_err(|err| format!("Invalid {} color '{}': {}", kind, rgb, err))?.to_be_bytes();
+
+    let size: Vec<usize> = tokens.map(|n| n.parse::<usize>().map_err(|err| format!("Invalid {} size '{}' in '{}': {}", kind, n, line, err))).collect::<Result<_, _>>()?;
+    let size = match size[..] {
+        [] => (1, 1),
+        [w, h] => (w, h),
+        _ => return Err(format!("Expected a width and a height in '{}'", line))
+    };
+    Ok(ColorArea { ceiling: kind == "ceiling", pos, size, color: Color::new(r, g, b, 255) })
+}
+
+fn parse_span(line: &str, textures: &TextureRegistry) -> Result<(TextureId, usize), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let index = tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid span texture in '{}'", line))?;
+    let texture = textures.texture_id(index).ok_or(format!("Unknown texture {} in '{}'", index, line))?;
+    let span = tokens.next().and_then(|n| n.parse::<usize>().ok()).filter(|span| *span > 0).ok_or(format!("Invalid span in '{}'", line))?;
+    Ok((texture, span))
+}
+
+fn parse_prefab(line: &str, textures: &TextureRegistry, depth: usize) -> Result<((usize, usize), Board), String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid prefab position in '{}'", line));
+    let pos = (coord()?, coord()?);
+    let name = tokens.next().ok_or(format!("Prefab without a name in '{}'", line))?;
+    if depth >= MAX_PREFAB_DEPTH {
+        return Err(format!("Prefab '{}' is nested more than {} deep", name, MAX_PREFAB_DEPTH))
+    }
+
+    let path = prefab_path(name);
+    let text = fs::read_to_string(&path).map_err(|err| format!("Could not load prefab '{}': {}", path, err))?;
+    let prefab = parse_board(&text, textures, depth + 1).map_err(|err| format!("Prefab '{}': {}", name, err))?;
+    Ok((pos, prefab))
+}
+
+fn parse_decal(line: &str, textures: &TextureRegistry) -> Result<Decal, String> {
+    let mut tokens = line.split_whitespace().skip(1);
+    let mut coord = || tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid decal position in '{}'", line));
+    let cell = (coord()?, coord()?);
+    let face = tokens.next().and_then(Face::from_name).ok_or(format!("Invalid decal face in '{}', expected north, south, east or west", line))?;
+    let index = tokens.next().and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid decal texture in '{}'", line))?;
+    let texture = textures.texture_id(index).ok_or(format!("Unknown texture {} in '{}'", index, line))?;
+
+    let mut number = |name: &str| tokens.next().map(|n| n.parse::<f32>().map_err(|err| format!("Invalid decal {} in '{}': {}", name, line, err))).transpose();
+    let u = number("offset")?.ok_or(format!("Decal without an offset in '{}'", line))?;
+    let height = number("height")?.ok_or(format!("Decal without a height in '{}'", line))?;
+    let size = number("size")?.unwrap_or(DEFAULT_DECAL_SIZE);
+    if size <= 0.0 {
+        return Err(format!("Invalid decal size in '{}'", line))
+    }
+    Ok(Decal { texture, cell, face, u, height, size })
+}
+
+fn parse_variants(line: &str, textures: &TextureRegistry) -> Result<TextureVariants, String> {
+    let mut ids = vec![];
+    let mut seed = 0;
+    for token in line.split_whitespace().skip(1) {
+        if let Some(value) = token.strip_prefix("seed=") {
+            seed = value.parse::<u32>().map_err(|err| format!("Invalid variant seed '{}': {}", value, err))?;
+            continue
+        }
+
+        let index = token.parse::<usize>().map_err(|err| format!("Invalid texture '{}' in '{}': {}", token, line, err))?;
+        ids.push(textures.texture_id(index).ok_or(format!("Unknown texture {} in '{}'", index, line))?);
+    }
+
+    let (base, others) = ids.split_first().ok_or(format!("Variants without a texture in '{}'", line))?;
+    Ok(TextureVariants { base: *base, others: others.to_vec(), seed })
+}
+
+fn parse_spawn(line: &str) -> Result<Spawn, String> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next() != Some("spawn") {
+        return Err(format!("Invalid spawn '{}'", line))
+    }
+
+    let kind = tokens.next().and_then(SpawnKind::from_name).ok_or(format!("Invalid spawn kind in '{}'", line))?;
+    let mut coord = || tokens.next().and_then(|n| n.parse::<f32>().ok()).ok_or(format!("Invalid spawn position in '{}'", line));
+    let pos = Vector2::new(coord()?, coord()?);
+
+    let mut spawn = Spawn::new(kind, pos);
+    for property in tokens {
+        let (name, value) = property.split_once('=').ok_or(format!("Invalid spawn property '{}'", property))?;
+        let value = value.parse::<f32>().map_err(|err| format!("Invalid spawn property '{}': {}", property, err))?;
+        spawn.set_property(name, value);
+    }
+
+    Ok(spawn)
+}
+
+// a cell with its optional "@H" height, shared with the Tiled importer's tile lookup
+pub(crate) fn parse_cell_token(token: &str, textures: &TextureRegistry) -> Result<(Cell, f32), String> {
+    let (token, height) = match token.split_once('@') {
+        Some((token, height)) => (token, height.parse::<f32>().map_err(|err| format!("Invalid height '{}': {}", height, err))?),
+        None => (token, 1.0)
+    };
+
+    Ok((parse_cell(token, textures)?, height))
+}
+
+// by its number, or by its name for a theme's texture. saving writes the number
+fn parse_texture(value: &str, textures: &TextureRegistry) -> Result<TextureId, String> {
+    match value.parse::<usize>() {
+        Ok(index) => textures.texture_id(index).ok_or(format!("Unknown texture {}", index)),
+        Err(_) => textures.by_path(&theme_path(value)).ok_or(format!("Unknown texture '{}'", value))
+    }
+}
+
+pub fn parse_cell(token: &str, textures: &TextureRegistry) -> Result<Cell, String> {
+    if token == "." {
+        return Ok(Cell::EMPTY)
+    }
+    if token == "m" {
+        return Ok(Cell::Mirror)
+    }
+
+    let (kind, value) = token.split_once(':').ok_or(format!("Invalid cell '{}'", token))?;
+    if kind == "c" {
+        let rgba = u32::from_str_radix(value, 16).map_err(|err| format!("Invalid color '{}': {}", value, err))?;
+        let [r, g, b, a] = rgba.to_be_bytes();
+        return Ok(Cell::COLOR(Color::new(r, g, b, a)))
+    }
+
+    if kind == "s" {
+        let (index, segment) = value.split_once(':').ok_or(format!("Invalid segment '{}'", token))?;
+        let id = parse_texture(index, textures)?;
+        let segment = Segment::from_name(segment).ok_or(format!("Invalid segment '{}'", segment))?;
+        return Ok(Cell::Segment(id, segment))
+    }
+
+    if kind == "k" {
+        let (name, index) = value.split_once(':').ok_or(format!("Invalid cell '{}'", token))?;
+        let kind = cell_kind_by_name(name).ok_or(format!("Unknown cell kind '{}'", name))?;
+        let id = parse_texture(index, textures)?;
+        return Ok(Cell::Custom(kind, id))
+    }
+
+    if kind == "a" {
+        let index = value.parse::<usize>().map_err(|err| format!("Invalid animation '{}': {}", value, err))?;
+        let id = textures.animation_id(index).ok_or(format!("Unknown animation {}", index))?;
+        return Ok(Cell::AnimatedTexture(id))
+    }
+
+    let id = parse_texture(value, textures)?;
+    match kind {
+        "t" => Ok(Cell::TEXTURE(id)),
+        "g" => Ok(Cell::TranslucentTexture(id)),
+        "d" => Ok(Cell::Door(id)),
+        _ => Err(format!("Invalid cell '{}'", token))
+    }
+}
+
+// problems that would make the map unplayable, one message per cell or spawn
+pub fn validate(board: &Board, textures: &TextureRegistry) -> Vec<String> {
+    let mut problems = vec![];
+
+    for y in 0..board.rows {
+        for x in 0..board.cols {
+            if let Cell::TEXTURE(id) | Cell::TranslucentTexture(id) | Cell::Door(id) | Cell::Segment(id, _) = board.at(x, y) {
+                if textures.texture_id(id.index()).is_none() {
+                    problems.push(format!("Cell {},{}: Unknown texture {}", x, y, id.index()));
+                }
+            }
+
+            if let Cell::AnimatedTexture(id) = board.at(x, y) {
+                if textures.animation_id(id.index()).is_none() {
+                    problems.push(format!("Cell {},{}: Unknown animation {}", x, y, id.index()));
+                }
+            }
+
+            for (name, flat) in [("floor", board.floor_at(x, y)), ("ceiling", board.ceiling_at(x, y))] {
+                if let Some(id) = flat.filter(|id| textures.flat_id(id.index()).is_none()) {
+                    problems.push(format!("Cell {},{}: Unknown {} texture {}", x, y, name, id.index()));
+                }
+            }
+        }
+    }
+
+    let in_bounds = |pos: Vector2| pos.x >= 0.0 && pos.y >= 0.0 && pos.x < board.cols as f32 && pos.y < board.rows as f32;
+    for (i, spawn) in board.spawns.iter().enumerate() {
+        if !in_bounds(spawn.pos) {
+            problems.push(format!("Spawn {} ({}): Position {},{} is out of the map", i, spawn.kind.name(), spawn.pos.x, spawn.pos.y));
+        } else if is_solid(board, spawn.pos.x as usize, spawn.pos.y as usize) {
+            problems.push(format!("Spawn {} ({}): Position {},{} is inside a wall", i, spawn.kind.name(), spawn.pos.x, spawn.pos.y));
+        }
+    }
+
+    for ((x, y), teleporter) in board.teleporters() {
+        let (tx, ty) = teleporter.target;
+        if is_solid(board, *x, *y) {
+            problems.push(format!("Teleporter {},{}: Is inside a wall", x, y));
+        }
+        if tx >= board.cols || ty >= board.rows {
+            problems.push(format!("Teleporter {},{}: Target {},{} is out of the map", x, y, tx, ty));
+        } else if is_solid(board, tx, ty) {
+            problems.push(format!("Teleporter {},{}: Target {},{} is inside a wall", x, y, tx, ty));
+        }
+    }
+
+    for ((x, y), trigger) in board.triggers() {
+        if let Trigger::Switch { targets, .. } = trigger {
+            for (tx, ty) in targets.iter() {
+                if *tx >= board.cols || *ty >= board.rows {
+                    problems.push(format!("Switch {},{}: Target {},{} is out of the map", x, y, tx, ty));
+                }
+            }
+        }
+    }
+
+    for ((x, y), key) in board.locks() {
+        if !matches!(board.at(*x, *y), Cell::Door(_)) {
+            problems.push(format!("Lock {},{}: Is not on a door", x, y));
+        }
+        let has_key = board.spawns.iter().any(|spawn| spawn.kind == SpawnKind::Pickup && Item::from_spawn(spawn) == Some(Item::Key(*key)));
+        if !has_key {
+            problems.push(format!("Lock {},{}: The map has no {} key", x, y, key.name()));
+        }
+    }
+
+    for (x, y) in board.exits() {
+        if is_solid(board, *x, *y) {
+            problems.push(format!("Exit {},{}: Is inside a wall", x, y));
+        }
+    }
+
+    for (x, y) in board.secrets() {
+        if is_solid(board, *x, *y) {
+            problems.push(format!("Secret {},{}: Is inside a wall", x, y));
+        }
+    }
+
+    for ((x, y), _) in board.gates() {
+        if !matches!(board.at(*x, *y), Cell::Door(_)) {
+            problems.push(format!("Gate {},{}: Is not on a door", x, y));
+        }
+    }
+
+    let players: Vec<&Spawn> = board.spawns.iter().filter(|spawn| spawn.kind == SpawnKind::Player).collect();
+    match players[..] {
+        [] => problems.push("Map has no player spawn".to_string()),
+        [player] if in_bounds(player.pos) => {
+            // everything the player has to walk up to must be reachable, doors count as open
+            // and teleporters as a way to their target
+            let reachable = reachable_cells(board, (player.pos.x as usize, player.pos.y as usize));
+            for (i, spawn) in board.spawns.iter().enumerate() {
+                if !matches!(spawn.kind, SpawnKind::Pickup | SpawnKind::Trigger) || !in_bounds(spawn.pos) { continue }
+                if !reachable[spawn.pos.y as usize * board.cols + spawn.pos.x as usize] {
+                    problems.push(format!("Spawn {} ({}): Position {},{} can not be reached from the player spawn", i, spawn.kind.name(), spawn.pos.x, spawn.pos.y));
+                }
+            }
+            // one inside a wall is already reported
+            for (x, y) in board.exits().filter(|(x, y)| !is_solid(board, *x, *y)) {
+                if !reachable[y * board.cols + x] {
+                    problems.push(format!("Exit {},{}: Can not be reached from the player spawn", x, y));
+                }
+            }
+        },
+        [_] => {},
+        _ => problems.push(format!("Map has {} player spawns, expected 1", players.len()))
+    }
+
+    problems
+}
+
+fn is_solid(board: &Board, x: usize, y: usize) -> bool {
+    !matches!(board.at(x, y), Cell::EMPTY | Cell::Door(_))
+}
+
+fn reachable_cells(board: &Board, from: (usize, usize)) -> Vec<bool> {
+    let mut reachable = vec![false; board.rows * board.cols];
+    if is_solid(board, from.0, from.1) { return reachable }
+
+    let mut queue = VecDeque::from([from]);
+    reachable[from.1 * board.cols + from.0] = true;
+    while let Some((x, y)) = queue.pop_front() {
+        let target = board.teleporter(x, y).map(|teleporter| teleporter.target);
+        for (nx, ny) in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].into_iter().chain(target) {
+            if nx >= board.cols || ny >= board.rows || reachable[ny * board.cols + nx] || is_solid(board, nx, ny) { continue }
+            reachable[ny * board.cols + nx] = true;
+            queue.push_back((nx, ny));
+        }
+    }
+
+    reachable
+}
+
+// FNV-1a over the map as it is saved, cells, spawns and everything else alike, so it is the same
+// on every run and every system for the same map and changes with any edit to it. textures go in by
+// their paths and flats by their pixels, the ids move around when an asset or a mod is added
+pub fn content_hash(board: &Board, textures: &TextureRegistry) -> u64 {
+    let paths = textures.paths_by_id();
+    let texture = |id: TextureId| paths.get(&id).map_or_else(|| id.index().to_string(), |path| path.to_string());
+    let animation = |id: AnimationId| match textures.animation_id(id.index()).map(|id| textures.animation(id)) {
+        Some(animation) => format!("{}@{}", animation.frames.iter().map(|(frame, _)| texture(*frame)).collect::<Vec<String>>().join("+"), animation.fps),
+        None => id.index().to_string()
+    };
+    let mut text = write_board(board, &texture, &animation);
+
+    // a line per row with the floor and ceiling of every cell, each flat hashed once
+    let mut flats: HashMap<FlatId, u64> = HashMap::new();
+    let mut flat = |id: Option<FlatId>| match id.and_then(|id| textures.flat_id(id.index())) {
+        Some(id) => format!("{:016x}", *flats.entry(id).or_insert_with(|| bytes_hash(textures.flat(id).pixels.iter().flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])))),
+        None => "-".to_string()
+    };
+    for y in 0..board.rows {
+        let row: Vec<String> = (0..board.cols).map(|x| format!("{}/{}", flat(board.floor_at(x, y)), flat(board.ceiling_at(x, y)))).collect();
+        text.push_str(&format!("flats {}\n", row.join(" ")));
+    }
+    text_hash(&text)
+}
+
+pub fn text_hash(text: &str) -> u64 {
+    bytes_hash(text.bytes())
+}
+
+fn bytes_hash(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn save_board(path: &str, board: &Board) -> Result<(), String> {
+    fs::write(path, board_to_text(board)).map_err(|err| format!("Could not save map '{}': {}", path, err))
+}
+
+// maps made in Tiled are imported every time they are loaded
+pub fn load_board(path: &str, textures: &TextureRegistry) -> Result<Board, String> {
+    if is_tiled_map(path) {
+        return load_tiled(path, textures)
+    }
+
+    let text = fs::read_to_string(path).map_err(|err| format!("Could not load map '{}': {}", path, err))?;
+    board_from_text(&text, textures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::FlatTexture;
+
+    #[test]
+    fn an_exit_walled_off_from_the_player_is_reported() {
+        // walls all around and down the middle, the player on the left of them
+        let mut board = Board::new(5, 7);
+        for x in 0..7 {
+            board.set(x, 0, Cell::COLOR(Color::RED));
+            board.set(x, 4, Cell::COLOR(Color::RED));
+        }
+        for y in 0..5 {
+            board.set(0, y, Cell::COLOR(Color::RED));
+            board.set(3, y, Cell::COLOR(Color::RED));
+            board.set(6, y, Cell::COLOR(Color::RED));
+        }
+        board.spawns.push(Spawn::new(SpawnKind::Player, Vector2::new(1.5, 2.5)));
+        board.set_exit(2, 1, true);
+        board.set_exit(5, 2, true);
+
+        let mut textures = TextureRegistry::new();
+        let door = textures.register("door", "door.png");
+        let exits: Vec<String> = validate(&board, &textures).into_iter().filter(|problem| problem.starts_with("Exit")).collect();
+        assert_eq!(exits, vec!["Exit 5,2: Can not be reached from the player spawn".to_string()]);
+
+        // a door in the wall counts as open
+        board.set(3, 2, Cell::Door(door));
+        assert!(validate(&board, &textures).iter().all(|problem| !problem.starts_with("Exit")));
+    }
+
+    #[test]
+    fn the_hash_follows_texture_paths_and_flats_not_ids() {
+        let walled = |textures: &mut TextureRegistry| {
+            let mut board = Board::new(3, 3);
+            board.set(1, 1, Cell::TEXTURE(textures.register("brick", "brick.png")));
+            board
+        };
+        let mut textures = TextureRegistry::new();
+        let board = walled(&mut textures);
+
+        // an asset added before it moves the brick to another id
+        let mut modded = TextureRegistry::new();
+        modded.register("moss", "moss.png");
+        let moved = walled(&mut modded);
+        assert_ne!(board_to_text(&board), board_to_text(&moved));
+        assert_eq!(content_hash(&board, &textures), content_hash(&moved, &modded));
+
+        let mut floored = board.clone();
+        let flat = textures.add_flat(FlatTexture { width: 1, height: 1, pixels: vec![Color::GRAY] });
+        floored.set_floor(0, 0, Some(flat));
+        assert_ne!(content_hash(&board, &textures), content_hash(&floored, &textures));
+
+        // another flat with the same pixels is the same floor
+        let mut same = board.clone();
+        same.set_floor(0, 0, Some(textures.add_flat(FlatTexture { width: 1, height: 1, pixels: vec![Color::GRAY] })));
+        assert_eq!(content_hash(&floored, &textures), content_hash(&same, &textures));
+    }
+}
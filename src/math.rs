@@ -0,0 +1,53 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use raylib::math::Vector2;
+
+// sine and cosine from plain IEEE operations. the simulation uses these instead of the
+// platform libm, whose results may differ in the last bits between machines
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    let a = angle - (angle / TAU).round() * TAU;
+
+    // fold into [-PI/2, PI/2], where the series converge fast: sin(PI - a) = sin(a), cos(PI - a) = -cos(a)
+    let (a, cos_sign) = if a > FRAC_PI_2 { (PI - a, -1.0) }
+    else if a < -FRAC_PI_2 { (-PI - a, -1.0) }
+    else { (a, 1.0) };
+
+    let a2 = a * a;
+    let sin = a * (1.0 - a2 / 6.0 * (1.0 - a2 / 20.0 * (1.0 - a2 / 42.0 * (1.0 - a2 / 72.0 * (1.0 - a2 / 110.0)))));
+    let cos = 1.0 - a2 / 2.0 * (1.0 - a2 / 12.0 * (1.0 - a2 / 30.0 * (1.0 - a2 / 56.0 * (1.0 - a2 / 90.0 * (1.0 - a2 / 132.0)))));
+    (sin, cos_sign * cos)
+}
+
+pub fn rotated(v: Vector2, angle: f32) -> Vector2 {
+    let (sin, cos) = sin_cos(angle);
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+// exact quarter turns, rotating by FRAC_PI_2 would round
+pub fn perp_right(v: Vector2) -> Vector2 {
+    Vector2::new(-v.y, v.x)
+}
+
+pub fn perp_left(v: Vector2) -> Vector2 {
+    Vector2::new(v.y, -v.x)
+}
+
+// small deterministic generator, the same seed always gives the same numbers
+pub struct Lcg(u32);
+
+impl Lcg {
+    pub fn new(seed: u32) -> Lcg {
+        Lcg(seed)
+    }
+
+    // in [0, 1)
+    pub fn next_float(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    // in [0, n)
+    pub fn below(&mut self, n: usize) -> usize {
+        usize::min((self.next_float() * n as f32) as usize, n.saturating_sub(1))
+    }
+}
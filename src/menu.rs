@@ -0,0 +1,234 @@
+use std::rc::Rc;
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, ffi::{GamepadAxis, GamepadButton, KeyboardKey}, math::{Rectangle, Vector2}, RaylibHandle, RaylibThread};
+
+use crate::{credits::{Credits, CREDITS_PATH}, narration::narrate, panel::{draw_panel, Panel}, state::{GameState, Transition}, ui, window_size};
+
+// a value the settings screen changes through its cvar, so it applies right away just like from the console
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting {
+    pub label: &'static str,
+    pub cvar: &'static str,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32
+}
+
+impl Setting {
+    // as many decimals as the step has, so repeated steps don't show rounding noise
+    fn text(&self) -> String {
+        let decimals = f32::max(-f32::floor(self.step.log10()), 0.0) as usize;
+        format!("{:.*}", decimals, self.value)
+    }
+
+    fn command(&self) -> String {
+        format!("{} {}", self.cvar, self.text())
+    }
+
+    fn change(&mut self, steps: f32) {
+        self.value = f32::clamp(self.value + steps * self.step, self.min, self.max);
+    }
+}
+
+const MENU_ITEMS: [&str; 4] = ["Resume", "Settings", "Credits", "Quit"];
+const MENU_FONT: i32 = 30;
+const LINE_HEIGHT: i32 = 45;
+const GAMEPAD: i32 = 0;
+// how far the left stick has to be pushed to move the selection
+const STICK_DEADZONE: f32 = 0.5;
+
+// what the menus react to, the same from the keyboard, the d-pad and the left stick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Accept,
+    Back,
+    None
+}
+
+// the left stick moves the selection once per push, like a key press, instead of every frame it is held
+#[derive(Default)]
+pub struct MenuNav {
+    stick: (i32, i32),
+    // what was last narrated, so only a change of focus or value is read out
+    focus: Option<String>
+}
+
+impl MenuNav {
+    // reads out the focused item whenever it changes, after the menu's title when it opens
+    pub fn focus(&mut self, title: &str, text: String) {
+        match &self.focus {
+            Some(focus) if *focus == text => return,
+            Some(_) => narrate(&text),
+            None => narrate(&format!("{}, {}", title, text))
+        }
+        self.focus = Some(text);
+    }
+
+    pub fn read(&mut self, rl: &RaylibHandle) -> MenuInput {
+        let pad = rl.is_gamepad_available(GAMEPAD);
+        let key = |keys: &[KeyboardKey]| keys.iter().any(|key| rl.is_key_pressed(*key));
+        let button = |buttons: &[GamepadButton]| pad && buttons.iter().any(|button| rl.is_gamepad_button_pressed(GAMEPAD, *button));
+
+        let axis = |axis: GamepadAxis| {
+            let value = if pad { rl.get_gamepad_axis_movement(GAMEPAD, axis) }
+            else { 0.0 };
+            (value > STICK_DEADZONE) as i32 - (value < -STICK_DEADZONE) as i32
+        };
+        let stick = (axis(GamepadAxis::GAMEPAD_AXIS_LEFT_X), axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y));
+        let pushed = (if stick.0 != self.stick.0 { stick.0 } else { 0 }, if stick.1 != self.stick.1 { stick.1 } else { 0 });
+        self.stick = stick;
+
+        if key(&[KeyboardKey::KEY_ESCAPE, KeyboardKey::KEY_BACKSPACE, KeyboardKey::KEY_P]) || button(&[GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT]) { MenuInput::Back }
+        else if key(&[KeyboardKey::KEY_ENTER, KeyboardKey::KEY_KP_ENTER]) || button(&[GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN]) { MenuInput::Accept }
+        else if key(&[KeyboardKey::KEY_UP]) || button(&[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP]) || pushed.1 < 0 { MenuInput::Up }
+        else if key(&[KeyboardKey::KEY_DOWN]) || button(&[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN]) || pushed.1 > 0 { MenuInput::Down }
+        else if key(&[KeyboardKey::KEY_LEFT]) || button(&[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT]) || pushed.0 < 0 { MenuInput::Left }
+        else if key(&[KeyboardKey::KEY_RIGHT]) || button(&[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT]) || pushed.0 > 0 { MenuInput::Right }
+        else { MenuInput::None }
+    }
+}
+
+// Esc, P, B or Start again resumes, the settings are handed on to the settings screen
+pub struct PauseMenu {
+    settings: Vec<Setting>,
+    selected: usize,
+    panel: Option<Rc<Panel>>,
+    nav: MenuNav
+}
+
+impl PauseMenu {
+    pub fn new(settings: Vec<Setting>, panel: Option<Rc<Panel>>) -> PauseMenu {
+        PauseMenu { settings, selected: 0, panel, nav: MenuNav::default() }
+    }
+}
+
+pub struct SettingsMenu {
+    settings: Vec<Setting>,
+    selected: usize,
+    panel: Option<Rc<Panel>>,
+    nav: MenuNav
+}
+
+impl SettingsMenu {
+    pub fn new(settings: Vec<Setting>, panel: Option<Rc<Panel>>) -> SettingsMenu {
+        SettingsMenu { settings, selected: 0, panel, nav: MenuNav::default() }
+    }
+}
+
+// up and down move the selection around a list of `len` items
+pub fn select(input: MenuInput, selected: usize, len: usize) -> usize {
+    match input {
+        MenuInput::Down => (selected + 1) % len,
+        MenuInput::Up => (selected + len - 1) % len,
+        _ => selected
+    }
+}
+
+pub fn render_lines(d: &mut RaylibDrawHandle, title: &str, lines: &[String], selected: usize) {
+    let (font, line_height) = (ui(MENU_FONT), ui(LINE_HEIGHT));
+    let top = window_size().y as i32 / 2 - (lines.len() as i32 + 2) * line_height / 2;
+    let width = d.measure_text(title, ui(MENU_FONT + 10));
+    d.draw_text(title, (window_size().x as i32 - width) / 2, top, ui(MENU_FONT + 10), Color::WHITE);
+
+    for (i, line) in lines.iter().enumerate() {
+        let width = d.measure_text(line, font);
+        let (x, y) = ((window_size().x as i32 - width) / 2, top + (i as i32 + 2) * line_height);
+        // a bar behind the focused line, so the focus shows without a mouse pointer to follow
+        if i == selected {
+            let padding = (line_height - font) / 2;
+            d.draw_rectangle(x - 2 * padding, y - padding, width + 4 * padding, line_height, Color::YELLOW.alpha(0.2));
+            d.draw_rectangle_lines(x - 2 * padding, y - padding, width + 4 * padding, line_height, Color::YELLOW);
+        }
+        let color = if i == selected { Color::YELLOW }
+        else { Color::LIGHTGRAY };
+        d.draw_text(line, x, y, font, color);
+    }
+}
+
+impl GameState for PauseMenu {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread) -> Transition {
+        let input = self.nav.read(rl);
+        if input == MenuInput::Back {
+            return Transition::Pop
+        }
+
+        self.selected = select(input, self.selected, MENU_ITEMS.len());
+        self.nav.focus("Paused", MENU_ITEMS[self.selected].to_string());
+        if input != MenuInput::Accept { return Transition::None }
+
+        match MENU_ITEMS[self.selected] {
+            "Resume" => Transition::Pop,
+            "Settings" => Transition::Push(Box::new(SettingsMenu::new(self.settings.clone(), self.panel.clone()))),
+            "Credits" => match Credits::load(CREDITS_PATH) {
+                Ok(credits) => Transition::Push(Box::new(credits)),
+                Err(err) => {
+                    println!("ERROR: {}", err);
+                    Transition::None
+                }
+            },
+            _ => Transition::Quit
+        }
+    }
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn render(&mut self, d: &mut RaylibDrawHandle, _thread: &RaylibThread, _alpha: f32) {
+        d.draw_rectangle_v(Vector2::zero(), window_size(), Color::BLACK.alpha(0.5));
+        let lines: Vec<String> = MENU_ITEMS.iter().map(|item| item.to_string()).collect();
+        render_lines(d, "PAUSED", &lines, self.selected);
+    }
+
+    // keeps its copy in step with what the settings screen above changed
+    fn run_command(&mut self, command: &str) {
+        let Some((name, value)) = command.split_once(' ') else { return };
+        let Ok(value) = value.parse::<f32>() else { return };
+        if let Some(setting) = self.settings.iter_mut().find(|setting| setting.cvar == name) {
+            setting.value = value;
+        }
+    }
+
+    fn is_overlay(&self) -> bool { true }
+}
+
+impl GameState for SettingsMenu {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread) -> Transition {
+        let input = self.nav.read(rl);
+        if input == MenuInput::Back {
+            return Transition::Pop
+        }
+
+        self.selected = select(input, self.selected, self.settings.len());
+        let setting = &self.settings[self.selected];
+        self.nav.focus("Settings", format!("{} {}", setting.label, setting.text()));
+        // accepting steps the value up too, for a pad whose d-pad is taken up by moving around
+        let steps = match input {
+            MenuInput::Right | MenuInput::Accept => 1.0,
+            MenuInput::Left => -1.0,
+            _ => return Transition::None
+        };
+
+        let setting = &mut self.settings[self.selected];
+        setting.change(steps);
+        Transition::Command(setting.command())
+    }
+
+    fn update(&mut self, _dt: f32) {}
+
+    // a panel over the pause menu, the game stays visible around it to see the changes
+    fn render(&mut self, d: &mut RaylibDrawHandle, _thread: &RaylibThread, _alpha: f32) {
+        let height = (self.settings.len() as i32 + 3) * ui(LINE_HEIGHT);
+        let (width, top) = (window_size().x as i32 * 3 / 4, (window_size().y as i32 - height) / 2 - ui(LINE_HEIGHT) / 2);
+        let rec = Rectangle::new(((window_size().x as i32 - width) / 2) as f32, top as f32, width as f32, height as f32);
+        draw_panel(d, self.panel.as_deref(), rec, Color::BLACK.alpha(0.9));
+
+        let lines: Vec<String> = self.settings.iter().map(|setting| format!("< {}: {} >", setting.label, setting.text())).collect();
+        render_lines(d, "SETTINGS", &lines, self.selected);
+    }
+
+    fn is_overlay(&self) -> bool { true }
+}
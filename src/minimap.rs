@@ -0,0 +1,349 @@
+use std::{ops::{Add, Mul, Sub}, process::exit};
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle, RaylibScissorModeExt, RaylibTextureModeExt}, math::{Rectangle, Vector2}, texture::RenderTexture2D, RaylibHandle, RaylibThread};
+
+use crate::{board::{Cell, MIRROR_COLOR}, game::Game, player::Player, raycast::CellHit, texture::AnimationId, transform::{Transform2D, Transform2DApplayer}, MINIMAP_ASPECT_RATIO, ui_scale, window_size};
+
+fn render_player(d: &mut impl RaylibDraw, mt: &Transform2D, player: &Player, half_fov: f32) {
+    let zoom =  f32::max(mt.zoom.x, mt.zoom.y);
+    let pos = player.pos.apply(mt);
+
+    let p1 = player.dir.rotated(half_fov);
+    let p2 = player.dir.rotated(-half_fov);
+
+    d.draw_triangle(pos, pos.add(p1.apply_zoom(mt)), pos.add(p2.apply_zoom(mt)), Color::PURPLE);
+    d.draw_circle_v(pos, 0.2 * zoom, Color::RED);
+}
+
+// share of the window the corner minimap grows to at most with the ui scale
+const MAX_MINIMAP_RATIO: f32 = 0.5;
+// more enemy and pickup icons than this in view and only this many are drawn a frame, the nearest
+// half of them every frame and the others taking turns with the second half
+const MAX_ENTITY_ICONS: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimapMode {
+    Corner,
+    Expanded,
+    Hidden
+}
+
+// which corner of the window the corner minimap sits in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimapAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight
+}
+
+impl MinimapAnchor {
+    // the corners in the order the cvar counts them
+    pub const ALL: [MinimapAnchor; 4] = [MinimapAnchor::TopLeft, MinimapAnchor::TopRight, MinimapAnchor::BottomLeft, MinimapAnchor::BottomRight];
+
+    pub fn from_index(index: i64) -> MinimapAnchor {
+        MinimapAnchor::ALL[index.clamp(0, 3) as usize]
+    }
+
+    pub fn index(self) -> i64 {
+        MinimapAnchor::ALL.iter().position(|anchor| *anchor == self).unwrap_or(0) as i64
+    }
+
+    // the top left corner of a minimap of `size` kept `margin` away from the edges it is anchored to
+    pub fn place(self, size: Vector2, margin: f32) -> Vector2 {
+        let far = window_size().sub(size).sub(Vector2::one().mul(margin));
+        match self {
+            MinimapAnchor::TopLeft => Vector2::new(margin, margin),
+            MinimapAnchor::TopRight => Vector2::new(far.x, margin),
+            MinimapAnchor::BottomLeft => Vector2::new(margin, far.y),
+            MinimapAnchor::BottomRight => far
+        }
+    }
+}
+
+// static layer of the minimap, only redrawn when the board or the zoom changes
+pub struct MinimapCache {
+    target: RenderTexture2D,
+    key: Option<(u64, Vector2, Vec<usize>)>,
+    // the animations on the board as of a revision, so the frames can be checked without going over every cell
+    animations: Option<(u64, Vec<AnimationId>)>,
+    // where the icons taking turns go on from next frame
+    entity_cursor: usize
+}
+
+impl MinimapCache {
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> MinimapCache {
+        // big enough for the corner minimap at any ui scale
+        let size = window_size().mul(MAX_MINIMAP_RATIO);
+        let target = match rl.load_render_texture(thread, size.x as u32, size.y as u32) {
+            Ok(target) => target,
+            Err(err) => {
+                println!("ERROR: {}", err);
+                exit(1);
+            },
+        };
+
+        MinimapCache { target, key: None, animations: None, entity_cursor: 0 }
+    }
+}
+
+pub fn render_minimap(d: &mut RaylibDrawHandle, thread: &RaylibThread, mt: &Transform2D, game: &Game, cache: &mut MinimapCache, columns: &[Vec<CellHit>]) {
+    if !game.minimap_shown() { return }
+
+    let size = Vector2::new(game.board.cols as f32, game.board.rows as f32).apply_zoom(mt);
+    let capacity = Vector2::new(cache.target.texture.width as f32, cache.target.texture.height as f32);
+    // a minimap bigger than the cache falls back to drawing every cell live
+    let live = size.x > capacity.x || size.y > capacity.y;
+
+    // animated cells redraw the layer whenever one of them shows a new frame
+    let revision = game.board.revision();
+    if cache.animations.as_ref().map(|(revision, _)| *revision) != Some(revision) {
+        cache.animations = Some((revision, board_animations(game)));
+    }
+    let frames = cache.animations.as_ref().map_or(vec![], |(_, ids)| ids.iter().map(|id| game.textures.animation(*id).frame(game.time)).collect());
+
+    let key = (revision, mt.zoom, frames);
+    if !live && cache.key.as_ref() != Some(&key) {
+        let local = Transform2D { offset: Vector2::zero(), zoom: mt.zoom };
+        let mut td = d.begin_texture_mode(thread, &mut cache.target);
+        td.clear_background(Color::BLANK);
+        render_board(&mut td, &local, game, (0, 0, game.board.cols, game.board.rows), 1.0);
+        cache.key = Some(key);
+    }
+
+    // zooming and panning can move the map past its viewport
+    let viewport = game.minimap_viewport();
+    let mut d = d.begin_scissor_mode(viewport.x as i32, viewport.y as i32, viewport.width as i32, viewport.height as i32);
+    // the map fades with the opacity, what moves on it stays easy to make out
+    let opacity = game.minimap_opacity;
+    d.draw_rectangle_rec(viewport, Color::BLACK.alpha(0.6 * opacity));
+
+    if live {
+        // zoomed in that far only a few of the cells are in the viewport
+        render_board(&mut d, mt, game, visible_cells(game, mt, viewport), opacity);
+    } else {
+        // render textures are stored upside down
+        let source_rec = Rectangle::new(0.0, capacity.y - size.y, size.x, -size.y);
+        d.draw_texture_rec(&cache.target, source_rec, mt.offset, Color::WHITE.alpha(opacity));
+    }
+
+    render_lights(&mut d, mt, game);
+    render_entities(&mut d, mt, game, viewport, &mut cache.entity_cursor);
+    render_paths(&mut d, mt, game);
+    render_rays(&mut d, mt, game, columns);
+    render_remote_players(&mut d, mt, game);
+    render_player(&mut d, mt, &game.player, game.half_fov());
+}
+
+fn render_remote_players(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game) {
+    let zoom = f32::max(mt.zoom.x, mt.zoom.y);
+    for sprite in game.remote_players.iter() {
+        d.draw_circle_v(sprite.pos.apply(mt), 0.2 * zoom, Color::SKYBLUE);
+    }
+}
+
+// a dot for every enemy and pickup in the viewport, as many as the budget allows on crowded maps
+fn render_entities(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game, viewport: Rectangle, cursor: &mut usize) {
+    let zoom = f32::max(mt.zoom.x, mt.zoom.y);
+    let enemies = game.enemies.iter().map(|enemy| (enemy.pos(), Color::RED));
+    let pickups = game.pickups.iter().map(|pickup| (pickup.sprite.pos, Color::LIME));
+    let mut icons: Vec<(usize, Vector2, Color)> = enemies.chain(pickups)
+        .filter(|(pos, _)| viewport.check_collision_point_rec(pos.apply(mt)))
+        .enumerate()
+        .map(|(i, (pos, color))| (i, pos, color))
+        .collect();
+
+    if icons.len() > MAX_ENTITY_ICONS {
+        let half = MAX_ENTITY_ICONS / 2;
+        let player = game.player.pos;
+        icons.select_nth_unstable_by(half, |a, b| a.1.distance_to(player).total_cmp(&b.1.distance_to(player)));
+
+        // the same order every frame, so the turns go all the way around
+        let (near, far) = icons.split_at_mut(half);
+        far.sort_by_key(|(i, _, _)| *i);
+        let start = *cursor % far.len();
+        let turn: Vec<(usize, Vector2, Color)> = far.iter().cycle().skip(start).take(MAX_ENTITY_ICONS - half).copied().collect();
+        *cursor = start + turn.len();
+
+        icons = near.iter().copied().chain(turn).collect();
+    }
+
+    for (_, pos, color) in icons {
+        d.draw_circle_v(pos.apply(mt), 0.15 * zoom, color);
+    }
+}
+
+// every animation some cell of the board shows, once each
+fn board_animations(game: &Game) -> Vec<AnimationId> {
+    let mut animations = vec![];
+    for y in 0..game.board.rows {
+        for x in 0..game.board.cols {
+            if let Cell::AnimatedTexture(id) = game.board.at(x, y) {
+                if !animations.contains(id) {
+                    animations.push(*id);
+                }
+            }
+        }
+    }
+
+    animations
+}
+
+// the first and one past the last column and row of the cells that `viewport` shows some of
+fn visible_cells(game: &Game, mt: &Transform2D, viewport: Rectangle) -> (usize, usize, usize, usize) {
+    let cell = |screen: f32, offset: f32, zoom: f32, max: usize| ((screen - offset) / zoom).clamp(0.0, max as f32);
+    let x0 = cell(viewport.x, mt.offset.x, mt.zoom.x, game.board.cols).floor() as usize;
+    let y0 = cell(viewport.y, mt.offset.y, mt.zoom.y, game.board.rows).floor() as usize;
+    let x1 = cell(viewport.x + viewport.width, mt.offset.x, mt.zoom.x, game.board.cols).ceil() as usize;
+    let y1 = cell(viewport.y + viewport.height, mt.offset.y, mt.zoom.y, game.board.rows).ceil() as usize;
+    (x0, y0, x1, y1)
+}
+
+fn render_lights(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game) {
+    if !game.show_lights { return }
+
+    let zoom = f32::max(mt.zoom.x, mt.zoom.y);
+    for light in game.lights.lights() {
+        let pos = light.pos.apply(mt);
+        d.draw_circle_v(pos, light.radius * zoom, Color::YELLOW.alpha(0.15));
+        d.draw_circle_lines(pos.x as i32, pos.y as i32, light.radius * zoom, Color::YELLOW);
+    }
+}
+
+// every ray of the last cast, the ones it passed through marked apart from where it stopped
+fn render_rays(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game, columns: &[Vec<CellHit>]) {
+    if !game.show_rays { return }
+
+    let zoom = f32::min(mt.zoom.x, mt.zoom.y);
+    let from = game.player.pos.apply(mt);
+    for cells in columns.iter() {
+        // the first entry of a column is only a placeholder
+        let hits = cells.get(1..).unwrap_or_default();
+        // rays bend where they bounced off a mirror, so they are drawn from hit to hit
+        let mut start = from;
+        for (i, hit) in hits.iter().enumerate() {
+            let point = hit.board_point().apply(mt);
+            d.draw_line_v(start, point, Color::YELLOW.alpha(0.15));
+            start = point;
+
+            let color = if i + 1 < hits.len() { Color::SKYBLUE }
+            else if let Cell::EMPTY = hit.cell { continue }
+            else { Color::RED };
+            d.draw_circle_v(point, f32::max(zoom * 0.05, 1.0), color);
+        }
+    }
+
+    let Some((origin, hits)) = &game.picked else { return };
+    let Some(last) = hits.last() else { return };
+    d.draw_line_ex(origin.apply(mt), last.hit.point.apply(mt), 2.0, Color::MAGENTA);
+    for hit in hits.iter() {
+        if let Cell::EMPTY = hit.cell { continue }
+        let (x, y) = hit.hit.cell;
+        let rec = Rectangle::new(x as f32, y as f32, 1.0, 1.0).apply(mt);
+        d.draw_rectangle_lines_ex(rec, 2.0, Color::MAGENTA);
+    }
+}
+
+// each enemy's path from where it stands, with the cell it is heading for outlined
+fn render_paths(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game) {
+    if !game.debug_paths { return }
+
+    for enemy in game.enemies.iter() {
+        let mut from = enemy.pos();
+        for (x, y) in enemy.path.iter() {
+            let to = Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5);
+            d.draw_line_ex(from.apply(mt), to.apply(mt), 2.0, Color::ORANGE);
+            from = to;
+        }
+
+        if let Some((x, y)) = enemy.path.last() {
+            let rec = Rectangle::new(*x as f32, *y as f32, 1.0, 1.0).apply(mt);
+            d.draw_rectangle_lines_ex(rec, 2.0, Color::RED);
+        }
+    }
+}
+
+// the color with its alpha scaled by `opacity`
+fn faded(color: Color, opacity: f32) -> Color {
+    Color::new(color.r, color.g, color.b, (color.a as f32 * opacity) as u8)
+}
+
+// only the cells from `x0`,`y0` up to `x1`,`y1` and the grid around them
+fn render_board(d: &mut impl RaylibDraw, mt: &Transform2D, game: &Game, (x0, y0, x1, y1): (usize, usize, usize, usize), opacity: f32) {
+    let fade = |color: Color| faded(color, opacity);
+    let (from, to) = (Vector2::new(x0 as f32, y0 as f32), Vector2::new(x1 as f32, y1 as f32));
+    d.draw_rectangle_v(from.apply(mt), to.sub(from).apply_zoom(mt), fade(Color::BLACK));
+
+    // render grid
+    for y in y0..=y1 {
+        d.draw_line_v(Vector2::new(from.x, y as f32).apply(mt), Vector2::new(to.x, y as f32).apply(mt), fade(Color::GRAY));
+    }
+
+    for x in x0..=x1 {
+        d.draw_line_v(Vector2::new(x as f32, from.y).apply(mt), Vector2::new(x as f32, to.y).apply(mt), fade(Color::GRAY));
+    }
+
+    // render cells
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let cell = game.board.at(x, y);
+
+            let pos = Vector2::new(x as f32, y as f32).apply(mt);
+            let size = Vector2::one().apply_zoom(mt);
+
+            match cell {
+                Cell::EMPTY => {},
+                Cell::COLOR(color) => d.draw_rectangle_v(pos, size, fade(*color)),
+                Cell::Mirror => d.draw_rectangle_v(pos, size, fade(MIRROR_COLOR)),
+                Cell::TranslucentTexture(id) | Cell::TEXTURE(id) | Cell::Custom(_, id) => {
+                    let (texture, source_rec) = game.textures.region(*id);
+                    let dest_rec = Rectangle::new(x as f32, y as f32, 1.0, 1.0).apply(mt);
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, fade(Color::WHITE));
+                },
+                Cell::Segment(_, segment) => {
+                    let (a, b) = segment.endpoints(x, y);
+                    d.draw_line_ex(a.apply(mt), b.apply(mt), f32::max(size.x / 8.0, 1.0), fade(Color::LIGHTGRAY));
+                },
+                Cell::AnimatedTexture(id) => {
+                    let (texture, source_rec) = game.textures.animation_region(*id, game.time);
+                    let dest_rec = Rectangle::new(x as f32, y as f32, 1.0, 1.0).apply(mt);
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, fade(Color::WHITE));
+                },
+                Cell::Door(id) => {
+                    let open = game.board.door(x, y).map_or(0.0, |door| door.open);
+                    let (texture, region) = game.textures.region(*id);
+                    let source_rec = Rectangle::new(region.x, region.y, region.width * (1.0 - open), region.height);
+                    let dest_rec = Rectangle::new(x as f32 + open, y as f32, 1.0 - open, 1.0).apply(mt);
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, fade(Color::WHITE));
+                },
+            }
+        }
+    }
+
+    let zoom = f32::min(mt.zoom.x, mt.zoom.y);
+    for (x, y) in game.board.exits() {
+        d.draw_circle_v(Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5).apply(mt), zoom * 0.3, fade(Color::GOLD));
+    }
+
+    // teleporters with a line to where they send the player
+    for ((x, y), teleporter) in game.board.teleporters() {
+        let from = Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5).apply(mt);
+        let to = Vector2::new(teleporter.target.0 as f32 + 0.5, teleporter.target.1 as f32 + 0.5).apply(mt);
+        d.draw_line_v(from, to, fade(Color::VIOLET.alpha(0.5)));
+        d.draw_circle_v(from, zoom * 0.3, fade(Color::VIOLET));
+    }
+}
+
+// grows with the ui scale, up to half the window
+pub fn calulate_minimap_size(board_size: Vector2) -> Vector2 {
+    let ratio = f32::min(MINIMAP_ASPECT_RATIO * ui_scale(), MAX_MINIMAP_RATIO);
+    if board_size.x > board_size.y {
+        let x = window_size().x * ratio;
+        let y = (x / board_size.x) * board_size.y;
+        Vector2::new(x, y)
+    } else {
+        let y = window_size().y * ratio;
+        let x = (y / board_size.y) * board_size.x;
+        Vector2::new(x, y)
+    }
+}
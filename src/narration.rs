@@ -0,0 +1,54 @@
+use std::{process::{Child, Command, Stdio}, sync::{Mutex, OnceLock}};
+
+// what the menus focus and what the hud announces, read out for players who can't make out the screen.
+// the text is handed to a speech program, "espeak" or "say" for example, as its last argument,
+// or printed as "NARRATE: <text>" with "stdout" so a screen reader following the terminal picks it up
+pub struct Narrator {
+    program: String,
+    args: Vec<String>,
+    // what is still being read, cut off when something newer comes along like a screen reader does
+    speaking: Mutex<Option<Child>>
+}
+
+static NARRATOR: OnceLock<Narrator> = OnceLock::new();
+
+impl Narrator {
+    // the command line of the speech program, None for an empty one
+    pub fn new(command: &str) -> Option<Narrator> {
+        let mut words = command.split_whitespace().map(|word| word.to_string());
+        let program = words.next()?;
+        Some(Narrator { program, args: words.collect(), speaking: Mutex::new(None) })
+    }
+
+    fn say(&self, text: &str) {
+        if self.program == "stdout" {
+            println!("NARRATE: {}", text);
+            return
+        }
+
+        let Ok(mut speaking) = self.speaking.lock() else { return };
+        if let Some(mut child) = speaking.take() {
+            // it may well be done already
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        let child = Command::new(&self.program).args(&self.args).arg(text)
+            .stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+        match child {
+            Ok(child) => *speaking = Some(child),
+            Err(err) => println!("ERROR: Could not run narrator '{}': {}", self.program, err)
+        }
+    }
+}
+
+// set once from the engine config, nothing is narrated without it
+pub fn set_narrator(narrator: Narrator) -> Result<(), String> {
+    NARRATOR.set(narrator).map_err(|_| "Narrator is already set".to_string())
+}
+
+pub fn narrate(text: &str) {
+    if let Some(narrator) = NARRATOR.get() {
+        narrator.say(text);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, net::{SocketAddr, ToSocketAddrs, UdpSocket}};
+
+use raylib::math::Vector2;
+
+// players not heard from for this long have left
+const PEER_TIMEOUT: f32 = 3.0;
+// seconds between two states sent, the steps in between only receive
+const SEND_INTERVAL: f32 = 0.05;
+// big enough for the state of every player at once
+const MAX_PACKET: usize = 8192;
+
+// where another player is and which map it is on
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemotePlayer {
+    pub id: u32,
+    pub map: String,
+    pub pos: Vector2,
+    pub dir: Vector2,
+    last_seen: f32
+}
+
+// "<id> <x> <y> <dir x> <dir y> <map>", the map last since its path may hold spaces
+fn state_line(id: u32, map: &str, pos: Vector2, dir: Vector2) -> String {
+    format!("{} {} {} {} {} {}", id, pos.x, pos.y, dir.x, dir.y, map)
+}
+
+fn parse_state(line: &str, time: f32) -> Option<RemotePlayer> {
+    let words: Vec<&str> = line.splitn(6, ' ').collect();
+    let [id, x, y, dx, dy, map] = words[..] else { return None };
+    let number = |word: &str| word.parse::<f32>().ok();
+    Some(RemotePlayer {
+        id: id.parse::<u32>().ok()?,
+        map: map.to_string(),
+        pos: Vector2::new(number(x)?, number(y)?),
+        dir: Vector2::new(number(dx)?, number(dy)?),
+        last_seen: time
+    })
+}
+
+// the host keeps every client by its address and sends each of them everyone else,
+// clients only talk to the host and keep the last list it sent
+enum Role {
+    Host { clients: BTreeMap<SocketAddr, RemotePlayer>, next_id: u32 },
+    Client { server: SocketAddr, players: Vec<RemotePlayer> }
+}
+
+// the players share their positions over udp, a lost packet is as good as a late one
+pub struct NetSession {
+    socket: UdpSocket,
+    role: Role,
+    time: f32,
+    send_timer: f32
+}
+
+impl NetSession {
+    // listens for clients on `port`, the host itself is player 0
+    pub fn host(port: u16) -> Result<NetSession, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|err| format!("Could not listen on port {}: {}", port, err))?;
+        NetSession::new(socket, Role::Host { clients: BTreeMap::new(), next_id: 1 })
+    }
+
+    pub fn connect(address: &str) -> Result<NetSession, String> {
+        let server = address.to_socket_addrs().ok().and_then(|mut addrs| addrs.next()).ok_or(format!("Could not resolve '{}'", address))?;
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|err| format!("Could not open a socket: {}", err))?;
+        NetSession::new(socket, Role::Client { server, players: vec![] })
+    }
+
+    fn new(socket: UdpSocket, role: Role) -> Result<NetSession, String> {
+        socket.set_nonblocking(true).map_err(|err| format!("Could not set up the socket: {}", err))?;
+        Ok(NetSession { socket, role, time: 0.0, send_timer: 0.0 })
+    }
+
+    // takes in whatever arrived and every SEND_INTERVAL sends where the player is now
+    pub fn update(&mut self, dt: f32, map: &str, pos: Vector2, dir: Vector2) {
+        self.time += dt;
+        self.receive();
+
+        let time = self.time;
+        match &mut self.role {
+            Role::Host { clients, .. } => clients.retain(|_, client| time - client.last_seen < PEER_TIMEOUT),
+            Role::Client { players, .. } => players.retain(|player| time - player.last_seen < PEER_TIMEOUT)
+        }
+
+        self.send_timer -= dt;
+        if self.send_timer > 0.0 { return }
+        self.send_timer = SEND_INTERVAL;
+
+        match &self.role {
+            Role::Host { clients, .. } => {
+                for addr in clients.keys() {
+                    let others = clients.iter().filter(|(other, _)| *other != addr).map(|(_, client)| state_line(client.id, &client.map, client.pos, client.dir));
+                    let lines: Vec<String> = std::iter::once(state_line(0, map, pos, dir)).chain(others).collect();
+                    let _ = self.socket.send_to(lines.join("\n").as_bytes(), addr);
+                }
+            },
+            // the host knows clients by their address, the id sent is ignored
+            Role::Client { server, .. } => {
+                let _ = self.socket.send_to(state_line(0, map, pos, dir).as_bytes(), server);
+            }
+        }
+    }
+
+    fn receive(&mut self) {
+        let mut buffer = [0; MAX_PACKET];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buffer) {
+                Ok(packet) => packet,
+                // nothing more waiting, or a client that left bounced what was sent to it and the rest arrives next step
+                Err(_) => return
+            };
+            let Ok(text) = std::str::from_utf8(&buffer[..len]) else { continue };
+
+            match &mut self.role {
+                Role::Host { clients, next_id } => {
+                    let Some(mut state) = parse_state(text, self.time) else { continue };
+                    state.id = match clients.get(&addr) {
+                        Some(client) => client.id,
+                        None => {
+                            println!("INFO: Player {} joined from {}", next_id, addr);
+                            *next_id += 1;
+                            *next_id - 1
+                        }
+                    };
+                    clients.insert(addr, state);
+                },
+                Role::Client { server, players } => {
+                    if addr != *server { continue }
+                    *players = text.lines().filter_map(|line| parse_state(line, self.time)).collect();
+                }
+            }
+        }
+    }
+
+    // everyone but this player
+    pub fn players(&self) -> Vec<&RemotePlayer> {
+        match &self.role {
+            Role::Host { clients, .. } => clients.values().collect(),
+            Role::Client { players, .. } => players.iter().collect()
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use raylib::{color::Color, math::{Rectangle, Vector2}, texture::{Image, Texture2D}, RaylibHandle, RaylibThread};
+
+use crate::backend::Backend;
+
+// a theme can replace the built in look by dropping its own panel here
+pub const PANEL_PATH: &str = "./res/ui/panel.png";
+// pixels in from every edge of the panel texture that its corners and edges take up
+pub const PANEL_BORDER: f32 = 8.0;
+
+// the background of menus, dialog boxes and the console, drawn as nine slices so the corners keep
+// their size, the edges only stretch along themselves and the middle fills whatever is left
+pub struct Panel {
+    texture: Texture2D,
+    border: f32
+}
+
+// the built in look, a dark box with a light frame just inside its edge
+fn panel_image() -> Image {
+    let size = 3 * PANEL_BORDER as i32;
+    let mut image = Image::gen_image_color(size, size, Color::new(0, 0, 0, 255));
+    image.draw_rectangle_lines(Rectangle::new(2.0, 2.0, size as f32 - 4.0, size as f32 - 4.0), 2, Color::GRAY);
+    image
+}
+
+impl Panel {
+    // the theme's panel if there is one, the built in one otherwise
+    pub fn load(rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<Panel, String> {
+        let texture = if Path::new(PANEL_PATH).is_file() { rl.load_texture(thread, PANEL_PATH)? }
+        else { rl.load_texture_from_image(thread, &panel_image())? };
+        Ok(Panel { texture, border: PANEL_BORDER })
+    }
+
+    // `tint` is how the plain rectangle drawn without a panel looked, only its alpha is kept
+    pub fn draw(&self, d: &mut impl Backend, dest: Rectangle, tint: Color) {
+        let tint = Color::WHITE.alpha(tint.a as f32 / 255.0);
+        let (tw, th) = (self.texture.width as f32, self.texture.height as f32);
+        // a panel smaller than its corners squeezes them
+        let border = f32::min(self.border, f32::min(dest.width, dest.height) / 2.0);
+
+        let source_x = [0.0, self.border, tw - self.border, tw];
+        let source_y = [0.0, self.border, th - self.border, th];
+        let xs = [dest.x, dest.x + border, dest.x + dest.width - border, dest.x + dest.width];
+        let ys = [dest.y, dest.y + border, dest.y + dest.height - border, dest.y + dest.height];
+        for row in 0..3 {
+            for col in 0..3 {
+                let source_rec = Rectangle::new(source_x[col], source_y[row], source_x[col + 1] - source_x[col], source_y[row + 1] - source_y[row]);
+                let dest_rec = Rectangle::new(xs[col], ys[row], xs[col + 1] - xs[col], ys[row + 1] - ys[row]);
+                d.draw_texture_pro(&self.texture, source_rec, dest_rec, Vector2::zero(), 0.0, tint);
+            }
+        }
+    }
+}
+
+// a panel where there is one, the plain rectangle it replaces where there isn't
+pub fn draw_panel(d: &mut impl Backend, panel: Option<&Panel>, dest: Rectangle, color: Color) {
+    match panel {
+        Some(panel) => panel.draw(d, dest, color),
+        None => d.draw_rectangle_rec(dest, color)
+    }
+}
@@ -0,0 +1,156 @@
+use std::{f32::consts::TAU, ops::{Add, Mul}};
+
+use raylib::{color::Color, math::Vector2};
+
+use crate::{board::Board, math::Lcg, spawn::SpawnKind};
+
+// more than this and the oldest make room for new ones
+pub const MAX_PARTICLES: usize = 1024;
+// wall heights per second squared, pulling everything that isn't dust down
+const GRAVITY: f32 = 2.0;
+// share of the speed kept bouncing off the floor
+const BOUNCE: f32 = 0.4;
+
+const IMPACT_PARTICLES: usize = 12;
+const IMPACT_SPEED: f32 = 1.5;
+// a torch throws this many sparks per second for every point of its "sparks" property
+const SPARK_RATE: f32 = 4.0;
+
+// a speck flying through the level, drawn as a small square facing the camera that walls in front of it hide
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub pos: Vector2,
+    pub vel: Vector2,
+    // above the floor in wall heights, and how fast that changes
+    pub height: f32,
+    pub rise: f32,
+    // how strongly gravity pulls it, floating dust barely falls
+    pub weight: f32,
+    // seconds left and seconds it started with, it fades out over them
+    pub life: f32,
+    pub lifetime: f32,
+    // in wall heights
+    pub size: f32,
+    pub color: Color,
+    // sparks light themselves, everything else is lit like the sprites
+    pub glow: bool
+}
+
+impl Particle {
+    pub fn alpha(&self) -> f32 {
+        if self.lifetime <= 0.0 { 0.0 }
+        else { (self.life / self.lifetime).clamp(0.0, 1.0) }
+    }
+}
+
+// impacts burst out of whatever was hit, lights with a "sparks" property throw sparks up
+pub struct Particles {
+    pub particles: Vec<Particle>,
+    // the most there may be, lowered by the entity budget (see budget.rs)
+    pub limit: usize,
+    // the same seed every map, so a demo sees the same particles it was recorded with
+    rng: Lcg,
+    spark_timer: f32
+}
+
+impl Default for Particles {
+    fn default() -> Particles {
+        Particles::new()
+    }
+}
+
+impl Particles {
+    pub fn new() -> Particles {
+        Particles { particles: vec![], limit: MAX_PARTICLES, rng: Lcg::new(0x9a27), spark_timer: 0.0 }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.rng = Lcg::new(0x9a27);
+        self.spark_timer = 0.0;
+    }
+
+    pub fn emit(&mut self, particle: Particle) {
+        if self.limit == 0 { return }
+        if self.particles.len() >= self.limit {
+            self.particles.remove(0);
+        }
+        self.particles.push(particle);
+    }
+
+    // a direction within `spread` radians either side of `dir`
+    fn scatter(&mut self, dir: Vector2, spread: f32) -> Vector2 {
+        dir.rotated((self.rng.next_float() * 2.0 - 1.0) * spread)
+    }
+
+    // chips and dust thrown back out of a wall at `pos`, towards `back` where the shot came from
+    pub fn impact(&mut self, pos: Vector2, back: Vector2, height: f32, color: Color) {
+        let back = back.normalized();
+        // a little off the wall, so the first step doesn't put them inside it
+        let pos = pos.add(back.mul(0.02));
+        for i in 0..IMPACT_PARTICLES {
+            let dust = i % 3 == 0;
+            let speed = IMPACT_SPEED * (0.3 + self.rng.next_float());
+            let vel = self.scatter(back, 1.2).mul(if dust { speed * 0.2 } else { speed });
+            let life = 0.3 + self.rng.next_float() * if dust { 1.2 } else { 0.4 };
+            let rise = (self.rng.next_float() - 0.3) * speed;
+            self.emit(Particle {
+                pos, vel, height, rise,
+                weight: if dust { 0.05 } else { 1.0 },
+                life, lifetime: life,
+                size: if dust { 0.04 } else { 0.02 },
+                color: if dust { Color::GRAY } else { color },
+                glow: false
+            });
+        }
+    }
+
+    fn spark(&mut self, pos: Vector2) {
+        let vel = Vector2::new(1.0, 0.0).rotated(self.rng.next_float() * TAU).mul(0.2 * self.rng.next_float());
+        let life = 0.4 + self.rng.next_float() * 0.6;
+        let color = if self.rng.next_float() < 0.5 { Color::ORANGE } else { Color::YELLOW };
+        let rise = 0.8 + self.rng.next_float() * 0.6;
+        self.emit(Particle {
+            pos, vel, height: 0.6, rise,
+            weight: 0.8, life, lifetime: life, size: 0.015, color, glow: true
+        });
+    }
+
+    pub fn update(&mut self, board: &Board, dt: f32) {
+        // every torch throws its share of a fixed rate, so sparks don't depend on the frame rate
+        self.spark_timer += dt * SPARK_RATE;
+        while self.spark_timer >= 1.0 {
+            self.spark_timer -= 1.0;
+            let torches: Vec<(Vector2, usize)> = board.spawns.iter()
+                .filter(|spawn| spawn.kind == SpawnKind::Light && spawn.property("off").unwrap_or(0.0) == 0.0)
+                .filter_map(|spawn| spawn.property("sparks").filter(|sparks| *sparks > 0.0).map(|sparks| (spawn.pos, sparks as usize)))
+                .collect();
+            for (pos, sparks) in torches {
+                for _ in 0..sparks {
+                    self.spark(pos);
+                }
+            }
+        }
+
+        for particle in self.particles.iter_mut() {
+            particle.life -= dt;
+            particle.rise -= GRAVITY * particle.weight * dt;
+            particle.height += particle.rise * dt;
+            if particle.height < 0.0 {
+                particle.height = 0.0;
+                particle.rise = -particle.rise * BOUNCE;
+                particle.vel = particle.vel.mul(BOUNCE);
+            }
+
+            // walls stop them dead, they fall the rest of the way where they hit
+            let next = particle.pos.add(particle.vel.mul(dt));
+            let inside = next.x >= 0.0 && next.y >= 0.0 && next.x < board.cols as f32 && next.y < board.rows as f32;
+            if inside && board.is_passable(next.x as usize, next.y as usize) {
+                particle.pos = next;
+            } else {
+                particle.vel = Vector2::zero();
+            }
+        }
+        self.particles.retain(|particle| particle.life > 0.0 && particle.height <= 1.0);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::board::{Board, Cell};
+
+// breadth first search over passable cells, returns the cells after `from` up to and including `to`
+pub fn find_path(board: &Board, from: (usize, usize), to: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+    if from == to { return Some(vec![]) }
+    if !board.is_passable(to.0, to.1) { return None }
+
+    let index = |(x, y): (usize, usize)| y * board.cols + x;
+    let mut came_from: Vec<Option<(usize, usize)>> = vec![None; board.rows * board.cols];
+    let mut queue = VecDeque::from([from]);
+    came_from[index(from)] = Some(from);
+
+    while let Some(cell) = queue.pop_front() {
+        if cell == to { break }
+
+        let (x, y) = cell;
+        let neighbours = [
+            (x.wrapping_sub(1), y), (x + 1, y),
+            (x, y.wrapping_sub(1)), (x, y + 1)
+        ];
+
+        for next in neighbours {
+            if next.0 >= board.cols || next.1 >= board.rows { continue }
+            if came_from[index(next)].is_some() || !board.is_passable(next.0, next.1) { continue }
+
+            came_from[index(next)] = Some(cell);
+            queue.push_back(next);
+        }
+    }
+
+    came_from[index(to)]?;
+
+    let mut path = vec![to];
+    let mut cell = to;
+    while let Some(prev) = came_from[index(cell)] {
+        if prev == from { break }
+        path.push(prev);
+        cell = prev;
+    }
+
+    path.reverse();
+    Some(path)
+}
+
+// cells are grouped into sectors this many cells a side, each split into the regions walkable inside it
+const SECTOR: usize = 8;
+
+// a coarse graph of the walkable regions of a board, built once for its layout, so a path across a big
+// open map only searches the cells of the regions on the way instead of the whole board.
+// doors count as walkable here whether they are open or not, the search over the cells checks them
+#[derive(Default)]
+pub struct NavGraph {
+    cols: usize,
+    layout: Option<u64>,
+    // the region of every cell, None for the ones nothing walks on
+    regions: Vec<Option<usize>>,
+    // middle of every region and the regions it opens into
+    centers: Vec<(f32, f32)>,
+    neighbours: Vec<Vec<usize>>
+}
+
+impl NavGraph {
+    pub fn new() -> NavGraph {
+        NavGraph::default()
+    }
+
+    pub fn build(board: &Board) -> NavGraph {
+        let mut graph = NavGraph::new();
+        graph.update(board);
+        graph
+    }
+
+    fn walkable(board: &Board, x: usize, y: usize) -> bool {
+        matches!(board.at(x, y), Cell::EMPTY | Cell::Door(_))
+    }
+
+    fn region(&self, (x, y): (usize, usize)) -> Option<usize> {
+        self.regions[y * self.cols + x]
+    }
+
+    pub fn region_count(&self) -> usize {
+        self.centers.len()
+    }
+
+    // rebuilt only when walls went up or came down, doors opening and closing leave it as it is
+    pub fn update(&mut self, board: &Board) {
+        if self.layout == Some(board.layout_revision()) { return }
+
+        self.cols = board.cols;
+        self.layout = Some(board.layout_revision());
+        self.regions = vec![None; board.rows * board.cols];
+        self.centers.clear();
+        self.neighbours.clear();
+
+        let index = |(x, y): (usize, usize)| y * board.cols + x;
+        for y in 0..board.rows {
+            for x in 0..board.cols {
+                if self.regions[index((x, y))].is_some() || !NavGraph::walkable(board, x, y) { continue }
+
+                // flood fill that stays inside the sector it started in
+                let region = self.centers.len();
+                let sector = (x / SECTOR, y / SECTOR);
+                let mut queue = VecDeque::from([(x, y)]);
+                let mut sum = (0.0, 0.0);
+                let mut count = 0;
+                self.regions[index((x, y))] = Some(region);
+
+                while let Some((cx, cy)) = queue.pop_front() {
+                    sum = (sum.0 + cx as f32 + 0.5, sum.1 + cy as f32 + 0.5);
+                    count += 1;
+
+                    let neighbours = [
+                        (cx.wrapping_sub(1), cy), (cx + 1, cy),
+                        (cx, cy.wrapping_sub(1)), (cx, cy + 1)
+                    ];
+                    for next in neighbours {
+                        if next.0 >= board.cols || next.1 >= board.rows { continue }
+                        if (next.0 / SECTOR, next.1 / SECTOR) != sector { continue }
+                        if self.regions[index(next)].is_some() || !NavGraph::walkable(board, next.0, next.1) { continue }
+
+                        self.regions[index(next)] = Some(region);
+                        queue.push_back(next);
+                    }
+                }
+
+                self.centers.push((sum.0 / count as f32, sum.1 / count as f32));
+                self.neighbours.push(vec![]);
+            }
+        }
+
+        // regions meet where two walkable cells of different ones touch
+        for y in 0..board.rows {
+            for x in 0..board.cols {
+                let Some(a) = self.regions[index((x, y))] else { continue };
+                for next in [(x + 1, y), (x, y + 1)] {
+                    if next.0 >= board.cols || next.1 >= board.rows { continue }
+                    let Some(b) = self.regions[index(next)] else { continue };
+                    if a == b || self.neighbours[a].contains(&b) { continue }
+
+                    self.neighbours[a].push(b);
+                    self.neighbours[b].push(a);
+                }
+            }
+        }
+    }
+
+    // A* over the regions, the ones from the region of `from` to the region of `to`
+    fn corridor(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let distance = |a: usize, b: usize| {
+            let (a, b) = (self.centers[a], self.centers[b]);
+            f32::hypot(a.0 - b.0, a.1 - b.1)
+        };
+
+        let mut cost = vec![f32::INFINITY; self.centers.len()];
+        let mut came_from: Vec<Option<usize>> = vec![None; self.centers.len()];
+        let mut open = vec![from];
+        cost[from] = 0.0;
+
+        // few enough regions that picking the best open one by hand beats keeping a heap in order
+        while !open.is_empty() {
+            let best = (0..open.len())
+                .min_by(|&a, &b| (cost[open[a]] + distance(open[a], to)).total_cmp(&(cost[open[b]] + distance(open[b], to))))?;
+            let region = open.swap_remove(best);
+            if region == to { break }
+
+            for &next in self.neighbours[region].iter() {
+                let next_cost = cost[region] + distance(region, next);
+                if next_cost >= cost[next] { continue }
+
+                cost[next] = next_cost;
+                came_from[next] = Some(region);
+                if !open.contains(&next) {
+                    open.push(next);
+                }
+            }
+        }
+
+        if from != to && came_from[to].is_none() { return None }
+
+        let mut corridor = vec![to];
+        let mut region = to;
+        while let Some(prev) = came_from[region] {
+            corridor.push(prev);
+            region = prev;
+        }
+        Some(corridor)
+    }
+
+    // the same path as `find_path` gives, only searching the cells of the regions the graph says lead to `to`.
+    // when a closed door cuts the way through them it falls back to searching the whole board
+    pub fn find_path(&self, board: &Board, from: (usize, usize), to: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        if from == to { return Some(vec![]) }
+        if !board.is_passable(to.0, to.1) { return None }
+
+        // built for another layout, or asked from inside a wall
+        if self.layout != Some(board.layout_revision()) { return find_path(board, from, to) }
+        let (Some(start), Some(goal)) = (self.region(from), self.region(to)) else { return find_path(board, from, to) };
+
+        // not one region of walkable cells leads there, open doors or not
+        let corridor = self.corridor(start, goal)?;
+        let mut allowed = vec![false; self.centers.len()];
+        for region in corridor {
+            allowed[region] = true;
+        }
+
+        let mut came_from = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(cell) = queue.pop_front() {
+            if cell == to { break }
+
+            let (x, y) = cell;
+            let neighbours = [
+                (x.wrapping_sub(1), y), (x + 1, y),
+                (x, y.wrapping_sub(1)), (x, y + 1)
+            ];
+
+            for next in neighbours {
+                if next.0 >= board.cols || next.1 >= board.rows { continue }
+                if came_from.contains_key(&next) || !board.is_passable(next.0, next.1) { continue }
+                if !self.region(next).is_some_and(|region| allowed[region]) { continue }
+
+                came_from.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+
+        if !came_from.contains_key(&to) { return find_path(board, from, to) }
+
+        let mut path = vec![to];
+        let mut cell = to;
+        while let Some(&prev) = came_from.get(&cell) {
+            if prev == from { break }
+            path.push(prev);
+            cell = prev;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+}
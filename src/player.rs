@@ -0,0 +1,219 @@
+use std::ops::{Add, AddAssign, Mul};
+
+use raylib::math::Vector2;
+
+use crate::{item::{Item, Key}, math::{perp_right, rotated}, EPS};
+
+pub struct Player {
+    pub pos: Vector2,
+    pub dir: Vector2,
+    pub spd: Vector2,
+    pub turn_spd: f32,
+    pub health: f32,
+    pub max_health: f32,
+    pub ammo: u32,
+    pub max_ammo: u32,
+    // keys collected in the current map, kept sorted
+    pub keys: Vec<Key>,
+    // how far the horizon is moved down from the middle of the screen, in screen heights
+    pub pitch: f32,
+    // eye height above the floor in wall heights, moving towards the crouched or standing one
+    pub eye: f32,
+    // height of a jump above the floor and how fast it is rising
+    pub jump: f32,
+    pub jump_spd: f32,
+    // seconds left in which a jump still starts after the floor was left without one
+    pub coyote: f32,
+    pub vel: Vector2,
+    // speeding up and slowing down instead of starting and stopping at once
+    pub smoothing: bool,
+    pub head_bob: bool,
+    // keyboard turns go from one 45 degree heading to the next instead of anywhere in between
+    pub snap_turn: bool,
+    // the heading a snapped turn is on its way to, in radians
+    snap_target: Option<f32>,
+    bob_phase: f32,
+    bob: f32
+}
+
+// past this the walls shear too far to look right
+pub const MAX_PITCH: f32 = 0.4;
+
+// fraction of the max health below which the screen starts warning the player
+pub const LOW_HEALTH: f32 = 0.3;
+
+pub const EYE_HEIGHT: f32 = 0.5;
+pub const CROUCH_HEIGHT: f32 = 0.3;
+// wall heights per second the eye moves while crouching or standing up
+const CROUCH_SPEED: f32 = 1.5;
+const JUMP_SPEED: f32 = 1.6;
+const GRAVITY: f32 = 5.0;
+// the floor is flat for now, so only something else lifting the player off it starts this grace window
+const COYOTE_TIME: f32 = 0.1;
+// how quickly smoothed movement reaches full speed, and slows down once no key is held
+const ACCELERATION: f32 = 10.0;
+const FRICTION: f32 = 8.0;
+// how much faster than walking a sprint goes
+const SPRINT_SPEED: f32 = 1.6;
+// radians of bob per unit walked, and how far the eye moves up and down
+const BOB_FREQUENCY: f32 = 8.0;
+const BOB_HEIGHT: f32 = 0.02;
+const SNAP_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+// how much faster than a free turn a snapped one swings round, so a tap doesn't feel sluggish
+const SNAP_TURN_SPEED: f32 = 2.0;
+
+impl Player {
+    pub fn new(x: f32, y: f32) -> Player {
+        Player {
+            pos: Vector2::new(x, y),
+            dir: Vector2::new(1.0, 0.0),
+            spd: Vector2::one(),
+            turn_spd: std::f32::consts::FRAC_PI_2,
+            health: 100.0,
+            max_health: 100.0,
+            ammo: 50,
+            max_ammo: 200,
+            keys: vec![],
+            pitch: 0.0,
+            eye: EYE_HEIGHT,
+            jump: 0.0,
+            jump_spd: 0.0,
+            coyote: 0.0,
+            vel: Vector2::zero(),
+            smoothing: true,
+            head_bob: true,
+            snap_turn: false,
+            snap_target: None,
+            bob_phase: 0.0,
+            bob: 0.0
+        }
+    }
+
+    // `forward` and `right` are -1, 0 or 1, without smoothing the player moves at full speed right away
+    pub fn walk(&mut self, forward: f32, right: f32, sprint: bool, dt: f32) {
+        let wish = self.dir.mul(forward).add(perp_right(self.dir).mul(right)).mul(self.spd);
+        let wish = if sprint { wish.mul(SPRINT_SPEED) }
+        else { wish };
+        self.vel = if !self.smoothing { wish }
+        else if wish.length() > EPS { self.vel.lerp(wish, f32::min(ACCELERATION * dt, 1.0)) }
+        else { self.vel.mul(f32::max(1.0 - FRICTION * dt, 0.0)) };
+
+        self.pos.add_assign(self.vel.mul(dt))
+    }
+
+    // the bob follows the distance walked and fades with the speed, so stopping settles the view
+    pub fn update_bob(&mut self, walked: f32) {
+        self.bob_phase += walked * BOB_FREQUENCY;
+        let speed = f32::min(self.vel.length() / f32::max(self.spd.x, EPS), 1.0);
+        self.bob = if self.head_bob && self.on_ground() { f32::sin(self.bob_phase) * BOB_HEIGHT * speed }
+        else { 0.0 };
+    }
+
+    pub fn turn_left(&mut self, delta: f32) {
+        self.dir = rotated(self.dir, -self.turn_spd * delta)
+    }
+
+    pub fn turn_right(&mut self, delta: f32) {
+        self.dir = rotated(self.dir, self.turn_spd * delta)
+    }
+
+    // `turn` is -1 (left), 0 or 1 (right). a held key keeps going to the next heading once one is reached,
+    // letting go still finishes the turn under way
+    pub fn turn_snapped(&mut self, turn: f32, delta: f32) {
+        let angle = f32::atan2(self.dir.y, self.dir.x);
+        if self.snap_target.is_none() && turn != 0.0 {
+            // a heading already on a step by rounding counts as on it, so the turn goes a whole step
+            let step = angle / SNAP_ANGLE;
+            let next = if turn > 0.0 { f32::floor(step + EPS) + 1.0 }
+            else { f32::ceil(step - EPS) - 1.0 };
+            self.snap_target = Some(next * SNAP_ANGLE);
+        }
+
+        let Some(target) = self.snap_target else { return };
+        let left = (target - angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+        let max = self.turn_spd * SNAP_TURN_SPEED * delta;
+        if left.abs() <= max {
+            self.dir = Vector2::new(f32::cos(target), f32::sin(target));
+            self.snap_target = None;
+        } else {
+            self.dir = rotated(self.dir, max.copysign(left));
+        }
+    }
+
+    pub fn damage(&mut self, amount: f32) {
+        self.health = f32::max(self.health - amount, 0.0)
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.health = f32::min(self.health + amount, self.max_health)
+    }
+
+    // false when the item would be wasted, the pickup then stays where it is
+    pub fn pick_up(&mut self, item: Item) -> bool {
+        match item {
+            Item::Health(amount) => {
+                if self.health >= self.max_health { return false }
+                self.heal(amount);
+            },
+            Item::Ammo(amount) => {
+                if self.ammo >= self.max_ammo { return false }
+                self.ammo = u32::min(self.ammo + amount, self.max_ammo);
+            },
+            Item::Key(key) => {
+                if self.has_key(key) { return false }
+                self.keys.push(key);
+                self.keys.sort();
+            }
+        }
+
+        true
+    }
+
+    pub fn has_key(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    // 0 at or above the low health mark, up to 1 with no health left
+    pub fn low_health(&self) -> f32 {
+        let mark = self.max_health * LOW_HEALTH;
+        if mark <= 0.0 { return 0.0 }
+        (1.0 - self.health / mark).clamp(0.0, 1.0)
+    }
+
+    pub fn eye_height(&self) -> f32 {
+        self.eye + self.jump + self.bob
+    }
+
+    pub fn on_ground(&self) -> bool {
+        self.jump <= 0.0
+    }
+
+    // jumping only starts from the floor or shortly after leaving it, crouching is held.
+    // returns whether a jump started
+    pub fn update_height(&mut self, dt: f32, jump: bool, crouch: bool) -> bool {
+        let jumped = jump && (self.on_ground() || self.coyote > 0.0);
+        if jumped {
+            self.jump_spd = JUMP_SPEED;
+            self.coyote = 0.0;
+        }
+
+        self.jump_spd -= GRAVITY * dt;
+        self.jump += self.jump_spd * dt;
+        if self.jump <= 0.0 {
+            self.jump = 0.0;
+            self.jump_spd = 0.0;
+        }
+        self.coyote = if self.on_ground() { COYOTE_TIME }
+        else { (self.coyote - dt).max(0.0) };
+
+        let target = if crouch { CROUCH_HEIGHT } else { EYE_HEIGHT };
+        let step = CROUCH_SPEED * dt;
+        self.eye += (target - self.eye).clamp(-step, step);
+        jumped
+    }
+
+    // positive looks up
+    pub fn look_up(&mut self, amount: f32) {
+        self.pitch = (self.pitch + amount).clamp(-MAX_PITCH, MAX_PITCH)
+    }
+}
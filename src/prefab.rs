@@ -0,0 +1,163 @@
+use std::{collections::HashMap, fs, ops::{Add, Sub}, path::Path};
+
+use raylib::math::Vector2;
+
+use crate::{board::{Board, Cell}, map::{load_board, save_board}, spawn::SpawnKind, texture::TextureRegistry};
+
+// prefabs are plain text maps kept here by name, "./prefabs/<name>.txt"
+pub const PREFABS_DIR: &str = "./prefabs";
+// one "<name> <weight>" line per prefab the generator should pick more or less often than the others,
+// which weigh 1. a weight of 0 keeps the generator off it
+pub const WEIGHTS_PATH: &str = "./prefabs/weights.cfg";
+
+// a prefab the generator picks `weight` times as often as one weighing 1
+pub struct WeightedPrefab {
+    pub prefab: Board,
+    pub weight: usize
+}
+
+pub fn prefab_path(name: &str) -> String {
+    format!("{}/{}.txt", PREFABS_DIR, name)
+}
+
+// the cells of a rectangle of `board` with their heights, floors, ceilings and the spawns standing on them,
+// as a board of its own with the spawns moved along with the corner. the player start is left out,
+// a map only has the one
+pub fn copy_region(board: &Board, x: usize, y: usize, w: usize, h: usize) -> Board {
+    let (w, h) = (usize::min(w, board.cols.saturating_sub(x)), usize::min(h, board.rows.saturating_sub(y)));
+    let mut region = Board::new(h, w);
+    for ry in 0..h {
+        for rx in 0..w {
+            let (bx, by) = (x + rx, y + ry);
+            region.set(rx, ry, *board.at(bx, by));
+            region.set_height(rx, ry, board.height_at(bx, by));
+            region.set_floor(rx, ry, board.floor_at(bx, by));
+            region.set_ceiling(rx, ry, board.ceiling_at(bx, by));
+            region.set_floor_color(rx, ry, board.floor_color_at(bx, by));
+            region.set_ceiling_color(rx, ry, board.ceiling_color_at(bx, by));
+        }
+    }
+
+    let corner = Vector2::new(x as f32, y as f32);
+    region.spawns = board.spawns.iter()
+        .filter(|spawn| spawn.kind != SpawnKind::Player)
+        .filter(|spawn| spawn.pos.x >= corner.x && spawn.pos.y >= corner.y && spawn.pos.x < (x + w) as f32 && spawn.pos.y < (y + h) as f32)
+        .map(|spawn| {
+            let mut spawn = spawn.clone();
+            spawn.pos = spawn.pos.sub(corner);
+            spawn
+        })
+        .collect();
+    region
+}
+
+// `prefab` with its top left corner on x,y, replacing whatever was there. what hangs over the edge
+// of the board is cut off, spawns included
+pub fn stamp(board: &mut Board, prefab: &Board, x: usize, y: usize) {
+    for py in 0..prefab.rows {
+        for px in 0..prefab.cols {
+            let (bx, by) = (x + px, y + py);
+            if bx >= board.cols || by >= board.rows { continue }
+            board.set(bx, by, *prefab.at(px, py));
+            board.set_height(bx, by, prefab.height_at(px, py));
+            board.set_floor(bx, by, prefab.floor_at(px, py));
+            board.set_ceiling(bx, by, prefab.ceiling_at(px, py));
+            board.set_floor_color(bx, by, prefab.floor_color_at(px, py));
+            board.set_ceiling_color(bx, by, prefab.ceiling_color_at(px, py));
+        }
+    }
+
+    let corner = Vector2::new(x as f32, y as f32);
+    for spawn in prefab.spawns.iter() {
+        let pos = spawn.pos.add(corner);
+        if pos.x >= board.cols as f32 || pos.y >= board.rows as f32 { continue }
+
+        let mut spawn = spawn.clone();
+        spawn.pos = pos;
+        board.spawns.push(spawn);
+    }
+}
+
+pub fn save_prefab(name: &str, prefab: &Board) -> Result<String, String> {
+    fs::create_dir_all(PREFABS_DIR).map_err(|err| format!("Could not create '{}': {}", PREFABS_DIR, err))?;
+    let path = prefab_path(name);
+    save_board(&path, prefab)?;
+    Ok(path)
+}
+
+pub fn load_prefab(name: &str, textures: &TextureRegistry) -> Result<Board, String> {
+    load_board(&prefab_path(name), textures)
+}
+
+// turned clockwise a quarter `turns` times, spawns and the edge walls along with it
+pub fn rotated(prefab: &Board, turns: usize) -> Board {
+    let mut board = prefab.clone();
+    for _ in 0..turns % 4 {
+        let mut turned = Board::new(board.cols, board.rows);
+        for y in 0..board.rows {
+            for x in 0..board.cols {
+                let (tx, ty) = (board.rows - 1 - y, x);
+                let cell = match *board.at(x, y) {
+                    Cell::Segment(id, segment) => Cell::Segment(id, segment.turned()),
+                    cell => cell
+                };
+                turned.set(tx, ty, cell);
+                turned.set_height(tx, ty, board.height_at(x, y));
+                turned.set_floor(tx, ty, board.floor_at(x, y));
+                turned.set_ceiling(tx, ty, board.ceiling_at(x, y));
+                turned.set_floor_color(tx, ty, board.floor_color_at(x, y));
+                turned.set_ceiling_color(tx, ty, board.ceiling_color_at(x, y));
+            }
+        }
+        turned.spawns = board.spawns.iter().map(|spawn| {
+            let mut spawn = spawn.clone();
+            spawn.pos = Vector2::new(board.rows as f32 - spawn.pos.y, spawn.pos.x);
+            spawn
+        }).collect();
+        board = turned;
+    }
+    board
+}
+
+fn parse_weights(text: &str) -> Result<HashMap<String, usize>, String> {
+    let mut weights = HashMap::new();
+    for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        let (name, weight) = line.split_once(char::is_whitespace).ok_or(format!("Expected a name and a weight in '{}'", line))?;
+        let weight = weight.trim().parse::<usize>().map_err(|err| format!("Invalid weight in '{}': {}", line, err))?;
+        weights.insert(name.to_string(), weight);
+    }
+    Ok(weights)
+}
+
+// every prefab in the directory with its weight, the ones that don't load are skipped
+pub fn load_prefabs(textures: &TextureRegistry) -> Vec<WeightedPrefab> {
+    let weights = match fs::read_to_string(WEIGHTS_PATH).map(|text| parse_weights(&text)) {
+        Ok(Ok(weights)) => weights,
+        Ok(Err(err)) => {
+            println!("ERROR: {}: {}", WEIGHTS_PATH, err);
+            HashMap::new()
+        },
+        // without the file every prefab weighs the same
+        Err(_) => HashMap::new()
+    };
+
+    let Ok(entries) = fs::read_dir(PREFABS_DIR) else { return vec![] };
+    let mut paths: Vec<String> = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    // the same order on every system, so a seed generates the same map
+    paths.sort();
+
+    paths.iter().filter_map(|path| match load_board(path, textures) {
+        Ok(prefab) => {
+            let name = Path::new(path).file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            Some(WeightedPrefab { prefab, weight: weights.get(&name).copied().unwrap_or(1) })
+        },
+        Err(err) => {
+            println!("ERROR: Skipping prefab '{}': {}", path, err);
+            None
+        }
+    }).collect()
+}
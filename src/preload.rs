@@ -0,0 +1,155 @@
+use std::{collections::{HashMap, HashSet}, fs, sync::mpsc::{channel, Receiver, Sender}, thread::{self, JoinHandle}};
+
+use raylib::{texture::Image, RaylibHandle, RaylibThread};
+
+use crate::{board::Board, map::board_from_text, texture::{TextureId, TextureRegistry}, tiled::{board_from_tiled, is_tiled_map, tiles_path}};
+
+// cells from an exit the player has to be within for the map behind it to start loading
+pub const PRELOAD_DISTANCE: f32 = 4.0;
+
+// a map's files as read off the disk, they are parsed where the texture registry is
+enum MapFiles {
+    Text(String),
+    // the map and its tile lookup
+    Tiled(String, String)
+}
+
+impl MapFiles {
+    fn read(path: &str) -> Result<MapFiles, String> {
+        let read = |path: &str, what: &str| fs::read_to_string(path).map_err(|err| format!("Could not load {} '{}': {}", what, path, err));
+        if is_tiled_map(path) { Ok(MapFiles::Tiled(read(path, "map")?, read(&tiles_path(path), "tile lookup")?)) }
+        else { Ok(MapFiles::Text(read(path, "map")?)) }
+    }
+
+    fn parse(&self, path: &str, textures: &TextureRegistry) -> Result<Board, String> {
+        match self {
+            MapFiles::Text(text) => board_from_text(text, textures),
+            MapFiles::Tiled(json, lookup) => board_from_tiled(json, lookup, textures).map_err(|err| format!("{}: {}", path, err))
+        }
+    }
+}
+
+// what the loader thread reads, by the path of the map it is for
+enum Job {
+    Map(String),
+    Images(String, Vec<(TextureId, String)>)
+}
+
+impl Job {
+    fn run(self) -> Done {
+        match self {
+            Job::Map(path) => {
+                let files = MapFiles::read(&path);
+                Done::Map(path, files)
+            },
+            Job::Images(path, files) => Done::Images(path, files.into_iter().map(|(id, file)| (id, Image::load_image(&file).map_err(|err| format!("{}: {}", file, err)))).collect())
+        }
+    }
+}
+
+enum Done {
+    Map(String, Result<MapFiles, String>),
+    Images(String, Vec<(TextureId, Result<Image, String>)>)
+}
+
+// decoding an image only touches memory, it takes the window to make a texture of it, which is left
+// to the main thread
+unsafe impl Send for Done {}
+
+// the maps the exits of the current one lead to, read and parsed with their textures decoded on a
+// thread of their own while the player walks up to them, so going through is instant
+pub struct Preload {
+    // loaded or failed to, by path. an error is kept so it isn't tried again every frame
+    boards: HashMap<String, Result<Board, String>>,
+    // asked for and not done yet, textures included
+    pending: HashSet<String>,
+    // None once dropped, so the loader knows no more jobs are coming
+    jobs: Option<Sender<Job>>,
+    done: Receiver<Done>,
+    loader: Option<JoinHandle<()>>
+}
+
+impl Default for Preload {
+    fn default() -> Preload {
+        Preload::new()
+    }
+}
+
+impl Preload {
+    pub fn new() -> Preload {
+        let (jobs, queue) = channel::<Job>();
+        let (finished, done) = channel();
+        let loader = thread::spawn(move || {
+            for job in queue {
+                // nobody is left to tell once the game closed
+                let _ = finished.send(job.run());
+            }
+        });
+
+        Preload { boards: HashMap::new(), pending: HashSet::new(), jobs: Some(jobs), done, loader: Some(loader) }
+    }
+
+    // starts loading the map at `path` unless it is loaded or on its way
+    pub fn request(&mut self, path: &str) {
+        if self.boards.contains_key(path) || self.pending.contains(path) { return }
+        let Some(jobs) = &self.jobs else { return };
+        if jobs.send(Job::Map(path.to_string())).is_ok() {
+            self.pending.insert(path.to_string());
+        }
+    }
+
+    // the map at `path` or why it didn't load, None if it wasn't preloaded. taken out, the board is
+    // the game's from then on
+    pub fn take(&mut self, path: &str) -> Option<Result<Board, String>> {
+        self.pending.remove(path);
+        self.boards.remove(path)
+    }
+
+    pub fn boards(&self) -> impl Iterator<Item = &Board> {
+        self.boards.values().filter_map(|board| board.as_ref().ok())
+    }
+
+    // another map was entered, its exits lead elsewhere. what is still on its way is dropped when it arrives
+    pub fn clear(&mut self) {
+        self.boards.clear();
+        self.pending.clear();
+    }
+
+    // parses the maps read since the last call and makes textures of the images decoded for them
+    pub fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, textures: &mut TextureRegistry) {
+        let done: Vec<Done> = self.done.try_iter().collect();
+        for done in done {
+            match done {
+                Done::Map(path, files) => {
+                    if !self.pending.contains(&path) { continue }
+                    let board = files.and_then(|files| files.parse(&path, textures));
+                    let files = board.as_ref().map_or(vec![], |board| textures.unloaded_files(&board.textures(textures)));
+                    self.boards.insert(path.clone(), board);
+
+                    let queued = !files.is_empty() && self.jobs.as_ref().is_some_and(|jobs| jobs.send(Job::Images(path.clone(), files)).is_ok());
+                    if !queued {
+                        self.pending.remove(&path);
+                    }
+                },
+                Done::Images(path, images) => {
+                    if !self.pending.remove(&path) { continue }
+                    for (id, image) in images {
+                        if let Err(err) = image.and_then(|image| textures.upload(rl, thread, id, &image)) {
+                            println!("ERROR: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// what is still queued is finished before the game closes, a map read is quick
+impl Drop for Preload {
+    fn drop(&mut self) {
+        self.jobs = None;
+        if let Some(loader) = self.loader.take() {
+            let _ = loader.join();
+        }
+    }
+}
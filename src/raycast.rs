@@ -0,0 +1,600 @@
+use std::ops::{Add, Mul, Sub};
+
+use raylib::math::Vector2;
+use rayon::prelude::*;
+
+use crate::{board::{Board, Cell, Segment}, cellkind::{cell_kind, RayResponse}, game::Game, EPS};
+
+pub struct Straight {
+    pub a: f32,
+    pub b: f32,
+    pub dir: Vector2
+}
+
+impl Straight {
+    pub fn new(p1: Vector2, p2: Vector2) -> Straight {
+        let dir = p2.sub(p1);
+
+        let a = if dir.x != 0.0 { dir.y / dir.x }
+        else { 0.0 };
+
+        let b = p1.y - (p1.x * a);
+
+        Straight { a, b, dir }
+    }
+
+    pub fn f(&self, x: f32) -> f32 {
+        (x * self.a) + self.b
+    }
+
+    pub fn f1(&self, y: f32) -> f32 {
+        (y - self.b) / self.a
+    }
+}
+
+pub fn next_ray_step(current: Vector2, straight: &Straight) -> Vector2 {
+    // a vertical line has no equation in x, it only ever crosses horizontal grid lines
+    if straight.dir.x == 0.0 {
+        let y = if straight.dir.y > 0.0 { f32::ceil(current.y) }
+        else { f32::floor(current.y) };
+        return Vector2::new(current.x, y)
+    }
+
+    let x = if straight.dir.x > 0.0 { f32::ceil(current.x) }
+    else { f32::floor(current.x) };
+    let y = straight.f(x);
+
+    if straight.a != 0.0 {
+        let y2 = if straight.dir.y > 0.0 { f32::ceil(current.y) }
+        else { f32::floor(current.y) };
+        let x2 = straight.f1(y2);
+
+        if Vector2::new(x2, y2).sub(current).length_sqr() < Vector2::new(x, y).sub(current).length_sqr() {
+            return Vector2::new(x2, y2)
+        }
+    }
+
+    Vector2::new(x, y)
+}
+
+// the grid line a ray crossed to hit a cell, X for vertical lines and Y for horizontal ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    X,
+    Y
+}
+
+impl Side {
+    pub fn of(point: Vector2) -> Side {
+        if point.y == f32::floor(point.y) { Side::Y }
+        else { Side::X }
+    }
+}
+
+// how far along the face of its cell a point on a grid line is, from 0 to 1. the side says which
+// coordinate runs along the face, the point itself can't be trusted to sit exactly on the line
+pub fn wall_x(point: Vector2, side: Side) -> f32 {
+    match side {
+        Side::Y => point.x.rem_euclid(1.0),
+        Side::X => point.y.rem_euclid(1.0)
+    }
+}
+
+// a cell a ray hit. `point` and `distance` are along the ray, past any mirror it bounced off,
+// while `cell` and `wall_x`, the column of the face to sample, are on the board
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub point: Vector2,
+    pub distance: f32,
+    pub cell: (usize, usize),
+    pub side: Side,
+    pub wall_x: f32
+}
+
+// how a ray walks the grid, DDA is replacing the walk along the line equation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Traversal {
+    Straight,
+    Dda
+}
+
+// how a point of a ray that bounced off mirrors maps back onto the board. the ray keeps going
+// straight past every mirror and the board is flipped around the mirror instead, so the distance
+// to its points is how far the ray travelled and walls seen in a mirror are drawn that far away
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fold {
+    sign: Vector2,
+    offset: Vector2
+}
+
+impl Fold {
+    pub const NONE: Fold = Fold { sign: Vector2::new(1.0, 1.0), offset: Vector2::new(0.0, 0.0) };
+
+    pub fn point(self, point: Vector2) -> Vector2 {
+        Vector2::new(self.sign.x * point.x + self.offset.x, self.sign.y * point.y + self.offset.y)
+    }
+
+    pub fn dir(self, dir: Vector2) -> Vector2 {
+        Vector2::new(self.sign.x * dir.x, self.sign.y * dir.y)
+    }
+
+    // back from the board to the ray
+    fn unfold(self, point: Vector2) -> Vector2 {
+        Vector2::new((point.x - self.offset.x) * self.sign.x, (point.y - self.offset.y) * self.sign.y)
+    }
+
+    // the board cell under cell `x`, `y` of the ray
+    fn cell(self, x: i64, y: i64) -> (i64, i64) {
+        let center = self.point(Vector2::new(x as f32 + 0.5, y as f32 + 0.5));
+        (f32::floor(center.x) as i64, f32::floor(center.y) as i64)
+    }
+
+    // flipped around the grid line crossed at `point`
+    fn mirrored(self, side: Side, point: Vector2) -> Fold {
+        let mut fold = self;
+        match side {
+            Side::X => {
+                fold.offset.x += 2.0 * self.sign.x * f32::round(point.x);
+                fold.sign.x = -self.sign.x;
+            },
+            Side::Y => {
+                fold.offset.y += 2.0 * self.sign.y * f32::round(point.y);
+                fold.sign.y = -self.sign.y;
+            }
+        }
+        fold
+    }
+}
+
+// translucent cells a view ray collects by default, every one is another slice drawn in its column
+pub const DEFAULT_TRANSLUCENT_LAYERS: usize = 8;
+// past this a glass-heavy map could still make a single column cost as much as the rest of the frame
+pub const MAX_TRANSLUCENT_LAYERS: usize = 64;
+
+// at most `max_layers` translucent cells are passed through, the last one stops the ray.
+// mirrors stop it too, only the renderer follows rays into them
+pub fn cast_ray(start: Vector2, dir: Vector2, board: &Board, far_plane: f32, max_layers: usize) -> Vec<RayHit> {
+    cast_ray_with(Traversal::Straight, start, dir, board, far_plane, max_layers)
+}
+
+pub fn cast_ray_with(traversal: Traversal, start: Vector2, dir: Vector2, board: &Board, far_plane: f32, max_layers: usize) -> Vec<RayHit> {
+    walk(traversal, start, dir, board, far_plane, max_layers, 0).hits
+}
+
+fn walk(traversal: Traversal, start: Vector2, dir: Vector2, board: &Board, far_plane: f32, max_layers: usize, max_bounces: usize) -> Ray {
+    let mut ray = Ray { start, dir, hits: vec![], folds: vec![], fold: Fold::NONE, layers: 0, max_layers, bounces: 0, max_bounces, steps: 0 };
+    match traversal {
+        Traversal::Straight => walk_straight(&mut ray, board, far_plane),
+        Traversal::Dda => walk_dda(&mut ray, board, far_plane)
+    }
+
+    ray
+}
+
+struct Ray {
+    start: Vector2,
+    dir: Vector2,
+    hits: Vec<RayHit>,
+    // how each hit maps back onto the board
+    folds: Vec<Fold>,
+    fold: Fold,
+    layers: usize,
+    max_layers: usize,
+    bounces: usize,
+    max_bounces: usize,
+    // cells entered before the ray stopped
+    steps: usize
+}
+
+impl Ray {
+    fn hit(&mut self, point: Vector2, side: Side, cell: (usize, usize)) {
+        let wall_x = wall_x(self.fold.point(point), side);
+        self.hits.push(RayHit { point, distance: point.distance_to(self.start), cell, side, wall_x });
+        self.folds.push(self.fold);
+    }
+
+    // handles the ray entering cell `ray_x`, `ray_y` of its unfolded grid at `point`, returning whether it stops there
+    fn enter(&mut self, board: &Board, point: Vector2, side: Side, ray_x: i64, ray_y: i64) -> bool {
+        self.steps += 1;
+        let (x, y) = self.fold.cell(ray_x, ray_y);
+        let (x, y) = board_cell(board, x, y);
+
+        match board.at(x, y) {
+            Cell::EMPTY => false,
+            Cell::TranslucentTexture(_) => {
+                self.hit(point, side, (x, y));
+                self.layers += 1;
+                self.layers >= self.max_layers
+            },
+            Cell::Door(_) => {
+                let blocked = board.door(x, y).is_none_or(|door| door.blocks(wall_x(self.fold.point(point), side)));
+                if blocked {
+                    self.hit(point, side, (x, y));
+                }
+                blocked
+            },
+            // the ray only stops if it crosses the wall before leaving the cell
+            Cell::Segment(_, segment) => {
+                let hit = segment_hit(self.fold.point(self.start), self.fold.dir(self.dir), segment.endpoints(x, y));
+                if let Some(hit) = hit {
+                    let side = if matches!(segment, Segment::North | Segment::South) { Side::Y }
+                    else { Side::X };
+                    self.hit(self.fold.unfold(hit), side, (x, y));
+                }
+                hit.is_some()
+            },
+            // registered kinds decide for themselves, kinds that are gone are walls
+            Cell::Custom(kind, _) => match cell_kind(*kind).map_or(RayResponse::Stop, |kind| (kind.on_ray_hit)(board, (x, y))) {
+                RayResponse::Pass => false,
+                RayResponse::PassThrough => {
+                    self.hit(point, side, (x, y));
+                    self.layers += 1;
+                    self.layers >= self.max_layers
+                },
+                RayResponse::Stop => {
+                    self.hit(point, side, (x, y));
+                    true
+                }
+            },
+            // the glass is kept as a hit to tint what the ray sees past it
+            Cell::Mirror => {
+                self.hit(point, side, (x, y));
+                if self.bounces >= self.max_bounces { return true }
+
+                self.bounces += 1;
+                self.fold = self.fold.mirrored(side, point);
+                // flipped, the mirror's cell is now the one the ray came from, which is the first it sees
+                self.enter(board, point, side, ray_x, ray_y)
+            },
+            // whatever is behind a short wall still shows above it
+            _ if board.height_at(x, y) < 1.0 => {
+                self.hit(point, side, (x, y));
+                self.layers += 1;
+                self.layers >= self.max_layers
+            },
+            _ => {
+                self.hit(point, side, (x, y));
+                true
+            },
+        }
+    }
+}
+
+fn walk_straight(ray: &mut Ray, board: &Board, far_plane: f32) {
+    let (start, dir) = (ray.start, ray.dir);
+    let straight = Straight::new(start, start.add(dir));
+    let eps = Vector2::new(f32::signum(straight.dir.x) * EPS, f32::signum(straight.dir.y) * EPS);
+
+    let mut point = next_ray_step(start, &straight);
+
+    let mut dist = point.distance_to(start).powi(2);
+    let mut last_dist = dist - 1.0;
+
+    while dist < far_plane*far_plane  && dist != last_dist {
+        let x = if dir.x > 0.0 { f32::floor(point.x) }
+        else { f32::ceil(point.x) - 1.0};
+
+        let y = if dir.y > 0.0 { f32::floor(point.y + eps.y) }
+        else { f32::ceil(point.y) - 1.0 };
+
+        if ray.enter(board, point, Side::of(point), x as i64, y as i64) { break }
+
+        point = next_ray_step(point.add(eps), &straight);
+
+        last_dist = dist;
+        dist = point.distance_to(start).powi(2);
+    }
+}
+
+// steps from grid line to grid line by whichever of the two is closer along the ray
+fn walk_dda(ray: &mut Ray, board: &Board, far_plane: f32) {
+    let (start, dir) = (ray.start, ray.dir);
+    let length = dir.length();
+    if length < EPS { return }
+
+    let mut cell = (f32::floor(start.x) as i64, f32::floor(start.y) as i64);
+    let step = (if dir.x > 0.0 { 1 } else { -1 }, if dir.y > 0.0 { 1 } else { -1 });
+
+    let delta = Vector2::new(
+        if dir.x != 0.0 { 1.0 / dir.x.abs() } else { f32::INFINITY },
+        if dir.y != 0.0 { 1.0 / dir.y.abs() } else { f32::INFINITY }
+    );
+    let mut side_dist = Vector2::new(
+        if dir.x > 0.0 { (f32::floor(start.x) + 1.0 - start.x) * delta.x } else { (start.x - f32::floor(start.x)) * delta.x },
+        if dir.y > 0.0 { (f32::floor(start.y) + 1.0 - start.y) * delta.y } else { (start.y - f32::floor(start.y)) * delta.y }
+    );
+
+    loop {
+        let (t, side) = if side_dist.x < side_dist.y {
+            cell.0 += step.0;
+            side_dist.x += delta.x;
+            (side_dist.x - delta.x, Side::X)
+        } else {
+            cell.1 += step.1;
+            side_dist.y += delta.y;
+            (side_dist.y - delta.y, Side::Y)
+        };
+        if t * length >= far_plane { break }
+
+        if ray.enter(board, start.add(dir.mul(t)), side, cell.0, cell.1) { break }
+    }
+}
+
+// the cell of the board a grid cell falls on, a ray that starts or goes off the board sees its edge
+pub fn board_cell(board: &Board, x: i64, y: i64) -> (usize, usize) {
+    (x.clamp(0, board.cols as i64 - 1) as usize, y.clamp(0, board.rows as i64 - 1) as usize)
+}
+
+// where a ray from `start` crosses the segment from `a` to `b`, if it does
+fn segment_hit(start: Vector2, dir: Vector2, (a, b): (Vector2, Vector2)) -> Option<Vector2> {
+    let cross = |u: Vector2, v: Vector2| u.x * v.y - u.y * v.x;
+    let edge = b.sub(a);
+    let denom = cross(dir, edge);
+    if denom.abs() < EPS { return None }
+
+    let rel = a.sub(start);
+    let t = cross(rel, edge) / denom;
+    let s = cross(rel, dir) / denom;
+    if t <= EPS || !(0.0..=1.0).contains(&s) { return None }
+
+    Some(start.add(dir.mul(t)))
+}
+
+// a hit as the renderer draws it, with what is in the cell and how the hit maps back onto the
+// board after the mirrors the ray bounced off
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellHit {
+    pub cell: Cell,
+    pub hit: RayHit,
+    pub fold: Fold
+}
+
+impl CellHit {
+    // the first entry of every column, it hit nothing
+    const PLACEHOLDER: CellHit = CellHit {
+        cell: Cell::EMPTY,
+        hit: RayHit { point: Vector2::new(0.0, 0.0), distance: 0.0, cell: (0, 0), side: Side::X, wall_x: 0.0 },
+        fold: Fold::NONE
+    };
+
+    // where the ray hit the cell on the board, rather than along the ray
+    pub fn board_point(&self) -> Vector2 {
+        self.fold.point(self.hit.point)
+    }
+}
+
+// the cell behind each point the ray hit
+fn hitted_cells(board: &Board, ray: &Ray) -> Vec<CellHit> {
+    ray.hits.iter().zip(ray.folds.iter()).map(|(hit, fold)| {
+        let (x, y) = hit.cell;
+        CellHit { cell: *board.at(x, y), hit: *hit, fold: *fold }
+    }).collect()
+}
+
+// everything the rays of a frame depend on besides the board, so they can be cast without a game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub pos: Vector2,
+    pub dir: Vector2,
+    pub half_fov: f32,
+    pub far_plane: f32,
+    pub layers: usize,
+    pub bounces: usize
+}
+
+impl View {
+    pub fn of(game: &Game) -> View {
+        View {
+            pos: game.player.pos,
+            dir: game.player.dir,
+            half_fov: game.half_fov(),
+            far_plane: game.far_plane,
+            layers: game.translucent_layers,
+            bounces: game.mirror_bounces
+        }
+    }
+
+    // the direction of the ray seen at `screen_x`, from 0 (left edge) to 1 (right edge)
+    pub fn ray_dir(&self, screen_x: f32) -> Vector2 {
+        let start = self.dir.rotated(self.half_fov);
+        let end = self.dir.rotated(-self.half_fov);
+        start.add(end.sub(start).mul(screen_x))
+    }
+}
+
+// casts the single ray seen at `screen_x`, from 0 (left edge) to 1 (right edge)
+pub fn pick_ray(game: &Game, screen_x: f32) -> Vec<CellHit> {
+    let view = View::of(game);
+    let ray = walk(Traversal::Straight, view.pos, view.ray_dir(screen_x), &game.board, view.far_plane, view.layers, view.bounces);
+    hitted_cells(&game.board, &ray)
+}
+
+pub fn get_hitted_cells(game: &Game, rays: usize) -> Vec<Vec<CellHit>> {
+    get_hitted_cells_with(Traversal::Straight, game, rays)
+}
+
+pub fn get_hitted_cells_with(traversal: Traversal, game: &Game, rays: usize) -> Vec<Vec<CellHit>> {
+    cast_columns(traversal, View::of(game), &game.board, rays)
+}
+
+// the cells behind every column of the view, each column starting with an empty placeholder
+pub fn cast_columns(traversal: Traversal, view: View, board: &Board, rays: usize) -> Vec<Vec<CellHit>> {
+    let mut all_cells = vec![vec![CellHit::PLACEHOLDER]; rays];
+
+    // every column is independent, so rays are cast across threads and only drawing stays on the main one
+    all_cells.par_iter_mut().enumerate().for_each(|(i, cells)| {
+        let dir = view.ray_dir(i as f32 / rays as f32);
+        let ray = walk(traversal, view.pos, dir, board, view.far_plane, view.layers, view.bounces);
+        cells.extend(hitted_cells(board, &ray));
+    });
+
+    all_cells
+}
+
+// how far apart two traversals may put the same hit
+const TRAVERSAL_TOLERANCE: f32 = 1e-3;
+
+// the columns where the DDA walk doesn't see the same cells at the same points as `columns`
+pub fn traversal_mismatches(game: &Game, columns: &[Vec<CellHit>]) -> Vec<bool> {
+    let dda = get_hitted_cells_with(Traversal::Dda, game, columns.len());
+    columns.iter().zip(dda.iter()).map(|(straight, dda)| {
+        straight.len() != dda.len() || straight.iter().zip(dda.iter()).any(|(a, b)| {
+            a.hit.cell != b.hit.cell || a.hit.side != b.hit.side || a.hit.point.distance_to(b.hit.point) > TRAVERSAL_TOLERANCE
+        })
+    }).collect()
+}
+
+// how many cells the ray of every column walked through, the same columns `get_hitted_cells` casts
+pub fn traversal_steps(game: &Game, rays: usize) -> Vec<usize> {
+    let view = View::of(game);
+    (0..rays).into_par_iter().map(|i| {
+        let dir = view.ray_dir(i as f32 / rays as f32);
+        walk(Traversal::Straight, view.pos, dir, &game.board, view.far_plane, view.layers, view.bounces).steps
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::color::Color;
+
+    use super::*;
+    use crate::texture::TextureRegistry;
+
+    const TOLERANCE: f32 = 1e-4;
+
+    // walls all around an empty `cols` by `rows` board
+    fn walled(cols: usize, rows: usize) -> Board {
+        let mut board = Board::new(rows, cols);
+        for x in 0..cols {
+            board.set(x, 0, Cell::COLOR(Color::RED));
+            board.set(x, rows - 1, Cell::COLOR(Color::RED));
+        }
+        for y in 0..rows {
+            board.set(0, y, Cell::COLOR(Color::RED));
+            board.set(cols - 1, y, Cell::COLOR(Color::RED));
+        }
+        board
+    }
+
+    fn both(start: Vector2, dir: Vector2, board: &Board, max_layers: usize) -> [Vec<RayHit>; 2] {
+        [Traversal::Straight, Traversal::Dda].map(|traversal| cast_ray_with(traversal, start, dir, board, 100.0, max_layers))
+    }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_wall_across_the_cell() {
+        let board = walled(5, 5);
+        let start = Vector2::new(2.5, 2.5);
+        let cases = [
+            (Vector2::new(1.0, 0.0), (4, 2), Side::X, Vector2::new(4.0, 2.5)),
+            (Vector2::new(-1.0, 0.0), (0, 2), Side::X, Vector2::new(1.0, 2.5)),
+            (Vector2::new(0.0, 1.0), (2, 4), Side::Y, Vector2::new(2.5, 4.0)),
+            (Vector2::new(0.0, -1.0), (2, 0), Side::Y, Vector2::new(2.5, 1.0))
+        ];
+
+        for (dir, cell, side, point) in cases {
+            for hits in both(start, dir, &board, 0) {
+                assert_eq!(hits.len(), 1, "{:?}", dir);
+                let hit = hits[0];
+                assert_eq!((hit.cell, hit.side), (cell, side), "{:?}", dir);
+                assert!(hit.point.distance_to(point) < TOLERANCE, "{:?} hit at {:?}", dir, hit.point);
+                assert!((hit.distance - 1.5).abs() < TOLERANCE);
+                assert!((hit.wall_x - 0.5).abs() < TOLERANCE);
+            }
+        }
+    }
+
+    #[test]
+    fn an_oblique_hit_is_sampled_where_it_landed_on_the_face() {
+        let board = walled(5, 5);
+        for hits in both(Vector2::new(1.5, 2.5), Vector2::new(1.0, 0.25), &board, 0) {
+            let hit = hits[0];
+            assert_eq!((hit.cell, hit.side), ((4, 3), Side::X));
+            assert!((hit.wall_x - 0.125).abs() < TOLERANCE, "wall_x {}", hit.wall_x);
+        }
+    }
+
+    #[test]
+    fn a_hit_seen_in_a_mirror_is_on_the_board_and_its_cell_is_kept() {
+        let mut board = walled(6, 5);
+        board.set(4, 2, Cell::Mirror);
+        let (start, dir) = (Vector2::new(1.5, 2.2), Vector2::new(1.0, 0.1));
+
+        for traversal in [Traversal::Straight, Traversal::Dda] {
+            let ray = walk(traversal, start, dir, &board, 100.0, 0, 1);
+            assert_eq!(ray.hits.iter().map(|hit| hit.cell).collect::<Vec<_>>(), vec![(4, 2), (0, 2)]);
+
+            // past the mirror the point goes on along the ray, folded back it is on the west wall
+            let hit = ray.hits[1];
+            assert!(hit.point.distance_to(Vector2::new(7.0, 2.75)) < TOLERANCE, "hit at {:?}", hit.point);
+            assert!((hit.wall_x - 0.75).abs() < TOLERANCE, "wall_x {}", hit.wall_x);
+
+            let cells = hitted_cells(&board, &ray);
+            assert_eq!(cells.iter().map(|cell| cell.hit).collect::<Vec<_>>(), ray.hits);
+            assert_eq!(cells[1].cell, Cell::COLOR(Color::RED));
+        }
+    }
+
+    #[test]
+    fn a_ray_through_grid_corners_stops_at_the_wall_on_the_diagonal() {
+        let mut board = walled(6, 6);
+        board.set(3, 3, Cell::COLOR(Color::BLUE));
+
+        for hits in both(Vector2::new(1.5, 1.5), Vector2::new(1.0, 1.0), &board, 0) {
+            let hit = hits.last().unwrap();
+            assert_eq!(hit.cell, (3, 3));
+            assert!(hit.point.distance_to(Vector2::new(3.0, 3.0)) < TOLERANCE, "hit at {:?}", hit.point);
+            assert!((hit.distance - 1.5 * std::f32::consts::SQRT_2).abs() < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn rays_starting_off_the_board_only_see_cells_on_it() {
+        let board = walled(5, 5);
+        assert_eq!(board_cell(&board, -3, 7), (0, 4));
+        assert_eq!(board_cell(&board, 2, 3), (2, 3));
+
+        for dir in [Vector2::new(1.0, 0.0), Vector2::new(-1.0, 0.0), Vector2::new(0.3, 1.0)] {
+            for hits in both(Vector2::new(-2.5, 2.5), dir, &board, 0) {
+                assert!(!hits.is_empty(), "{:?}", dir);
+                assert!(hits.iter().all(|hit| hit.cell.0 < board.cols && hit.cell.1 < board.rows), "{:?}", dir);
+            }
+        }
+    }
+
+    #[test]
+    fn translucent_cells_stack_until_the_layer_limit() {
+        let mut textures = TextureRegistry::new();
+        let glass = textures.register("glass.png", "glass.png");
+        let mut board = walled(8, 3);
+        for x in 2..5 {
+            board.set(x, 1, Cell::TranslucentTexture(glass));
+        }
+        let (start, dir) = (Vector2::new(1.5, 1.5), Vector2::new(1.0, 0.0));
+
+        for hits in both(start, dir, &board, 2) {
+            assert_eq!(hits.iter().map(|hit| hit.cell).collect::<Vec<_>>(), vec![(2, 1), (3, 1)]);
+        }
+
+        for hits in both(start, dir, &board, 8) {
+            assert_eq!(hits.iter().map(|hit| hit.cell).collect::<Vec<_>>(), vec![(2, 1), (3, 1), (4, 1), (7, 1)]);
+            assert!(hits.windows(2).all(|pair| pair[0].distance < pair[1].distance));
+        }
+    }
+
+    #[test]
+    fn both_traversals_agree_on_a_sweep() {
+        let mut board = walled(9, 9);
+        board.set(4, 4, Cell::COLOR(Color::BLUE));
+        board.set(6, 2, Cell::COLOR(Color::BLUE));
+
+        for i in 0..64 {
+            let dir = Vector2::new(1.0, 0.0).rotated(i as f32 / 64.0 * std::f32::consts::TAU + 0.01);
+            let [straight, dda] = both(Vector2::new(2.3, 6.6), dir, &board, 0);
+            assert_eq!(straight.len(), dda.len(), "{:?}", dir);
+            for (a, b) in straight.iter().zip(dda.iter()) {
+                assert_eq!((a.cell, a.side), (b.cell, b.side), "{:?}", dir);
+                assert!(a.point.distance_to(b.point) < TRAVERSAL_TOLERANCE, "{:?}", dir);
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+
+use crate::ghost::Ghost;
+
+const RECORDS_FILE: &str = "records.txt";
+// the run of every best time, "<hash>.txt" next to the records file
+const GHOSTS_DIR: &str = "ghosts";
+
+// the best time of every map finished so far, one "<hash> <seconds> <map>" line each in the file they are
+// saved to. a time only stands for the version of the map it was set on, so an edited map starts over
+#[derive(Default)]
+pub struct Records {
+    best: BTreeMap<(String, u64), f32>,
+    path: Option<PathBuf>
+}
+
+impl Records {
+    pub fn new() -> Records {
+        Records::default()
+    }
+
+    // a missing file means nothing was finished yet
+    pub fn load(dir: &Path) -> Result<Records, String> {
+        let path = dir.join(RECORDS_FILE);
+        let mut records = Records { path: Some(path.clone()), ..Records::default() };
+        if !path.exists() {
+            return Ok(records)
+        }
+
+        let text = fs::read_to_string(&path).map_err(|err| format!("Could not load records '{}': {}", path.display(), err))?;
+        for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let mut words = line.splitn(3, ' ');
+            let (Some(hash), Some(time), Some(map)) = (words.next(), words.next(), words.next()) else {
+                return Err(format!("Expected a hash, a time and a map in '{}' of {}", line, path.display()))
+            };
+            let hash = u64::from_str_radix(hash, 16).map_err(|err| format!("Invalid hash in '{}' of {}: {}", line, path.display(), err))?;
+            let time = time.parse::<f32>().map_err(|err| format!("Invalid time in '{}' of {}: {}", line, path.display(), err))?;
+            records.best.insert((map.to_string(), hash), time);
+        }
+        Ok(records)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| format!("Could not create '{}': {}", dir.display(), err))?;
+        }
+
+        let text: String = self.best.iter().map(|((map, hash), time)| format!("{:016x} {} {}\n", hash, time, map)).collect();
+        fs::write(path, text).map_err(|err| format!("Could not save records '{}': {}", path.display(), err))
+    }
+
+    fn ghost_path(&self, hash: u64) -> Option<PathBuf> {
+        Some(self.path.as_ref()?.parent()?.join(GHOSTS_DIR).join(format!("{:016x}.txt", hash)))
+    }
+
+    // the run the best time of this version of a map was set with, None before there is one
+    pub fn ghost(&self, hash: u64) -> Option<Ghost> {
+        let path = self.ghost_path(hash).filter(|path| path.exists())?;
+        let loaded = fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|text| Ghost::from_text(&text));
+        match loaded {
+            Ok(ghost) => Some(ghost),
+            Err(err) => {
+                println!("ERROR: Could not load ghost '{}': {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn save_ghost(&self, hash: u64, ghost: &Ghost) -> Result<(), String> {
+        let Some(path) = self.ghost_path(hash) else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| format!("Could not create '{}': {}", dir.display(), err))?;
+        }
+        fs::write(&path, ghost.to_text()).map_err(|err| format!("Could not save ghost '{}': {}", path.display(), err))
+    }
+
+    pub fn best(&self, map: &str, hash: u64) -> Option<f32> {
+        self.best.get(&(map.to_string(), hash)).copied()
+    }
+
+    // keeps `time` if it beats the best one of this version of the map, saving right away, and says whether it did
+    pub fn submit(&mut self, map: &str, hash: u64, time: f32) -> bool {
+        if self.best(map, hash).is_some_and(|best| best <= time) { return false }
+
+        self.best.insert((map.to_string(), hash), time);
+        if let Err(err) = self.save() {
+            println!("ERROR: {}", err);
+        }
+        true
+    }
+}
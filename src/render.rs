@@ -0,0 +1,990 @@
+use std::{f32::consts::TAU, ops::{Add, AddAssign, Div, Mul, MulAssign, Sub}, process::exit, time::Instant};
+
+use raylib::{color::Color, drawing::RaylibDrawHandle, math::{Rectangle, Vector2}, texture::{Image, RaylibTexture2D, Texture2D}, RaylibHandle, RaylibThread};
+
+use crate::{achievement::render_toasts, backend::Backend, board::{Cell, MIRROR_COLOR}, caption::render_captions, cellkind::{cell_kind, RenderHint}, columns::{ColumnCapture, ColumnHit}, decal::Face, damage::{render_damage_indicators, render_low_health}, editor::render_editor, fog::Fog, game::Game, grade::render_color_grade, item::{render_status_bar, status_bar_height}, levels::render_level_select, minimap::{render_minimap, MinimapCache}, panel::draw_panel, particle::Particle, software::{SoftwareBackend, TextureImages}, raycast::{get_hitted_cells, traversal_mismatches, traversal_steps, CellHit, Side}, sprite::Sprite, texture::FlatId, transform::{Transform2D, Transform2DApplayer}, weapon::render_weapon, ui, EPS, NUM_OF_RAYS, window_size};
+
+// the texture is allocated once at its largest size and only the top left
+// `width` x `height` pixels are used
+pub struct FrameBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub capacity: (usize, usize),
+    pub pixels: Vec<u8>,
+    pub texture: Texture2D
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderPass {
+    Floors,
+    Walls,
+    Sprites,
+    Particles,
+    Post,
+    Hud,
+    Minimap
+}
+
+impl RenderPass {
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderPass::Floors => "floors",
+            RenderPass::Walls => "walls",
+            RenderPass::Sprites => "sprites",
+            RenderPass::Particles => "particles",
+            RenderPass::Post => "post",
+            RenderPass::Hud => "hud",
+            RenderPass::Minimap => "minimap"
+        }
+    }
+}
+
+pub const DEFAULT_PASSES: [RenderPass; 7] = [
+    RenderPass::Floors,
+    RenderPass::Walls,
+    RenderPass::Sprites,
+    RenderPass::Particles,
+    RenderPass::Post,
+    RenderPass::Hud,
+    RenderPass::Minimap
+];
+
+// what the cached columns were cast from
+#[derive(Clone, Copy, PartialEq)]
+struct View {
+    pos: Vector2,
+    dir: Vector2,
+    revision: u64,
+    rays: usize,
+    far_plane: f32,
+    pitch: f32,
+    eye: f32,
+    health: f32,
+    fog: Fog,
+    lights: u64,
+    exposure: f32,
+    fov: f32,
+    translucent_layers: usize,
+    mirror_bounces: usize,
+    // the floor and ceiling spans are drawn from these too, hot reloads and theme switches change them
+    sky: Option<FlatId>,
+    textures: u64,
+    theme: usize
+}
+
+impl View {
+    fn of(game: &Game, rays: usize, exposure: f32) -> View {
+        View {
+            pos: game.player.pos,
+            dir: game.player.dir,
+            revision: game.board.revision(),
+            rays,
+            far_plane: game.far_plane,
+            pitch: game.player.pitch,
+            eye: game.player.eye_height(),
+            health: game.player.health,
+            fog: game.fog,
+            lights: game.lights.generation(),
+            exposure,
+            fov: game.fov,
+            translucent_layers: game.translucent_layers,
+            mirror_bounces: game.mirror_bounces,
+            sky: game.sky,
+            textures: game.textures.generation(),
+            theme: game.theme
+        }
+    }
+}
+
+pub const MIN_RAYS: usize = 40;
+
+pub struct Renderer {
+    fb: FrameBuffer,
+    passes: Vec<(RenderPass, bool)>,
+    rays: usize,
+    zbuffer: Vec<f32>,
+    columns: Vec<Vec<CellHit>>,
+    // columns where the DDA traversal disagrees with the one the columns were cast with
+    mismatches: Vec<bool>,
+    // cells every column's ray walked through, only kept while the heatmap is shown
+    steps: Vec<usize>,
+    // how much every light is scaled by, adapted to the frame while auto exposure is on
+    exposure: f32,
+    view: Option<View>,
+    minimap: MinimapCache,
+    // milliseconds the last frame spent casting and in every pass
+    timings: Vec<(&'static str, f32)>,
+    // walls written into their own frame buffer, uploaded once a frame instead of drawn column by column
+    software: bool,
+    walls: FrameBuffer,
+    images: TextureImages
+}
+
+impl Renderer {
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Renderer {
+        // one ray per window column is the most the frame buffer has to hold
+        let mut fb = FrameBuffer::new(rl, thread, window_size().x as usize, window_size().y as usize);
+        fb.resize(NUM_OF_RAYS, column_height(NUM_OF_RAYS));
+        let mut walls = FrameBuffer::new(rl, thread, window_size().x as usize, window_size().y as usize);
+        walls.resize(NUM_OF_RAYS, column_height(NUM_OF_RAYS));
+
+        Renderer {
+            fb,
+            passes: DEFAULT_PASSES.iter().map(|pass| (*pass, true)).collect(),
+            rays: NUM_OF_RAYS,
+            zbuffer: vec![f32::INFINITY; NUM_OF_RAYS],
+            columns: vec![],
+            mismatches: vec![],
+            steps: vec![],
+            exposure: 1.0,
+            view: None,
+            minimap: MinimapCache::new(rl, thread),
+            timings: vec![],
+            software: false,
+            walls,
+            images: TextureImages::new()
+        }
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    pub fn timings(&self) -> &[(&'static str, f32)] {
+        &self.timings
+    }
+
+    pub fn rays(&self) -> usize {
+        self.rays
+    }
+
+    pub fn set_rays(&mut self, rays: usize) {
+        self.rays = rays.clamp(MIN_RAYS, self.fb.capacity.0);
+        self.fb.resize(self.rays, column_height(self.rays));
+        self.walls.resize(self.rays, column_height(self.rays));
+        self.zbuffer = vec![f32::INFINITY; self.rays];
+    }
+
+    // the columns of the last frame as they went into drawing the walls
+    pub fn capture_columns(&self, game: &Game) -> ColumnCapture {
+        let columns = self.columns.iter().rev().map(|cells| cells.iter().map(|cell| ColumnHit {
+            cell: format!("{:?}", cell.cell),
+            x: cell.hit.cell.0,
+            y: cell.hit.cell.1,
+            side: format!("{:?}", cell.hit.side),
+            distance: cell.hit.point.sub(game.player.pos).dot(game.player.dir),
+            u: texture_u(game, cell),
+            shade: wall_shade(game, cell, self.exposure * game.daylight.brightness())
+        }).collect()).collect();
+
+        ColumnCapture {
+            map: game.editor.path.clone(),
+            pos: (game.player.pos.x, game.player.pos.y),
+            dir: (game.player.dir.x, game.player.dir.y),
+            columns
+        }
+    }
+
+    pub fn is_software(&self) -> bool {
+        self.software
+    }
+
+    pub fn set_software(&mut self, software: bool) {
+        self.software = software;
+    }
+
+    // the frame buffers hold a ray per window column and the minimap cache a share of the window, so a bigger
+    // window gets bigger ones. a smaller one keeps them and loses the rays that no longer have a column
+    pub fn fit_window(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        let (width, height) = (window_size().x as usize, window_size().y as usize);
+        if width > self.fb.capacity.0 || height > self.fb.capacity.1 {
+            self.fb = FrameBuffer::new(rl, thread, width, height);
+            self.walls = FrameBuffer::new(rl, thread, width, height);
+            self.minimap = MinimapCache::new(rl, thread);
+        }
+        self.set_rays(self.rays.min(width));
+        // the columns are cast for the old column height, and a new frame buffer has nothing drawn in it
+        self.view = None;
+    }
+
+    pub fn passes(&self) -> &[(RenderPass, bool)] {
+        &self.passes
+    }
+
+    // passes run in the given order, any pass left out is skipped
+    pub fn set_order(&mut self, order: &[RenderPass]) {
+        self.passes = order.iter().map(|pass| (*pass, self.is_enabled(*pass))).collect();
+    }
+
+    pub fn is_enabled(&self, pass: RenderPass) -> bool {
+        self.passes.iter().any(|(p, enabled)| *p == pass && *enabled)
+    }
+
+    pub fn set_enabled(&mut self, pass: RenderPass, enabled: bool) {
+        for (p, e) in self.passes.iter_mut() {
+            if *p == pass { *e = enabled }
+        }
+    }
+
+    pub fn render(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, game: &Game) {
+        self.zbuffer.fill(f32::INFINITY);
+        self.timings.clear();
+        let start = Instant::now();
+
+        // the daylight darkens everything on top of the exposure
+        let exposure = self.exposure * game.daylight.brightness();
+        // only recast when the camera or the board changed since the last frame
+        let view = View::of(game, self.rays, exposure);
+        let recast = self.view != Some(view);
+        if recast {
+            self.columns = get_hitted_cells(game, self.rays);
+        }
+        // also when validation was just switched on or off
+        if recast || self.mismatches.is_empty() == game.validate_traversal {
+            self.mismatches = if game.validate_traversal { traversal_mismatches(game, &self.columns) }
+            else { vec![] };
+        }
+        if recast || self.steps.is_empty() == game.show_heatmap {
+            self.steps = if game.show_heatmap { traversal_steps(game, self.rays) }
+            else { vec![] };
+        }
+        self.view = Some(view);
+        self.timings.push(("cast", start.elapsed().as_secs_f32() * 1000.0));
+
+        for i in 0..self.passes.len() {
+            let (pass, enabled) = self.passes[i];
+            if !enabled { continue }
+
+            let start = Instant::now();
+            match pass {
+                RenderPass::Floors => render_floor_and_ceiling(d, game, &mut self.fb, recast, exposure),
+                RenderPass::Walls if self.software => {
+                    self.walls.clear();
+                    self.images.sync(game.textures.generation());
+                    let mut backend = SoftwareBackend::new(&mut self.walls, &mut self.images, d.get_frame_time());
+                    render_walls(&mut backend, game, &self.columns, &mut self.zbuffer, exposure);
+                    self.walls.flush();
+                    draw_frame_buffer(d, &self.walls);
+                },
+                RenderPass::Walls => render_walls(d, game, &self.columns, &mut self.zbuffer, exposure),
+                RenderPass::Sprites => {
+                    render_sprites(d, game, &self.zbuffer, exposure);
+                    render_path_markers(d, game, &self.zbuffer);
+                },
+                RenderPass::Particles => render_particles(d, game, &self.zbuffer, exposure),
+                RenderPass::Post => {
+                    self.exposure = if game.auto_exposure { adapt_exposure(self.exposure, measure_luminance(game, &self.columns), d.get_frame_time()) }
+                    else { 1.0 };
+                    render_color_grade(d, game.board.grade);
+                },
+                RenderPass::Hud => {
+                    render_heatmap(d, &self.steps);
+                    render_mismatches(d, &self.mismatches);
+                    render_stats(d, game, &self.columns);
+                    render_timer(d, game);
+                    render_sign(d, game);
+                    render_captions(d, game);
+                    render_level_stats(d, game);
+                    render_killcam(d, game);
+                    render_low_health(d, game);
+                    render_damage_indicators(d, game);
+                    render_weapon(d, game);
+                    render_status_bar(d, game);
+                    render_editor(d, game);
+                    render_level_select(d, game);
+                    render_toasts(d, game);
+                },
+                RenderPass::Minimap => render_minimap(d, thread, &game.minimap, game, &mut self.minimap, &self.columns)
+            }
+            self.timings.push((pass.name(), start.elapsed().as_secs_f32() * 1000.0));
+        }
+    }
+}
+
+impl FrameBuffer {
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread, width: usize, height: usize) -> FrameBuffer {
+        let image = Image::gen_image_color(width as i32, height as i32, Color::BLANK);
+        let texture = match rl.load_texture_from_image(thread, &image) {
+            Ok(texture) => texture,
+            Err(err) => {
+                println!("ERROR: {}", err);
+                exit(1);
+            },
+        };
+
+        FrameBuffer {
+            width, height,
+            capacity: (width, height),
+            pixels: vec![0; width * height * 4],
+            texture
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = usize::min(width, self.capacity.0);
+        self.height = usize::min(height, self.capacity.1);
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0)
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, color: Color) {
+        let i = (y * self.capacity.0 + x) * 4;
+        self.pixels[i] = color.r;
+        self.pixels[i + 1] = color.g;
+        self.pixels[i + 2] = color.b;
+        self.pixels[i + 3] = color.a;
+    }
+
+    pub fn flush(&mut self) {
+        self.texture.update_texture(&self.pixels)
+    }
+}
+
+// height of the frame for `rays` columns, keeping the window aspect ratio
+fn column_height(rays: usize) -> usize {
+    (window_size().y / (window_size().x / rays as f32)) as usize
+}
+
+// textures can only be tinted, so they fade towards black as the fog gets thicker
+fn fog_tint(fog: &Fog, dist: f32, shade: f32) -> Color {
+    shade_color(Color::WHITE, shade * (1.0 - fog.factor(dist)))
+}
+
+// how strongly the glass of a mirror tints its reflection
+const MIRROR_ALPHA: f32 = 0.2;
+// brightness of Y-side faces, so corners between two walls stay readable
+const SIDE_SHADE: f32 = 0.7;
+// how far in front of a wall its light is sampled
+const EPS_LIGHT: f32 = 0.01;
+
+// colors lose this much of their saturation with no health left
+const LOW_HEALTH_DESATURATION: f32 = 0.8;
+
+// textures can only be tinted, so only flat colors and the floors lose their color
+fn desaturate(color: Color, amount: f32) -> Color {
+    if amount <= 0.0 { return color }
+
+    let gray = 0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32;
+    let mix = |c: u8| (c as f32 + (gray - c as f32) * amount) as u8;
+    Color::new(mix(color.r), mix(color.g), mix(color.b), color.a)
+}
+
+pub fn shade_color(color: Color, factor: f32) -> Color {
+    let factor = factor.clamp(0.0, 1.0);
+    Color::new(
+        (color.r as f32 * factor) as u8,
+        (color.g as f32 * factor) as u8,
+        (color.b as f32 * factor) as u8,
+        color.a
+    )
+}
+
+fn render_floor_and_ceiling(d: &mut impl Backend, game: &Game, fb: &mut FrameBuffer, recast: bool, exposure: f32) {
+    // the frame buffer still holds the last frame, so it only needs redrawing
+    if recast {
+        cast_floor_and_ceiling(game, fb, exposure);
+    }
+    draw_frame_buffer(d, fb);
+}
+
+// stretched over the whole window
+fn draw_frame_buffer(d: &mut impl Backend, fb: &FrameBuffer) {
+    let source_rec = Rectangle::new(0.0, 0.0, fb.width as f32, fb.height as f32);
+    let dest_rec = Rectangle::new(0.0, 0.0, window_size().x, window_size().y);
+    d.draw_texture_pro(&fb.texture, source_rec, dest_rec, Vector2::zero(), 0.0, Color::WHITE);
+}
+
+// how far the horizon sits below the middle of a view `height` tall
+fn pitch_offset(game: &Game, height: f32) -> f32 {
+    game.player.pitch * height
+}
+
+fn cast_floor_and_ceiling(game: &Game, fb: &mut FrameBuffer, exposure: f32) {
+    let half_fov = game.half_fov();
+    let left = game.player.dir.rotated(-half_fov);
+    let right = game.player.dir.rotated(half_fov);
+    let perp = f32::cos(half_fov);
+    let focal = game.focal_length();
+
+    let width = fb.width as f32;
+    let height = fb.height as i64;
+    // looking up or down moves the horizon, the floor and ceiling rows are counted away from it
+    let offset = pitch_offset(game, fb.height as f32) as i64;
+    let horizon = height / 2 + offset;
+    let mirror = height - 1 - height / 2 + offset;
+    let eye = game.player.eye_height();
+
+    let cast_row = |fb: &mut FrameBuffer, y: usize, dist: f32, ceiling: bool| {
+        if dist > game.far_plane { return }
+
+        let mut point = game.player.pos.add(left.mul(dist / perp));
+        let step = right.sub(left).mul(dist / perp).div(width);
+
+        // walk the row in spans that stay inside one cell, so the board is only queried once per span
+        let mut x = 0;
+        while x < fb.width {
+            let run = span_length(point, step, fb.width - x);
+            let inside = point.x >= 0.0 && point.y >= 0.0 && point.x < game.board.cols as f32 && point.y < game.board.rows as f32;
+            let (cx, cy) = (point.x as usize, point.y as usize);
+            let (flat, color) = if !inside { (None, None) }
+            else if ceiling { (game.board.ceiling_at(cx, cy), game.board.ceiling_color_at(cx, cy)) }
+            else { (game.board.floor_at(cx, cy), game.board.floor_color_at(cx, cy)) };
+
+            let light = game.lights.value(cx, cy) * exposure;
+            let desaturation = game.player.low_health() * LOW_HEALTH_DESATURATION;
+            // a solid color is the same all along the span
+            if let Some(color) = color {
+                let color = desaturate(game.fog.apply(shade_color(color, light), dist), desaturation);
+                for _ in 0..run {
+                    fb.put(x, y, color);
+                    x += 1;
+                }
+                point.add_assign(step.mul(run as f32));
+                continue
+            }
+
+            let Some(flat) = flat.map(|id| game.textures.flat(id)) else {
+                point.add_assign(step.mul(run as f32));
+                x += run;
+                continue
+            };
+
+            for _ in 0..run {
+                let u = point.x - cx as f32;
+                let v = point.y - cy as f32;
+                fb.put(x, y, desaturate(game.fog.apply(shade_color(flat.sample(u, v), light), dist), desaturation));
+
+                point.add_assign(step);
+                x += 1;
+            }
+        }
+    };
+
+    // the sky wraps once around the player, each column showing the part in its direction. it sits
+    // behind the ceiling, filling in wherever there is none, and the horizon takes it along when looking up
+    let sky = game.sky.map(|id| game.textures.flat(id));
+    let sky_u: Vec<f32> = if sky.is_none() { vec![] }
+    else { (0..fb.width).map(|x| {
+        let dir = left.add(right.sub(left).mul((x as f32 + 0.5) / width));
+        (f32::atan2(dir.y, dir.x) / TAU).rem_euclid(1.0)
+    }).collect() };
+    let sky_row = |fb: &mut FrameBuffer, y: usize| {
+        let Some(sky) = sky else { return };
+        let v = (1.0 + (y as i64 - horizon) as f32 / height as f32).clamp(0.0, 1.0);
+        for (x, u) in sky_u.iter().enumerate() {
+            fb.put(x, y, shade_color(sky.sample(*u, v), exposure));
+        }
+    };
+
+    fb.clear();
+    for p in 1.. {
+        let floor_y = horizon + p;
+        let ceiling_y = mirror - p;
+        if floor_y >= height && ceiling_y < 0 { break }
+
+        // perpendicular distance of the floor or ceiling seen by this row, from how far below or above the eye it is
+        let dist = width * focal / (2.0 * p as f32);
+        if (0..height).contains(&floor_y) {
+            cast_row(fb, floor_y as usize, dist * eye, false);
+        }
+        if (0..height).contains(&ceiling_y) {
+            sky_row(fb, ceiling_y as usize);
+            cast_row(fb, ceiling_y as usize, dist * (1.0 - eye), true);
+        }
+    }
+
+    fb.flush();
+}
+
+// number of pixels, up to `max`, before stepping from `point` leaves its cell
+fn span_length(point: Vector2, step: Vector2, max: usize) -> usize {
+    let axis = |p: f32, s: f32| {
+        if s > EPS { (f32::floor(p) + 1.0 - p) / s }
+        else if s < -EPS { (p - f32::floor(p)) / -s }
+        else { f32::INFINITY }
+    };
+
+    let run = f32::ceil(f32::min(axis(point.x, step.x), axis(point.y, step.y)));
+    if run.is_finite() { (run as usize).clamp(1, max) }
+    else { max }
+}
+
+fn game_transform(rays: usize) -> Transform2D {
+    let mut gt = Transform2D::default();
+    gt.zoom.mul_assign(window_size().x / rays as f32);
+    gt
+}
+
+// how far across its texture a hit lands, from 0 to 1
+fn texture_u(game: &Game, cell: &CellHit) -> f32 {
+    match cell.cell {
+        // a texture spanning several cells is placed by the position along the wall on the board
+        Cell::TEXTURE(id) | Cell::TranslucentTexture(id) => {
+            let along = if cell.hit.side == Side::Y { cell.hit.cell.0 }
+            else { cell.hit.cell.1 };
+            ((along as f32 + cell.hit.wall_x) / game.board.span_of(id) as f32).rem_euclid(1.0)
+        },
+        Cell::Segment(_, segment) => segment.u(cell.board_point()),
+        // the door slides along its face, so shift the slice by how far it is open
+        Cell::Door(_) => {
+            let open = game.board.door(cell.hit.cell.0, cell.hit.cell.1).map_or(0.0, |door| door.open);
+            (cell.hit.wall_x - open).clamp(0.0, 1.0)
+        },
+        _ => cell.hit.wall_x
+    }
+}
+
+// the shade a wall is drawn with before the fog
+fn wall_shade(game: &Game, cell: &CellHit, exposure: f32) -> f32 {
+    let light = wall_light(game, cell) * exposure;
+    if cell.hit.side == Side::Y { SIDE_SHADE * light }
+    else { light }
+}
+
+// walls are lit by the cell the ray reached them from
+fn wall_light(game: &Game, cell: &CellHit) -> f32 {
+    game.lights.at(cell.fold.point(cell.hit.point.add(game.player.pos.sub(cell.hit.point).normalized().mul(EPS_LIGHT))))
+}
+
+// average brightness the walls reach the eye with, what auto exposure aims to keep at `TARGET_LUMINANCE`.
+// only the lighting and the fog count, so a dark texture isn't mistaken for a dark room
+fn measure_luminance(game: &Game, columns: &[Vec<CellHit>]) -> f32 {
+    if columns.is_empty() { return TARGET_LUMINANCE }
+
+    let total: f32 = columns.iter().map(|cells| match cells.iter().skip(1).last() {
+        Some(cell) => {
+            let dist = cell.hit.point.sub(game.player.pos).dot(game.player.dir);
+            let side = if cell.hit.side == Side::Y { SIDE_SHADE } else { 1.0 };
+            wall_light(game, cell) * side * (1.0 - game.fog.factor(dist))
+        },
+        // nothing was hit, so the column only shows fog
+        None => 0.0
+    }).sum();
+
+    total / columns.len() as f32
+}
+
+const TARGET_LUMINANCE: f32 = 0.5;
+// exposure only ever brightens, maps that are already lit well enough are left alone
+const MAX_EXPOSURE: f32 = 4.0;
+// seconds to get most of the way to a new exposure
+const EXPOSURE_ADAPT_TIME: f32 = 1.0;
+// close enough to stop adapting, so the floors aren't recast every frame
+const EXPOSURE_SNAP: f32 = 0.01;
+
+fn adapt_exposure(exposure: f32, luminance: f32, dt: f32) -> f32 {
+    let wanted = (TARGET_LUMINANCE / f32::max(luminance, EPS)).clamp(1.0, MAX_EXPOSURE);
+    if (wanted - exposure).abs() < EXPOSURE_SNAP { return exposure }
+
+    exposure + (wanted - exposure) * (1.0 - f32::exp(-dt / EXPOSURE_ADAPT_TIME))
+}
+
+fn render_walls(d: &mut impl Backend, game: &Game, hitted_cells: &[Vec<CellHit>], zbuffer: &mut [f32], exposure: f32) {
+    let gt = game_transform(hitted_cells.len());
+    let window_size = window_size().div(gt.zoom);
+    // walls stand on the floor, which is `eye` below the horizon for a wall one unit away
+    let horizon = window_size.y / 2.0 + pitch_offset(game, window_size.y);
+    let eye = game.player.eye_height();
+
+    for (x, cells) in hitted_cells.iter().rev().enumerate() {
+        // draw back to front so translucent slices blend over whatever is behind them
+        let mut cells: Vec<(f32, &CellHit)> = cells.iter().map(|cell| (cell.hit.point.sub(game.player.pos).dot(game.player.dir), cell)).collect();
+        cells.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (i, (dist, cell)) in cells.into_iter().enumerate() {
+            let height = game.board.height_at(cell.hit.cell.0, cell.hit.cell.1);
+            // registered kinds say for themselves how they are drawn
+            let hint = match cell.cell {
+                Cell::Custom(kind, _) => cell_kind(kind).map_or(Some(RenderHint::Solid), |kind| Some((kind.render_hint)(&game.board, cell.hit.cell))),
+                _ => None
+            };
+            if hint == Some(RenderHint::Hidden) { continue }
+
+            // sprites aren't reflected, the glass hides the ones behind it
+            let solid = matches!(cell.cell, Cell::COLOR(_) | Cell::TEXTURE(_) | Cell::Door(_) | Cell::AnimatedTexture(_) | Cell::Segment(..) | Cell::Mirror) || hint == Some(RenderHint::Solid);
+            if height >= 1.0 && solid {
+                zbuffer[x] = f32::min(zbuffer[x], dist);
+            }
+
+            // every wall stands on the floor, so only its top moves with the height
+            let full_h = (window_size.y / dist) / (2.0 * window_size.y / window_size.x) * game.focal_length();
+            let h = full_h * height;
+            let pos = Vector2::new(x as f32, horizon + full_h * eye - h);
+            let side_shade = wall_shade(game, cell, exposure);
+
+            match &cell.cell {
+                Cell::EMPTY => {},
+                // the reflection is already drawn behind the glass, which only tints it.
+                // with nothing behind it, out of bounces, the glass fills the column
+                Cell::Mirror => {
+                    let alpha = if i == 0 { 1.0 }
+                    else { MIRROR_ALPHA };
+                    let color = game.fog.apply(shade_color(MIRROR_COLOR, side_shade), dist).alpha(alpha);
+                    d.draw_rectangle_v(pos.apply(&gt), Vector2::new(1.0, h).apply_zoom(&gt), color);
+                },
+                Cell::COLOR(color) => {
+                    let color = desaturate(game.fog.apply(shade_color(*color, side_shade), dist), game.player.low_health() * LOW_HEALTH_DESATURATION);
+                    d.draw_rectangle_v(pos.apply(&gt), Vector2::new(1.0, h).apply_zoom(&gt), color);
+                },
+                Cell::TranslucentTexture(_) | Cell::TEXTURE(_) | Cell::AnimatedTexture(_) | Cell::Custom(..) => {
+                    let (texture, region) = match cell.cell {
+                        Cell::AnimatedTexture(id) => game.textures.animation_region(id, game.time),
+                        Cell::TranslucentTexture(id) | Cell::TEXTURE(id) | Cell::Custom(_, id) => game.textures.region(game.board.texture_at(id, cell.hit.cell.0, cell.hit.cell.1)),
+                        _ => continue
+                    };
+                    let mut tx = f32::min(texture_u(game, cell) * region.width, region.width - 1.0);
+
+                    // short walls show the bottom of their texture, tall ones stretch it
+                    let th = region.height * f32::min(height, 1.0);
+                    let tw = 1.0;
+                    let mut ty = region.height - th;
+
+                    // scrolling cells move their texture by a share of its size every second, wrapping around
+                    let scroll = game.board.scroll_at(cell.hit.cell.0, cell.hit.cell.1);
+                    tx = (tx + scroll.x * game.time * region.width).rem_euclid(region.width);
+                    ty = (ty - scroll.y * game.time * region.height).rem_euclid(region.height);
+
+                    let color = fog_tint(&game.fog, dist, side_shade);
+
+                    // a column that runs past the bottom of the texture continues from its top
+                    let first = f32::min(th, region.height - ty);
+                    let first_h = h * first / th;
+                    let source_rec = Rectangle::new(region.x + tx, region.y + ty, tw, first);
+                    let dest_rec = Rectangle::new(pos.x, pos.y, 1.0, first_h).apply(&gt);
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+
+                    if first < th {
+                        let source_rec = Rectangle::new(region.x + tx, region.y, tw, th - first);
+                        let dest_rec = Rectangle::new(pos.x, pos.y + first_h, 1.0, h - first_h).apply(&gt);
+                        d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+                    }
+                },
+                Cell::Segment(id, _) => {
+                    let (texture, region) = game.textures.region(*id);
+                    let tx = f32::min(texture_u(game, cell) * region.width, region.width - 1.0);
+                    let th = region.height * f32::min(height, 1.0);
+
+                    let color = fog_tint(&game.fog, dist, side_shade);
+
+                    let source_rec = Rectangle::new(region.x + tx, region.y + region.height - th, 1.0, th);
+                    let dest_rec = Rectangle::new(pos.x, pos.y, 1.0, h).apply(&gt);
+
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+                },
+                Cell::Door(id) => {
+                    let (texture, region) = game.textures.region(*id);
+                    let tx = f32::min(texture_u(game, cell) * region.width, region.width - 1.0);
+
+                    let color = fog_tint(&game.fog, dist, side_shade);
+
+                    let source_rec = Rectangle::new(region.x + tx, region.y, 1.0, region.height);
+                    let dest_rec = Rectangle::new(pos.x, pos.y, 1.0, h).apply(&gt);
+
+                    d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+                }
+            }
+
+            if matches!(cell.cell, Cell::COLOR(_) | Cell::TEXTURE(_) | Cell::AnimatedTexture(_)) {
+                let floor = Vector2::new(x as f32, horizon + full_h * eye);
+                render_decals(d, game, cell, floor, full_h, fog_tint(&game.fog, dist, side_shade), &gt);
+            }
+        }
+    }
+}
+
+// the slice of every decal on the face a column hit, over the wall already drawn there.
+// `floor` is where the wall meets the floor and `full_h` how tall a standard wall is there
+fn render_decals(d: &mut impl Backend, game: &Game, cell: &CellHit, floor: Vector2, full_h: f32, color: Color, gt: &Transform2D) {
+    let point = cell.board_point();
+    let face = Face::of(cell.hit.cell, point, cell.hit.side);
+    let along = cell.hit.wall_x;
+    let height = game.board.height_at(cell.hit.cell.0, cell.hit.cell.1);
+
+    let decals = game.board.decals.iter().chain(game.bullet_holes.iter()).filter(|decal| decal.cell == cell.hit.cell && decal.face == face);
+    for decal in decals {
+        let Some(u) = decal.column(along) else { continue };
+        let (texture, region) = game.textures.region(decal.texture);
+        let size = decal.size * region.height / region.width;
+
+        // a decal sticking out over the top of a short wall is cut off there
+        let bottom = decal.height - size / 2.0;
+        let top = f32::min(decal.height + size / 2.0, height);
+        if top <= bottom { continue }
+        let cut = (decal.height + size / 2.0 - top) / size * region.height;
+
+        let tx = f32::min(u * region.width, region.width - 1.0);
+        let source_rec = Rectangle::new(region.x + tx, region.y + cut, 1.0, region.height - cut);
+        let dest_rec = Rectangle::new(floor.x, floor.y - top * full_h, 1.0, (top - bottom) * full_h).apply(gt);
+        d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+    }
+}
+
+// depth and screen column of a point on the board, None when it is behind the camera
+fn project(game: &Game, rays: usize, pos: Vector2) -> Option<(f32, f32)> {
+    let tan_fov = f32::tan(game.half_fov());
+    let perp = game.player.dir.rotated(std::f32::consts::FRAC_PI_2);
+
+    let rel = pos.sub(game.player.pos);
+    let depth = rel.dot(game.player.dir);
+    if depth <= EPS { return None }
+
+    Some((depth, (rays as f32 / 2.0) * (1.0 + rel.dot(perp) / (depth * tan_fov))))
+}
+
+fn render_sprites(d: &mut impl Backend, game: &Game, zbuffer: &[f32], exposure: f32) {
+    let rays = zbuffer.len();
+    let gt = &game_transform(rays);
+    let window_size = window_size().div(gt.zoom);
+    let horizon = window_size.y / 2.0 + pitch_offset(game, window_size.y);
+
+    // project every sprite into camera space and draw from far to near
+    let sprites = game.sprites.iter()
+        .chain(game.enemies.iter().map(|enemy| &enemy.sprite))
+        .chain(game.pickups.iter().map(|pickup| &pickup.sprite))
+        .chain(game.remote_players.iter())
+        .chain(game.ghost_sprite.iter());
+    let mut visible: Vec<(f32, f32, &Sprite)> = sprites.filter_map(|sprite| {
+        project(game, rays, sprite.pos).map(|(depth, screen_x)| (depth, screen_x, sprite))
+    }).collect();
+    visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (depth, screen_x, sprite) in visible {
+        let (texture, region) = game.textures.region(sprite.texture);
+        let wall_h = window_size.x * game.focal_length() / (2.0 * depth);
+        let h = wall_h * sprite.scale;
+        let w = h * region.width / region.height;
+        let top = horizon + wall_h * game.player.eye_height() - h;
+
+        let x0 = screen_x - w / 2.0;
+        let first = f32::max(f32::floor(x0), 0.0) as usize;
+        let last = f32::min(f32::ceil(x0 + w), rays as f32) as usize;
+
+        let color = fog_tint(&game.fog, depth, game.lights.at(sprite.pos) * exposure).alpha(sprite.alpha);
+        let tw = region.width;
+        let th = region.height;
+
+        for (x, z) in zbuffer.iter().enumerate().take(last).skip(first) {
+            if depth >= *z { continue }
+
+            let u = (x as f32 - x0) / w;
+            let source_rec = Rectangle::new(region.x + u * tw, region.y, tw / w, th);
+            let dest_rec = Rectangle::new(x as f32, top, 1.0, h).apply(gt);
+            d.draw_texture_pro(texture, source_rec, dest_rec, Vector2::zero(), 0.0, color);
+        }
+    }
+}
+
+// colors every column from blue to red by how many cells its ray walked through, relative to the busiest one
+fn render_heatmap(d: &mut impl Backend, steps: &[usize]) {
+    let Some(max) = steps.iter().max() else { return };
+
+    let gt = game_transform(steps.len());
+    let height = window_size().y / gt.zoom.y;
+    for (x, count) in steps.iter().rev().enumerate() {
+        let heat = *count as f32 / f32::max(*max as f32, 1.0);
+        let color = Color::color_from_hsv(240.0 * (1.0 - heat), 1.0, 1.0);
+        d.draw_rectangle_rec(Rectangle::new(x as f32, 0.0, 1.0, height).apply(&gt), color.alpha(0.6));
+    }
+
+    let total: usize = steps.iter().sum();
+    d.draw_text(&format!("traversal steps: max {}, avg {:.1}", max, total as f32 / steps.len() as f32), 10, window_size().y as i32 / 2 + 25, 20, Color::WHITE);
+}
+
+// marks the columns the two traversals disagree on, drawn in the same order as the walls
+fn render_mismatches(d: &mut impl Backend, mismatches: &[bool]) {
+    if mismatches.is_empty() { return }
+
+    let gt = game_transform(mismatches.len());
+    let height = window_size().y / gt.zoom.y;
+    for (x, mismatch) in mismatches.iter().rev().enumerate() {
+        if !mismatch { continue }
+        d.draw_rectangle_rec(Rectangle::new(x as f32, 0.0, 1.0, height).apply(&gt), Color::RED.alpha(0.4));
+    }
+
+    let count = mismatches.iter().filter(|mismatch| **mismatch).count();
+    d.draw_text(&format!("traversal mismatches: {}/{}", count, mismatches.len()), 10, window_size().y as i32 / 2, 20, Color::RED);
+}
+
+// frame timing and what the rays saw, for comparing renderer changes
+fn render_stats(d: &mut impl Backend, game: &Game, columns: &[Vec<CellHit>]) {
+    if !game.show_stats { return }
+
+    let player = &game.player;
+    let inside = player.pos.x >= 0.0 && player.pos.y >= 0.0 && player.pos.x < game.board.cols as f32 && player.pos.y < game.board.rows as f32;
+    let cell = if inside { format!("{},{} {:?}", player.pos.x as usize, player.pos.y as usize, game.board.at(player.pos.x as usize, player.pos.y as usize)) }
+    else { "outside".to_string() };
+    // every column starts with a placeholder entry
+    let hits: usize = columns.iter().map(|cells| cells.len().saturating_sub(1)).sum();
+
+    let lines = [
+        format!("frame: {:.2} ms", d.get_frame_time() * 1000.0),
+        format!("pos: {:.2}, {:.2}", player.pos.x, player.pos.y),
+        format!("dir: {:.2}, {:.2}", player.dir.x, player.dir.y),
+        format!("rays: {}", columns.len()),
+        format!("cells hit: {}", hits),
+        format!("cell: {}", cell)
+    ];
+
+    let (size, margin) = (ui(20), ui(10));
+    // kept above the status bar
+    let top = window_size().y as i32 - status_bar_height() - (lines.len() as i32 + 1) * size - margin;
+    d.draw_rectangle(0, top - margin / 2, ui(320), (lines.len() as i32 + 1) * size + margin * 3 / 2, Color::BLACK.alpha(0.5));
+    d.draw_fps(margin, top);
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(line, margin, top + (i as i32 + 1) * size, size, Color::WHITE);
+    }
+}
+
+// game time in the level, so it stops while paused and follows the timescale
+fn render_timer(d: &mut impl Backend, game: &Game) {
+    if !game.show_timer { return }
+
+    let minutes = (game.level_time / 60.0) as u32;
+    let seconds = game.level_time % 60.0;
+    let text = format!("{:02}:{:05.2}", minutes, seconds);
+    let size = ui(30);
+    let width = d.measure_text(&text, size);
+    d.draw_text(&text, (window_size().x as i32 - width) / 2, ui(10), size, Color::WHITE);
+}
+
+// a sign's text in a box near the bottom of the screen
+fn render_sign(d: &mut impl Backend, game: &Game) {
+    let Some((text, _)) = &game.sign else { return };
+
+    let (size, margin) = (ui(24), ui(10));
+    let width = d.measure_text(text, size);
+    let (x, y) = ((window_size().x as i32 - width) / 2, window_size().y as i32 * 3 / 4);
+    draw_panel(d, game.panel.as_deref(), Rectangle::new((x - margin) as f32, (y - margin) as f32, (width + 2 * margin) as f32, (size + 2 * margin) as f32), Color::BLACK.alpha(0.7));
+    d.draw_text(text, x, y, size, Color::WHITE);
+}
+
+// a banner over the replay, and the respawn prompt once it is over
+fn render_killcam(d: &mut impl Backend, game: &Game) {
+    let Some(killcam) = &game.killcam else { return };
+
+    let (size, margin) = (ui(30), ui(10));
+    let title = "YOU DIED";
+    d.draw_text(title, (window_size().x as i32 - d.measure_text(title, size)) / 2, margin, size, Color::RED);
+
+    let text = if killcam.is_done() { "Press use to respawn" }
+    else { "KILL CAM" };
+    let width = d.measure_text(text, size);
+    let (x, y) = ((window_size().x as i32 - width) / 2, window_size().y as i32 * 3 / 4);
+    draw_panel(d, game.panel.as_deref(), Rectangle::new((x - margin) as f32, (y - margin) as f32, (width + 2 * margin) as f32, (size + 2 * margin) as f32), Color::BLACK.alpha(0.7));
+    d.draw_text(text, x, y, size, Color::WHITE);
+}
+
+// what was found of the finished map, in a panel over the frozen view
+fn render_level_stats(d: &mut impl Backend, game: &Game) {
+    let Some(stats) = &game.level_stats else { return };
+
+    let time = |time: f32| format!("{:02}:{:05.2}", (time / 60.0) as u32, time % 60.0);
+    let best = match game.best_time {
+        Some((_, true)) => "Best: New record!".to_string(),
+        Some((best, false)) => format!("Best: {}", time(best)),
+        None => "Best: --:--.--".to_string()
+    };
+    let lines = [
+        format!("Time: {}", time(stats.time)),
+        best,
+        format!("Kills: {} / {}", stats.kills, stats.enemies),
+        format!("Items: {} / {}", stats.items, stats.pickups),
+        format!("Secrets: {} / {}", stats.secrets, stats.total_secrets),
+        "Press use to continue".to_string()
+    ];
+
+    let (size, margin) = (ui(30), ui(10));
+    let height = (lines.len() as i32 + 2) * (size + margin);
+    let (width, top) = (i32::min(ui(window_size().x as i32 / 2), window_size().x as i32), (window_size().y as i32 - height) / 2);
+    draw_panel(d, game.panel.as_deref(), Rectangle::new(((window_size().x as i32 - width) / 2) as f32, top as f32, width as f32, height as f32), Color::BLACK.alpha(0.8));
+
+    let title = "LEVEL COMPLETE";
+    d.draw_text(title, (window_size().x as i32 - d.measure_text(title, size)) / 2, top + margin, size, Color::GOLD);
+    for (i, line) in lines.iter().enumerate() {
+        let color = if i + 1 == lines.len() { Color::LIGHTGRAY }
+        else { Color::WHITE };
+        d.draw_text(line, (window_size().x as i32 - d.measure_text(line, size)) / 2, top + margin + (i as i32 + 1) * (size + margin), size, color);
+    }
+}
+
+// every particle is a square the size of its share of a wall, hidden column by column behind closer walls
+fn render_particles(d: &mut impl Backend, game: &Game, zbuffer: &[f32], exposure: f32) {
+    let rays = zbuffer.len();
+    let gt = &game_transform(rays);
+    let window_size = window_size().div(gt.zoom);
+    let horizon = window_size.y / 2.0 + pitch_offset(game, window_size.y);
+
+    let mut visible: Vec<(f32, f32, &Particle)> = game.particles.particles.iter().filter_map(|particle| {
+        project(game, rays, particle.pos).map(|(depth, screen_x)| (depth, screen_x, particle))
+    }).collect();
+    visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (depth, screen_x, particle) in visible {
+        let wall_h = window_size.x * game.focal_length() / (2.0 * depth);
+        // at least a pixel of the screen, so far away ones don't flicker in and out
+        let size = f32::max(wall_h * particle.size, gt.zoom.y.recip());
+        let y = horizon + wall_h * (game.player.eye_height() - particle.height) - size / 2.0;
+
+        let light = if particle.glow { 1.0 }
+        else { game.lights.at(particle.pos) * exposure };
+        let color = game.fog.apply(shade_color(particle.color, light), depth).alpha(particle.alpha());
+
+        let x0 = screen_x - size / 2.0;
+        let first = f32::max(f32::floor(x0), 0.0) as usize;
+        let last = f32::min(f32::ceil(x0 + size), rays as f32) as usize;
+        for (x, z) in zbuffer.iter().enumerate().take(last).skip(first) {
+            if depth >= *z { continue }
+
+            let dest_rec = Rectangle::new(x as f32, y, 1.0, size).apply(gt);
+            d.draw_rectangle_rec(dest_rec, color);
+        }
+    }
+}
+
+// a marker on the floor under every cell of every enemy path
+fn render_path_markers(d: &mut impl Backend, game: &Game, zbuffer: &[f32]) {
+    if !game.debug_paths { return }
+
+    let rays = zbuffer.len();
+    let gt = &game_transform(rays);
+    let window_size = window_size().div(gt.zoom);
+    let horizon = window_size.y / 2.0 + pitch_offset(game, window_size.y);
+
+    for enemy in game.enemies.iter() {
+        for (i, (x, y)) in enemy.path.iter().enumerate() {
+            let Some((depth, screen_x)) = project(game, rays, Vector2::new(*x as f32 + 0.5, *y as f32 + 0.5)) else { continue };
+            let column = screen_x as usize;
+            if screen_x < 0.0 || column >= rays || depth >= zbuffer[column] { continue }
+
+            let floor = horizon + window_size.x * game.focal_length() / (2.0 * depth) * game.player.eye_height();
+            let size = window_size.x / (8.0 * depth);
+            let color = if i + 1 == enemy.path.len() { Color::RED }
+            else { Color::ORANGE };
+
+            let rec = Rectangle::new(screen_x - size / 2.0, floor - size / 4.0, size, size / 2.0).apply(gt);
+            d.draw_rectangle_rec(rec, color.alpha(0.7));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Board, player::Player, texture::{FlatTexture, TextureRegistry}};
+
+    #[test]
+    fn switching_the_theme_or_the_sky_recasts_the_view() {
+        let mut game = Game::new(Board::new(3, 3), TextureRegistry::new(), Player::new(1.5, 1.5));
+        let view = View::of(&game, MIN_RAYS, 1.0);
+        assert!(View::of(&game, MIN_RAYS, 1.0) == view);
+
+        game.theme += 1;
+        assert!(View::of(&game, MIN_RAYS, 1.0) != view);
+        let view = View::of(&game, MIN_RAYS, 1.0);
+
+        game.sky = Some(game.textures.add_flat(FlatTexture { width: 1, height: 1, pixels: vec![Color::SKYBLUE] }));
+        assert!(View::of(&game, MIN_RAYS, 1.0) != view);
+    }
+}
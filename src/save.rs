@@ -0,0 +1,758 @@
+use std::{collections::BTreeMap, fs};
+
+use raylib::{color::Color, math::Vector2};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{board::{Board, Cell, Segment, TextureVariants}, cellkind::{cell_kind, cell_kind_by_name}, decal::{Decal, Face}, door::DoorState, game::Game, grade::ColorGrade, item::Key, player::Player, script::{Compare, Value as ScriptValue}, spawn::{Spawn, SpawnKind}, sprite::Sprite, teleporter::Teleporter, texture::{TextureId, TextureRegistry}, trigger::{Condition, Requirement, Trigger}, world::LevelState};
+
+// bumped whenever the save format changes, together with a step in `migrate`
+// that upgrades saves from the version before
+pub const SAVE_VERSION: u32 = 11;
+
+// textures loaded from a file are saved by path, generated ones can only be saved by id
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TextureRef {
+    Path(String),
+    Id(usize)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CellData {
+    Empty,
+    Color([u8; 4]),
+    Texture(TextureRef),
+    Translucent(TextureRef),
+    Door(TextureRef),
+    Animated(usize),
+    Segment(TextureRef, String),
+    Mirror,
+    // by the name of its kind, ids depend on the order kinds were registered in
+    Custom(String, TextureRef)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DoorData {
+    pub x: usize,
+    pub y: usize,
+    pub open: f32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TeleporterData {
+    pub pos: [usize; 2],
+    pub target: [usize; 2],
+    pub turn: f32
+}
+
+// an exit without a target goes on to the next map
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExitData {
+    pub pos: [usize; 2],
+    pub target: Option<String>
+}
+
+// switches keep whether they were flipped
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TriggerData {
+    Switch { pos: [usize; 2], targets: Vec<[usize; 2]>, on: bool },
+    Sign { pos: [usize; 2], text: String }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LockData {
+    pub pos: [usize; 2],
+    pub key: String
+}
+
+// keys, values and comparisons by name
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ConditionData {
+    HasKey(String),
+    Finished(String),
+    Compare(String, String, f32)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RequirementData {
+    pub pos: [usize; 2],
+    pub condition: ConditionData,
+    pub message: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GateData {
+    pub pos: [usize; 2],
+    pub level: String
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScrollData {
+    pub pos: [usize; 2],
+    pub speed: [f32; 2]
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DecalData {
+    pub texture: TextureRef,
+    pub cell: [usize; 2],
+    pub face: String,
+    pub u: f32,
+    pub height: f32,
+    pub size: f32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GradeData {
+    pub tint: [u8; 4],
+    pub strength: f32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VariantsData {
+    pub base: TextureRef,
+    pub others: Vec<TextureRef>,
+    pub seed: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpawnData {
+    pub kind: String,
+    pub pos: [f32; 2],
+    pub properties: Vec<(String, f32)>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BoardData {
+    pub rows: usize,
+    pub cols: usize,
+    pub cells: Vec<CellData>,
+    pub floors: Vec<Option<usize>>,
+    pub ceilings: Vec<Option<usize>>,
+    pub heights: Vec<f32>,
+    pub floor_colors: Vec<Option<[u8; 4]>>,
+    pub ceiling_colors: Vec<Option<[u8; 4]>>,
+    pub doors: Vec<DoorData>,
+    pub teleporters: Vec<TeleporterData>,
+    pub exits: Vec<ExitData>,
+    pub triggers: Vec<TriggerData>,
+    pub locks: Vec<LockData>,
+    pub secrets: Vec<[usize; 2]>,
+    pub requirements: Vec<RequirementData>,
+    pub gates: Vec<GateData>,
+    pub scrolls: Vec<ScrollData>,
+    pub decals: Vec<DecalData>,
+    pub variants: Vec<VariantsData>,
+    pub spans: Vec<(TextureRef, usize)>,
+    pub grade: Option<GradeData>,
+    pub sky: Option<String>,
+    pub budget: Option<usize>,
+    pub spawns: Vec<SpawnData>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerData {
+    pub pos: [f32; 2],
+    pub dir: [f32; 2],
+    pub spd: [f32; 2],
+    pub turn_spd: f32,
+    pub health: f32,
+    pub ammo: u32,
+    // the keys by name
+    pub keys: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpriteData {
+    pub pos: [f32; 2],
+    pub texture: TextureRef,
+    pub scale: f32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveData {
+    pub version: u32,
+    pub board: BoardData,
+    pub player: PlayerData,
+    pub sprites: Vec<SpriteData>,
+    // path of the map being played and what was changed in every map visited, this one included
+    pub level: String,
+    pub world: BTreeMap<String, LevelState>
+}
+
+fn texture_ref(textures: &TextureRegistry, id: TextureId) -> TextureRef {
+    match textures.path_of(id) {
+        Some(path) => TextureRef::Path(path.to_string()),
+        None => TextureRef::Id(id.index())
+    }
+}
+
+fn texture_id(textures: &TextureRegistry, texture: &TextureRef) -> Result<TextureId, String> {
+    match texture {
+        TextureRef::Path(path) => textures.by_path(path).ok_or(format!("Texture '{}' is not loaded", path)),
+        TextureRef::Id(index) => textures.texture_id(*index).ok_or(format!("Unknown texture {}", index))
+    }
+}
+
+impl BoardData {
+    pub fn from_board(board: &Board, textures: &TextureRegistry) -> BoardData {
+        let mut data = BoardData {
+            rows: board.rows,
+            cols: board.cols,
+            cells: vec![],
+            floors: vec![],
+            heights: vec![],
+            ceilings: vec![],
+            floor_colors: vec![],
+            ceiling_colors: vec![],
+            doors: vec![],
+            teleporters: board.teleporters().map(|((x, y), teleporter)| TeleporterData {
+                pos: [*x, *y],
+                target: [teleporter.target.0, teleporter.target.1],
+                turn: teleporter.turn
+            }).collect(),
+            exits: board.exits().map(|(x, y)| ExitData {
+                pos: [*x, *y],
+                target: board.exit_target(*x, *y).map(|target| target.to_string())
+            }).collect(),
+            triggers: board.triggers().map(|((x, y), trigger)| match trigger {
+                Trigger::Switch { targets, on } => TriggerData::Switch {
+                    pos: [*x, *y],
+                    targets: targets.iter().map(|(tx, ty)| [*tx, *ty]).collect(),
+                    on: *on
+                },
+                Trigger::Sign(text) => TriggerData::Sign { pos: [*x, *y], text: text.clone() }
+            }).collect(),
+            locks: board.locks().map(|((x, y), key)| LockData { pos: [*x, *y], key: key.name().to_string() }).collect(),
+            secrets: board.secrets().map(|(x, y)| [*x, *y]).collect(),
+            requirements: board.requirements().flat_map(|((x, y), requirements)| requirements.iter().map(|requirement| RequirementData {
+                pos: [*x, *y],
+                condition: match &requirement.condition {
+                    Condition::HasKey(key) => ConditionData::HasKey(key.name().to_string()),
+                    Condition::Finished(map) => ConditionData::Finished(map.clone()),
+                    Condition::Compare(value, compare, number) => ConditionData::Compare(value.name().to_string(), compare.name().to_string(), *number)
+                },
+                message: requirement.message.clone()
+            })).collect(),
+            gates: board.gates().map(|((x, y), level)| GateData { pos: [*x, *y], level: level.clone() }).collect(),
+            scrolls: board.scrolls().map(|((x, y), speed)| ScrollData { pos: [*x, *y], speed: [speed.x, speed.y] }).collect(),
+            decals: board.decals.iter().map(|decal| DecalData {
+                texture: texture_ref(textures, decal.texture),
+                cell: [decal.cell.0, decal.cell.1],
+                face: decal.face.name().to_string(),
+                u: decal.u,
+                height: decal.height,
+                size: decal.size
+            }).collect(),
+            variants: board.variants.iter().map(|variants| VariantsData {
+                base: texture_ref(textures, variants.base),
+                others: variants.others.iter().map(|other| texture_ref(textures, *other)).collect(),
+                seed: variants.seed
+            }).collect(),
+            spans: board.spans.iter().map(|(texture, span)| (texture_ref(textures, *texture), *span)).collect(),
+            grade: board.grade.map(|grade| GradeData { tint: [grade.tint.r, grade.tint.g, grade.tint.b, grade.tint.a], strength: grade.strength }),
+            sky: board.sky.clone(),
+            budget: board.budget,
+            spawns: board.spawns.iter().map(|spawn| SpawnData {
+                kind: spawn.kind.name().to_string(),
+                pos: [spawn.pos.x, spawn.pos.y],
+                properties: spawn.properties.clone()
+            }).collect()
+        };
+
+        for y in 0..board.rows {
+            for x in 0..board.cols {
+                data.cells.push(match board.at(x, y) {
+                    Cell::EMPTY => CellData::Empty,
+                    Cell::COLOR(color) => CellData::Color([color.r, color.g, color.b, color.a]),
+                    Cell::TEXTURE(id) => CellData::Texture(texture_ref(textures, *id)),
+                    Cell::TranslucentTexture(id) => CellData::Translucent(texture_ref(textures, *id)),
+                    Cell::Door(id) => CellData::Door(texture_ref(textures, *id)),
+                    Cell::AnimatedTexture(id) => CellData::Animated(id.index()),
+                    Cell::Segment(id, segment) => CellData::Segment(texture_ref(textures, *id), segment.name().to_string()),
+                    Cell::Mirror => CellData::Mirror,
+                    Cell::Custom(kind, id) => CellData::Custom(cell_kind(*kind).map_or(String::new(), |kind| kind.name.to_string()), texture_ref(textures, *id))
+                });
+                data.floors.push(board.floor_at(x, y).map(|id| id.index()));
+                data.ceilings.push(board.ceiling_at(x, y).map(|id| id.index()));
+                data.heights.push(board.height_at(x, y));
+                data.floor_colors.push(board.floor_color_at(x, y).map(|color| [color.r, color.g, color.b, color.a]));
+                data.ceiling_colors.push(board.ceiling_color_at(x, y).map(|color| [color.r, color.g, color.b, color.a]));
+
+                if let Some(door) = board.door(x, y) {
+                    data.doors.push(DoorData { x, y, open: door.open });
+                }
+            }
+        }
+
+        data
+    }
+
+    pub fn to_board(&self, textures: &TextureRegistry) -> Result<Board, String> {
+        let size = self.rows * self.cols;
+        if self.cells.len() != size || self.floors.len() != size || self.ceilings.len() != size || self.heights.len() != size
+            || self.floor_colors.len() != size || self.ceiling_colors.len() != size {
+            return Err(format!("Board data doesn't match its size {}x{}", self.cols, self.rows))
+        }
+
+        let mut board = Board::new(self.rows, self.cols);
+        for (i, cell) in self.cells.iter().enumerate() {
+            let (x, y) = (i % self.cols, i / self.cols);
+            board.set(x, y, match cell {
+                CellData::Empty => Cell::EMPTY,
+                CellData::Color([r, g, b, a]) => Cell::COLOR(Color::new(*r, *g, *b, *a)),
+                CellData::Texture(texture) => Cell::TEXTURE(texture_id(textures, texture)?),
+                CellData::Translucent(texture) => Cell::TranslucentTexture(texture_id(textures, texture)?),
+                CellData::Door(texture) => Cell::Door(texture_id(textures, texture)?),
+                CellData::Animated(index) => Cell::AnimatedTexture(textures.animation_id(*index).ok_or(format!("Unknown animation {}", index))?),
+                CellData::Segment(texture, segment) => Cell::Segment(texture_id(textures, texture)?, Segment::from_name(segment).ok_or(format!("Unknown segment '{}'", segment))?),
+                CellData::Mirror => Cell::Mirror,
+                CellData::Custom(kind, texture) => Cell::Custom(cell_kind_by_name(kind).ok_or(format!("Unknown cell kind '{}'", kind))?, texture_id(textures, texture)?)
+            });
+
+            let flat = |index: &Option<usize>| match index {
+                Some(index) => textures.flat_id(*index).map(Some).ok_or(format!("Unknown flat texture {}", index)),
+                None => Ok(None)
+            };
+            board.set_floor(x, y, flat(&self.floors[i])?);
+            board.set_ceiling(x, y, flat(&self.ceilings[i])?);
+            board.set_height(x, y, self.heights[i]);
+            board.set_floor_color(x, y, self.floor_colors[i].map(|[r, g, b, a]| Color::new(r, g, b, a)));
+            board.set_ceiling_color(x, y, self.ceiling_colors[i].map(|[r, g, b, a]| Color::new(r, g, b, a)));
+        }
+
+        for data in self.doors.iter() {
+            let door = board.door_mut(data.x, data.y).ok_or(format!("No door at {},{}", data.x, data.y))?;
+            door.open = data.open.clamp(0.0, 1.0);
+            door.state = if door.open >= 1.0 { DoorState::Open }
+            else { DoorState::Closed };
+        }
+
+        for data in self.teleporters.iter() {
+            let [x, y] = data.pos;
+            if x >= board.cols || y >= board.rows {
+                return Err(format!("Teleporter {},{} is out of bounds", x, y))
+            }
+            board.set_teleporter(x, y, Some(Teleporter::new((data.target[0], data.target[1]), data.turn)));
+        }
+
+        for data in self.exits.iter() {
+            let [x, y] = data.pos;
+            if x >= board.cols || y >= board.rows {
+                return Err(format!("Exit {},{} is out of bounds", x, y))
+            }
+            match &data.target {
+                Some(target) => board.link_exit(x, y, target),
+                None => board.set_exit(x, y, true)
+            }
+        }
+
+        for data in self.triggers.iter() {
+            let ([x, y], trigger) = match data {
+                TriggerData::Switch { pos, targets, on } => (*pos, Trigger::Switch { targets: targets.iter().map(|[tx, ty]| (*tx, *ty)).collect(), on: *on }),
+                TriggerData::Sign { pos, text } => (*pos, Trigger::Sign(text.clone()))
+            };
+            if x >= board.cols || y >= board.rows {
+                return Err(format!("Trigger {},{} is out of bounds", x, y))
+            }
+            board.set_trigger(x, y, Some(trigger));
+        }
+
+        for data in self.locks.iter() {
+            let [x, y] = data.pos;
+            if x >= board.cols || y >= board.rows {
+                return Err(format!("Lock {},{} is out of bounds", x, y))
+            }
+            board.set_lock(x, y, Some(Key::from_name(&data.key).ok_or(format!("Unknown key '{}'", data.key))?));
+        }
+
+        for [x, y] in self.secrets.iter().copied() {
+            if x >= board.cols || y >= board.rows {
+                return Err(format!("Secret {},{} is out of bounds", x, y))
+            }
+            board.set_secret(x, y, true);
+        }
+
+        let (cols, rows) = (board.cols, board.rows);
+        let in_bounds = |[x, y]: [usize; 2], what: &str| if x < cols && y < rows { Ok((x, y)) }
+            else { Err(format!("{} {},{} is out of bounds", what, x, y)) };
+
+        for data in self.requirements.iter() {
+            let (x, y) = in_bounds(data.pos, "Requirement")?;
+            let condition = match &data.condition {
+                ConditionData::HasKey(key) => Condition::HasKey(Key::from_name(key).ok_or(format!("Unknown key '{}'", key))?),
+                ConditionData::Finished(map) => Condition::Finished(map.clone()),
+                ConditionData::Compare(value, compare, number) => Condition::Compare(
+                    ScriptValue::from_name(value).ok_or(format!("Unknown value '{}'", value))?,
+                    Compare::from_name(compare).ok_or(format!("Unknown comparison '{}'", compare))?,
+                    *number
+                )
+            };
+            board.add_requirement(x, y, Requirement { condition, message: data.message.clone() });
+        }
+
+        for data in self.gates.iter() {
+            let (x, y) = in_bounds(data.pos, "Gate")?;
+            board.set_gate(x, y, Some(data.level.clone()));
+        }
+
+        for data in self.scrolls.iter() {
+            let (x, y) = in_bounds(data.pos, "Scroll")?;
+            board.set_scroll(x, y, Vector2::new(data.speed[0], data.speed[1]));
+        }
+
+        for data in self.decals.iter() {
+            let cell = in_bounds(data.cell, "Decal")?;
+            board.decals.push(Decal {
+                texture: texture_id(textures, &data.texture)?,
+                cell,
+                face: Face::from_name(&data.face).ok_or(format!("Unknown face '{}'", data.face))?,
+                u: data.u,
+                height: data.height,
+                size: data.size
+            });
+        }
+
+        for data in self.variants.iter() {
+            board.variants.push(TextureVariants {
+                base: texture_id(textures, &data.base)?,
+                others: data.others.iter().map(|other| texture_id(textures, other)).collect::<Result<Vec<TextureId>, String>>()?,
+                seed: data.seed
+            });
+        }
+
+        for (texture, span) in self.spans.iter() {
+            board.spans.push((texture_id(textures, texture)?, *span));
+        }
+
+        board.grade = self.grade.as_ref().map(|grade| ColorGrade::new(Color::new(grade.tint[0], grade.tint[1], grade.tint[2], grade.tint[3]), grade.strength));
+        board.sky = self.sky.clone();
+        board.budget = self.budget;
+
+        for data in self.spawns.iter() {
+            let kind = SpawnKind::from_name(&data.kind).ok_or(format!("Unknown spawn kind '{}'", data.kind))?;
+            let mut spawn = Spawn::new(kind, Vector2::new(data.pos[0], data.pos[1]));
+            spawn.properties = data.properties.clone();
+            board.spawns.push(spawn);
+        }
+
+        Ok(board)
+    }
+}
+
+impl SaveData {
+    pub fn from_game(game: &Game) -> SaveData {
+        let player = &game.player;
+        let mut world = game.world.levels.clone();
+        world.insert(game.editor.path.clone(), game.level_state.clone());
+
+        SaveData {
+            version: SAVE_VERSION,
+            board: BoardData::from_board(&game.board, &game.textures),
+            player: PlayerData {
+                pos: [player.pos.x, player.pos.y],
+                dir: [player.dir.x, player.dir.y],
+                spd: [player.spd.x, player.spd.y],
+                turn_spd: player.turn_spd,
+                health: player.health,
+                ammo: player.ammo,
+                keys: player.keys.iter().map(|key| key.name().to_string()).collect()
+            },
+            sprites: game.sprites.iter().map(|sprite| SpriteData {
+                pos: [sprite.pos.x, sprite.pos.y],
+                texture: texture_ref(&game.textures, sprite.texture),
+                scale: sprite.scale
+            }).collect(),
+            level: game.editor.path.clone(),
+            world
+        }
+    }
+
+    // everything is converted before touching the game, so a bad save leaves it unchanged
+    pub fn apply(&self, game: &mut Game) -> Result<(), String> {
+        let board = self.board.to_board(&game.textures)?;
+
+        let mut player = Player::new(self.player.pos[0], self.player.pos[1]);
+        player.dir = Vector2::new(self.player.dir[0], self.player.dir[1]);
+        player.spd = Vector2::new(self.player.spd[0], self.player.spd[1]);
+        player.turn_spd = self.player.turn_spd;
+        player.health = self.player.health.clamp(0.0, player.max_health);
+        player.ammo = self.player.ammo.min(player.max_ammo);
+        player.keys = self.player.keys.iter()
+            .map(|key| Key::from_name(key).ok_or(format!("Unknown key '{}'", key)))
+            .collect::<Result<Vec<Key>, String>>()?;
+
+        let sprites = self.sprites.iter().map(|data| {
+            let mut sprite = Sprite::new(data.pos[0], data.pos[1], texture_id(&game.textures, &data.texture)?);
+            sprite.scale = data.scale;
+            Ok(sprite)
+        }).collect::<Result<Vec<Sprite>, String>>()?;
+
+        game.player = player;
+        game.sprites = sprites;
+
+        // the board already has its doors as they were, the enemies and pickups are spawned without
+        // the ones that were gone. saves from before the world state keep the current map
+        game.world.levels = self.world.clone();
+        let level = if self.level.is_empty() { game.editor.path.clone() }
+        else { self.level.clone() };
+        game.restore_level(level, board);
+        Ok(())
+    }
+}
+
+pub fn save_game(path: &str, game: &Game) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&SaveData::from_game(game)).map_err(|err| format!("Could not serialize save: {}", err))?;
+    fs::write(path, json).map_err(|err| format!("Could not write save '{}': {}", path, err))
+}
+
+// upgrades an older save one version at a time, saves from before versioning are version 0
+pub fn migrate(mut save: Value) -> Result<Value, String> {
+    let object = save.as_object_mut().ok_or("Save is not a JSON object")?;
+    let version = match object.get("version") {
+        Some(version) => version.as_u64().ok_or("Save version is not a number")?,
+        None => 0
+    };
+
+    if version > SAVE_VERSION as u64 {
+        return Err(format!("Save version {} is newer than this game supports ({})", version, SAVE_VERSION))
+    }
+
+    for from in version..SAVE_VERSION as u64 {
+        match from {
+            // version 0 only lacked the version field
+            0 => {},
+            // version 1 had every wall at the standard height
+            1 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                let size = ["rows", "cols"].iter()
+                    .map(|key| board.get(*key).and_then(|value| value.as_u64()).ok_or(format!("Save board has no {}", key)))
+                    .product::<Result<u64, String>>()?;
+                board.insert("heights".to_string(), Value::from(vec![Value::from(1.0); size as usize]));
+            },
+            // version 2 had no teleporters
+            2 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                board.insert("teleporters".to_string(), Value::from(Vec::<Value>::new()));
+            },
+            // version 3 didn't know which map it was in or what changed in the others
+            3 => {
+                object.insert("level".to_string(), Value::from(""));
+                object.insert("world".to_string(), Value::Object(Default::default()));
+            },
+            // version 4 didn't remember which maps were finished
+            4 => {
+                let world = object.get_mut("world").and_then(|world| world.as_object_mut()).ok_or("Save has no world")?;
+                for level in world.values_mut() {
+                    let level = level.as_object_mut().ok_or("Save world has a level that is not an object")?;
+                    level.insert("completed".to_string(), Value::Bool(false));
+                }
+            },
+            // version 5 had no secrets
+            5 => {
+                let world = object.get_mut("world").and_then(|world| world.as_object_mut()).ok_or("Save has no world")?;
+                for level in world.values_mut() {
+                    let level = level.as_object_mut().ok_or("Save world has a level that is not an object")?;
+                    level.insert("secrets".to_string(), Value::from(Vec::<Value>::new()));
+                }
+            },
+            // version 6 had no exits
+            6 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                board.insert("exits".to_string(), Value::from(Vec::<Value>::new()));
+            },
+            // version 7 had no switches or signs
+            7 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                board.insert("triggers".to_string(), Value::from(Vec::<Value>::new()));
+            },
+            // version 8 didn't save the player's health, ammo and keys or the locked doors
+            8 => {
+                let fresh = Player::new(0.0, 0.0);
+                let player = object.get_mut("player").and_then(|player| player.as_object_mut()).ok_or("Save has no player")?;
+                player.insert("health".to_string(), Value::from(fresh.health));
+                player.insert("ammo".to_string(), Value::from(fresh.ammo));
+                player.insert("keys".to_string(), Value::from(Vec::<Value>::new()));
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                board.insert("locks".to_string(), Value::from(Vec::<Value>::new()));
+            },
+            // version 9 didn't save which cells are secrets, only the ones found in every map
+            9 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                board.insert("secrets".to_string(), Value::from(Vec::<Value>::new()));
+            },
+            // version 10 lost what the map text has past the cells, doors, teleporters, keys and secrets
+            10 => {
+                let board = object.get_mut("board").and_then(|board| board.as_object_mut()).ok_or("Save has no board")?;
+                let size = ["rows", "cols"].iter()
+                    .map(|key| board.get(*key).and_then(|value| value.as_u64()).ok_or(format!("Save board has no {}", key)))
+                    .product::<Result<u64, String>>()?;
+                for key in ["floor_colors", "ceiling_colors"] {
+                    board.insert(key.to_string(), Value::from(vec![Value::Null; size as usize]));
+                }
+                for key in ["requirements", "gates", "scrolls", "decals", "variants", "spans"] {
+                    board.insert(key.to_string(), Value::from(Vec::<Value>::new()));
+                }
+                for key in ["grade", "sky", "budget"] {
+                    board.insert(key.to_string(), Value::Null);
+                }
+            },
+            _ => return Err(format!("Save version {} can no longer be loaded", from))
+        }
+    }
+
+    object.insert("version".to_string(), Value::from(SAVE_VERSION));
+    Ok(save)
+}
+
+pub fn load_game(path: &str, game: &mut Game) -> Result<(), String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("Could not read save '{}': {}", path, err))?;
+    let save: Value = serde_json::from_str(&json).map_err(|err| format!("Could not parse save '{}': {}", path, err))?;
+    let save = migrate(save).map_err(|err| format!("Could not load save '{}': {}", path, err))?;
+    let data: SaveData = serde_json::from_value(save).map_err(|err| format!("Save '{}' is incompatible: {}", path, err))?;
+    data.apply(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{map::content_hash, script::Script};
+
+    use super::*;
+
+    // a 2x2 save as the game wrote it at `version`, every field the versions after it added left out
+    fn fixture(version: u32) -> Value {
+        let mut board = json!({
+            "rows": 2,
+            "cols": 2,
+            "cells": ["Empty", {"Color": [255, 0, 0, 255]}, "Empty", "Empty"],
+            "floors": [null, null, null, null],
+            "ceilings": [null, null, null, null],
+            "doors": [],
+            "spawns": [{"kind": "player", "pos": [0.5, 0.5], "properties": []}]
+        });
+        let mut player = json!({"pos": [0.5, 0.5], "dir": [1.0, 0.0], "spd": [1.0, 1.0], "turn_spd": 1.5});
+        let mut save = json!({"sprites": []});
+
+        if version >= 1 { save["version"] = json!(version) }
+        if version >= 2 { board["heights"] = json!([1.0, 2.0, 1.0, 1.0]) }
+        if version >= 3 { board["teleporters"] = json!([]) }
+        if version >= 4 {
+            save["level"] = json!("maps/a.txt");
+            save["world"] = json!({"maps/a.txt": {"open_doors": [], "collected": [1], "killed": []}});
+        }
+        if version >= 5 { save["world"]["maps/a.txt"]["completed"] = json!(true) }
+        if version >= 6 { save["world"]["maps/a.txt"]["secrets"] = json!([[1, 1]]) }
+        if version >= 7 { board["exits"] = json!([{"pos": [1, 1], "target": "maps/b.txt"}]) }
+        if version >= 8 { board["triggers"] = json!([{"Sign": {"pos": [0, 1], "text": "hello"}}]) }
+        if version >= 9 {
+            player["health"] = json!(40.0);
+            player["ammo"] = json!(7);
+            player["keys"] = json!(["red"]);
+            board["locks"] = json!([]);
+        }
+        if version >= 10 { board["secrets"] = json!([[1, 1]]) }
+        if version >= 11 {
+            board["floor_colors"] = json!([null, null, [0, 255, 0, 255], null]);
+            board["ceiling_colors"] = json!([null, null, null, null]);
+            board["requirements"] = json!([{"pos": [1, 1], "condition": {"HasKey": "red"}, "message": null}]);
+            for key in ["gates", "scrolls", "decals", "variants", "spans"] {
+                board[key] = json!([]);
+            }
+            board["grade"] = json!(null);
+            board["sky"] = json!("sky.png");
+            board["budget"] = json!(200);
+        }
+
+        save["board"] = board;
+        save["player"] = player;
+        save
+    }
+
+    #[test]
+    fn every_old_version_migrates_to_a_save_that_loads() {
+        for version in 0..SAVE_VERSION {
+            let save = migrate(fixture(version)).unwrap_or_else(|err| panic!("version {}: {}", version, err));
+            assert_eq!(save["version"], json!(SAVE_VERSION), "version {}", version);
+
+            let data: SaveData = serde_json::from_value(save).unwrap_or_else(|err| panic!("version {}: {}", version, err));
+            let board = data.board.to_board(&TextureRegistry::new()).unwrap_or_else(|err| panic!("version {}: {}", version, err));
+            assert_eq!(*board.at(1, 0), Cell::COLOR(Color::RED), "version {}", version);
+        }
+    }
+
+    #[test]
+    fn migrating_fills_in_what_older_versions_lacked() {
+        let save = migrate(fixture(1)).unwrap();
+        assert_eq!(save["board"]["heights"], json!([1.0, 1.0, 1.0, 1.0]));
+        assert_eq!(save["level"], json!(""));
+
+        let save = migrate(fixture(4)).unwrap();
+        assert_eq!(save["world"]["maps/a.txt"]["completed"], json!(false));
+        assert_eq!(save["world"]["maps/a.txt"]["secrets"], json!([]));
+
+        let save = migrate(fixture(8)).unwrap();
+        let fresh = Player::new(0.0, 0.0);
+        assert_eq!((&save["player"]["health"], &save["player"]["ammo"]), (&json!(fresh.health), &json!(fresh.ammo)));
+        assert_eq!(save["player"]["keys"], json!([]));
+
+        let save = migrate(fixture(10)).unwrap();
+        assert_eq!(save["board"]["floor_colors"], json!([null, null, null, null]));
+        assert_eq!((&save["board"]["sky"], &save["board"]["budget"]), (&json!(null), &json!(null)));
+    }
+
+    #[test]
+    fn a_current_save_is_left_as_it_is() {
+        assert_eq!(migrate(fixture(SAVE_VERSION)).unwrap(), fixture(SAVE_VERSION));
+    }
+
+    #[test]
+    fn saves_the_game_cannot_read_are_refused() {
+        assert!(migrate(fixture(SAVE_VERSION + 1)).unwrap_err().contains("newer"));
+        assert!(migrate(json!([1, 2, 3])).is_err());
+        assert!(migrate(json!({"version": "two"})).is_err());
+        // versions that still need a step but lost the part it changes
+        assert!(migrate(json!({"version": 1})).unwrap_err().contains("board"));
+    }
+
+    #[test]
+    fn a_save_made_in_another_map_replaces_everything_that_went_with_the_current_one() {
+        let mut saved = Game::new(Board::new(2, 3), TextureRegistry::new(), Player::new(0.5, 0.5));
+        saved.editor.path = "raycast-save-test-a.txt".to_string();
+        saved.board.set(1, 0, Cell::COLOR(Color::RED));
+        let save = SaveData::from_game(&saved);
+
+        let mut game = Game::new(Board::new(4, 4), TextureRegistry::new(), Player::new(1.5, 1.5));
+        game.editor.path = "raycast-save-test-b.txt".to_string();
+        game.script = Script::from_text("on load\nheal 1\nend\n", &game.textures).unwrap();
+        game.map_hash = content_hash(&game.board, &game.textures);
+        game.editor.history.touch(&game.board, 0, 0);
+        game.board.set(0, 0, Cell::COLOR(Color::BLUE));
+        game.editor.history.finish(&game.board);
+
+        save.apply(&mut game).unwrap();
+        assert_eq!(game.editor.path, "raycast-save-test-a.txt");
+        assert_eq!((game.board.cols, game.board.rows, *game.board.at(1, 0)), (3, 2, Cell::COLOR(Color::RED)));
+        assert_eq!(game.map_hash, content_hash(&game.board, &game.textures));
+        // the other map's script and edits are gone with it
+        assert!(game.script.blocks.is_empty());
+        assert!(!game.editor.history.undo(&mut game.board));
+        assert_eq!(game.player.pos, Vector2::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn an_incompatible_save_leaves_the_game_unchanged() {
+        let mut save = fixture(SAVE_VERSION);
+        save.as_object_mut().unwrap().remove("player");
+        let path = std::env::temp_dir().join(format!("raycast-save-{}.json", std::process::id()));
+        fs::write(&path, save.to_string()).unwrap();
+
+        let mut game = Game::new(Board::new(1, 1), TextureRegistry::new(), Player::new(0.5, 0.5));
+        let result = load_game(path.to_str().unwrap(), &mut game);
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap_err().contains("incompatible"));
+        assert_eq!((game.board.cols, game.board.rows), (1, 1));
+    }
+}
@@ -0,0 +1,291 @@
+use std::{fs, path::Path};
+
+use crate::{board::Cell, map::parse_cell, texture::TextureRegistry};
+
+// map logic in a "<map>.script" file next to the map. this is a small language of its own rather than
+// embedded lua or rhai: a script is a list of hooks with no variables, loops or functions, so map authors
+// only learn the statements below and the game needs no interpreter. the game only ever asks which
+// blocks a `Hook` fires, so a bigger language could be run from the same hooks later.
+// a block of statements per hook:
+//   on load                   once the map is entered
+//   on use <x> <y>            the player uses the cell, before its door or trigger
+//   on enter <x> <y>          the player steps onto the cell
+//   on tick [seconds]         every simulation step, or every that many seconds
+// "once" after the hook runs a block at most one time per visit of the map, and every block ends with "end".
+// the statements run in order:
+//   require <value> <op> <number>   stops the block unless it holds, op is one of < <= = >= >,
+//                                   value is enemies (alive), kills, secrets, health, ammo or time
+//   say <text>                      shows the text like a sign
+//   toggle <x> <y>                  flips the door and lights in the cell like a switch
+//   set <x> <y> <cell>              puts a map cell token there, "." clears it
+//   teleport <x> <y>                moves the player to the middle of the cell
+//   damage <amount> / heal <amount>
+//   finish [map]                    finishes the map like an exit
+//   daylight <level> [seconds]      sets the daylight from 0 to 1, and how long a day cycle takes, 0 for none
+// a block stopped by a require doesn't count as run for "once"
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hook {
+    Load,
+    Use(usize, usize),
+    Enter(usize, usize),
+    // seconds between runs, 0 every step
+    Tick(f32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Enemies,
+    Kills,
+    Secrets,
+    Health,
+    Ammo,
+    Time
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compare {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    Require(Value, Compare, f32),
+    Say(String),
+    Toggle(usize, usize),
+    Set(usize, usize, Cell),
+    Teleport(usize, usize),
+    Damage(f32),
+    Heal(f32),
+    Finish(Option<String>),
+    Daylight(f32, Option<f32>)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub hook: Hook,
+    pub once: bool,
+    pub statements: Vec<Statement>,
+    // already run, for "once" blocks, and seconds left until a timed tick block runs again
+    pub ran: bool,
+    pub timer: f32
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub blocks: Vec<Block>
+}
+
+const VALUES: [Value; 6] = [Value::Enemies, Value::Kills, Value::Secrets, Value::Health, Value::Ammo, Value::Time];
+const COMPARES: [Compare; 5] = [Compare::Less, Compare::LessOrEqual, Compare::Equal, Compare::GreaterOrEqual, Compare::Greater];
+
+impl Value {
+    pub fn name(self) -> &'static str {
+        match self {
+            Value::Enemies => "enemies",
+            Value::Kills => "kills",
+            Value::Secrets => "secrets",
+            Value::Health => "health",
+            Value::Ammo => "ammo",
+            Value::Time => "time"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Value> {
+        VALUES.into_iter().find(|value| value.name() == name)
+    }
+}
+
+impl Compare {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compare::Less => "<",
+            Compare::LessOrEqual => "<=",
+            Compare::Equal => "=",
+            Compare::GreaterOrEqual => ">=",
+            Compare::Greater => ">"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Compare> {
+        COMPARES.into_iter().find(|compare| compare.name() == name)
+    }
+
+    pub fn holds(self, a: f32, b: f32) -> bool {
+        match self {
+            Compare::Less => a < b,
+            Compare::LessOrEqual => a <= b,
+            Compare::Equal => a == b,
+            Compare::GreaterOrEqual => a >= b,
+            Compare::Greater => a > b
+        }
+    }
+}
+
+// the script of "maps/level.txt" is "maps/level.script"
+pub fn script_path(map: &str) -> String {
+    Path::new(map).with_extension("script").to_string_lossy().to_string()
+}
+
+fn parse_hook(line: &str) -> Result<(Hook, bool), String> {
+    let mut words: Vec<&str> = line.split_whitespace().skip(1).collect();
+    let once = words.last() == Some(&"once");
+    if once {
+        words.pop();
+    }
+
+    let coord = |word: Option<&&str>| word.and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid position in '{}'", line));
+    let hook = match words.first() {
+        Some(&"load") if words.len() == 1 => Hook::Load,
+        Some(&"use") if words.len() == 3 => Hook::Use(coord(words.get(1))?, coord(words.get(2))?),
+        Some(&"enter") if words.len() == 3 => Hook::Enter(coord(words.get(1))?, coord(words.get(2))?),
+        Some(&"tick") if words.len() == 1 => Hook::Tick(0.0),
+        Some(&"tick") if words.len() == 2 => {
+            let seconds = words[1].parse::<f32>().map_err(|err| format!("Invalid tick interval in '{}': {}", line, err))?;
+            Hook::Tick(seconds.max(0.0))
+        },
+        _ => return Err(format!("Invalid hook '{}', expected load, use <x> <y>, enter <x> <y> or tick [seconds]", line))
+    };
+    Ok((hook, once))
+}
+
+fn parse_statement(line: &str, textures: &TextureRegistry) -> Result<Statement, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let coord = |i: usize| words.get(i).and_then(|n| n.parse::<usize>().ok()).ok_or(format!("Invalid position in '{}'", line));
+    let number = |i: usize| words.get(i).and_then(|n| n.parse::<f32>().ok()).ok_or(format!("Invalid number in '{}'", line));
+    let expect = |count: usize| if words.len() == count { Ok(()) }
+        else { Err(format!("Expected {} values in '{}'", count - 1, line)) };
+
+    match words[0] {
+        "require" => {
+            expect(4)?;
+            let value = Value::from_name(words[1]).ok_or(format!("Unknown value '{}' in '{}'", words[1], line))?;
+            let compare = Compare::from_name(words[2]).ok_or(format!("Unknown comparison '{}' in '{}'", words[2], line))?;
+            Ok(Statement::Require(value, compare, number(3)?))
+        },
+        "say" if words.len() > 1 => Ok(Statement::Say(words[1..].join(" "))),
+        "toggle" => {
+            expect(3)?;
+            Ok(Statement::Toggle(coord(1)?, coord(2)?))
+        },
+        "set" => {
+            expect(4)?;
+            Ok(Statement::Set(coord(1)?, coord(2)?, parse_cell(words[3], textures)?))
+        },
+        "teleport" => {
+            expect(3)?;
+            Ok(Statement::Teleport(coord(1)?, coord(2)?))
+        },
+        "damage" => {
+            expect(2)?;
+            Ok(Statement::Damage(number(1)?))
+        },
+        "heal" => {
+            expect(2)?;
+            Ok(Statement::Heal(number(1)?))
+        },
+        "finish" if words.len() <= 2 => Ok(Statement::Finish(words.get(1).map(|map| map.to_string()))),
+        "daylight" if matches!(words.len(), 2 | 3) => Ok(Statement::Daylight(number(1)?, words.get(2).map(|_| number(2)).transpose()?)),
+        _ => Err(format!("Invalid statement '{}'", line))
+    }
+}
+
+impl Script {
+    pub fn from_text(text: &str, textures: &TextureRegistry) -> Result<Script, String> {
+        let mut script = Script::default();
+        let mut block: Option<Block> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let at_line = |err: String| format!("line {}: {}", i + 1, err);
+
+            match block.as_mut() {
+                None if line.starts_with("on ") => {
+                    let (hook, once) = parse_hook(line).map_err(at_line)?;
+                    block = Some(Block { hook, once, statements: vec![], ran: false, timer: 0.0 });
+                },
+                None => return Err(at_line(format!("Expected 'on <hook>', got '{}'", line))),
+                Some(_) if line == "end" => script.blocks.extend(block.take()),
+                Some(block) => block.statements.push(parse_statement(line, textures).map_err(at_line)?)
+            }
+        }
+
+        if block.is_some() {
+            return Err("Missing 'end' at the end of the script".to_string())
+        }
+        Ok(script)
+    }
+
+    // a map without a script file has nothing scripted
+    pub fn load(map: &str, textures: &TextureRegistry) -> Result<Script, String> {
+        let path = script_path(map);
+        if !Path::new(&path).exists() {
+            return Ok(Script::default())
+        }
+
+        let text = fs::read_to_string(&path).map_err(|err| format!("Could not load script '{}': {}", path, err))?;
+        Script::from_text(&text, textures).map_err(|err| format!("Could not load script '{}': {}", path, err))
+    }
+
+    // what the script reloaded over `old` had already done carries over to the blocks left as they were,
+    // so reloading it doesn't run its "once" blocks again or start its timers over. edited blocks start fresh
+    pub fn keep_progress(&mut self, old: &Script) {
+        let mut unmatched: Vec<&Block> = old.blocks.iter().collect();
+        for block in self.blocks.iter_mut() {
+            let Some(i) = unmatched.iter().position(|old| old.hook == block.hook && old.once == block.once && old.statements == block.statements) else { continue };
+            let old = unmatched.remove(i);
+            block.ran = old.ran;
+            block.timer = old.timer;
+        }
+    }
+
+    // the blocks to run for the hook, by index
+    pub fn fire(&self, hook: Hook) -> Vec<usize> {
+        (0..self.blocks.len()).filter(|i| self.blocks[*i].hook == hook && !(self.blocks[*i].once && self.blocks[*i].ran)).collect()
+    }
+
+    // the tick blocks due after `dt` more seconds, by index
+    pub fn tick(&mut self, dt: f32) -> Vec<usize> {
+        let mut due = vec![];
+        for (i, block) in self.blocks.iter_mut().enumerate() {
+            let Hook::Tick(interval) = block.hook else { continue };
+            if block.once && block.ran { continue }
+
+            block.timer -= dt;
+            if block.timer <= 0.0 {
+                block.timer = interval;
+                due.push(i);
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "on load once\nsay hello\nend\non tick 5\nheal 1\nend\non enter 2 3 once\ndamage 4\nend\n";
+
+    #[test]
+    fn a_reloaded_script_keeps_what_its_unchanged_blocks_did() {
+        let textures = TextureRegistry::new();
+        let mut old = Script::from_text(SCRIPT, &textures).unwrap();
+        for block in old.blocks.iter_mut() {
+            block.ran = true;
+            block.timer = 2.0;
+        }
+
+        // the enter block is edited and a new load block added
+        let mut script = Script::from_text(&SCRIPT.replace("damage 4", "damage 5").replace("on load once\nsay hello", "on load\nheal 2\nend\non load once\nsay hello"), &textures).unwrap();
+        script.keep_progress(&old);
+
+        let progress: Vec<(bool, f32)> = script.blocks.iter().map(|block| (block.ran, block.timer)).collect();
+        assert_eq!(progress, vec![(false, 0.0), (true, 2.0), (true, 2.0), (false, 0.0)]);
+        assert!(script.fire(Hook::Load).len() == 1 && script.fire(Hook::Enter(2, 3)).len() == 1);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use raylib::{color::Color, math::{Rectangle, Vector2}, texture::{RaylibTexture2D, Texture2D}};
+
+use crate::{backend::Backend, render::FrameBuffer, texture::FlatTexture, window_size};
+
+// cpu copies of the textures the software renderer samples, read back once each.
+// cleared whenever the registry changes, a freed texture's id can be handed out again
+#[derive(Default)]
+pub struct TextureImages {
+    generation: u64,
+    images: HashMap<u32, Option<FlatTexture>>
+}
+
+impl TextureImages {
+    pub fn new() -> TextureImages {
+        TextureImages::default()
+    }
+
+    pub fn sync(&mut self, generation: u64) {
+        if self.generation != generation {
+            self.generation = generation;
+            self.images.clear();
+        }
+    }
+
+    // None for a texture that couldn't be read back, it is skipped instead of read every frame
+    fn get(&mut self, texture: &Texture2D) -> Option<&FlatTexture> {
+        self.images.entry(texture.id).or_insert_with(|| match texture.load_image() {
+            Ok(image) => Some(FlatTexture::from_image(&image)),
+            Err(err) => {
+                println!("ERROR: {}", err);
+                None
+            }
+        }).as_ref()
+    }
+}
+
+// draws into a frame buffer instead of issuing a draw call per column, the buffer is uploaded once
+// when it is done. window coordinates are scaled down to the buffer, rotation and origin are ignored
+// since the view only draws axis aligned columns, and text is left to the hud
+pub struct SoftwareBackend<'a> {
+    fb: &'a mut FrameBuffer,
+    images: &'a mut TextureImages,
+    frame_time: f32,
+    scale: Vector2
+}
+
+// `color` over what is already at `i`, the buffer starts out clear
+fn blend(pixels: &mut [u8], i: usize, color: Color) {
+    let a = color.a as f32 / 255.0;
+    let mix = |src: u8, dst: u8| (src as f32 * a + dst as f32 * (1.0 - a)) as u8;
+    pixels[i] = mix(color.r, pixels[i]);
+    pixels[i + 1] = mix(color.g, pixels[i + 1]);
+    pixels[i + 2] = mix(color.b, pixels[i + 2]);
+    pixels[i + 3] = (color.a as f32 + pixels[i + 3] as f32 * (1.0 - a)) as u8;
+}
+
+fn tint(color: Color, tint: Color) -> Color {
+    let mul = |a: u8, b: u8| ((a as u16 * b as u16) / 255) as u8;
+    Color::new(mul(color.r, tint.r), mul(color.g, tint.g), mul(color.b, tint.b), mul(color.a, tint.a))
+}
+
+impl<'a> SoftwareBackend<'a> {
+    pub fn new(fb: &'a mut FrameBuffer, images: &'a mut TextureImages, frame_time: f32) -> SoftwareBackend<'a> {
+        let scale = Vector2::new(fb.width as f32 / window_size().x, fb.height as f32 / window_size().y);
+        SoftwareBackend { fb, images, frame_time, scale }
+    }
+
+    // the buffer pixels a window rectangle covers, by their centers
+    fn pixels(&self, rec: Rectangle) -> (usize, usize, usize, usize) {
+        let clamp = |v: f32, max: usize| f32::clamp(f32::round(v), 0.0, max as f32) as usize;
+        (
+            clamp(rec.x * self.scale.x, self.fb.width), clamp((rec.x + rec.width) * self.scale.x, self.fb.width),
+            clamp(rec.y * self.scale.y, self.fb.height), clamp((rec.y + rec.height) * self.scale.y, self.fb.height)
+        )
+    }
+}
+
+impl Backend for SoftwareBackend<'_> {
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        self.draw_rectangle_rec(Rectangle::new(x as f32, y as f32, width as f32, height as f32), color)
+    }
+
+    fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color) {
+        let (x0, x1, y0, y1) = self.pixels(rec);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                blend(&mut self.fb.pixels, (y * self.fb.capacity.0 + x) * 4, color);
+            }
+        }
+    }
+
+    fn draw_rectangle_v(&mut self, pos: Vector2, size: Vector2, color: Color) {
+        self.draw_rectangle_rec(Rectangle::new(pos.x, pos.y, size.x, size.y), color)
+    }
+
+    fn draw_texture_pro(&mut self, texture: &Texture2D, source_rec: Rectangle, dest_rec: Rectangle, _origin: Vector2, _rotation: f32, color: Color) {
+        if dest_rec.width <= 0.0 || dest_rec.height <= 0.0 { return }
+        let (x0, x1, y0, y1) = self.pixels(dest_rec);
+        let Some(image) = self.images.get(texture) else { return };
+
+        for x in x0..x1 {
+            let u = ((x as f32 + 0.5) / self.scale.x - dest_rec.x) / dest_rec.width;
+            let tx = usize::min(f32::max(source_rec.x + u * source_rec.width, 0.0) as usize, image.width - 1);
+            for y in y0..y1 {
+                let v = ((y as f32 + 0.5) / self.scale.y - dest_rec.y) / dest_rec.height;
+                let ty = usize::min(f32::max(source_rec.y + v * source_rec.height, 0.0) as usize, image.height - 1);
+                blend(&mut self.fb.pixels, (y * self.fb.capacity.0 + x) * 4, tint(image.pixels[ty * image.width + tx], color));
+            }
+        }
+    }
+
+    fn draw_text(&mut self, _text: &str, _x: i32, _y: i32, _size: i32, _color: Color) {}
+
+    fn draw_fps(&mut self, _x: i32, _y: i32) {}
+
+    fn measure_text(&self, _text: &str, _size: i32) -> i32 {
+        0
+    }
+
+    fn get_frame_time(&self) -> f32 {
+        self.frame_time
+    }
+}
@@ -0,0 +1,71 @@
+use raylib::math::Vector2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnKind {
+    Player,
+    Enemy,
+    Pickup,
+    Light,
+    Trigger
+}
+
+pub const SPAWN_KINDS: [SpawnKind; 5] = [
+    SpawnKind::Player,
+    SpawnKind::Enemy,
+    SpawnKind::Pickup,
+    SpawnKind::Light,
+    SpawnKind::Trigger
+];
+
+impl SpawnKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnKind::Player => "player",
+            SpawnKind::Enemy => "enemy",
+            SpawnKind::Pickup => "pickup",
+            SpawnKind::Light => "light",
+            SpawnKind::Trigger => "trigger"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SpawnKind> {
+        SPAWN_KINDS.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    fn default_properties(self) -> Vec<(String, f32)> {
+        let properties: &[(&str, f32)] = match self {
+            SpawnKind::Player => &[],
+            SpawnKind::Enemy => &[("health", 100.0), ("speed", 1.0)],
+            SpawnKind::Pickup => &[("item", 0.0), ("amount", 25.0)],
+            SpawnKind::Light => &[("radius", 4.0), ("intensity", 1.0), ("sparks", 0.0)],
+            SpawnKind::Trigger => &[("radius", 1.0)]
+        };
+
+        properties.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    }
+}
+
+// where an entity appears when the map starts, with its editable properties
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spawn {
+    pub kind: SpawnKind,
+    pub pos: Vector2,
+    pub properties: Vec<(String, f32)>
+}
+
+impl Spawn {
+    pub fn new(kind: SpawnKind, pos: Vector2) -> Spawn {
+        Spawn { kind, pos, properties: kind.default_properties() }
+    }
+
+    pub fn property(&self, name: &str) -> Option<f32> {
+        self.properties.iter().find(|(n, _)| n == name).map(|(_, value)| *value)
+    }
+
+    pub fn set_property(&mut self, name: &str, value: f32) {
+        match self.properties.iter_mut().find(|(n, _)| n == name) {
+            Some(property) => property.1 = value,
+            None => self.properties.push((name.to_string(), value))
+        }
+    }
+}
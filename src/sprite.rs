@@ -0,0 +1,22 @@
+use raylib::math::Vector2;
+
+use crate::texture::TextureId;
+
+pub struct Sprite {
+    pub pos: Vector2,
+    pub texture: TextureId,
+    pub scale: f32,
+    // 1 is opaque
+    pub alpha: f32
+}
+
+impl Sprite {
+    pub fn new(x: f32, y: f32, texture: TextureId) -> Sprite {
+        Sprite {
+            pos: Vector2::new(x, y),
+            texture,
+            scale: 1.0,
+            alpha: 1.0
+        }
+    }
+}
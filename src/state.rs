@@ -0,0 +1,546 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use raylib::{drawing::RaylibDrawHandle, math::Vector2, RaylibHandle, RaylibThread};
+
+use crate::{audio::AudioManager, bindings::{Action, Binding, ACTIONS}, columns::save_capture, console::{render_console, Console}, cvar::{CvarRegistry, CvarValue}, demo::{Demo, DemoMode}, game::{Game, Input}, generator::Layout, menu::{PauseMenu, Setting}, minimap::MinimapAnchor, prefab::{load_prefab, save_prefab}, raycast::MAX_TRANSLUCENT_LAYERS, render::{Renderer, MIN_RAYS}, transition::TransitionKind, watchdog::FrameWatchdog, set_ui_scale, ui_scale, window_size, MAX_UI_SCALE, MIN_UI_SCALE};
+
+pub enum Transition {
+    None,
+    Push(Box<dyn GameState>),
+    Pop,
+    // run by every state below the one that asked for it, from the top down
+    Command(String),
+    // empties the stack, which ends the main loop
+    Quit
+}
+
+pub trait GameState {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition;
+    fn update(&mut self, dt: f32);
+    // `alpha` is how far the frame is between the last simulation step and the next one
+    fn render(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, alpha: f32);
+
+    // overlays are drawn on top of the state below them
+    fn is_overlay(&self) -> bool { false }
+
+    // the window changed size, window_size() has the new one
+    fn resize(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread) {}
+    fn run_command(&mut self, _command: &str) {}
+}
+
+pub struct StateStack {
+    pub states: Vec<Box<dyn GameState>>
+}
+
+pub struct Gameplay {
+    pub game: Game,
+    pub renderer: Renderer,
+    pub audio: AudioManager,
+    pub cvars: CvarRegistry<Gameplay>,
+    pub console: Console,
+    pub demo: DemoMode,
+    // the screen transition put back once the demo ends, demos run without one
+    demo_transition: Option<TransitionKind>,
+    pub watchdog: FrameWatchdog
+}
+
+impl Default for StateStack {
+    fn default() -> StateStack {
+        StateStack::new()
+    }
+}
+
+impl StateStack {
+    pub fn new() -> StateStack {
+        StateStack { states: vec![] }
+    }
+
+    pub fn push(&mut self, state: Box<dyn GameState>) {
+        self.states.push(state)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn handle_input(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        let transition = match self.states.last_mut() {
+            Some(state) => state.handle_input(rl, thread),
+            None => Transition::None
+        };
+
+        match transition {
+            Transition::None => {},
+            Transition::Push(state) => self.states.push(state),
+            Transition::Pop => { self.states.pop(); },
+            Transition::Command(command) => {
+                for state in self.states.iter_mut().rev().skip(1) {
+                    state.run_command(&command);
+                }
+            },
+            Transition::Quit => self.states.clear()
+        }
+    }
+
+    // every state, the ones below the top are drawn again once it pops
+    pub fn resize(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        for state in self.states.iter_mut() {
+            state.resize(rl, thread);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(state) = self.states.last_mut() {
+            state.update(dt);
+        }
+    }
+
+    pub fn render(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, alpha: f32) {
+        let mut first = self.states.len();
+        while first > 0 {
+            first -= 1;
+            if !self.states[first].is_overlay() { break }
+        }
+
+        for state in self.states.iter_mut().skip(first) {
+            state.render(d, thread, alpha);
+        }
+    }
+}
+
+const RAY_STEP: usize = 43;
+// same range the engine config accepts
+const MIN_FOV: f32 = 10.0;
+const MAX_FOV: f32 = 170.0;
+// faster than this the fixed steps get too long for collisions to hold up
+const MAX_TIMESCALE: f32 = 4.0;
+// every bounce casts the rest of the ray again, so a hall of mirrors can't multiply the cost without end
+const MAX_MIRROR_BOUNCES: i64 = 8;
+// cells along each side of a generated map when no size is given
+const DEFAULT_GENERATED_SIZE: usize = 32;
+const MAX_MINIMAP_MARGIN: f32 = 100.0;
+
+impl Gameplay {
+    // the cvars start at whatever the game was set up with
+    pub fn new(game: Game, renderer: Renderer, audio: AudioManager) -> Gameplay {
+        let mut cvars: CvarRegistry<Gameplay> = CvarRegistry::new();
+        cvars.register("r_rays", "Number of rays cast per frame", CvarValue::Int(renderer.rays() as i64),
+            |gameplay, value| gameplay.renderer.set_rays(value.as_int().max(0) as usize));
+        cvars.register("r_fog_end", "Distance at which the fog hides everything", CvarValue::Float(game.fog.end),
+            |gameplay, value| gameplay.game.fog.end = value.as_float());
+        cvars.register("p_speed", "Player movement speed", CvarValue::Float(game.player.spd.x),
+            |gameplay, value| gameplay.game.player.spd = Vector2::one().scale_by(value.as_float()));
+        cvars.register("p_snap_turn", "Turn with the keyboard in 45 degree steps", CvarValue::Bool(game.player.snap_turn),
+            |gameplay, value| gameplay.game.player.snap_turn = value.as_bool());
+        cvars.register("ai_debug", "Draw enemy paths", CvarValue::Bool(game.debug_paths),
+            |gameplay, value| gameplay.game.debug_paths = value.as_bool());
+        cvars.register("r_stats", "Show frame time, camera and ray statistics", CvarValue::Bool(game.show_stats),
+            |gameplay, value| gameplay.game.show_stats = value.as_bool());
+        cvars.register("r_validate_dda", "Cast every column with DDA too and mark where it disagrees", CvarValue::Bool(game.validate_traversal),
+            |gameplay, value| gameplay.game.validate_traversal = value.as_bool());
+        cvars.register("r_heatmap", "Color every column by how many cells its ray walked through", CvarValue::Bool(game.show_heatmap),
+            |gameplay, value| gameplay.game.show_heatmap = value.as_bool());
+        cvars.register("r_auto_exposure", "Brighten dark scenes towards an even exposure", CvarValue::Bool(game.auto_exposure),
+            |gameplay, value| gameplay.game.auto_exposure = value.as_bool());
+        cvars.register("r_daylight", "Brightness of everything in view, from 0 to 1, the middle of the day with a day cycle", CvarValue::Float(game.daylight.level),
+            |gameplay, value| gameplay.game.daylight.level = value.as_float().clamp(0.0, 1.0));
+        cvars.register("r_day_cycle", "Seconds from one noon to the next, 0 keeps the daylight still", CvarValue::Float(game.daylight.cycle),
+            |gameplay, value| gameplay.game.daylight.cycle = value.as_float().max(0.0));
+        cvars.register("r_night", "Share of the daylight left at midnight, from 0 to 1", CvarValue::Float(game.daylight.night),
+            |gameplay, value| gameplay.game.daylight.night = value.as_float().clamp(0.0, 1.0));
+        cvars.register("aim_assist", "Share of the way shots turn to an enemy close to the crosshair, 0 is off", CvarValue::Float(game.weapon.aim_assist),
+            |gameplay, value| gameplay.game.weapon.aim_assist = value.as_float().clamp(0.0, 1.0));
+        cvars.register("timescale", "How fast game time runs, 1 is real time", CvarValue::Float(game.timescale),
+            |gameplay, value| gameplay.game.timescale = value.as_float().clamp(0.0, MAX_TIMESCALE));
+        cvars.register("time_trial", "Race a ghost of the best run of the map", CvarValue::Bool(game.time_trial),
+            |gameplay, value| gameplay.game.time_trial = value.as_bool());
+        cvars.register("hud_timer", "Show the time spent in the current level", CvarValue::Bool(game.show_timer),
+            |gameplay, value| gameplay.game.show_timer = value.as_bool());
+        cvars.register("run_summary", "Save a json and a route image of every finished map", CvarValue::Bool(game.export_summary),
+            |gameplay, value| gameplay.game.export_summary = value.as_bool());
+        cvars.register("r_translucent_layers", "How many glass and other see-through cells a ray passes before the last one stops it", CvarValue::Int(game.translucent_layers as i64),
+            |gameplay, value| gameplay.game.translucent_layers = value.as_int().clamp(1, MAX_TRANSLUCENT_LAYERS as i64) as usize);
+        cvars.register("r_mirror_bounces", "How many mirrors a ray bounces off, 0 draws mirrors as plain glass", CvarValue::Int(game.mirror_bounces as i64),
+            |gameplay, value| gameplay.game.mirror_bounces = value.as_int().clamp(0, MAX_MIRROR_BOUNCES) as usize);
+        cvars.register("r_transition", "Screen transition between scenes, 0 none, 1 fade, 2 melt, 3 wipe", CvarValue::Int(TransitionKind::index(game.transition_kind)),
+            |gameplay, value| gameplay.game.transition_kind = TransitionKind::from_index(value.as_int()));
+        cvars.register("r_fov", "Horizontal field of view in degrees", CvarValue::Float(game.fov),
+            |gameplay, value| gameplay.game.fov = value.as_float().clamp(MIN_FOV, MAX_FOV));
+        cvars.register("m_sensitivity", "How far the view turns per pixel the mouse moves", CvarValue::Float(game.mouse_sensitivity),
+            |gameplay, value| gameplay.game.mouse_sensitivity = value.as_float().max(0.0));
+        cvars.register("s_volume", "Master volume, from 0 to 1", CvarValue::Float(audio.master_volume),
+            |gameplay, value| gameplay.audio.master_volume = value.as_float().clamp(0.0, 1.0));
+        cvars.register("s_captions", "Write out door, heartbeat and secret sounds on screen with where they came from", CvarValue::Bool(game.captions.enabled),
+            |gameplay, value| gameplay.game.captions.enabled = value.as_bool());
+        cvars.register("ui_scale", "Size of the hud, minimap, menus and their fonts, from 0.5 to 3", CvarValue::Float(ui_scale()),
+            |gameplay, value| {
+                set_ui_scale(value.as_float());
+                gameplay.game.fit_minimap();
+            });
+        cvars.register("r_software", "Draw the walls into a pixel buffer uploaded once a frame instead of a draw call per column", CvarValue::Bool(renderer.is_software()),
+            |gameplay, value| gameplay.renderer.set_software(value.as_bool()));
+        cvars.register("minimap_anchor", "Corner the minimap sits in, 0 top left, 1 top right, 2 bottom left, 3 bottom right", CvarValue::Int(game.minimap_anchor.index()),
+            |gameplay, value| {
+                gameplay.game.minimap_anchor = MinimapAnchor::from_index(value.as_int());
+                gameplay.game.fit_minimap();
+            });
+        cvars.register("minimap_margin", "Pixels between the minimap and the window edges", CvarValue::Float(game.minimap_margin),
+            |gameplay, value| {
+                gameplay.game.minimap_margin = value.as_float().clamp(0.0, MAX_MINIMAP_MARGIN);
+                gameplay.game.fit_minimap();
+            });
+        cvars.register("minimap_opacity", "How solid the minimap is drawn, from 0 to 1", CvarValue::Float(game.minimap_opacity),
+            |gameplay, value| gameplay.game.minimap_opacity = value.as_float().clamp(0.0, 1.0));
+        cvars.register("r_theme", "Theme the textures are drawn from, by its place among the themes", CvarValue::Int(game.theme as i64),
+            |gameplay, value| {
+                let theme = value.as_int().clamp(0, gameplay.game.themes.len().saturating_sub(1) as i64) as usize;
+                gameplay.game.theme_stale |= theme != gameplay.game.theme;
+                gameplay.game.theme = theme;
+            });
+        cvars.register("r_show_rays", "Draw the cast rays and their hits on the minimap", CvarValue::Bool(game.show_rays),
+            |gameplay, value| gameplay.game.show_rays = value.as_bool());
+
+        cvars.register("max_entities", "Live entities at once before particles and then bullet holes are dropped, 0 is no limit", CvarValue::Int(game.budget.max as i64),
+            |gameplay, value| gameplay.game.budget.max = value.as_int().max(0) as usize);
+        cvars.register("frame_budget", "Log where the time went in every frame longer than this many milliseconds, 0 is off", CvarValue::Float(0.0),
+            |gameplay, value| gameplay.watchdog.budget = value.as_float().max(0.0));
+
+        Gameplay { game, renderer, audio, cvars, console: Console::new(), demo: DemoMode::Off, demo_transition: None, watchdog: FrameWatchdog::new() }
+    }
+
+    pub fn set_cvar(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let change = self.cvars.set(name, value)?;
+        change.apply(self);
+        Ok(())
+    }
+
+    // "name" prints a cvar, "name value" sets it and "cvars" lists them all,
+    // "bind", "unbind" and "bindings" edit and list the controls, "record", "play" and "stop" the demos,
+    // "capture_columns" dumps the last frame's columns, "prefab" and "save_prefab" load and save the editor clipboard,
+    // "editor_recover" opens the autosave in the editor
+    pub fn execute(&mut self, command: &str) {
+        let mut words = command.split_whitespace();
+        let Some(name) = words.next() else { return };
+        let value: Vec<&str> = words.collect();
+
+        if matches!(name, "bind" | "unbind" | "bindings") {
+            if let Err(err) = self.edit_bindings(name, &value) {
+                self.console.print(err);
+            }
+            return
+        }
+
+        if matches!(name, "record" | "play" | "stop") {
+            if let Err(err) = self.demo_command(name, &value) {
+                self.console.print(err);
+            }
+            return
+        }
+
+        if name == "capture_columns" {
+            if let Err(err) = self.capture_columns(&value) {
+                self.console.print(err);
+            }
+            return
+        }
+
+        if matches!(name, "prefab" | "save_prefab") {
+            if let Err(err) = self.prefab_command(name, &value) {
+                self.console.print(err);
+            }
+            return
+        }
+
+        if name == "editor_recover" {
+            match self.game.recover_autosave() {
+                Ok(map) => self.console.print(format!("Restored the unsaved changes to {}", map)),
+                Err(err) => self.console.print(err)
+            }
+            return
+        }
+
+        if name == "generate" {
+            if let Err(err) = self.generate_level(&value) {
+                self.console.print(err);
+            }
+            return
+        }
+
+        if name == "cvars" {
+            let lines: Vec<String> = self.cvars.cvars().iter().map(|cvar| format!("{} {} - {}", cvar.name, cvar.value, cvar.help)).collect();
+            for line in lines {
+                self.console.print(line);
+            }
+            return
+        }
+
+        if value.is_empty() {
+            match self.cvars.get(name) {
+                Some(value) => self.console.print(format!("{} {}", name, value)),
+                None => self.console.print(format!("Unknown cvar '{}'", name))
+            }
+            return
+        }
+
+        if let Err(err) = self.set_cvar(name, &value.join(" ")) {
+            self.console.print(err);
+        }
+    }
+
+    // "prefab <name>" loads a prefab into the editor clipboard, "save_prefab <name>" saves the clipboard as one
+    fn prefab_command(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        let [name] = args else { return Err(format!("Usage: {} <name>", command)) };
+        if command == "prefab" {
+            let prefab = load_prefab(name, &self.game.textures)?;
+            self.console.print(format!("Loaded a {}x{} prefab, ctrl+v in the editor stamps it", prefab.cols, prefab.rows));
+            self.game.editor.clipboard = Some(prefab);
+            return Ok(())
+        }
+
+        let clipboard = self.game.editor.clipboard.as_ref().ok_or("The clipboard is empty, select cells with shift and copy them with ctrl+c")?;
+        let path = save_prefab(name, clipboard)?;
+        self.console.print(format!("Saved the clipboard to '{}'", path));
+        Ok(())
+    }
+
+    // "capture_columns <path>" writes what the renderer worked out for every column of the last frame,
+    // for comparing against another build with --diff-columns
+    fn capture_columns(&mut self, args: &[&str]) -> Result<(), String> {
+        let [path] = args else { return Err("Usage: capture_columns <path>".to_string()) };
+        save_capture(path, &self.renderer.capture_columns(&self.game))?;
+        self.console.print(format!("Captured {} columns to '{}'", self.renderer.rays(), path));
+        Ok(())
+    }
+
+    // "bindings [action]" lists, "bind <action> <key>" adds, "unbind <action> [key]" removes one or all
+    fn edit_bindings(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        let action = args.first().map(|name| Action::from_name(name).ok_or(format!("Unknown action '{}'", name))).transpose()?;
+        let binding = args.get(1).map(|name| Binding::from_name(name).ok_or(format!("Unknown key '{}'", name))).transpose()?;
+        let bindings = &mut self.game.bindings;
+
+        match (command, action, binding) {
+            ("bind", Some(action), Some(binding)) => bindings.bind(action, binding),
+            ("unbind", Some(action), Some(binding)) => {
+                if !bindings.unbind(action, binding) {
+                    return Err(format!("{} is not bound to {}", action.name(), binding.name()))
+                }
+            },
+            ("unbind", Some(action), None) => bindings.clear(action),
+            ("bindings", _, None) => {},
+            _ => return Err(format!("Usage: {}", match command {
+                "bind" => "bind <action> <key>",
+                "unbind" => "unbind <action> [key]",
+                _ => "bindings [action]"
+            }))
+        }
+
+        let actions = match action {
+            Some(action) => vec![action],
+            None => ACTIONS.to_vec()
+        };
+        for action in actions {
+            let names: Vec<String> = self.game.bindings.get(action).iter().map(|binding| binding.name()).collect();
+            self.console.print(format!("{}: {}", action.name(), names.join(", ")));
+        }
+        Ok(())
+    }
+
+    // "generate <maze|dungeon> [size] [seed]", a random seed is printed so the map can be made again
+    fn generate_level(&mut self, args: &[&str]) -> Result<(), String> {
+        let layout = Layout::from_name(args.first().ok_or("Usage: generate <maze|dungeon> [size] [seed]")?)?;
+        let size = match args.get(1) {
+            Some(size) => size.parse::<usize>().map_err(|err| format!("Invalid size '{}': {}", size, err))?,
+            None => DEFAULT_GENERATED_SIZE
+        };
+        let seed = match args.get(2) {
+            Some(seed) => seed.parse::<u32>().map_err(|err| format!("Invalid seed '{}': {}", seed, err))?,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.subsec_nanos())
+        };
+
+        self.game.generate_level(layout, size, seed)?;
+        self.console.print(format!("Generated a {}x{} map with seed {}", size, size, seed));
+        Ok(())
+    }
+
+    // "record <path>" restarts the current map and records from there until "stop" saves it,
+    // "play <path>" restarts the demo's map and replays it
+    fn demo_command(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        if command == "stop" {
+            self.stop_demo();
+            return Ok(())
+        }
+
+        let path = args.first().ok_or(format!("Usage: {} <path>", command))?.to_string();
+        self.stop_demo();
+        let demo = if command == "record" {
+            let player = &self.game.player;
+            Demo::new(self.game.editor.path.clone(), player.dir, player.health, player.ammo)
+        } else { Demo::load(&path)? };
+
+        self.game.restart_level(&demo.map)?;
+        let player = &mut self.game.player;
+        player.dir = demo.dir;
+        player.health = demo.health;
+        player.ammo = demo.ammo;
+        player.pitch = 0.0;
+        self.game.input = Input::default();
+        self.demo_transition = self.game.transition_kind.take();
+
+        self.console.print(format!("{} {} on {}", if command == "record" { "Recording" } else { "Playing" }, path, demo.map));
+        self.demo = if command == "record" { DemoMode::Recording(path, demo) }
+        else { DemoMode::Playing(demo, 0) };
+        Ok(())
+    }
+
+    // a recording is saved, a playback says where it left the player so runs can be compared
+    fn stop_demo(&mut self) {
+        match std::mem::replace(&mut self.demo, DemoMode::Off) {
+            DemoMode::Off => return,
+            DemoMode::Recording(path, demo) => match demo.save(&path) {
+                Ok(()) => self.console.print(format!("Saved {} ticks to {}", demo.ticks.len(), path)),
+                Err(err) => self.console.print(err)
+            },
+            DemoMode::Playing(demo, tick) => {
+                let player = &self.game.player;
+                self.console.print(format!("Played {} of {} ticks, player at {:.3},{:.3}", tick, demo.ticks.len(), player.pos.x, player.pos.y));
+            }
+        }
+        self.game.transition_kind = self.demo_transition.take();
+    }
+
+    // what the settings screen can change, starting at the current values
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting { label: "Field of view", cvar: "r_fov", value: self.game.fov, min: MIN_FOV, max: MAX_FOV, step: 5.0 },
+            Setting { label: "Rays", cvar: "r_rays", value: self.renderer.rays() as f32, min: MIN_RAYS as f32, max: window_size().x, step: RAY_STEP as f32 },
+            Setting { label: "Mouse sensitivity", cvar: "m_sensitivity", value: self.game.mouse_sensitivity, min: 0.0005, max: 0.01, step: 0.0005 },
+            Setting { label: "Volume", cvar: "s_volume", value: self.audio.master_volume, min: 0.0, max: 1.0, step: 0.1 },
+            Setting { label: "Captions", cvar: "s_captions", value: self.game.captions.enabled as i32 as f32, min: 0.0, max: 1.0, step: 1.0 },
+            Setting { label: "UI scale", cvar: "ui_scale", value: ui_scale(), min: MIN_UI_SCALE, max: MAX_UI_SCALE, step: 0.1 },
+            Setting { label: "Theme", cvar: "r_theme", value: self.game.theme as f32, min: 0.0, max: self.game.themes.len().saturating_sub(1) as f32, step: 1.0 },
+            Setting { label: "Daylight", cvar: "r_daylight", value: self.game.daylight.level, min: 0.0, max: 1.0, step: 0.1 },
+            Setting { label: "Day length", cvar: "r_day_cycle", value: self.game.daylight.cycle, min: 0.0, max: 600.0, step: 30.0 },
+            Setting { label: "Minimap corner", cvar: "minimap_anchor", value: self.game.minimap_anchor.index() as f32, min: 0.0, max: 3.0, step: 1.0 },
+            Setting { label: "Minimap margin", cvar: "minimap_margin", value: self.game.minimap_margin, min: 0.0, max: MAX_MINIMAP_MARGIN, step: 5.0 },
+            Setting { label: "Minimap opacity", cvar: "minimap_opacity", value: self.game.minimap_opacity, min: 0.0, max: 1.0, step: 0.1 },
+            Setting { label: "Snap turning", cvar: "p_snap_turn", value: self.game.player.snap_turn as i32 as f32, min: 0.0, max: 1.0, step: 1.0 },
+            Setting { label: "Aim assist", cvar: "aim_assist", value: self.game.weapon.aim_assist, min: 0.0, max: 1.0, step: 0.1 }
+        ]
+    }
+
+    fn toggle_cvar(&mut self, name: &str) {
+        let on = self.cvars.get(name).is_some_and(|value| value.as_bool());
+        self.execute(&format!("{} {}", name, !on as i32));
+    }
+}
+
+impl GameState for Gameplay {
+    fn handle_input(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        // before anything else, so a new map isn't drawn with placeholders and demos get it too
+        if self.game.theme_stale {
+            self.game.apply_theme(rl, thread);
+        }
+        if self.game.textures_stale {
+            self.game.stream_textures(rl, thread);
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_GRAVE) {
+            self.console.active = !self.console.active;
+        }
+
+        // the console takes over the keyboard while it is open
+        if self.console.active {
+            self.game.input = Input::default();
+            if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_ESCAPE) {
+                self.console.active = false;
+                return Transition::None
+            }
+            if let Some(command) = self.console.handle_input(rl) {
+                self.execute(&command);
+            }
+            return Transition::None
+        }
+
+        let start = rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, raylib::ffi::GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT);
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_ESCAPE) || rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_P) || start {
+            self.audio.pause_music();
+            self.game.stop_interpolation();
+            self.game.input = Input::default();
+            return Transition::Push(Box::new(PauseMenu::new(self.settings(), self.game.panel.clone())))
+        }
+
+        // trade rendering quality for speed at runtime
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_KP_ADD) {
+            self.execute(&format!("r_rays {}", self.renderer.rays() + RAY_STEP));
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_MINUS) || rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_KP_SUBTRACT) {
+            self.execute(&format!("r_rays {}", self.renderer.rays().saturating_sub(RAY_STEP)));
+        }
+
+        // shift+F3 for the enemy paths, F3 alone for the stats
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F3) {
+            if rl.is_key_down(raylib::ffi::KeyboardKey::KEY_LEFT_SHIFT) { self.toggle_cvar("ai_debug") }
+            else { self.toggle_cvar("r_stats") }
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F4) {
+            self.toggle_cvar("r_show_rays");
+        }
+
+        if rl.is_key_pressed(raylib::ffi::KeyboardKey::KEY_F8) {
+            self.game.reload_assets(rl, thread, true);
+        }
+
+        // a playing demo is the only input the game gets
+        if !matches!(self.demo, DemoMode::Playing(..)) {
+            self.watchdog.measure("input", || self.game.handle_input(rl, thread));
+        }
+        Transition::None
+    }
+
+    fn update(&mut self, dt: f32) {
+        match &mut self.demo {
+            DemoMode::Off => {},
+            DemoMode::Recording(_, demo) => demo.record(&self.game.input),
+            DemoMode::Playing(demo, tick) => match demo.ticks.get(*tick) {
+                Some(input) => {
+                    self.game.input = input.clone();
+                    *tick += 1;
+                },
+                None => self.stop_demo()
+            }
+        }
+        let timescale = self.game.timescale;
+        self.watchdog.measure("update", || self.game.update(dt * timescale));
+
+        let (game, audio) = (&mut self.game, &mut self.audio);
+        self.watchdog.measure("audio", || {
+            for (effect, pos) in game.sounds.drain(..) {
+                game.captions.hear(effect, pos, &game.player);
+                audio.play(effect, pos, &game.player, &game.board);
+            }
+            audio.update(&game.player, &game.board);
+        });
+    }
+
+    // the last thing gameplay does in a frame, so it is where the frame is checked against the budget
+    fn render(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread, alpha: f32) {
+        self.game.render(d, thread, &mut self.renderer, alpha);
+        render_console(d, &self.console, self.game.panel.as_deref());
+
+        for (system, ms) in self.renderer.timings() {
+            self.watchdog.record(system, *ms);
+        }
+        self.watchdog.end_frame();
+    }
+
+    fn resize(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.renderer.fit_window(rl, thread);
+        self.game.fit_minimap();
+    }
+
+    fn run_command(&mut self, command: &str) {
+        self.execute(command);
+    }
+}
@@ -0,0 +1,63 @@
+use std::{fs, path::Path};
+
+use raylib::{color::Color, math::Vector2};
+use serde::Serialize;
+
+use crate::{capture::timestamp, game::{generated_seed, Game}, thumbnail::{render_top_down, top_down_cell_size}, world::LevelStats};
+
+pub const SUMMARIES_DIR: &str = "./summaries";
+
+// longest side of the route image in pixels
+const ROUTE_SIZE: usize = 512;
+
+// what a finished run is shared as, next to a png of the route taken through the map
+#[derive(Serialize)]
+pub struct RunSummary {
+    pub map: String,
+    // only generated maps have one
+    pub seed: Option<u32>,
+    // which version of the map the run was on, runs are only compared to runs with the same one
+    pub map_hash: String,
+    pub stats: LevelStats,
+    pub route: Vec<(f32, f32)>
+}
+
+impl RunSummary {
+    pub fn from_game(game: &Game, stats: &LevelStats) -> RunSummary {
+        RunSummary {
+            map: game.editor.path.clone(),
+            seed: generated_seed(&game.editor.path),
+            map_hash: format!("{:016x}", game.map_hash),
+            stats: stats.clone(),
+            route: game.trail.iter().map(|pos| (pos.x, pos.y)).collect()
+        }
+    }
+}
+
+// writes "summary-<time>.json" and "summary-<time>.png", returning the path of the json
+pub fn export_summary(game: &Game, stats: &LevelStats) -> Result<String, String> {
+    fs::create_dir_all(SUMMARIES_DIR).map_err(|err| format!("Could not create '{}': {}", SUMMARIES_DIR, err))?;
+    let name = format!("{}/summary-{}", SUMMARIES_DIR, timestamp());
+
+    let summary = RunSummary::from_game(game, stats);
+    let json = serde_json::to_string_pretty(&summary).map_err(|err| format!("Could not serialize summary: {}", err))?;
+    let path = format!("{}.json", name);
+    fs::write(&path, json).map_err(|err| format!("Could not write '{}': {}", path, err))?;
+
+    let cell = top_down_cell_size(&game.board, ROUTE_SIZE) as f32;
+    let mut image = render_top_down(&game.board, ROUTE_SIZE);
+    for pair in game.trail.windows(2) {
+        image.draw_line_v(pair[0].scale_by(cell), pair[1].scale_by(cell), Color::RED);
+    }
+    if let Some(end) = game.trail.last() {
+        let end: Vector2 = end.scale_by(cell);
+        image.draw_circle(end.x as i32, end.y as i32, i32::max(cell as i32 / 3, 2), Color::RED);
+    }
+
+    let image_path = format!("{}.png", name);
+    image.export_image(&image_path);
+    if !Path::new(&image_path).exists() {
+        return Err(format!("Could not write '{}'", image_path))
+    }
+    Ok(path)
+}
@@ -0,0 +1,12 @@
+// stepping onto a teleporter cell moves the player to `target`, turned by `turn` degrees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Teleporter {
+    pub target: (usize, usize),
+    pub turn: f32
+}
+
+impl Teleporter {
+    pub fn new(target: (usize, usize), turn: f32) -> Teleporter {
+        Teleporter { target, turn }
+    }
+}
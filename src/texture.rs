@@ -0,0 +1,525 @@
+use std::{collections::{HashMap, HashSet}, fs, path::Path};
+
+use raylib::{color::Color, math::Rectangle, texture::{Image, RaylibTexture2D, Texture2D}, RaylibHandle, RaylibThread};
+
+use crate::assets::AssetRoots;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlatId(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationId(usize);
+
+impl TextureId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl FlatId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl AnimationId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+// CPU-side texture used by the floor and ceiling caster
+pub struct FlatTexture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>
+}
+
+// frames of an animated wall, each one a whole texture or a rectangle of a sprite sheet
+pub struct Animation {
+    pub frames: Vec<(TextureId, Option<Rectangle>)>,
+    pub fps: f32
+}
+
+// every texture packed side by side, so drawing them doesn't rebind textures
+pub struct Atlas {
+    pub texture: Texture2D,
+    // None for textures that weren't loaded when the atlas was packed or were unloaded since
+    pub regions: Vec<Option<Rectangle>>
+}
+
+const ATLAS_WIDTH: i32 = 2048;
+const ATLAS_PADDING: i32 = 1;
+
+// drawn instead of textures that couldn't be loaded or were unloaded, loud enough to be noticed
+fn placeholder_image() -> Image {
+    Image::gen_image_checked(64, 64, 8, 8, Color::MAGENTA, Color::BLACK)
+}
+
+// "<name>.sheet" next to "<name>.png" cuts the png into regions, each one registered as
+// "<png path>#<region>" and usable anywhere a whole texture is:
+//   <region> <x> <y> <width> <height>
+//   anim <fps> <region...>            an animation of those regions, after the other animations
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SheetDef {
+    pub regions: Vec<(String, Rectangle)>,
+    pub animations: Vec<(f32, Vec<String>)>
+}
+
+impl SheetDef {
+    pub fn from_text(text: &str) -> Result<SheetDef, String> {
+        let mut sheet = SheetDef::default();
+        for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words[0] == "anim" {
+                let fps = words.get(1).and_then(|fps| fps.parse::<f32>().ok()).ok_or(format!("Invalid animation fps in '{}'", line))?;
+                let frames: Vec<String> = words[2..].iter().map(|frame| frame.to_string()).collect();
+                if let Some(frame) = frames.iter().find(|frame| !sheet.regions.iter().any(|(name, _)| name == *frame)) {
+                    return Err(format!("Unknown region '{}' in '{}'", frame, line))
+                }
+                if frames.is_empty() {
+                    return Err(format!("Animation without frames in '{}'", line))
+                }
+                sheet.animations.push((fps, frames));
+                continue
+            }
+
+            if words.len() != 5 {
+                return Err(format!("Expected '<region> <x> <y> <width> <height>', got '{}'", line))
+            }
+            let number = |word: &str| word.parse::<f32>().map_err(|err| format!("Invalid number '{}' in '{}': {}", word, line, err));
+            sheet.regions.push((words[0].to_string(), Rectangle::new(number(words[1])?, number(words[2])?, number(words[3])?, number(words[4])?)));
+        }
+        Ok(sheet)
+    }
+}
+
+// a texture that is only loaded while a map uses it, asked for by name it stays loaded
+struct Streamed {
+    file: String,
+    pinned: bool
+}
+
+#[derive(Default)]
+pub struct TextureRegistry {
+    // None while a streamed texture isn't needed
+    textures: Vec<Option<Texture2D>>,
+    flats: Vec<FlatTexture>,
+    // the flats read from image files, the skies for one
+    flat_paths: HashMap<String, FlatId>,
+    paths: HashMap<String, TextureId>,
+    animations: Vec<Animation>,
+    atlas: Option<Atlas>,
+    streamed: HashMap<TextureId, Streamed>,
+    // regions cut from sprite sheets, by the sheet they are in and where
+    sheet_regions: HashMap<TextureId, (TextureId, Rectangle)>,
+    // drawn for the streamed textures that aren't loaded
+    placeholder: Option<Texture2D>,
+    // bumped whenever the pixels behind an id can have changed, for whatever keeps copies of them
+    generation: u64
+}
+
+impl FlatTexture {
+    pub fn from_image(image: &Image) -> FlatTexture {
+        FlatTexture {
+            width: image.width as usize,
+            height: image.height as usize,
+            pixels: image.get_image_data().to_vec()
+        }
+    }
+
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        let x = usize::min((u * self.width as f32) as usize, self.width - 1);
+        let y = usize::min((v * self.height as f32) as usize, self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Animation {
+    pub fn from_frames(frames: &[TextureId], fps: f32) -> Animation {
+        Animation { frames: frames.iter().map(|id| (*id, None)).collect(), fps }
+    }
+
+    pub fn frame(&self, time: f32) -> usize {
+        if self.frames.is_empty() { return 0 }
+        (f32::max(time * self.fps, 0.0) as usize) % self.frames.len()
+    }
+}
+
+impl TextureRegistry {
+    pub fn new() -> TextureRegistry {
+        TextureRegistry::default()
+    }
+
+    // loading the same path twice hands out the same id
+    pub fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, path: &str) -> Result<TextureId, String> {
+        self.load_as(rl, thread, path, path)
+    }
+
+    // registers the texture under `path` while reading it from `file`, so a mod
+    // can replace a texture without changing how maps and saves refer to it
+    pub fn load_as(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, path: &str, file: &str) -> Result<TextureId, String> {
+        if let Some(id) = self.paths.get(path).copied() {
+            let sheet = self.sheet_of(id);
+            if let Some(streamed) = self.streamed.get_mut(&sheet) {
+                streamed.pinned = true;
+                if self.textures[sheet.0].is_none() {
+                    self.textures[sheet.0] = Some(rl.load_texture(thread, &streamed.file)?);
+                    self.generation += 1;
+                }
+            }
+            return Ok(id)
+        }
+
+        let id = self.add(rl.load_texture(thread, file)?);
+        self.paths.insert(path.to_string(), id);
+        Ok(id)
+    }
+
+    // like `load_as`, but a file that can't be loaded is replaced by a checkerboard so the game keeps
+    // running, only failing to make the checkerboard itself is an error
+    pub fn load_or_placeholder(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, path: &str, file: &str) -> Result<TextureId, String> {
+        match self.load_as(rl, thread, path, file) {
+            Ok(id) => Ok(id),
+            Err(err) => {
+                println!("ERROR: {}", err);
+                // a streamed texture keeps its id and draws the placeholder
+                if let Some(id) = self.by_path(path) {
+                    return Ok(id)
+                }
+                let id = self.add(rl.load_texture_from_image(thread, &placeholder_image())?);
+                self.paths.insert(path.to_string(), id);
+                Ok(id)
+            }
+        }
+    }
+
+    // every png in the asset folders, registered by its base path, so maps and saves can refer
+    // to any of them without the game loading each one by name
+    pub fn load_all(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, assets: &AssetRoots) -> Result<Vec<TextureId>, String> {
+        let mut ids = assets.files("png").iter()
+            .map(|name| self.load_or_placeholder(rl, thread, &assets.base_path(name), &assets.resolve(name)))
+            .collect::<Result<Vec<TextureId>, String>>()?;
+        ids.extend(self.load_sheets(assets)?);
+        Ok(ids)
+    }
+
+    // the regions of every sheet in the asset folders, cut from the png registered next to it
+    fn load_sheets(&mut self, assets: &AssetRoots) -> Result<Vec<TextureId>, String> {
+        let mut ids = vec![];
+        for name in assets.files("sheet") {
+            let file = assets.resolve(&name);
+            let png = assets.base_path(&Path::new(&name).with_extension("png").to_string_lossy());
+            let sheet = self.by_path(&png).ok_or(format!("Sheet '{}' has no '{}' next to it", file, png))?;
+            let text = fs::read_to_string(&file).map_err(|err| format!("Could not load sheet '{}': {}", file, err))?;
+            let def = SheetDef::from_text(&text).map_err(|err| format!("Could not load sheet '{}': {}", file, err))?;
+
+            for (region, rect) in def.regions.iter() {
+                let id = self.by_path(&format!("{}#{}", png, region)).unwrap_or_else(|| {
+                    self.textures.push(None);
+                    TextureId(self.textures.len() - 1)
+                });
+                self.paths.insert(format!("{}#{}", png, region), id);
+                self.sheet_regions.insert(id, (sheet, *rect));
+                ids.push(id);
+            }
+            for (fps, frames) in def.animations.iter() {
+                let frames: Vec<TextureId> = frames.iter().filter_map(|frame| self.by_path(&format!("{}#{}", png, frame))).collect();
+                self.add_animation(Animation::from_frames(&frames, *fps));
+            }
+        }
+        Ok(ids)
+    }
+
+    // the texture `id` is cut from, itself unless it is a region of a sheet
+    pub fn sheet_of(&self, id: TextureId) -> TextureId {
+        self.sheet_regions.get(&id).map_or(id, |(sheet, _)| *sheet)
+    }
+
+    // like `load_all` without loading any of them yet, `stream` loads the ones a map needs
+    pub fn register_all(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, assets: &AssetRoots) -> Result<Vec<TextureId>, String> {
+        if self.placeholder.is_none() {
+            self.placeholder = Some(rl.load_texture_from_image(thread, &placeholder_image())?);
+        }
+
+        let mut ids: Vec<TextureId> = assets.files("png").iter().map(|name| self.register(&assets.base_path(name), &assets.resolve(name))).collect();
+        ids.extend(self.load_sheets(assets)?);
+        Ok(ids)
+    }
+
+    // a slot for `path` that stays empty until it is streamed in from `file`, so maps can refer to it
+    // without a window. registering the same path twice hands out the same id
+    pub fn register(&mut self, path: &str, file: &str) -> TextureId {
+        if let Some(id) = self.by_path(path) {
+            return id
+        }
+
+        self.textures.push(None);
+        let id = TextureId(self.textures.len() - 1);
+        self.paths.insert(path.to_string(), id);
+        self.streamed.insert(id, Streamed { file: file.to_string(), pinned: false });
+        id
+    }
+
+    // loads the streamed textures in `needed` and frees the rest of them, true if any changed
+    pub fn stream(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, needed: &HashSet<TextureId>) -> bool {
+        // a region needs the whole sheet it is cut from
+        let needed: HashSet<TextureId> = needed.iter().map(|id| self.sheet_of(*id)).collect();
+        let (mut loaded, mut freed) = (0, 0);
+        for (id, streamed) in self.streamed.iter() {
+            let keep = streamed.pinned || needed.contains(id);
+            match (keep, self.textures[id.0].is_some()) {
+                (true, false) => match rl.load_texture(thread, &streamed.file) {
+                    Ok(texture) => {
+                        self.textures[id.0] = Some(texture);
+                        self.generation += 1;
+                        loaded += 1;
+                    },
+                    Err(err) => println!("ERROR: {}", err)
+                },
+                (false, true) => {
+                    self.textures[id.0] = None;
+                    self.generation += 1;
+                    if let Some(region) = self.atlas.as_mut().and_then(|atlas| atlas.regions.get_mut(id.0)) {
+                        *region = None;
+                    }
+                    freed += 1;
+                },
+                _ => {}
+            }
+        }
+
+        if loaded + freed > 0 {
+            println!("INFO: Streamed in {} textures and out {}", loaded, freed);
+        }
+        loaded + freed > 0
+    }
+
+    // the files of the streamed textures in `needed` that aren't loaded, whole sheets for their regions
+    pub fn unloaded_files(&self, needed: &HashSet<TextureId>) -> Vec<(TextureId, String)> {
+        let sheets: HashSet<TextureId> = needed.iter().map(|id| self.sheet_of(*id)).collect();
+        sheets.into_iter()
+            .filter(|id| !self.is_loaded(*id))
+            .filter_map(|id| self.streamed.get(&id).map(|streamed| (id, streamed.file.clone())))
+            .collect()
+    }
+
+    pub fn is_loaded(&self, id: TextureId) -> bool {
+        self.textures.get(self.sheet_of(id).0).is_some_and(|texture| texture.is_some())
+    }
+
+    // makes the texture of a streamed id out of an image decoded ahead of time, unless it was loaded meanwhile
+    pub fn upload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, id: TextureId, image: &Image) -> Result<(), String> {
+        if !self.streamed.contains_key(&id) || self.is_loaded(id) { return Ok(()) }
+        self.textures[id.0] = Some(rl.load_texture_from_image(thread, image)?);
+        self.generation += 1;
+        Ok(())
+    }
+
+    // reads `path` again from `file` and swaps it in under the same id, a new path is loaded like any other.
+    // the old texture is kept when the file can't be read, so a half written file doesn't break the map
+    pub fn reload_as(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, path: &str, file: &str) -> Result<TextureId, String> {
+        let Some(id) = self.by_path(path) else {
+            return self.load_or_placeholder(rl, thread, path, file)
+        };
+
+        self.textures[id.0] = Some(rl.load_texture(thread, file)?);
+        self.generation += 1;
+        // the atlas still has the old pixels
+        if let Some(region) = self.atlas.as_mut().and_then(|atlas| atlas.regions.get_mut(id.0)) {
+            *region = None;
+        }
+        Ok(id)
+    }
+
+    // points `path` at another file under the same id, loaded right away if it is in use and
+    // once a map needs it if it is streamed out
+    pub fn retarget(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, path: &str, file: &str) -> Result<TextureId, String> {
+        let Some(id) = self.by_path(path) else {
+            return self.load_or_placeholder(rl, thread, path, file)
+        };
+
+        if let Some(streamed) = self.streamed.get_mut(&id) {
+            streamed.file = file.to_string();
+            if self.textures[id.0].is_none() { return Ok(id) }
+        }
+        self.reload_as(rl, thread, path, file)
+    }
+
+    // frees the texture behind `id`, the id stays valid and draws the placeholder from then on
+    pub fn unload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, id: TextureId) -> Result<(), String> {
+        if id.0 >= self.textures.len() {
+            return Err(format!("Texture {} does not exist", id.0))
+        }
+
+        self.textures[id.0] = Some(rl.load_texture_from_image(thread, &placeholder_image())?);
+        self.generation += 1;
+        self.streamed.remove(&id);
+        self.sheet_regions.remove(&id);
+        self.paths.retain(|_, texture| *texture != id);
+        if let Some(region) = self.atlas.as_mut().and_then(|atlas| atlas.regions.get_mut(id.0)) {
+            *region = None;
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, texture: Texture2D) -> TextureId {
+        self.textures.push(Some(texture));
+        self.generation += 1;
+        TextureId(self.textures.len() - 1)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn path_of(&self, id: TextureId) -> Option<&str> {
+        self.paths.iter().find(|(_, texture)| **texture == id).map(|(path, _)| path.as_str())
+    }
+
+    pub fn by_path(&self, path: &str) -> Option<TextureId> {
+        self.paths.get(path).copied()
+    }
+
+    // the path every registered texture goes by, the other way around from `by_path`
+    pub fn paths_by_id(&self) -> HashMap<TextureId, &str> {
+        self.paths.iter().map(|(path, id)| (*id, path.as_str())).collect()
+    }
+
+    pub fn flat_id(&self, index: usize) -> Option<FlatId> {
+        if index < self.flats.len() { Some(FlatId(index)) }
+        else { None }
+    }
+
+    pub fn texture_id(&self, index: usize) -> Option<TextureId> {
+        if index < self.textures.len() { Some(TextureId(index)) }
+        else { None }
+    }
+
+    // only streamed textures are ever missing, and registering them makes the placeholder
+    pub fn get(&self, id: TextureId) -> &Texture2D {
+        let id = self.sheet_of(id);
+        match (&self.textures[id.0], &self.placeholder) {
+            (Some(texture), _) | (None, Some(texture)) => texture,
+            (None, None) => unreachable!("texture {} is streamed without a placeholder", id.0)
+        }
+    }
+
+    // texture and source rectangle to draw `id` with, pointing into the atlas once it is packed
+    pub fn region(&self, id: TextureId) -> (&Texture2D, Rectangle) {
+        if let Some((sheet, rect)) = self.sheet_regions.get(&id) {
+            let (texture, region) = self.region(*sheet);
+            return (texture, Rectangle::new(region.x + rect.x, region.y + rect.y, rect.width, rect.height))
+        }
+
+        if let Some(atlas) = &self.atlas {
+            if let Some(Some(region)) = atlas.regions.get(id.0) {
+                return (&atlas.texture, *region)
+            }
+        }
+
+        let texture = self.get(id);
+        (texture, Rectangle::new(0.0, 0.0, texture.width as f32, texture.height as f32))
+    }
+
+    // packs every texture loaded so far into rows sorted by height, textures loaded later are drawn on their own
+    pub fn pack_atlas(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<(), String> {
+        let images = self.textures.iter().map(|texture| texture.as_ref().map(|texture| texture.load_image()).transpose()).collect::<Result<Vec<Option<Image>>, String>>()?;
+
+        let mut order: Vec<usize> = (0..images.len()).filter(|i| images[*i].is_some()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(images[*i].as_ref().map_or(0, |image| image.height)));
+
+        let mut regions = vec![None; images.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for i in order {
+            let Some(image) = &images[i] else { continue };
+            if image.width > ATLAS_WIDTH {
+                return Err(format!("Texture {} is wider than the atlas ({} > {})", i, image.width, ATLAS_WIDTH))
+            }
+
+            if x + image.width > ATLAS_WIDTH {
+                x = 0;
+                y += row_height + ATLAS_PADDING;
+                row_height = 0;
+            }
+
+            regions[i] = Some(Rectangle::new(x as f32, y as f32, image.width as f32, image.height as f32));
+            x += image.width + ATLAS_PADDING;
+            row_height = i32::max(row_height, image.height);
+        }
+
+        let mut atlas = Image::gen_image_color(ATLAS_WIDTH, i32::max(y + row_height, 1), Color::BLANK);
+        for (image, region) in images.iter().zip(regions.iter()) {
+            let (Some(image), Some(region)) = (image, region) else { continue };
+            let source_rec = Rectangle::new(0.0, 0.0, image.width as f32, image.height as f32);
+            atlas.draw(image, source_rec, *region, Color::WHITE);
+        }
+
+        let texture = rl.load_texture_from_image(thread, &atlas)?;
+        self.atlas = Some(Atlas { texture, regions });
+        self.generation += 1;
+        Ok(())
+    }
+
+    // cuts `texture` into `frames` frames of the same width, left to right
+    pub fn sheet_animation(&self, texture: TextureId, frames: usize, fps: f32) -> Animation {
+        let (_, sheet) = self.region(texture);
+        let width = sheet.width / usize::max(frames, 1) as f32;
+        Animation {
+            frames: (0..frames).map(|i| (texture, Some(Rectangle::new(i as f32 * width, 0.0, width, sheet.height)))).collect(),
+            fps
+        }
+    }
+
+    pub fn add_animation(&mut self, animation: Animation) -> AnimationId {
+        self.animations.push(animation);
+        AnimationId(self.animations.len() - 1)
+    }
+
+    pub fn animation_id(&self, index: usize) -> Option<AnimationId> {
+        if index < self.animations.len() { Some(AnimationId(index)) }
+        else { None }
+    }
+
+    pub fn animation(&self, id: AnimationId) -> &Animation {
+        &self.animations[id.0]
+    }
+
+    // texture and source rectangle of the frame shown at `time`
+    pub fn animation_region(&self, id: AnimationId, time: f32) -> (&Texture2D, Rectangle) {
+        let animation = &self.animations[id.0];
+        let Some((texture, rect)) = animation.frames.get(animation.frame(time)) else {
+            return self.region(TextureId(0))
+        };
+
+        let (texture, region) = self.region(*texture);
+        match rect {
+            Some(rect) => (texture, Rectangle::new(region.x + rect.x, region.y + rect.y, rect.width, rect.height)),
+            None => (texture, region)
+        }
+    }
+
+    pub fn add_flat(&mut self, flat: FlatTexture) -> FlatId {
+        self.flats.push(flat);
+        FlatId(self.flats.len() - 1)
+    }
+
+    // read once per path, every map with the same sky shares it
+    pub fn load_flat(&mut self, path: &str) -> Result<FlatId, String> {
+        if let Some(id) = self.flat_paths.get(path) {
+            return Ok(*id)
+        }
+
+        let image = Image::load_image(path).map_err(|err| format!("Could not load '{}': {}", path, err))?;
+        let id = self.add_flat(FlatTexture::from_image(&image));
+        self.flat_paths.insert(path.to_string(), id);
+        Ok(id)
+    }
+
+    pub fn flat(&self, id: FlatId) -> &FlatTexture {
+        &self.flats[id.0]
+    }
+}
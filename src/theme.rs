@@ -0,0 +1,61 @@
+use std::{collections::{BTreeSet, HashMap}, fs, path::Path};
+
+// one "<name>.cfg" per theme here, a "<logical name> <file>" line for every texture it draws.
+// the files are paths from the game folder, "./themes/stone/brick.png" for example
+pub const THEMES_DIR: &str = "./themes";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub files: HashMap<String, String>
+}
+
+// the registry path a logical texture is registered under, the same whatever theme is on
+pub fn theme_path(name: &str) -> String {
+    format!("theme:{}", name)
+}
+
+fn parse_theme(name: &str, text: &str) -> Result<Theme, String> {
+    let mut files = HashMap::new();
+    for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (texture, file) = line.split_once(char::is_whitespace).ok_or(format!("Expected a texture name and a file in '{}'", line))?;
+        files.insert(texture.to_string(), file.trim().to_string());
+    }
+    Ok(Theme { name: name.to_string(), files })
+}
+
+// every theme in the folder by name, the ones that don't load are skipped
+pub fn load_themes() -> Vec<Theme> {
+    let Ok(entries) = fs::read_dir(THEMES_DIR) else { return vec![] };
+    let mut paths: Vec<String> = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cfg"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    // the cvar picks a theme by its place in the list
+    paths.sort();
+
+    paths.iter().filter_map(|path| {
+        let name = Path::new(path).file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        match fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|text| parse_theme(&name, &text)) {
+            Ok(theme) => Some(theme),
+            Err(err) => {
+                println!("ERROR: Skipping theme '{}': {}", path, err);
+                None
+            }
+        }
+    }).collect()
+}
+
+// the logical textures of all the themes, sorted so they get the same ids on every launch
+pub fn texture_names(themes: &[Theme]) -> Vec<String> {
+    let names: BTreeSet<&String> = themes.iter().flat_map(|theme| theme.files.keys()).collect();
+    names.into_iter().cloned().collect()
+}
+
+// what `name` is drawn with under `theme`, from the first theme that has it when that one doesn't
+pub fn theme_file<'a>(themes: &'a [Theme], theme: usize, name: &str) -> Option<&'a str> {
+    themes.get(theme).and_then(|theme| theme.files.get(name))
+        .or_else(|| themes.iter().find_map(|theme| theme.files.get(name)))
+        .map(|file| file.as_str())
+}
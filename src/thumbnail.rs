@@ -0,0 +1,92 @@
+use std::{fs, path::Path};
+
+use raylib::{color::Color, texture::Image};
+
+use crate::{board::{Board, Cell, MIRROR_COLOR}, editor::spawn_color, map::load_board, texture::TextureRegistry};
+
+// longest side of a thumbnail in pixels
+const THUMBNAIL_SIZE: usize = 128;
+
+// the thumbnail of "maps/e1.txt" is cached as "maps/e1.png"
+pub fn thumbnail_path(map_path: &str) -> String {
+    Path::new(map_path).with_extension("png").to_string_lossy().into_owned()
+}
+
+pub fn render_thumbnail(board: &Board) -> Image {
+    render_top_down(board, THUMBNAIL_SIZE)
+}
+
+// top-down view of the board at most `size` pixels along its longest side,
+// one colored square per cell and a dot per spawn
+pub fn render_top_down(board: &Board, size: usize) -> Image {
+    let cell = top_down_cell_size(board, size);
+    let mut image = Image::gen_image_color(board.cols as i32 * cell, board.rows as i32 * cell, Color::DARKGRAY);
+
+    for y in 0..board.rows {
+        for x in 0..board.cols {
+            let color = match board.at(x, y) {
+                Cell::EMPTY => continue,
+                Cell::COLOR(color) => *color,
+                Cell::TEXTURE(_) => Color::LIGHTGRAY,
+                Cell::TranslucentTexture(_) => Color::SKYBLUE,
+                Cell::Door(_) => Color::BROWN,
+                Cell::AnimatedTexture(_) => Color::LIME,
+                Cell::Mirror => MIRROR_COLOR,
+                Cell::Custom(..) => Color::ORANGE,
+                Cell::Segment(_, segment) => {
+                    let (a, b) = segment.endpoints(x, y);
+                    image.draw_line_v(a.scale_by(cell as f32), b.scale_by(cell as f32), Color::LIGHTGRAY);
+                    continue
+                }
+            };
+            image.draw_rectangle(x as i32 * cell, y as i32 * cell, cell, cell, color);
+        }
+    }
+
+    for (x, y) in board.exits() {
+        image.draw_circle(*x as i32 * cell + cell / 2, *y as i32 * cell + cell / 2, i32::max(cell / 3, 1), Color::GOLD);
+    }
+
+    for ((x, y), _) in board.teleporters() {
+        image.draw_circle(*x as i32 * cell + cell / 2, *y as i32 * cell + cell / 2, i32::max(cell / 3, 1), Color::VIOLET);
+    }
+
+    for spawn in board.spawns.iter() {
+        let (x, y) = ((spawn.pos.x * cell as f32) as i32, (spawn.pos.y * cell as f32) as i32);
+        image.draw_circle(x, y, i32::max(cell / 4, 1), spawn_color(spawn.kind));
+    }
+
+    image
+}
+
+// pixels per cell of a top-down view
+pub fn top_down_cell_size(board: &Board, size: usize) -> i32 {
+    usize::max(size / usize::max(board.rows.max(board.cols), 1), 1) as i32
+}
+
+pub fn save_thumbnail(map_path: &str, board: &Board) -> Result<(), String> {
+    let path = thumbnail_path(map_path);
+    render_thumbnail(board).export_image(&path);
+    if !Path::new(&path).exists() {
+        return Err(format!("Could not save thumbnail '{}'", path))
+    }
+
+    Ok(())
+}
+
+// makes sure a map has a thumbnail that is not older than the map itself
+pub fn ensure_thumbnail(map_path: &str, textures: &TextureRegistry) -> Result<String, String> {
+    let path = thumbnail_path(map_path);
+    let modified = |path: &str| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+
+    let fresh = match (modified(&path), modified(map_path)) {
+        (Some(thumbnail), Some(map)) => thumbnail >= map,
+        _ => false
+    };
+
+    if !fresh {
+        save_thumbnail(map_path, &load_board(map_path, textures)?)?;
+    }
+
+    Ok(path)
+}
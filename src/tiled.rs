@@ -0,0 +1,116 @@
+use std::{fs, path::Path};
+
+use raylib::math::Vector2;
+use serde::Deserialize;
+
+use crate::{board::Board, cvar::parse_config, map::parse_cell_token, spawn::{Spawn, SpawnKind}, texture::TextureRegistry};
+
+// the fields of a Tiled json map (.tmj) the importer reads, everything else is ignored
+#[derive(Deserialize)]
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    layers: Vec<TiledLayer>
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Vec<u32>,
+    #[serde(default)]
+    objects: Vec<TiledObject>
+}
+
+#[derive(Deserialize)]
+struct TiledObject {
+    // "type" before Tiled 1.9, "class" after
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    properties: Vec<TiledProperty>
+}
+
+#[derive(Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: serde_json::Value
+}
+
+// the top bits of a tile id say how the tile is flipped, walls can't be
+const TILE_FLIP_FLAGS: u32 = 0xE0000000;
+
+// the tile lookup of "maps/e1.tmj" is "maps/e1.tiles"
+pub fn tiles_path(map_path: &str) -> String {
+    Path::new(map_path).with_extension("tiles").to_string_lossy().into_owned()
+}
+
+pub fn is_tiled_map(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext == "tmj")
+}
+
+// tile layers are stacked in order with empty tiles (id 0) letting the layers below show,
+// each tile id is turned into a cell by the lookup's "<id> <cell>" lines, cells written like in text maps,
+// and objects whose type is a spawn kind become spawns
+pub fn board_from_tiled(json: &str, lookup: &str, textures: &TextureRegistry) -> Result<Board, String> {
+    let map: TiledMap = serde_json::from_str(json).map_err(|err| format!("Invalid Tiled map: {}", err))?;
+    let lookup = parse_config(lookup)?.into_iter()
+        .map(|(id, token)| {
+            let id = id.parse::<u32>().map_err(|err| format!("Invalid tile id '{}': {}", id, err))?;
+            let cell = parse_cell_token(&token, textures).map_err(|err| format!("Tile {}: {}", id, err))?;
+            Ok((id, cell))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut board = Board::new(map.height, map.width);
+    for layer in &map.layers {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.data.len() != map.width * map.height {
+                    return Err(format!("Tile layer has {} tiles, expected {}", layer.data.len(), map.width * map.height))
+                }
+
+                for (i, id) in layer.data.iter().map(|id| id & !TILE_FLIP_FLAGS).enumerate() {
+                    if id == 0 { continue }
+
+                    let (x, y) = (i % map.width, i / map.width);
+                    let (cell, height) = lookup.iter().find(|(tile, _)| *tile == id).map(|(_, cell)| *cell)
+                        .ok_or(format!("Cell {},{}: Tile {} is not in the lookup", x, y, id))?;
+                    board.set(x, y, cell);
+                    board.set_height(x, y, height);
+                }
+            },
+            "objectgroup" => {
+                for object in &layer.objects {
+                    let name = if object.class.is_empty() { &object.kind } else { &object.class };
+                    let Some(kind) = SpawnKind::from_name(name) else { continue };
+
+                    let mut spawn = Spawn::new(kind, Vector2::new(object.x / map.tilewidth, object.y / map.tileheight));
+                    for property in &object.properties {
+                        if let Some(value) = property.value.as_f64() {
+                            spawn.set_property(&property.name, value as f32);
+                        }
+                    }
+                    board.spawns.push(spawn);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    Ok(board)
+}
+
+pub fn load_tiled(path: &str, textures: &TextureRegistry) -> Result<Board, String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("Could not load map '{}': {}", path, err))?;
+    let lookup_path = tiles_path(path);
+    let lookup = fs::read_to_string(&lookup_path).map_err(|err| format!("Could not load tile lookup '{}': {}", lookup_path, err))?;
+    board_from_tiled(&json, &lookup, textures).map_err(|err| format!("{}: {}", path, err))
+}
@@ -0,0 +1,52 @@
+use std::ops::{Add, Mul};
+
+use raylib::math::{Rectangle, Vector2};
+
+pub struct Transform2D {
+    pub offset: Vector2,
+    pub zoom: Vector2
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        Transform2D {
+            offset: Vector2::zero(),
+            zoom: Vector2::one()
+        }
+    }
+}
+
+pub trait Transform2DApplayer {
+    fn apply(&self, t: &Transform2D) -> Self;
+    fn apply_zoom(&self, t: &Transform2D) -> Self;
+}
+
+impl Transform2DApplayer for Vector2 {
+    fn apply(&self, t: &Transform2D) -> Vector2 {
+        self.mul(t.zoom).add(t.offset)
+    }
+
+    fn apply_zoom(&self, t: &Transform2D) -> Vector2 {
+        self.mul(t.zoom)
+    }
+}
+
+impl Transform2DApplayer for Rectangle {
+    fn apply(&self, t: &Transform2D) -> Rectangle {
+        Rectangle::new(
+            (self.x * t.zoom.x) + t.offset.x,
+            (self.y * t.zoom.y) + t.offset.y,
+            self.width * t.zoom.x,
+            self.height * t.zoom.y
+        )
+    }
+
+    fn apply_zoom(&self, t: &Transform2D) -> Self {
+        Rectangle::new(
+            self.x * t.zoom.x,
+            self.y * t.zoom.y,
+            self.width * t.zoom.x,
+            self.height * t.zoom.y
+        )
+    }
+}
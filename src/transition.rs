@@ -0,0 +1,143 @@
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::{Rectangle, Vector2}, texture::Texture2D, RaylibThread};
+
+use crate::window_size;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionKind {
+    // to black and back
+    Fade,
+    // the old frame runs down the screen in uneven columns
+    Melt,
+    // the old frame is pushed off to the right
+    Wipe
+}
+
+impl TransitionKind {
+    // 0 turns transitions off
+    pub fn from_index(index: i64) -> Option<TransitionKind> {
+        match index {
+            1 => Some(TransitionKind::Fade),
+            2 => Some(TransitionKind::Melt),
+            3 => Some(TransitionKind::Wipe),
+            _ => None
+        }
+    }
+
+    pub fn index(kind: Option<TransitionKind>) -> i64 {
+        match kind {
+            None => 0,
+            Some(TransitionKind::Fade) => 1,
+            Some(TransitionKind::Melt) => 2,
+            Some(TransitionKind::Wipe) => 3
+        }
+    }
+
+    fn duration(self) -> f32 {
+        match self {
+            TransitionKind::Fade => 0.6,
+            TransitionKind::Melt => 1.2,
+            TransitionKind::Wipe => 0.5
+        }
+    }
+}
+
+// columns the old frame is cut into for the melt
+const MELT_COLUMNS: usize = 80;
+// longest a column waits before it falls, and how much neighbours may differ
+const MELT_MAX_DELAY: f32 = 0.3;
+const MELT_DELAY_STEP: f32 = 0.03;
+
+// drawn over everything else while the scene below it is swapped
+pub struct ScreenTransition {
+    pub kind: TransitionKind,
+    time: f32,
+    // the last frame of the old scene, only melts and wipes keep it
+    snapshot: Option<Texture2D>,
+    delays: Vec<f32>
+}
+
+impl ScreenTransition {
+    pub fn new(kind: TransitionKind) -> ScreenTransition {
+        ScreenTransition { kind, time: 0.0, snapshot: None, delays: vec![] }
+    }
+
+    pub fn needs_snapshot(&self) -> bool {
+        self.kind != TransitionKind::Fade && self.snapshot.is_none()
+    }
+
+    // copies what was drawn this frame, so it has to run after the old scene is rendered
+    pub fn capture(&mut self, d: &mut RaylibDrawHandle, thread: &RaylibThread) {
+        // draws still waiting in raylib's batch aren't on the screen yet
+        unsafe { raylib::ffi::rlDrawRenderBatchActive() };
+        let image = d.load_image_from_screen(thread);
+        // the drawing handle can't load textures, so this goes through raylib directly
+        let texture = unsafe { Texture2D::from_raw(raylib::ffi::LoadTextureFromImage(*image)) };
+        if texture.id == 0 {
+            println!("ERROR: Could not capture the screen for the transition");
+            self.kind = TransitionKind::Fade;
+        } else {
+            self.snapshot = Some(texture);
+        }
+
+        // a random walk, like Doom, so neighbouring columns fall close together
+        let mut delay = d.get_random_value::<i32>(0..100) as f32 / 100.0 * MELT_MAX_DELAY;
+        self.delays = (0..MELT_COLUMNS).map(|_| {
+            let step = d.get_random_value::<i32>(-1..2) as f32 * MELT_DELAY_STEP;
+            delay = (delay + step).clamp(0.0, MELT_MAX_DELAY);
+            delay
+        }).collect();
+    }
+
+    // whether the old scene is hidden, or kept in the snapshot, so it can be swapped out
+    pub fn ready(&self) -> bool {
+        match self.kind {
+            TransitionKind::Fade => self.time >= self.kind.duration() / 2.0,
+            TransitionKind::Melt | TransitionKind::Wipe => self.snapshot.is_some()
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        // a melt or wipe doesn't start until there is something to move
+        if !self.needs_snapshot() {
+            self.time += dt;
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.time >= self.kind.duration()
+    }
+
+    pub fn render(&self, d: &mut RaylibDrawHandle) {
+        let duration = self.kind.duration();
+        let progress = (self.time / duration).clamp(0.0, 1.0);
+        let size = window_size();
+
+        match (self.kind, &self.snapshot) {
+            (TransitionKind::Fade, _) => {
+                let alpha = 1.0 - (2.0 * progress - 1.0).abs();
+                d.draw_rectangle_v(Vector2::zero(), size, Color::BLACK.alpha(alpha));
+            },
+            (TransitionKind::Wipe, Some(snapshot)) => {
+                let offset = size.x * progress;
+                let source_rec = Rectangle::new(0.0, 0.0, snapshot.width as f32 * (1.0 - progress), snapshot.height as f32);
+                let dest_rec = Rectangle::new(offset, 0.0, size.x - offset, size.y);
+                d.draw_texture_pro(snapshot, source_rec, dest_rec, Vector2::zero(), 0.0, Color::WHITE);
+            },
+            (TransitionKind::Melt, Some(snapshot)) => {
+                let fall = duration - MELT_MAX_DELAY;
+                let column = snapshot.width as f32 / self.delays.len() as f32;
+                let width = size.x / self.delays.len() as f32;
+
+                for (i, delay) in self.delays.iter().enumerate() {
+                    // columns speed up as they fall
+                    let t = ((self.time - delay) / fall).clamp(0.0, 1.0);
+                    let source_rec = Rectangle::new(i as f32 * column, 0.0, column, snapshot.height as f32);
+                    let dest_rec = Rectangle::new(i as f32 * width, size.y * t * t, width, size.y);
+                    d.draw_texture_pro(snapshot, source_rec, dest_rec, Vector2::zero(), 0.0, Color::WHITE);
+                }
+            },
+            // the capture failed before the kind fell back to a fade
+            _ => {}
+        }
+    }
+}
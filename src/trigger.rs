@@ -0,0 +1,45 @@
+use crate::{item::Key, script::{Compare, Value}};
+
+// what using a cell does, the use action activates the first one in front of the player
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trigger {
+    // flips every door and light in its target cells each time it is used
+    Switch { targets: Vec<(usize, usize)>, on: bool },
+    // text shown on screen for a while
+    Sign(String)
+}
+
+impl Trigger {
+    pub fn switch(targets: Vec<(usize, usize)>) -> Trigger {
+        Trigger::Switch { targets, on: false }
+    }
+}
+
+// what has to hold for a cell's trigger, door, interactable, exit or teleporter to work
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    HasKey(Key),
+    // the map at this path was finished at least once
+    Finished(String),
+    // the same values a script's "require" compares
+    Compare(Value, Compare, f32)
+}
+
+// a condition and what is shown when it doesn't hold, something made up from the condition without one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Requirement {
+    pub condition: Condition,
+    pub message: Option<String>
+}
+
+impl Requirement {
+    pub fn text(&self) -> String {
+        if let Some(message) = &self.message { return message.clone() }
+
+        match &self.condition {
+            Condition::HasKey(key) => format!("You need the {} key", key.name()),
+            Condition::Finished(map) => format!("Finish {} first", map),
+            Condition::Compare(value, compare, number) => format!("Needs {} {} {}", value.name(), compare.name(), number)
+        }
+    }
+}
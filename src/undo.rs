@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use raylib::color::Color;
+
+use crate::{board::{Board, Cell}, spawn::Spawn, texture::FlatId};
+
+// steps kept to undo, the oldest are forgotten past it
+const MAX_UNDO_STEPS: usize = 200;
+
+// everything the editor can change about a single cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CellState {
+    cell: Cell,
+    height: f32,
+    floor: Option<FlatId>,
+    ceiling: Option<FlatId>,
+    floor_color: Option<Color>,
+    ceiling_color: Option<Color>
+}
+
+impl CellState {
+    fn of(board: &Board, x: usize, y: usize) -> CellState {
+        CellState {
+            cell: *board.at(x, y),
+            height: board.height_at(x, y),
+            floor: board.floor_at(x, y),
+            ceiling: board.ceiling_at(x, y),
+            floor_color: board.floor_color_at(x, y),
+            ceiling_color: board.ceiling_color_at(x, y)
+        }
+    }
+
+    fn apply(self, board: &mut Board, x: usize, y: usize) {
+        if *board.at(x, y) != self.cell {
+            board.set(x, y, self.cell);
+        }
+        board.set_height(x, y, self.height);
+        board.set_floor(x, y, self.floor);
+        board.set_ceiling(x, y, self.ceiling);
+        board.set_floor_color(x, y, self.floor_color);
+        board.set_ceiling_color(x, y, self.ceiling_color);
+    }
+}
+
+// the cells a change touched as they were before and after it, and the spawns if it changed any
+#[derive(Default)]
+struct UndoStep {
+    cells: BTreeMap<(usize, usize), (CellState, CellState)>,
+    spawns: Option<(Vec<Spawn>, Vec<Spawn>)>
+}
+
+impl UndoStep {
+    fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.spawns.is_none()
+    }
+}
+
+// what the editor changed, a stroke of the mouse or a single key at a time. cells and spawns are
+// touched before they change, the step is only made once the stroke ends and drops what didn't change
+#[derive(Default)]
+pub struct UndoHistory {
+    undo: Vec<UndoStep>,
+    redo: Vec<UndoStep>,
+    current: UndoStep
+}
+
+impl UndoHistory {
+    pub fn new() -> UndoHistory {
+        UndoHistory::default()
+    }
+
+    // another map was loaded, none of it applies anymore
+    pub fn clear(&mut self) {
+        *self = UndoHistory::default();
+    }
+
+    // the cell as it is now is what undoing the current step goes back to
+    pub fn touch(&mut self, board: &Board, x: usize, y: usize) {
+        if x >= board.cols || y >= board.rows { return }
+        let state = CellState::of(board, x, y);
+        self.current.cells.entry((x, y)).or_insert((state, state));
+    }
+
+    pub fn touch_spawns(&mut self, board: &Board) {
+        if self.current.spawns.is_none() {
+            self.current.spawns = Some((board.spawns.clone(), vec![]));
+        }
+    }
+
+    // ends the current step, a new change drops whatever was undone before it
+    pub fn finish(&mut self, board: &Board) {
+        let mut step = std::mem::take(&mut self.current);
+        for ((x, y), (_, after)) in step.cells.iter_mut() {
+            *after = CellState::of(board, *x, *y);
+        }
+        step.cells.retain(|_, (before, after)| before != after);
+        step.spawns = step.spawns.filter(|(before, _)| *before != board.spawns).map(|(before, _)| (before, board.spawns.clone()));
+        if step.is_empty() { return }
+
+        self.redo.clear();
+        self.undo.push(step);
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        self.finish(board);
+        let Some(step) = self.undo.pop() else { return false };
+        for ((x, y), (before, _)) in step.cells.iter() {
+            before.apply(board, *x, *y);
+        }
+        if let Some((before, _)) = &step.spawns {
+            board.spawns = before.clone();
+        }
+        self.redo.push(step);
+        true
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        self.finish(board);
+        let Some(step) = self.redo.pop() else { return false };
+        for ((x, y), (_, after)) in step.cells.iter() {
+            after.apply(board, *x, *y);
+        }
+        if let Some((_, after)) = &step.spawns {
+            board.spawns = after.clone();
+        }
+        self.undo.push(step);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paint(history: &mut UndoHistory, board: &mut Board, cells: &[(usize, usize)], cell: Cell) {
+        for (x, y) in cells.iter().copied() {
+            history.touch(board, x, y);
+            board.set(x, y, cell);
+        }
+        history.finish(board);
+    }
+
+    #[test]
+    fn a_stroke_is_undone_and_redone_as_one_step() {
+        let mut board = Board::new(4, 4);
+        let mut history = UndoHistory::new();
+        paint(&mut history, &mut board, &[(1, 1), (2, 1), (1, 1)], Cell::COLOR(Color::RED));
+
+        assert!(history.undo(&mut board));
+        assert_eq!(*board.at(1, 1), Cell::EMPTY);
+        assert_eq!(*board.at(2, 1), Cell::EMPTY);
+        assert!(!history.undo(&mut board));
+
+        assert!(history.redo(&mut board));
+        assert_eq!(*board.at(1, 1), Cell::COLOR(Color::RED));
+        assert_eq!(*board.at(2, 1), Cell::COLOR(Color::RED));
+        assert!(!history.redo(&mut board));
+    }
+
+    #[test]
+    fn a_new_change_drops_what_was_undone_and_no_change_makes_no_step() {
+        let mut board = Board::new(4, 4);
+        let mut history = UndoHistory::new();
+        paint(&mut history, &mut board, &[(0, 0)], Cell::COLOR(Color::RED));
+        paint(&mut history, &mut board, &[(0, 0)], Cell::COLOR(Color::RED));
+        assert!(history.undo(&mut board));
+        assert!(!history.undo(&mut board));
+
+        paint(&mut history, &mut board, &[(3, 3)], Cell::COLOR(Color::BLUE));
+        assert!(!history.redo(&mut board));
+        assert_eq!(*board.at(0, 0), Cell::EMPTY);
+        assert!(history.undo(&mut board));
+        assert_eq!(*board.at(3, 3), Cell::EMPTY);
+    }
+}
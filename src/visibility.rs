@@ -0,0 +1,99 @@
+use std::{collections::HashMap, ops::Sub};
+
+use raylib::math::Vector2;
+
+use crate::board::{Board, Cell};
+
+// walks every cell the segment crosses and stops at the first one that blocks sight
+pub fn line_of_sight(board: &Board, from: Vector2, to: Vector2) -> bool {
+    sight_doors(board, from, to).is_some_and(|doors| doors.iter().all(|&(x, y)| !board.blocks_sight(x, y)))
+}
+
+// the doors in the way of a segment, None when a wall is. open or shut, so what is seen through
+// them can be worked out once and only the doors looked at again when they move
+pub fn sight_doors(board: &Board, from: Vector2, to: Vector2) -> Option<Vec<(usize, usize)>> {
+    let delta = to.sub(from);
+    let (mut x, mut y) = (f32::floor(from.x) as i64, f32::floor(from.y) as i64);
+    let target = (f32::floor(to.x) as i64, f32::floor(to.y) as i64);
+
+    let step_x = if delta.x > 0.0 { 1 } else { -1 };
+    let step_y = if delta.y > 0.0 { 1 } else { -1 };
+
+    let t_delta_x = if delta.x != 0.0 { 1.0 / delta.x.abs() } else { f32::INFINITY };
+    let t_delta_y = if delta.y != 0.0 { 1.0 / delta.y.abs() } else { f32::INFINITY };
+
+    let mut t_max_x = if delta.x > 0.0 { (f32::floor(from.x) + 1.0 - from.x) * t_delta_x }
+    else { (from.x - f32::floor(from.x)) * t_delta_x };
+    let mut t_max_y = if delta.y > 0.0 { (f32::floor(from.y) + 1.0 - from.y) * t_delta_y }
+    else { (from.y - f32::floor(from.y)) * t_delta_y };
+
+    let mut doors = vec![];
+    while (x, y) != target {
+        if t_max_x < t_max_y {
+            if t_max_x > 1.0 { break }
+            x += step_x;
+            t_max_x += t_delta_x;
+        } else {
+            if t_max_y > 1.0 { break }
+            y += step_y;
+            t_max_y += t_delta_y;
+        }
+
+        if x < 0 || y < 0 || (x as usize) >= board.cols || (y as usize) >= board.rows { continue }
+        let (x, y) = (x as usize, y as usize);
+        match board.at(x, y) {
+            Cell::Door(_) => doors.push((x, y)),
+            _ if board.blocks_sight(x, y) => return None,
+            _ => {}
+        }
+    }
+
+    Some(doors)
+}
+
+struct Entry {
+    from: Vector2,
+    to: Vector2,
+    revision: u64,
+    visible: bool
+}
+
+// keeps the last line of sight result per observer and only recasts once either end
+// moved more than `threshold` or the board changed
+pub struct VisibilityCache {
+    pub threshold: f32,
+    entries: HashMap<usize, Entry>
+}
+
+impl Default for VisibilityCache {
+    fn default() -> VisibilityCache {
+        VisibilityCache::new(0.25)
+    }
+}
+
+impl VisibilityCache {
+    pub fn new(threshold: f32) -> VisibilityCache {
+        VisibilityCache { threshold, entries: HashMap::new() }
+    }
+
+    pub fn visible(&mut self, id: usize, board: &Board, from: Vector2, to: Vector2) -> bool {
+        if let Some(entry) = self.entries.get(&id) {
+            let fresh = entry.revision == board.revision()
+                && entry.from.distance_to(from) <= self.threshold
+                && entry.to.distance_to(to) <= self.threshold;
+            if fresh { return entry.visible }
+        }
+
+        let visible = line_of_sight(board, from, to);
+        self.entries.insert(id, Entry { from, to, revision: board.revision(), visible });
+        visible
+    }
+
+    pub fn forget(&mut self, id: usize) {
+        self.entries.remove(&id);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
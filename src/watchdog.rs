@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+// where the time of a frame went, system by system, so a stutter can be reported with
+// what caused it. only what the systems measured counts, waiting on the display doesn't
+#[derive(Default)]
+pub struct FrameWatchdog {
+    // milliseconds a frame may take before it is logged, 0 never logs
+    pub budget: f32,
+    // milliseconds by system, in the order they first ran this frame
+    systems: Vec<(&'static str, f32)>
+}
+
+impl FrameWatchdog {
+    pub fn new() -> FrameWatchdog {
+        FrameWatchdog::default()
+    }
+
+    // a system that runs more than once a frame adds up
+    pub fn record(&mut self, system: &'static str, ms: f32) {
+        match self.systems.iter_mut().find(|(name, _)| *name == system) {
+            Some((_, total)) => *total += ms,
+            None => self.systems.push((system, ms))
+        }
+    }
+
+    pub fn measure<T>(&mut self, system: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(system, start.elapsed().as_secs_f32() * 1000.0);
+        result
+    }
+
+    // logs the frame if it went over the budget, slowest system first, and starts the next one
+    pub fn end_frame(&mut self) {
+        let mut systems = std::mem::take(&mut self.systems);
+        let total: f32 = systems.iter().map(|(_, ms)| ms).sum();
+        if self.budget <= 0.0 || total <= self.budget { return }
+
+        systems.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let breakdown: Vec<String> = systems.iter().map(|(name, ms)| format!("{} {:.2} ms", name, ms)).collect();
+        println!("WARNING: Frame took {:.2} ms, over the {} ms budget: {}", total, self.budget, breakdown.join(", "));
+    }
+}
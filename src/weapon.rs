@@ -0,0 +1,129 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use raylib::{color::Color, drawing::{RaylibDraw, RaylibDrawHandle}, math::{Rectangle, Vector2}};
+
+use crate::{board::Board, enemy::Enemy, game::Game, math::rotated, raycast::{cast_ray, RayHit}, visibility::line_of_sight, window_size};
+
+// enemies are hit when the shot passes this close to them, scaled by their sprite
+const HIT_RADIUS: f32 = 0.3;
+// aim assist only turns shots towards enemies within this many radians of where they were aimed
+const AIM_ASSIST_ANGLE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hit {
+    Nothing,
+    Wall(RayHit),
+    Enemy(usize, Vector2)
+}
+
+pub struct Weapon {
+    pub damage: f32,
+    pub cooldown: f32,
+    pub range: f32,
+    // share of the way shots are turned to the enemy closest to the crosshair, 0 leaves them alone.
+    // meant for playing on a gamepad, where aiming finely is harder
+    pub aim_assist: f32,
+    pub last_hit: Hit,
+    timer: f32
+}
+
+impl Default for Weapon {
+    fn default() -> Weapon {
+        Weapon::new()
+    }
+}
+
+impl Weapon {
+    pub fn new() -> Weapon {
+        Weapon {
+            damage: 25.0,
+            cooldown: 0.4,
+            range: 20.0,
+            aim_assist: 0.0,
+            last_hit: Hit::Nothing,
+            timer: 0.0
+        }
+    }
+
+    // starts the firing animation, false while the last shot is still cooling down
+    pub fn fire(&mut self) -> bool {
+        if self.timer > 0.0 { return false }
+        self.timer = self.cooldown;
+        true
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.timer = f32::max(self.timer - dt, 0.0);
+    }
+
+    // 1 right after a shot, down to 0 when ready to fire again
+    pub fn recoil(&self) -> f32 {
+        if self.cooldown <= 0.0 { 0.0 }
+        else { self.timer / self.cooldown }
+    }
+}
+
+// `dir` turned by `strength` of the way to the enemy closest to it within the assist angle,
+// counting only the ones in range that can be seen from `from`
+pub fn assisted_aim(board: &Board, enemies: &[Enemy], from: Vector2, dir: Vector2, range: f32, strength: f32) -> Vector2 {
+    if strength <= 0.0 { return dir }
+
+    let closest = enemies.iter()
+        .filter(|enemy| enemy.pos().distance_to(from) <= range && line_of_sight(board, from, enemy.pos()))
+        .map(|enemy| {
+            let to = enemy.pos().sub(from);
+            f32::atan2(dir.x * to.y - dir.y * to.x, dir.dot(to))
+        })
+        .filter(|angle| angle.abs() <= AIM_ASSIST_ANGLE)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+    match closest {
+        Some(angle) => rotated(dir, angle * f32::min(strength, 1.0)),
+        None => dir
+    }
+}
+
+// nearest enemy or wall along a single ray, glass stops the shot as well
+pub fn hitscan(board: &Board, enemies: &[Enemy], from: Vector2, dir: Vector2, range: f32) -> Hit {
+    let dir = dir.normalized();
+    let wall = cast_ray(from, dir, board, range, 0).last().copied();
+
+    let mut hit = wall.map_or(Hit::Nothing, Hit::Wall);
+    let mut nearest = wall.map_or(range, |wall| wall.point.distance_to(from));
+
+    for (i, enemy) in enemies.iter().enumerate() {
+        let rel = enemy.pos().sub(from);
+        let along = rel.dot(dir);
+        if along <= 0.0 || along >= nearest { continue }
+
+        if rel.sub(dir.mul(along)).length() <= HIT_RADIUS * enemy.sprite.scale {
+            nearest = along;
+            hit = Hit::Enemy(i, from.add(dir.mul(along)));
+        }
+    }
+
+    hit
+}
+
+pub fn render_weapon(d: &mut RaylibDrawHandle, game: &Game) {
+    if game.editor.active || game.killcam.is_some() { return }
+
+    let center = window_size().div(2.0);
+    d.draw_line_ex(center.sub(Vector2::new(8.0, 0.0)), center.add(Vector2::new(8.0, 0.0)), 2.0, Color::WHITE);
+    d.draw_line_ex(center.sub(Vector2::new(0.0, 8.0)), center.add(Vector2::new(0.0, 8.0)), 2.0, Color::WHITE);
+
+    // the gun kicks down and flashes right after a shot
+    let recoil = game.weapon.recoil();
+    let kick = recoil * 30.0;
+    let barrel = Rectangle::new(center.x - 12.0, window_size().y - 190.0 + kick, 24.0, 110.0);
+    let body = Rectangle::new(center.x - 40.0, window_size().y - 100.0 + kick, 80.0, 100.0);
+
+    if recoil > 0.7 {
+        d.draw_circle_v(Vector2::new(center.x, barrel.y), 28.0, Color::YELLOW.alpha(0.8));
+        d.draw_circle_v(Vector2::new(center.x, barrel.y), 14.0, Color::WHITE);
+    }
+
+    d.draw_rectangle_rec(barrel, Color::DARKGRAY);
+    d.draw_rectangle_rec(body, Color::GRAY);
+    d.draw_rectangle_lines_ex(body, 2.0, Color::DARKGRAY);
+}
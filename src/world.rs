@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{board::Board, door::DoorState, spawn::SpawnKind};
+
+// what the player changed in a map, pickups and enemies by the index of the spawn they came from
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LevelState {
+    pub open_doors: Vec<(usize, usize)>,
+    pub collected: Vec<usize>,
+    pub killed: Vec<usize>,
+    pub secrets: Vec<(usize, usize)>,
+    // left through one of its exits at least once
+    pub completed: bool
+}
+
+impl LevelState {
+    // doors still opening when the player left count as open
+    pub fn capture_doors(&mut self, board: &Board) {
+        self.open_doors = (0..board.rows)
+            .flat_map(|y| (0..board.cols).map(move |x| (x, y)))
+            .filter(|(x, y)| board.door(*x, *y).is_some_and(|door| matches!(door.state, DoorState::Open | DoorState::Opening)))
+            .collect();
+    }
+
+    pub fn restore_doors(&self, board: &mut Board) {
+        for (x, y) in self.open_doors.iter() {
+            if let Some(door) = board.door_mut(*x, *y) {
+                door.open = 1.0;
+                door.state = DoorState::Open;
+            }
+        }
+    }
+}
+
+// how much of a map the player got through, shown once it is finished
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LevelStats {
+    pub time: f32,
+    pub kills: usize,
+    pub enemies: usize,
+    pub items: usize,
+    pub pickups: usize,
+    pub secrets: usize,
+    pub total_secrets: usize
+}
+
+impl LevelStats {
+    pub fn new(board: &Board, state: &LevelState, time: f32) -> LevelStats {
+        let count = |kind: SpawnKind| board.spawns.iter().filter(|spawn| spawn.kind == kind).count();
+        LevelStats {
+            time,
+            kills: state.killed.len(),
+            enemies: count(SpawnKind::Enemy),
+            items: state.collected.len(),
+            pickups: count(SpawnKind::Pickup),
+            secrets: state.secrets.len(),
+            total_secrets: board.secrets().count()
+        }
+    }
+}
+
+// the state of every map left so far, keyed by map path, so coming back finds it as it was left
+#[derive(Default)]
+pub struct WorldState {
+    pub levels: BTreeMap<String, LevelState>
+}
+
+impl WorldState {
+    pub fn new() -> WorldState {
+        WorldState::default()
+    }
+
+    pub fn leave(&mut self, path: &str, state: LevelState) {
+        self.levels.insert(path.to_string(), state);
+    }
+
+    pub fn is_completed(&self, path: &str) -> bool {
+        self.levels.get(path).is_some_and(|level| level.completed)
+    }
+
+    // a map entered for the first time starts untouched
+    pub fn enter(&mut self, path: &str) -> LevelState {
+        self.levels.remove(path).unwrap_or_default()
+    }
+}